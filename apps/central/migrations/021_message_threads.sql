@@ -0,0 +1,12 @@
+ALTER TABLE messages ADD COLUMN IF NOT EXISTS thread_root_id UUID REFERENCES messages(id) ON DELETE CASCADE;
+
+CREATE INDEX IF NOT EXISTS idx_messages_thread_root ON messages(thread_root_id, created_at DESC) WHERE thread_root_id IS NOT NULL;
+
+CREATE TABLE IF NOT EXISTS thread_follows (
+    root_message_id UUID NOT NULL REFERENCES messages(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (root_message_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_thread_follows_user ON thread_follows(user_id);
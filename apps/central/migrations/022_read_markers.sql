@@ -0,0 +1,21 @@
+CREATE TABLE IF NOT EXISTS read_markers (
+    conversation_id UUID NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    last_read_message_id UUID REFERENCES messages(id) ON DELETE SET NULL,
+    last_read_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (conversation_id, user_id)
+);
+
+INSERT INTO read_markers (conversation_id, user_id, last_read_message_id, last_read_at)
+SELECT cm.conversation_id, cm.user_id, latest.id, COALESCE(latest.created_at, NOW())
+FROM conversation_members cm
+LEFT JOIN LATERAL (
+    SELECT m.id, m.created_at FROM messages m
+    WHERE m.conversation_id = cm.conversation_id
+    ORDER BY m.created_at DESC
+    LIMIT 1
+) latest ON TRUE
+ON CONFLICT (conversation_id, user_id) DO NOTHING;
+
+ALTER TABLE user_preferences ADD COLUMN IF NOT EXISTS send_read_receipts BOOLEAN NOT NULL DEFAULT TRUE;
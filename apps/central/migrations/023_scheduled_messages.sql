@@ -0,0 +1,28 @@
+CREATE TABLE IF NOT EXISTS scheduled_messages (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    conversation_id UUID NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+    sender_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    encrypted_content BYTEA NOT NULL,
+    signature BYTEA NOT NULL,
+    reply_to_id UUID REFERENCES messages(id) ON DELETE SET NULL,
+    expires_at TIMESTAMPTZ,
+    sender_chain_id INTEGER,
+    sender_chain_iteration INTEGER,
+    attachment_s3_key TEXT,
+    attachment_file_size BIGINT,
+    attachment_encrypted_size BIGINT,
+    attachment_mime_type TEXT,
+    send_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_scheduled_messages_send_at ON scheduled_messages(send_at);
+CREATE INDEX IF NOT EXISTS idx_scheduled_messages_sender ON scheduled_messages(sender_id, conversation_id);
+
+CREATE TABLE IF NOT EXISTS scheduled_message_keys (
+    scheduled_message_id UUID NOT NULL REFERENCES scheduled_messages(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    encrypted_key BYTEA NOT NULL,
+    PRIMARY KEY (scheduled_message_id, user_id)
+);
@@ -0,0 +1,19 @@
+ALTER TABLE conversations ADD COLUMN IF NOT EXISTS keep_edit_history BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE IF NOT EXISTS message_revisions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    message_id UUID NOT NULL REFERENCES messages(id) ON DELETE CASCADE,
+    encrypted_content BYTEA NOT NULL,
+    signature BYTEA NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    replaced_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_message_revisions_message ON message_revisions(message_id, created_at);
+
+CREATE TABLE IF NOT EXISTS message_revision_keys (
+    revision_id UUID NOT NULL REFERENCES message_revisions(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    encrypted_key BYTEA NOT NULL,
+    PRIMARY KEY (revision_id, user_id)
+);
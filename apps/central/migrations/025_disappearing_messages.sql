@@ -0,0 +1 @@
+ALTER TABLE conversations ADD COLUMN IF NOT EXISTS message_ttl_seconds INTEGER;
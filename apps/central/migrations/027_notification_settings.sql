@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS conversation_notification_settings (
+    conversation_id UUID NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    level TEXT NOT NULL DEFAULT 'all' CHECK (level IN ('all', 'mentions', 'none')),
+    muted_until TIMESTAMPTZ,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (conversation_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_conversation_notification_settings_user
+    ON conversation_notification_settings(user_id);
@@ -0,0 +1,7 @@
+ALTER TABLE sessions ADD COLUMN IF NOT EXISTS device_name TEXT;
+ALTER TABLE sessions ADD COLUMN IF NOT EXISTS platform TEXT;
+ALTER TABLE sessions ADD COLUMN IF NOT EXISTS client_version TEXT;
+ALTER TABLE sessions ADD COLUMN IF NOT EXISTS ip_prefix TEXT;
+ALTER TABLE sessions ADD COLUMN IF NOT EXISTS last_seen_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+
+CREATE INDEX IF NOT EXISTS idx_sessions_user_id ON sessions(user_id);
@@ -0,0 +1,15 @@
+-- Existing sessions keep their bearer token until the old expiry but have no
+-- refresh token, so they sign in again once it runs out.
+ALTER TABLE sessions ADD COLUMN IF NOT EXISTS access_expires_at TIMESTAMPTZ;
+UPDATE sessions SET access_expires_at = expires_at WHERE access_expires_at IS NULL;
+ALTER TABLE sessions ALTER COLUMN access_expires_at SET NOT NULL;
+
+CREATE TABLE IF NOT EXISTS refresh_tokens (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    session_id UUID NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+    token_hash BYTEA NOT NULL UNIQUE,
+    used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_refresh_tokens_session_id ON refresh_tokens(session_id);
@@ -0,0 +1,5 @@
+-- Group calls started by a deleted account go with it, as direct calls
+-- already do through caller_id and callee_id.
+ALTER TABLE calls DROP CONSTRAINT IF EXISTS calls_initiator_id_fkey;
+ALTER TABLE calls ADD CONSTRAINT calls_initiator_id_fkey
+    FOREIGN KEY (initiator_id) REFERENCES users(id) ON DELETE CASCADE;
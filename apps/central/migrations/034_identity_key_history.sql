@@ -0,0 +1,16 @@
+CREATE TABLE IF NOT EXISTS identity_key_history (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    version INTEGER NOT NULL,
+    kem_public_key BYTEA NOT NULL,
+    dsa_public_key BYTEA NOT NULL,
+    -- Signature by the previous version's DSA key over this entry. NULL only
+    -- for the first key of an account.
+    continuity_signature BYTEA,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (user_id, version)
+);
+
+INSERT INTO identity_key_history (user_id, version, kem_public_key, dsa_public_key, created_at)
+SELECT id, 1, kem_public_key, dsa_public_key, created_at FROM users
+ON CONFLICT (user_id, version) DO NOTHING;
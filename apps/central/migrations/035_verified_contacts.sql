@@ -0,0 +1,7 @@
+-- Which contacts a user has verified by safety number. Encrypted client-side
+-- like user_friends, so the server cannot tell who is verified.
+CREATE TABLE IF NOT EXISTS user_verified_contacts (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    encrypted_verified_contacts BYTEA NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
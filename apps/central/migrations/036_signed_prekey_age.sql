@@ -0,0 +1,5 @@
+-- updated_at moves on every prekey upload, even when only one-time prekeys are
+-- replenished. Track when the signed prekey itself was last replaced.
+ALTER TABLE user_prekeys ADD COLUMN IF NOT EXISTS signed_prekey_created_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+
+UPDATE user_prekeys SET signed_prekey_created_at = updated_at;
@@ -0,0 +1,10 @@
+-- Due scheduled messages used to be deleted when a worker picked them up, so a
+-- failed delivery lost the message. Workers now mark rows as claimed and only
+-- delete them once the message has been sent. Rows that cannot be sent are kept
+-- as failed so the sender can see them, fix them or cancel them.
+ALTER TABLE scheduled_messages ADD COLUMN IF NOT EXISTS claimed_at TIMESTAMPTZ;
+ALTER TABLE scheduled_messages ADD COLUMN IF NOT EXISTS failed_at TIMESTAMPTZ;
+ALTER TABLE scheduled_messages ADD COLUMN IF NOT EXISTS failure_reason TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_scheduled_messages_due ON scheduled_messages(send_at)
+    WHERE failed_at IS NULL;
@@ -0,0 +1,6 @@
+-- Deleting a thread root must not take other members' replies (and their
+-- attachments) with it. Orphaned replies stay in the conversation, the same
+-- as replies whose `reply_to_id` target is gone.
+ALTER TABLE messages DROP CONSTRAINT IF EXISTS messages_thread_root_id_fkey;
+ALTER TABLE messages ADD CONSTRAINT messages_thread_root_id_fkey
+    FOREIGN KEY (thread_root_id) REFERENCES messages(id) ON DELETE SET NULL;
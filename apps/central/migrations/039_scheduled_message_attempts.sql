@@ -0,0 +1,4 @@
+-- Transient delivery failures are retried with a growing delay, and a
+-- message that keeps failing is parked as failed instead of retried forever.
+ALTER TABLE scheduled_messages ADD COLUMN IF NOT EXISTS attempts INT NOT NULL DEFAULT 0;
+ALTER TABLE scheduled_messages ADD COLUMN IF NOT EXISTS next_attempt_at TIMESTAMPTZ;
@@ -0,0 +1,3 @@
+-- Exports can wait for a free worker before they start, so stale exports are
+-- judged by when work began rather than when they were requested.
+ALTER TABLE data_exports ADD COLUMN IF NOT EXISTS started_at TIMESTAMPTZ;
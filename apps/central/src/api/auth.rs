@@ -1,26 +1,43 @@
 use axum::{
-    extract::State,
-    routing::{get, post},
+    extract::{ConnectInfo, Path, State},
+    http::HeaderMap,
+    routing::{delete, get, post, put},
     Json, Router,
 };
 use rand::RngCore;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
+use uuid::Uuid;
 
+use crate::config::{AuthConfig, ServerConfig};
 use crate::crypto;
+use crate::db::{AccountDeletion, GroupHandover};
 use crate::error::{AppError, Result};
-use crate::models::{PublicUser, UserKeys};
+use crate::models::{
+    DeviceInfo, PublicUser, RefreshOutcome, SessionInfo, SessionTokenHashes, User, UserKeys,
+    UsernameChange,
+};
+use crate::ws::{AccountDeletedData, GroupMemberLeftData, UserRenamedData, WsMessage};
 use crate::AppState;
 
 use super::middleware::AuthUser;
+use super::two_factor::LoginChallenge;
 
 pub fn routes() -> Router<Arc<AppState>> {
     Router::new()
         .route("/register", post(register))
         .route("/login", post(login))
-        .route("/me", get(me))
+        .route("/login/2fa", post(super::two_factor::complete_login))
+        .route("/refresh", post(refresh))
+        .route("/me", get(me).delete(delete_account))
+        .route("/username", put(change_username))
+        .route("/username/history", get(get_username_history))
         .route("/logout", post(logout))
+        .route("/logout-others", post(logout_others))
+        .route("/sessions", get(get_sessions))
+        .route("/sessions/{id}", delete(delete_session))
         .route("/keys", get(get_keys))
 }
 
@@ -33,23 +50,24 @@ pub struct RegisterRequest {
     pub dsa_public_key: Vec<u8>,
     pub dsa_encrypted_private: Vec<u8>,
     pub key_salt: Vec<u8>,
+    #[serde(default)]
+    pub device: Option<DeviceInfo>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct AuthResponse {
     pub user: PublicUser,
-    pub token: String,
+    #[serde(flatten)]
+    pub tokens: SessionTokens,
 }
 
 pub async fn register(
     State(state): State<Arc<AppState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
     Json(req): Json<RegisterRequest>,
 ) -> Result<Json<AuthResponse>> {
-    if req.username.len() < 3 {
-        return Err(AppError::BadRequest(
-            "username must be at least 3 characters".into(),
-        ));
-    }
+    validate_username(&req.username)?;
 
     if req.password.len() < state.config.auth.password_min_length {
         return Err(AppError::BadRequest(format!(
@@ -69,29 +87,49 @@ pub async fn register(
         key_salt: req.key_salt,
     };
 
+    if state
+        .db
+        .is_username_reserved(
+            &req.username,
+            None,
+            state.config.auth.username_reservation_days,
+        )
+        .await?
+    {
+        return Err(AppError::UserAlreadyExists);
+    }
+
     let user = state
         .db
         .create_user(&req.username, password_hash, keys)
         .await?;
 
-    let (token, token_hash) = generate_token();
+    let (tokens, token_hashes) = issue_tokens(&state.config.auth);
     state
         .db
-        .create_session(user.id, token_hash, state.config.auth.token_expiry_hours)
+        .create_session(
+            user.id,
+            &token_hashes,
+            &clean_device(req.device),
+            Some(ip_prefix(&state.config.server, &headers, peer)),
+        )
         .await?;
 
     tokio::spawn({
         let state = Arc::clone(&state);
         let user_id = user.id;
-        let kem_public_key = user.kem_public_key.clone();
         async move {
-            crate::ws::broadcast_key_update(&state, user_id, kem_public_key).await;
+            match state.db.get_current_identity_key(user_id).await {
+                Ok(Some(key)) => crate::ws::broadcast_key_update(&state, key).await,
+                Ok(None) => {}
+                Err(e) => tracing::error!("Failed to load identity key for {}: {}", user_id, e),
+            }
         }
     });
 
     Ok(Json(AuthResponse {
         user: user.into(),
-        token,
+        tokens,
     }))
 }
 
@@ -99,21 +137,35 @@ pub async fn register(
 pub struct LoginRequest {
     pub username: String,
     pub password: String,
+    #[serde(default)]
+    pub device: Option<DeviceInfo>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct LoginResponse {
     pub user: PublicUser,
-    pub token: String,
+    #[serde(flatten)]
+    pub tokens: SessionTokens,
     pub kem_encrypted_private: Vec<u8>,
     pub dsa_encrypted_private: Vec<u8>,
     pub key_salt: Vec<u8>,
 }
 
+/// Accounts with two-factor authentication get a challenge to redeem at
+/// `/login/2fa` instead of a session.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum LoginResult {
+    Session(LoginResponse),
+    TwoFactorRequired(LoginChallenge),
+}
+
 pub async fn login(
     State(state): State<Arc<AppState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
     Json(req): Json<LoginRequest>,
-) -> Result<Json<LoginResponse>> {
+) -> Result<Json<LoginResult>> {
     let user = state
         .db
         .get_user_by_username(&req.username)
@@ -125,24 +177,78 @@ pub async fn login(
         return Err(AppError::InvalidCredentials);
     }
 
-    let (token, token_hash) = generate_token();
+    let device = clean_device(req.device);
+    let ip_prefix = ip_prefix(&state.config.server, &headers, peer);
+
+    if state.db.is_two_factor_enabled(user.id).await? {
+        let challenge =
+            super::two_factor::create_login_challenge(&state, user.id, device, ip_prefix).await?;
+        return Ok(Json(LoginResult::TwoFactorRequired(challenge)));
+    }
+
+    let response = start_session(&state, user, &device, ip_prefix).await?;
+    Ok(Json(LoginResult::Session(response)))
+}
+
+pub async fn start_session(
+    state: &AppState,
+    user: User,
+    device: &DeviceInfo,
+    ip_prefix: String,
+) -> Result<LoginResponse> {
+    let (tokens, token_hashes) = issue_tokens(&state.config.auth);
     state
         .db
-        .create_session(user.id, token_hash, state.config.auth.token_expiry_hours)
+        .create_session(user.id, &token_hashes, device, Some(ip_prefix))
         .await?;
 
-    Ok(Json(LoginResponse {
+    Ok(LoginResponse {
         user: PublicUser {
             id: user.id,
             username: user.username,
             kem_public_key: user.kem_public_key,
             dsa_public_key: user.dsa_public_key,
         },
-        token,
+        tokens,
         kem_encrypted_private: user.kem_encrypted_private,
         dsa_encrypted_private: user.dsa_encrypted_private,
         key_salt: user.key_salt,
-    }))
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+pub async fn refresh(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<RefreshRequest>,
+) -> Result<Json<SessionTokens>> {
+    let token_bytes = hex::decode(&req.refresh_token).map_err(|_| AppError::Unauthorized)?;
+    let refresh_token_hash = Sha256::digest(&token_bytes).to_vec();
+
+    let (tokens, token_hashes) = issue_tokens(&state.config.auth);
+    match state
+        .db
+        .rotate_refresh_token(&refresh_token_hash, &token_hashes)
+        .await?
+    {
+        RefreshOutcome::Rotated => Ok(Json(tokens)),
+        RefreshOutcome::Reused {
+            session_id,
+            user_id,
+        } => {
+            tracing::warn!(
+                "Refresh token reuse for user {}, revoked session {}",
+                user_id,
+                session_id
+            );
+            state.subscriptions.revoke_sessions(&[session_id]).await;
+            Err(AppError::Unauthorized)
+        }
+        RefreshOutcome::Invalid => Err(AppError::Unauthorized),
+    }
 }
 
 pub async fn me(State(state): State<Arc<AppState>>, auth: AuthUser) -> Result<Json<PublicUser>> {
@@ -154,11 +260,252 @@ pub async fn me(State(state): State<Arc<AppState>>, auth: AuthUser) -> Result<Js
     Ok(Json(user))
 }
 
+fn validate_username(username: &str) -> Result<()> {
+    if username.len() < 3 {
+        return Err(AppError::BadRequest(
+            "username must be at least 3 characters".into(),
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChangeUsernameRequest {
+    pub username: String,
+}
+
+pub async fn change_username(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Json(req): Json<ChangeUsernameRequest>,
+) -> Result<Json<UsernameChange>> {
+    validate_username(&req.username)?;
+
+    // A user may take back a name they gave up themselves.
+    if state
+        .db
+        .is_username_reserved(
+            &req.username,
+            Some(auth.user_id),
+            state.config.auth.username_reservation_days,
+        )
+        .await?
+    {
+        return Err(AppError::UserAlreadyExists);
+    }
+
+    let change = state
+        .db
+        .change_username(
+            auth.user_id,
+            &req.username,
+            state.config.auth.username_change_cooldown_days,
+        )
+        .await?;
+
+    let contact_ids = state.db.get_user_contact_ids(auth.user_id).await?;
+    let msg = WsMessage::UserRenamed(UserRenamedData {
+        user_id: auth.user_id,
+        old_username: change.old_username.clone(),
+        new_username: change.new_username.clone(),
+    });
+    if let Ok(json) = serde_json::to_string(&msg) {
+        state.subscriptions.send_to_users(&contact_ids, &json).await;
+        state
+            .subscriptions
+            .broadcast_presence_update(auth.user_id, &json)
+            .await;
+        state.subscriptions.send_to_user(auth.user_id, &json).await;
+    }
+
+    tracing::info!(
+        "User {} renamed from {} to {}",
+        auth.user_id,
+        change.old_username,
+        change.new_username
+    );
+    Ok(Json(change))
+}
+
+pub async fn get_username_history(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+) -> Result<Json<Vec<UsernameChange>>> {
+    Ok(Json(state.db.get_username_history(auth.user_id).await?))
+}
+
+/// Confirms the password, and the second factor when enabled, before an
+/// irreversible account change.
+pub async fn reauthenticate(
+    state: &AppState,
+    user: &User,
+    password: &str,
+    two_factor_code: Option<&str>,
+) -> Result<()> {
+    if !crypto::verify_password(password, &user.password_hash)? {
+        return Err(AppError::InvalidCredentials);
+    }
+
+    if state.db.is_two_factor_enabled(user.id).await? {
+        let code = two_factor_code.ok_or(AppError::InvalidTwoFactorCode)?;
+        if !super::two_factor::verify_second_factor(state, user.id, code).await? {
+            return Err(AppError::InvalidTwoFactorCode);
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteAccountRequest {
+    pub password: String,
+    #[serde(default)]
+    pub two_factor_code: Option<String>,
+}
+
+pub async fn delete_account(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Json(req): Json<DeleteAccountRequest>,
+) -> Result<Json<serde_json::Value>> {
+    let user = state
+        .db
+        .get_user_by_id(auth.user_id)
+        .await?
+        .ok_or(AppError::UserNotFound)?;
+
+    reauthenticate(&state, &user, &req.password, req.two_factor_code.as_deref()).await?;
+
+    // Everything below is gone once the user row cascades, so gather it first.
+    let contact_ids = state.db.get_user_contact_ids(user.id).await?;
+    let groups = state.db.get_user_groups(user.id).await?;
+    let owned_servers = state.db.get_user_owned_servers(user.id).await?;
+    let s3_keys = state.db.get_user_s3_keys(user.id).await?;
+
+    let mut deletion = AccountDeletion {
+        owned_servers: owned_servers.iter().map(|s| s.id).collect(),
+        ..Default::default()
+    };
+    let mut remaining_groups = Vec::with_capacity(groups.len());
+    for group in groups {
+        let mut members = state.db.get_conversation_members(group.id).await?;
+        members.retain(|m| m.user_id != user.id);
+
+        if group.owner_id == Some(user.id) {
+            let Some(new_owner) = members.iter().min_by_key(|m| m.joined_at) else {
+                deletion.empty_groups.push(group.id);
+                continue;
+            };
+            deletion.handovers.push(GroupHandover {
+                conversation_id: group.id,
+                new_owner_id: new_owner.user_id,
+                announcement: super::conversations::ownership_change_payload(
+                    user.id,
+                    new_owner.user_id,
+                ),
+            });
+        }
+
+        let member_ids: Vec<Uuid> = members.iter().map(|m| m.user_id).collect();
+        remaining_groups.push((group.id, member_ids));
+    }
+
+    let (session_ids, announcements) = state.db.delete_user(user.id, deletion).await?;
+    state.subscriptions.revoke_sessions(&session_ids).await;
+
+    for server in &owned_servers {
+        tracing::info!(
+            "Deregistered server {} owned by deleted user {}",
+            server.id,
+            user.id
+        );
+    }
+
+    for sys in &announcements {
+        super::conversations::announce_ownership_change(&state, user.id, sys.sender_id, sys).await;
+    }
+
+    for (conversation_id, member_ids) in remaining_groups {
+        let msg = WsMessage::GroupMemberLeft(GroupMemberLeftData {
+            conversation_id,
+            user_id: user.id,
+        });
+        if let Ok(json) = serde_json::to_string(&msg) {
+            state.subscriptions.send_to_users(&member_ids, &json).await;
+        }
+    }
+
+    // Friends lists are encrypted, so friends are reached through their
+    // presence subscription as well as any conversation shared with the user.
+    let msg = WsMessage::AccountDeleted(AccountDeletedData { user_id: user.id });
+    if let Ok(json) = serde_json::to_string(&msg) {
+        state.subscriptions.send_to_users(&contact_ids, &json).await;
+        state
+            .subscriptions
+            .broadcast_presence_update(user.id, &json)
+            .await;
+    }
+
+    for s3_key in s3_keys {
+        if let Err(e) = state.s3.delete_file(&s3_key).await {
+            tracing::warn!("Failed to delete S3 object {}: {}", s3_key, e);
+        }
+    }
+
+    tracing::info!("Deleted account {}", user.id);
+    Ok(Json(serde_json::json!({ "success": true })))
+}
+
 pub async fn logout(
     State(state): State<Arc<AppState>>,
     auth: AuthUser,
 ) -> Result<Json<serde_json::Value>> {
     state.db.delete_session(auth.session_id).await?;
+    state
+        .subscriptions
+        .revoke_sessions(&[auth.session_id])
+        .await;
+    Ok(Json(serde_json::json!({ "success": true })))
+}
+
+pub async fn logout_others(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+) -> Result<Json<serde_json::Value>> {
+    let revoked = state
+        .db
+        .delete_other_sessions(auth.user_id, auth.session_id)
+        .await?;
+    state.subscriptions.revoke_sessions(&revoked).await;
+    Ok(Json(serde_json::json!({ "revoked": revoked.len() })))
+}
+
+pub async fn get_sessions(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+) -> Result<Json<Vec<SessionInfo>>> {
+    let sessions = state
+        .db
+        .get_user_sessions(auth.user_id)
+        .await?
+        .into_iter()
+        .map(|s| SessionInfo::from_session(s, auth.session_id))
+        .collect();
+    Ok(Json(sessions))
+}
+
+pub async fn delete_session(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Path(session_id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>> {
+    if !state
+        .db
+        .delete_user_session(auth.user_id, session_id)
+        .await?
+    {
+        return Err(AppError::NotFound("Session not found".into()));
+    }
+    state.subscriptions.revoke_sessions(&[session_id]).await;
     Ok(Json(serde_json::json!({ "success": true })))
 }
 
@@ -186,6 +533,83 @@ pub async fn get_keys(
     }))
 }
 
+const MAX_DEVICE_FIELD_LENGTH: usize = 64;
+
+pub fn clean_device(device: Option<DeviceInfo>) -> DeviceInfo {
+    let clean = |value: Option<String>| {
+        value
+            .map(|v| {
+                v.trim()
+                    .chars()
+                    .take(MAX_DEVICE_FIELD_LENGTH)
+                    .collect::<String>()
+            })
+            .filter(|v| !v.is_empty())
+    };
+    let device = device.unwrap_or_default();
+    DeviceInfo {
+        name: clean(device.name),
+        platform: clean(device.platform),
+        client_version: clean(device.client_version),
+    }
+}
+
+/// Coarse network location kept with a session: the /24 of an IPv4 address
+/// or the /48 of an IPv6 one. Behind proxies, the client is the hop the
+/// outermost trusted proxy recorded; anything left of it is client supplied.
+pub fn ip_prefix(config: &ServerConfig, headers: &HeaderMap, peer: SocketAddr) -> String {
+    let forwarded = match config.trusted_proxy_hops {
+        0 => None,
+        hops => headers
+            .get("x-forwarded-for")
+            .and_then(|h| h.to_str().ok())
+            .and_then(|v| v.rsplit(',').nth(hops - 1))
+            .and_then(|v| v.trim().parse::<IpAddr>().ok()),
+    };
+    let ip = forwarded.unwrap_or(peer.ip());
+
+    match ip.to_canonical() {
+        IpAddr::V4(v4) => {
+            let [a, b, c, _] = v4.octets();
+            format!("{}.{}.{}.0/24", a, b, c)
+        }
+        IpAddr::V6(v6) => {
+            let s = v6.segments();
+            format!("{:x}:{:x}:{:x}::/48", s[0], s[1], s[2])
+        }
+    }
+}
+
+/// Bearer credentials handed to the client. `token` authenticates requests
+/// for `expires_in` seconds; `refresh_token` is exchanged at `/refresh` for a
+/// new pair.
+#[derive(Debug, Serialize)]
+pub struct SessionTokens {
+    pub token: String,
+    pub refresh_token: String,
+    pub expires_in: u64,
+}
+
+pub fn issue_tokens(config: &AuthConfig) -> (SessionTokens, SessionTokenHashes) {
+    let (token, access_token_hash) = generate_token();
+    let (refresh_token, refresh_token_hash) = generate_token();
+    let now = chrono::Utc::now();
+    let expires_in = config.access_token_expiry_minutes * 60;
+
+    let hashes = SessionTokenHashes {
+        access_token_hash,
+        access_expires_at: now + chrono::Duration::seconds(expires_in as i64),
+        refresh_token_hash,
+        expires_at: now + chrono::Duration::days(config.refresh_token_expiry_days as i64),
+    };
+    let tokens = SessionTokens {
+        token,
+        refresh_token,
+        expires_in,
+    };
+    (tokens, hashes)
+}
+
 fn generate_token() -> (String, Vec<u8>) {
     let mut token_bytes = [0u8; 32];
     rand::thread_rng().fill_bytes(&mut token_bytes);
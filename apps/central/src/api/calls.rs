@@ -330,6 +330,7 @@ async fn reject_call(
                     call_id: Some(call_id),
                     call_duration_seconds: None,
                     pinned_at: None,
+                    mentioned_user_ids: Vec::new(),
                     created_at: message.created_at,
                 },
                 None,
@@ -401,6 +402,7 @@ async fn cancel_call(
                     call_id: Some(call_id),
                     call_duration_seconds: None,
                     pinned_at: None,
+                    mentioned_user_ids: Vec::new(),
                     created_at: message.created_at,
                 },
                 None,
@@ -495,6 +497,7 @@ async fn end_call(
                     call_id: Some(call_id),
                     call_duration_seconds: call.duration_seconds,
                     pinned_at: None,
+                    mentioned_user_ids: Vec::new(),
                     created_at: message.created_at,
                 },
                 None,
@@ -645,6 +648,7 @@ async fn leave_call(
                                 call_id: Some(call_id),
                                 call_duration_seconds: ended_call.duration_seconds,
                                 pinned_at: None,
+                                mentioned_user_ids: Vec::new(),
                                 created_at: message.created_at,
                             },
                             None,
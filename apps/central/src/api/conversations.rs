@@ -4,15 +4,21 @@ use axum::{
     Json, Router,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
 use uuid::Uuid;
 
 use crate::error::{AppError, Result};
-use crate::models::{ConversationType, ConversationWithRouting, PublicUser};
+use crate::models::{
+    Conversation, ConversationSettings, ConversationType, ConversationUnread,
+    ConversationWithUnread, Message, MessageType, NotificationLevel, NotificationSettings,
+    PublicUser,
+};
 use crate::ws::NewMessageData;
 use crate::ws::{
-    GroupCreatedData, GroupDeletedData, GroupMemberAddedData, GroupMemberLeftData,
-    GroupMemberRemovedData, GroupMetadataUpdatedData, GroupOwnerChangedData, WsMessage,
+    ConversationSettingsUpdatedData, GroupCreatedData, GroupDeletedData, GroupMemberAddedData,
+    GroupMemberLeftData, GroupMemberRemovedData, GroupMetadataUpdatedData, GroupOwnerChangedData,
+    ReadReceiptData, WsMessage,
 };
 use crate::AppState;
 
@@ -25,6 +31,7 @@ pub fn routes() -> Router<Arc<AppState>> {
         .route("/", get(get_conversations))
         .route("/", post(create_conversation))
         .route("/groups", post(create_group))
+        .route("/notifications", get(get_all_notification_settings))
         .route("/dm/{user_id}", post(get_or_create_dm))
         .route("/{id}/members", get(get_members))
         .route("/{id}/members", post(add_member))
@@ -32,6 +39,11 @@ pub fn routes() -> Router<Arc<AppState>> {
         .route("/{id}/members/{user_id}", delete(remove_member))
         .route("/{id}/leave", post(leave_group))
         .route("/{id}/hide", post(hide_conversation))
+        .route("/{id}/read", post(mark_read))
+        .route("/{id}/settings", get(get_settings))
+        .route("/{id}/settings", patch(update_settings))
+        .route("/{id}/notifications", get(get_notification_settings))
+        .route("/{id}/notifications", put(update_notification_settings))
         .route("/{id}/owner", put(update_owner))
         .route("/{id}/metadata", patch(update_metadata))
         .route("/{id}", delete(delete_conversation))
@@ -40,8 +52,28 @@ pub fn routes() -> Router<Arc<AppState>> {
 pub async fn get_conversations(
     State(state): State<Arc<AppState>>,
     auth: AuthUser,
-) -> Result<Json<Vec<ConversationWithRouting>>> {
+) -> Result<Json<Vec<ConversationWithUnread>>> {
     let conversations = state.db.get_user_conversations(auth.user_id).await?;
+    let unread: HashMap<Uuid, ConversationUnread> = state
+        .db
+        .get_unread_counts(auth.user_id)
+        .await?
+        .into_iter()
+        .map(|u| (u.conversation_id, u))
+        .collect();
+
+    let conversations = conversations
+        .into_iter()
+        .map(|conversation| {
+            let unread = unread.get(&conversation.id);
+            ConversationWithUnread {
+                last_read_message_id: unread.and_then(|u| u.last_read_message_id),
+                unread_count: unread.map(|u| u.unread_count).unwrap_or(0),
+                conversation,
+            }
+        })
+        .collect();
+
     Ok(Json(conversations))
 }
 
@@ -227,6 +259,7 @@ pub async fn create_group(
             call_id: sys.call_id,
             call_duration_seconds: sys.call_duration_seconds,
             pinned_at: sys.pinned_at,
+            mentioned_user_ids: Vec::new(),
             created_at: sys.created_at,
         });
 
@@ -468,6 +501,7 @@ pub async fn add_member(
         call_id: sys.call_id,
         call_duration_seconds: sys.call_duration_seconds,
         pinned_at: sys.pinned_at,
+        mentioned_user_ids: Vec::new(),
         created_at: sys.created_at,
     });
     if let Ok(json) = serde_json::to_string(&sys_ws) {
@@ -611,6 +645,7 @@ pub async fn add_members_bulk(
             call_id: sys.call_id,
             call_duration_seconds: sys.call_duration_seconds,
             pinned_at: sys.pinned_at,
+            mentioned_user_ids: Vec::new(),
             created_at: sys.created_at,
         });
 
@@ -713,6 +748,7 @@ pub async fn remove_member(
         call_id: sys.call_id,
         call_duration_seconds: sys.call_duration_seconds,
         pinned_at: sys.pinned_at,
+        mentioned_user_ids: Vec::new(),
         created_at: sys.created_at,
     });
     if let Ok(json) = serde_json::to_string(&sys_ws) {
@@ -767,67 +803,7 @@ pub async fn leave_group(
             members.choose(&mut rng).map(|m| m.user_id)
         }
         .ok_or(AppError::Internal(anyhow::anyhow!("No members available")))?;
-        state
-            .db
-            .update_conversation_owner(conversation_id, Some(new_owner))
-            .await?;
-        let _ = state
-            .db
-            .add_group_event(
-                conversation_id,
-                "owner_changed",
-                Some(auth.user_id),
-                Some(new_owner),
-            )
-            .await;
-
-        let msg = WsMessage::GroupOwnerChanged(GroupOwnerChangedData {
-            conversation_id,
-            old_owner_id: auth.user_id,
-            new_owner_id: new_owner,
-            changed_by: auth.user_id,
-        });
-        if let Ok(json) = serde_json::to_string(&msg) {
-            if let Ok(members) = state.db.get_conversation_members(conversation_id).await {
-                let member_ids: Vec<Uuid> = members.iter().map(|m| m.user_id).collect();
-                state.subscriptions.send_to_users(&member_ids, &json).await;
-            }
-        }
-
-        let payload =
-            serde_json::json!({ "old_owner_id": auth.user_id, "new_owner_id": new_owner })
-                .to_string();
-        let sys = state
-            .db
-            .create_system_message_with_content(
-                conversation_id,
-                auth.user_id,
-                "group_owner_changed",
-                payload.into_bytes(),
-            )
-            .await?;
-        let sys_ws = WsMessage::NewMessage(NewMessageData {
-            id: sys.id,
-            conversation_id: sys.conversation_id,
-            sender_id: sys.sender_id,
-            encrypted_content: sys.encrypted_content.clone(),
-            signature: sys.signature.clone(),
-            reply_to_id: sys.reply_to_id,
-            expires_at: sys.expires_at,
-            sender_chain_id: sys.sender_chain_id,
-            sender_chain_iteration: sys.sender_chain_iteration,
-            message_type: sys.message_type.clone(),
-            call_id: sys.call_id,
-            call_duration_seconds: sys.call_duration_seconds,
-            pinned_at: sys.pinned_at,
-            created_at: sys.created_at,
-        });
-        if let Ok(json) = serde_json::to_string(&sys_ws) {
-            if let Ok(members) = state.db.get_conversation_members(conversation_id).await {
-                let member_ids: Vec<Uuid> = members.iter().map(|m| m.user_id).collect();
-                state.subscriptions.send_to_users(&member_ids, &json).await;
-            }
-        }
+        transfer_group_ownership(&state, conversation_id, auth.user_id, new_owner).await?;
     }
 
     let payload = serde_json::json!({ "user_id": auth.user_id }).to_string();
@@ -854,6 +830,7 @@ pub async fn leave_group(
         call_id: sys.call_id,
         call_duration_seconds: sys.call_duration_seconds,
         pinned_at: sys.pinned_at,
+        mentioned_user_ids: Vec::new(),
         created_at: sys.created_at,
     });
     if let Ok(json) = serde_json::to_string(&sys_ws) {
@@ -932,9 +909,21 @@ pub async fn update_owner(
         .await?
         .ok_or(AppError::NotConversationMember)?;
 
+    transfer_group_ownership(&state, conversation_id, auth.user_id, req.new_owner_id).await?;
+
+    Ok(Json(super::messages::SuccessResponse { success: true }))
+}
+
+/// Hands a group to `new_owner_id` and tells its members.
+pub async fn transfer_group_ownership(
+    state: &AppState,
+    conversation_id: Uuid,
+    old_owner_id: Uuid,
+    new_owner_id: Uuid,
+) -> Result<()> {
     state
         .db
-        .update_conversation_owner(conversation_id, Some(req.new_owner_id))
+        .update_conversation_owner(conversation_id, Some(new_owner_id))
         .await?;
 
     let _ = state
@@ -942,36 +931,54 @@ pub async fn update_owner(
         .add_group_event(
             conversation_id,
             "owner_changed",
-            Some(auth.user_id),
-            Some(req.new_owner_id),
+            Some(old_owner_id),
+            Some(new_owner_id),
         )
         .await;
 
-    let msg = WsMessage::GroupOwnerChanged(GroupOwnerChangedData {
-        conversation_id,
-        old_owner_id: auth.user_id,
-        new_owner_id: req.new_owner_id,
-        changed_by: auth.user_id,
-    });
-    if let Ok(json) = serde_json::to_string(&msg) {
-        if let Ok(members) = state.db.get_conversation_members(conversation_id).await {
-            let member_ids: Vec<Uuid> = members.iter().map(|m| m.user_id).collect();
-            state.subscriptions.send_to_users(&member_ids, &json).await;
-        }
-    }
-
-    let payload =
-        serde_json::json!({ "old_owner_id": auth.user_id, "new_owner_id": req.new_owner_id })
-            .to_string();
     let sys = state
         .db
         .create_system_message_with_content(
             conversation_id,
-            auth.user_id,
+            old_owner_id,
             "group_owner_changed",
-            payload.into_bytes(),
+            ownership_change_payload(old_owner_id, new_owner_id),
         )
         .await?;
+    announce_ownership_change(state, old_owner_id, new_owner_id, &sys).await;
+
+    Ok(())
+}
+
+pub fn ownership_change_payload(old_owner_id: Uuid, new_owner_id: Uuid) -> Vec<u8> {
+    serde_json::json!({ "old_owner_id": old_owner_id, "new_owner_id": new_owner_id })
+        .to_string()
+        .into_bytes()
+}
+
+/// Sends the owner change and its system message to the group's members.
+pub async fn announce_ownership_change(
+    state: &AppState,
+    old_owner_id: Uuid,
+    new_owner_id: Uuid,
+    sys: &Message,
+) {
+    let conversation_id = sys.conversation_id;
+    let Ok(members) = state.db.get_conversation_members(conversation_id).await else {
+        return;
+    };
+    let member_ids: Vec<Uuid> = members.iter().map(|m| m.user_id).collect();
+
+    let msg = WsMessage::GroupOwnerChanged(GroupOwnerChangedData {
+        conversation_id,
+        old_owner_id,
+        new_owner_id,
+        changed_by: old_owner_id,
+    });
+    if let Ok(json) = serde_json::to_string(&msg) {
+        state.subscriptions.send_to_users(&member_ids, &json).await;
+    }
+
     let sys_ws = WsMessage::NewMessage(NewMessageData {
         id: sys.id,
         conversation_id: sys.conversation_id,
@@ -986,16 +993,12 @@ pub async fn update_owner(
         call_id: sys.call_id,
         call_duration_seconds: sys.call_duration_seconds,
         pinned_at: sys.pinned_at,
+        mentioned_user_ids: Vec::new(),
         created_at: sys.created_at,
     });
     if let Ok(json) = serde_json::to_string(&sys_ws) {
-        if let Ok(members) = state.db.get_conversation_members(conversation_id).await {
-            let member_ids: Vec<Uuid> = members.iter().map(|m| m.user_id).collect();
-            state.subscriptions.send_to_users(&member_ids, &json).await;
-        }
+        state.subscriptions.send_to_users(&member_ids, &json).await;
     }
-
-    Ok(Json(super::messages::SuccessResponse { success: true }))
 }
 
 #[derive(Debug, Deserialize)]
@@ -1076,6 +1079,270 @@ pub async fn hide_conversation(
     Ok(Json(super::messages::SuccessResponse { success: true }))
 }
 
+#[derive(Debug, Deserialize)]
+pub struct MarkReadRequest {
+    pub message_id: Uuid,
+}
+
+pub async fn mark_read(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Path(conversation_id): Path<Uuid>,
+    Json(req): Json<MarkReadRequest>,
+) -> Result<Json<super::messages::SuccessResponse>> {
+    state
+        .db
+        .get_conversation_routing(conversation_id, auth.user_id)
+        .await?
+        .ok_or(AppError::NotConversationMember)?;
+
+    let message = state
+        .db
+        .get_message(req.message_id)
+        .await?
+        .ok_or(AppError::NotFound("Message not found".into()))?;
+    if message.conversation_id != conversation_id {
+        return Err(AppError::NotFound("Message not found".into()));
+    }
+
+    let Some(marker) = state
+        .db
+        .mark_conversation_read(conversation_id, auth.user_id, req.message_id)
+        .await?
+    else {
+        return Ok(Json(super::messages::SuccessResponse { success: true }));
+    };
+
+    let ws_msg = WsMessage::ReadReceipt(ReadReceiptData {
+        conversation_id,
+        user_id: auth.user_id,
+        last_read_message_id: req.message_id,
+        read_at: marker.last_read_at,
+    });
+    if let Ok(json) = serde_json::to_string(&ws_msg) {
+        let recipients = if state.db.sends_read_receipts(auth.user_id).await? {
+            state
+                .db
+                .get_conversation_members(conversation_id)
+                .await?
+                .iter()
+                .map(|m| m.user_id)
+                .collect()
+        } else {
+            vec![auth.user_id]
+        };
+        state.subscriptions.send_to_users(&recipients, &json).await;
+    }
+
+    Ok(Json(super::messages::SuccessResponse { success: true }))
+}
+
+pub async fn get_settings(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Path(conversation_id): Path<Uuid>,
+) -> Result<Json<ConversationSettings>> {
+    state
+        .db
+        .get_conversation_routing(conversation_id, auth.user_id)
+        .await?
+        .ok_or(AppError::NotConversationMember)?;
+
+    let conv = state
+        .db
+        .get_conversation(conversation_id)
+        .await?
+        .ok_or(AppError::NotFound("Conversation not found".into()))?;
+
+    Ok(Json(ConversationSettings::from(&conv)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateSettingsRequest {
+    pub keep_edit_history: Option<bool>,
+    /// Disappearing-message timer in seconds; `0` turns it off.
+    pub message_ttl_seconds: Option<i32>,
+}
+
+const MIN_MESSAGE_TTL_SECONDS: i32 = 60;
+const MAX_MESSAGE_TTL_SECONDS: i32 = 90 * 24 * 3600;
+
+/// Groups are managed by their owner; either participant may change a DM.
+fn can_manage_settings(conv: &Conversation, user_id: Uuid) -> bool {
+    conv.conversation_type != ConversationType::Group.as_str() || conv.owner_id == Some(user_id)
+}
+
+pub async fn update_settings(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Path(conversation_id): Path<Uuid>,
+    Json(req): Json<UpdateSettingsRequest>,
+) -> Result<Json<ConversationSettings>> {
+    state
+        .db
+        .get_conversation_routing(conversation_id, auth.user_id)
+        .await?
+        .ok_or(AppError::NotConversationMember)?;
+
+    let conv = state
+        .db
+        .get_conversation(conversation_id)
+        .await?
+        .ok_or(AppError::NotFound("Conversation not found".into()))?;
+
+    if !can_manage_settings(&conv, auth.user_id) {
+        return Err(AppError::Forbidden);
+    }
+
+    let mut settings = ConversationSettings::from(&conv);
+
+    if let Some(enabled) = req.keep_edit_history {
+        if enabled != settings.keep_edit_history {
+            state
+                .db
+                .set_keep_edit_history(conversation_id, enabled)
+                .await?;
+            settings.keep_edit_history = enabled;
+        }
+    }
+
+    if let Some(ttl) = req.message_ttl_seconds {
+        let ttl = (ttl != 0).then_some(ttl);
+        if ttl.is_some_and(|t| !(MIN_MESSAGE_TTL_SECONDS..=MAX_MESSAGE_TTL_SECONDS).contains(&t)) {
+            return Err(AppError::BadRequest(format!(
+                "message_ttl_seconds must be 0 or between {} and {}",
+                MIN_MESSAGE_TTL_SECONDS, MAX_MESSAGE_TTL_SECONDS
+            )));
+        }
+
+        if ttl != settings.message_ttl_seconds {
+            state.db.set_message_ttl(conversation_id, ttl).await?;
+            settings.message_ttl_seconds = ttl;
+            announce_timer_change(&state, conversation_id, auth.user_id, ttl).await?;
+        }
+    }
+
+    let msg = WsMessage::ConversationSettingsUpdated(ConversationSettingsUpdatedData {
+        conversation_id,
+        updated_by: auth.user_id,
+        settings: settings.clone(),
+    });
+    if let Ok(json) = serde_json::to_string(&msg) {
+        if let Ok(members) = state.db.get_conversation_members(conversation_id).await {
+            let member_ids: Vec<Uuid> = members.iter().map(|m| m.user_id).collect();
+            state.subscriptions.send_to_users(&member_ids, &json).await;
+        }
+    }
+
+    Ok(Json(settings))
+}
+
+pub async fn get_all_notification_settings(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+) -> Result<Json<Vec<NotificationSettings>>> {
+    let settings = state
+        .db
+        .get_user_notification_settings(auth.user_id)
+        .await?;
+    Ok(Json(settings))
+}
+
+pub async fn get_notification_settings(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Path(conversation_id): Path<Uuid>,
+) -> Result<Json<NotificationSettings>> {
+    state
+        .db
+        .get_conversation_routing(conversation_id, auth.user_id)
+        .await?
+        .ok_or(AppError::NotConversationMember)?;
+
+    let settings = state
+        .db
+        .get_notification_settings(conversation_id, auth.user_id)
+        .await?
+        .unwrap_or_else(|| NotificationSettings::default_for(conversation_id));
+    Ok(Json(settings))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateNotificationSettingsRequest {
+    pub level: NotificationLevel,
+    pub muted_until: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+pub async fn update_notification_settings(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Path(conversation_id): Path<Uuid>,
+    Json(req): Json<UpdateNotificationSettingsRequest>,
+) -> Result<Json<NotificationSettings>> {
+    state
+        .db
+        .get_conversation_routing(conversation_id, auth.user_id)
+        .await?
+        .ok_or(AppError::NotConversationMember)?;
+
+    let muted_until = req.muted_until.filter(|t| *t > chrono::Utc::now());
+    let settings = state
+        .db
+        .upsert_notification_settings(conversation_id, auth.user_id, req.level, muted_until)
+        .await?;
+
+    let msg = WsMessage::NotificationSettingsUpdated(settings.clone());
+    if let Ok(json) = serde_json::to_string(&msg) {
+        state.subscriptions.send_to_user(auth.user_id, &json).await;
+    }
+
+    Ok(Json(settings))
+}
+
+async fn announce_timer_change(
+    state: &AppState,
+    conversation_id: Uuid,
+    user_id: Uuid,
+    ttl_seconds: Option<i32>,
+) -> Result<()> {
+    let payload = serde_json::json!({ "message_ttl_seconds": ttl_seconds }).to_string();
+    let sys = state
+        .db
+        .create_system_message_with_content(
+            conversation_id,
+            user_id,
+            &MessageType::DisappearingTimerChanged.to_string(),
+            payload.into_bytes(),
+        )
+        .await?;
+
+    let sys_ws = WsMessage::NewMessage(NewMessageData {
+        id: sys.id,
+        conversation_id: sys.conversation_id,
+        sender_id: sys.sender_id,
+        encrypted_content: sys.encrypted_content.clone(),
+        signature: sys.signature.clone(),
+        reply_to_id: sys.reply_to_id,
+        expires_at: sys.expires_at,
+        sender_chain_id: sys.sender_chain_id,
+        sender_chain_iteration: sys.sender_chain_iteration,
+        message_type: sys.message_type.clone(),
+        call_id: sys.call_id,
+        call_duration_seconds: sys.call_duration_seconds,
+        pinned_at: sys.pinned_at,
+        mentioned_user_ids: Vec::new(),
+        created_at: sys.created_at,
+    });
+    if let Ok(json) = serde_json::to_string(&sys_ws) {
+        if let Ok(members) = state.db.get_conversation_members(conversation_id).await {
+            let member_ids: Vec<Uuid> = members.iter().map(|m| m.user_id).collect();
+            state.subscriptions.send_to_users(&member_ids, &json).await;
+        }
+    }
+
+    Ok(())
+}
+
 pub async fn delete_conversation(
     State(state): State<Arc<AppState>>,
     auth: AuthUser,
@@ -0,0 +1,367 @@
+use axum::{
+    extract::{ConnectInfo, Path, State},
+    http::HeaderMap,
+    routing::post,
+    Json, Router,
+};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::error::{AppError, Result};
+use crate::models::DeviceInfo;
+use crate::ws::{DeviceLinkCompletedData, DeviceLinkRequestedData, WsMessage};
+use crate::AppState;
+
+use super::auth::{clean_device, ip_prefix, start_session, LoginResponse};
+use super::middleware::AuthUser;
+
+const LINK_TTL_SECONDS: u64 = 300;
+const MAX_PUBLIC_KEY_SIZE: usize = 4096;
+const MAX_TRANSFER_KEY_SIZE: usize = 8192;
+const MAX_ENCRYPTED_KEYS_SIZE: usize = 64 * 1024;
+
+// A link moves through three Redis keys, all expiring with the link:
+//   device_link:{id}          created by the existing device (QR contents)
+//   device_link:{id}:request  set once by the first new device to scan it
+//   device_link:{id}:approval set once the existing device uploads the keys
+pub fn routes() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", post(create_link))
+        .route("/{link_id}/request", post(request_link))
+        .route("/{link_id}/approve", post(approve_link))
+        .route("/{link_id}/reject", post(reject_link))
+        .route("/{link_id}/claim", post(claim_link))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PendingLink {
+    user_id: Uuid,
+    ephemeral_public_key: Vec<u8>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct LinkRequest {
+    device: DeviceInfo,
+    ip_prefix: String,
+    encrypted_transfer_key: Vec<u8>,
+    claim_token_hash: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct LinkApproval {
+    encrypted_keys: Vec<u8>,
+}
+
+fn link_key(link_id: Uuid) -> String {
+    format!("device_link:{}", link_id)
+}
+
+fn request_key(link_id: Uuid) -> String {
+    format!("device_link:{}:request", link_id)
+}
+
+fn approval_key(link_id: Uuid) -> String {
+    format!("device_link:{}:approval", link_id)
+}
+
+fn hash_claim_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+async fn redis_conn(state: &AppState) -> Result<redis::aio::MultiplexedConnection> {
+    state
+        .redis
+        .get_multiplexed_async_connection()
+        .await
+        .map_err(|e| AppError::Internal(anyhow::anyhow!("Redis connection failed: {}", e)))
+}
+
+async fn get_json<T: for<'de> Deserialize<'de>>(
+    conn: &mut redis::aio::MultiplexedConnection,
+    key: &str,
+) -> Result<Option<T>> {
+    let value: Option<String> = redis::cmd("GET")
+        .arg(key)
+        .query_async(conn)
+        .await
+        .map_err(|e| AppError::Internal(anyhow::anyhow!("Redis error: {}", e)))?;
+    Ok(value.and_then(|v| serde_json::from_str(&v).ok()))
+}
+
+/// Stores `value` under `key` only if it is not already set, expiring with
+/// the link. Returns false if another request got there first.
+async fn set_once<T: Serialize>(
+    conn: &mut redis::aio::MultiplexedConnection,
+    key: &str,
+    value: &T,
+    ttl_seconds: u64,
+) -> Result<bool> {
+    let value = serde_json::to_string(value).map_err(|e| AppError::Internal(e.into()))?;
+    let set: Option<String> = redis::cmd("SET")
+        .arg(key)
+        .arg(value)
+        .arg("NX")
+        .arg("EX")
+        .arg(ttl_seconds.max(1))
+        .query_async(conn)
+        .await
+        .map_err(|e| AppError::Internal(anyhow::anyhow!("Redis error: {}", e)))?;
+    Ok(set.is_some())
+}
+
+async fn remaining_ttl(conn: &mut redis::aio::MultiplexedConnection, key: &str) -> Result<u64> {
+    let ttl: i64 = redis::cmd("TTL")
+        .arg(key)
+        .query_async(conn)
+        .await
+        .map_err(|e| AppError::Internal(anyhow::anyhow!("Redis error: {}", e)))?;
+    Ok(ttl.max(0) as u64)
+}
+
+async fn delete_link(conn: &mut redis::aio::MultiplexedConnection, link_id: Uuid) {
+    let _: redis::RedisResult<()> = redis::cmd("DEL")
+        .arg(link_key(link_id))
+        .arg(request_key(link_id))
+        .arg(approval_key(link_id))
+        .query_async(conn)
+        .await;
+}
+
+async fn get_owned_link(
+    conn: &mut redis::aio::MultiplexedConnection,
+    link_id: Uuid,
+    user_id: Uuid,
+) -> Result<PendingLink> {
+    let link: PendingLink = get_json(conn, &link_key(link_id))
+        .await?
+        .ok_or_else(|| AppError::NotFound("Device link not found or expired".into()))?;
+    if link.user_id != user_id {
+        return Err(AppError::Forbidden);
+    }
+    Ok(link)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateLinkRequest {
+    pub ephemeral_public_key: Vec<u8>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreateLinkResponse {
+    pub link_id: Uuid,
+    pub expires_in: u64,
+}
+
+pub async fn create_link(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Json(req): Json<CreateLinkRequest>,
+) -> Result<Json<CreateLinkResponse>> {
+    if req.ephemeral_public_key.is_empty() || req.ephemeral_public_key.len() > MAX_PUBLIC_KEY_SIZE {
+        return Err(AppError::BadRequest("Invalid ephemeral public key".into()));
+    }
+
+    let link_id = Uuid::new_v4();
+    let mut conn = redis_conn(&state).await?;
+    set_once(
+        &mut conn,
+        &link_key(link_id),
+        &PendingLink {
+            user_id: auth.user_id,
+            ephemeral_public_key: req.ephemeral_public_key,
+        },
+        LINK_TTL_SECONDS,
+    )
+    .await?;
+
+    Ok(Json(CreateLinkResponse {
+        link_id,
+        expires_in: LINK_TTL_SECONDS,
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RequestLinkRequest {
+    pub encrypted_transfer_key: Vec<u8>,
+    #[serde(default)]
+    pub device: Option<DeviceInfo>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RequestLinkResponse {
+    pub claim_token: String,
+    pub expires_in: u64,
+}
+
+/// Called by the new device after scanning the QR code. `encrypted_transfer_key`
+/// is a fresh symmetric key encrypted to the link's ephemeral public key; the
+/// existing device uses it to encrypt the account keys on approval.
+pub async fn request_link(
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Path(link_id): Path<Uuid>,
+    Json(req): Json<RequestLinkRequest>,
+) -> Result<Json<RequestLinkResponse>> {
+    if req.encrypted_transfer_key.is_empty()
+        || req.encrypted_transfer_key.len() > MAX_TRANSFER_KEY_SIZE
+    {
+        return Err(AppError::BadRequest("Invalid transfer key".into()));
+    }
+
+    let mut conn = redis_conn(&state).await?;
+    let link: PendingLink = get_json(&mut conn, &link_key(link_id))
+        .await?
+        .ok_or_else(|| AppError::NotFound("Device link not found or expired".into()))?;
+    let ttl = remaining_ttl(&mut conn, &link_key(link_id)).await?;
+
+    let mut token_bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut token_bytes);
+    let claim_token = hex::encode(token_bytes);
+
+    let device = clean_device(req.device);
+    let request = LinkRequest {
+        device: device.clone(),
+        ip_prefix: ip_prefix(&state.config.server, &headers, peer),
+        encrypted_transfer_key: req.encrypted_transfer_key,
+        claim_token_hash: hash_claim_token(&claim_token),
+    };
+    if !set_once(&mut conn, &request_key(link_id), &request, ttl).await? {
+        return Err(AppError::BadRequest(
+            "This device link has already been used".into(),
+        ));
+    }
+
+    let msg = WsMessage::DeviceLinkRequested(DeviceLinkRequestedData {
+        link_id,
+        device_name: device.name,
+        platform: device.platform,
+        encrypted_transfer_key: request.encrypted_transfer_key,
+    });
+    if let Ok(json) = serde_json::to_string(&msg) {
+        state.subscriptions.send_to_user(link.user_id, &json).await;
+    }
+
+    Ok(Json(RequestLinkResponse {
+        claim_token,
+        expires_in: ttl,
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApproveLinkRequest {
+    pub encrypted_keys: Vec<u8>,
+}
+
+pub async fn approve_link(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Path(link_id): Path<Uuid>,
+    Json(req): Json<ApproveLinkRequest>,
+) -> Result<Json<serde_json::Value>> {
+    if req.encrypted_keys.is_empty() || req.encrypted_keys.len() > MAX_ENCRYPTED_KEYS_SIZE {
+        return Err(AppError::BadRequest("Invalid encrypted keys".into()));
+    }
+
+    let mut conn = redis_conn(&state).await?;
+    get_owned_link(&mut conn, link_id, auth.user_id).await?;
+    get_json::<LinkRequest>(&mut conn, &request_key(link_id))
+        .await?
+        .ok_or_else(|| AppError::BadRequest("No device has requested this link yet".into()))?;
+
+    let ttl = remaining_ttl(&mut conn, &link_key(link_id)).await?;
+    let approval = LinkApproval {
+        encrypted_keys: req.encrypted_keys,
+    };
+    if !set_once(&mut conn, &approval_key(link_id), &approval, ttl).await? {
+        return Err(AppError::BadRequest(
+            "This device link has already been approved".into(),
+        ));
+    }
+
+    Ok(Json(serde_json::json!({ "success": true })))
+}
+
+pub async fn reject_link(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Path(link_id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>> {
+    let mut conn = redis_conn(&state).await?;
+    get_owned_link(&mut conn, link_id, auth.user_id).await?;
+    delete_link(&mut conn, link_id).await;
+    Ok(Json(serde_json::json!({ "success": true })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClaimLinkRequest {
+    pub claim_token: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LinkedSession {
+    #[serde(flatten)]
+    pub session: LoginResponse,
+    pub encrypted_keys: Vec<u8>,
+}
+
+/// The new device polls this until the existing device approves. A rejected
+/// or expired link returns 404.
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ClaimLinkResponse {
+    Pending,
+    Approved(Box<LinkedSession>),
+}
+
+pub async fn claim_link(
+    State(state): State<Arc<AppState>>,
+    Path(link_id): Path<Uuid>,
+    Json(req): Json<ClaimLinkRequest>,
+) -> Result<Json<ClaimLinkResponse>> {
+    let mut conn = redis_conn(&state).await?;
+    let link: PendingLink = get_json(&mut conn, &link_key(link_id))
+        .await?
+        .ok_or_else(|| AppError::NotFound("Device link not found or expired".into()))?;
+    let request: LinkRequest = get_json(&mut conn, &request_key(link_id))
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    if request.claim_token_hash != hash_claim_token(&req.claim_token) {
+        return Err(AppError::Unauthorized);
+    }
+
+    let approval: Option<String> = redis::cmd("GETDEL")
+        .arg(approval_key(link_id))
+        .query_async(&mut conn)
+        .await
+        .map_err(|e| AppError::Internal(anyhow::anyhow!("Redis error: {}", e)))?;
+    let Some(approval) = approval.and_then(|a| serde_json::from_str::<LinkApproval>(&a).ok())
+    else {
+        return Ok(Json(ClaimLinkResponse::Pending));
+    };
+    delete_link(&mut conn, link_id).await;
+
+    let user = state
+        .db
+        .get_user_by_id(link.user_id)
+        .await?
+        .ok_or(AppError::UserNotFound)?;
+    let session = start_session(&state, user, &request.device, request.ip_prefix).await?;
+
+    let msg = WsMessage::DeviceLinkCompleted(DeviceLinkCompletedData {
+        link_id,
+        device_name: request.device.name,
+    });
+    if let Ok(json) = serde_json::to_string(&msg) {
+        state.subscriptions.send_to_user(link.user_id, &json).await;
+    }
+
+    Ok(Json(ClaimLinkResponse::Approved(Box::new(LinkedSession {
+        session,
+        encrypted_keys: approval.encrypted_keys,
+    }))))
+}
@@ -0,0 +1,84 @@
+use axum::{
+    extract::{Path, State},
+    routing::{get, post},
+    Json, Router,
+};
+use chrono::{Duration, Utc};
+use serde::Serialize;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::error::{AppError, Result};
+use crate::export_worker;
+use crate::models::{DataExport, DataExportStatus};
+use crate::AppState;
+
+use super::middleware::AuthUser;
+
+pub fn routes() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", post(request_export))
+        .route("/", get(get_latest_export))
+        .route("/{id}/download", get(get_download_url))
+}
+
+pub async fn request_export(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+) -> Result<Json<DataExport>> {
+    if let Some(latest) = state.db.get_latest_data_export(auth.user_id).await? {
+        let cooldown = Duration::hours(state.config.exports.cooldown_hours as i64);
+        if latest.status != DataExportStatus::Failed && latest.created_at + cooldown > Utc::now() {
+            return Err(AppError::TooManyRequests);
+        }
+    }
+
+    let export = state
+        .db
+        .create_data_export(auth.user_id)
+        .await?
+        .ok_or_else(|| AppError::BadRequest("an export is already in progress".into()))?;
+
+    tokio::spawn(export_worker::run_export(state.clone(), export.clone()));
+
+    Ok(Json(export))
+}
+
+pub async fn get_latest_export(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+) -> Result<Json<Option<DataExport>>> {
+    Ok(Json(state.db.get_latest_data_export(auth.user_id).await?))
+}
+
+#[derive(Debug, Serialize)]
+pub struct DownloadUrlResponse {
+    pub url: String,
+    pub expires_in: u64,
+}
+
+pub async fn get_download_url(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Path(export_id): Path<Uuid>,
+) -> Result<Json<DownloadUrlResponse>> {
+    let export = state
+        .db
+        .get_data_export(export_id, auth.user_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Export not found".into()))?;
+
+    if export.status != DataExportStatus::Ready {
+        return Err(AppError::BadRequest("export is not ready".into()));
+    }
+    let s3_key = export
+        .s3_key
+        .filter(|_| export.expires_at.is_some_and(|t| t > Utc::now()))
+        .ok_or_else(|| AppError::NotFound("Export has expired".into()))?;
+
+    let url = state.s3.generate_download_url(&s3_key).await?;
+    Ok(Json(DownloadUrlResponse {
+        url,
+        expires_in: state.config.s3.presigned_url_expiry_seconds,
+    }))
+}
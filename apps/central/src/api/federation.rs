@@ -22,6 +22,7 @@ pub fn routes() -> Router<Arc<AppState>> {
         .route("/request-token", post(request_token))
         .route("/verify-token", post(verify_token))
         .route("/heartbeat", post(heartbeat))
+        .route("/users/{user_id}", post(get_user_info))
         .route("/servers", get(get_owned_servers))
         .route("/servers/{server_id}", get(get_server))
         .route("/servers/{server_id}", post(update_server))
@@ -207,6 +208,50 @@ pub async fn verify_token(
     }))
 }
 
+#[derive(Debug, Deserialize)]
+pub struct UserInfoRequest {
+    pub server_id: Uuid,
+    pub timestamp: i64,
+    pub nonce: Uuid,
+    pub signature: Vec<u8>,
+}
+
+/// Lets community servers confirm a member's current username and keys, e.g.
+/// after a signed rename notice. Only registered servers may ask, with a
+/// request signed by their identity key.
+pub async fn get_user_info(
+    State(state): State<Arc<AppState>>,
+    Path(user_id): Path<Uuid>,
+    Json(req): Json<UserInfoRequest>,
+) -> Result<Json<FederationUserInfo>> {
+    let message_data = format!(
+        "user_info:{}:{}:{}:{}",
+        req.server_id, user_id, req.timestamp, req.nonce
+    );
+    verify_server_request(
+        &state,
+        req.server_id,
+        req.timestamp,
+        req.nonce,
+        &message_data,
+        &req.signature,
+    )
+    .await?;
+
+    let user = state
+        .db
+        .get_public_user(user_id)
+        .await?
+        .ok_or(AppError::UserNotFound)?;
+
+    Ok(Json(FederationUserInfo {
+        user_id: user.id,
+        username: user.username,
+        kem_public_key: user.kem_public_key,
+        dsa_public_key: user.dsa_public_key,
+    }))
+}
+
 #[derive(Debug, Deserialize)]
 pub struct HeartbeatRequest {
     pub server_id: Uuid,
@@ -225,18 +270,24 @@ pub struct HeartbeatResponse {
     pub acknowledged: bool,
 }
 
-pub async fn heartbeat(
-    State(state): State<Arc<AppState>>,
-    Json(req): Json<HeartbeatRequest>,
-) -> Result<Json<HeartbeatResponse>> {
+/// Checks a request signed by a registered server: fresh timestamp, unused
+/// nonce and a valid signature over `message_data`.
+async fn verify_server_request(
+    state: &AppState,
+    server_id: Uuid,
+    timestamp: i64,
+    nonce: Uuid,
+    message_data: &str,
+    signature: &[u8],
+) -> Result<RegisteredServer> {
     let server = state
         .db
-        .get_registered_server(req.server_id)
+        .get_registered_server(server_id)
         .await?
         .ok_or(AppError::NotFound("Server not found".into()))?;
 
     let now = Utc::now().timestamp();
-    if (now - req.timestamp).abs() > 300 {
+    if (now - timestamp).abs() > 300 {
         return Err(AppError::BadRequest(
             "Timestamp too old or in future".into(),
         ));
@@ -244,36 +295,49 @@ pub async fn heartbeat(
 
     let nonce_valid = state
         .db
-        .validate_and_store_heartbeat_nonce(req.nonce, req.server_id)
+        .validate_and_store_heartbeat_nonce(nonce, server_id)
         .await?;
 
     if !nonce_valid {
         tracing::warn!(
             "Replay attack detected: duplicate nonce {} from server {}",
-            req.nonce,
-            req.server_id
+            nonce,
+            server_id
         );
         return Err(AppError::Unauthorized);
     }
 
-    let message_data = format!(
-        "{}:{}:{}:{}",
-        req.server_id, req.member_count, req.timestamp, req.nonce
-    );
-
     use confide_sdk::crypto::keys::DsaKeyPair;
 
-    let signature_valid = DsaKeyPair::verify(
-        &server.dsa_public_key,
-        message_data.as_bytes(),
-        &req.signature,
-    )
-    .unwrap_or(false);
+    let signature_valid =
+        DsaKeyPair::verify(&server.dsa_public_key, message_data.as_bytes(), signature)
+            .unwrap_or(false);
 
     if !signature_valid {
         return Err(AppError::Unauthorized);
     }
 
+    Ok(server)
+}
+
+pub async fn heartbeat(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<HeartbeatRequest>,
+) -> Result<Json<HeartbeatResponse>> {
+    let message_data = format!(
+        "{}:{}:{}:{}",
+        req.server_id, req.member_count, req.timestamp, req.nonce
+    );
+    let server = verify_server_request(
+        &state,
+        req.server_id,
+        req.timestamp,
+        req.nonce,
+        &message_data,
+        &req.signature,
+    )
+    .await?;
+
     state
         .db
         .update_server_heartbeat(
@@ -154,6 +154,7 @@ async fn create_group_call(
             call_id: Some(call.id),
             call_duration_seconds: None,
             pinned_at: None,
+            mentioned_user_ids: Vec::new(),
             created_at: message.created_at,
         },
         None,
@@ -493,6 +494,7 @@ async fn leave_group_call(
                     call_id: Some(call_id),
                     call_duration_seconds: duration_seconds,
                     pinned_at: None,
+                    mentioned_user_ids: Vec::new(),
                     created_at: message.created_at,
                 },
                 None,
@@ -575,6 +577,7 @@ async fn end_group_call(
                 call_id: Some(call_id),
                 call_duration_seconds: ended_call.duration_seconds,
                 pinned_at: None,
+                mentioned_user_ids: Vec::new(),
                 created_at: message.created_at,
             },
             None,
@@ -1,14 +1,19 @@
 use axum::{
     extract::{Path, State},
-    routing::{get, post},
+    routing::{get, post, put},
     Json, Router,
 };
+use chrono::{DateTime, Duration, Utc};
+use confide_sdk::crypto::keys::DsaKeyPair;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use uuid::Uuid;
 
 use crate::error::{AppError, Result};
-use crate::models::{PendingKeyExchange, PreKeyBundle};
+use crate::models::{IdentityKeyRecord, PendingKeyExchange, PreKeyBundle, UserKeys};
+use crate::ws::{IdentityKeyChangedData, PrekeysLowData, SignedPrekeyStaleData, WsMessage};
 use crate::AppState;
 
 use super::middleware::AuthUser;
@@ -23,6 +28,10 @@ pub fn routes() -> Router<Arc<AppState>> {
         .route("/exchange/{id}/accept", post(accept_key_exchange))
         .route("/sessions/{conversation_id}/{peer_id}", get(get_session))
         .route("/sessions/{conversation_id}/{peer_id}", post(save_session))
+        .route("/identity", post(rotate_identity_keys))
+        .route("/identity/{user_id}", get(get_identity_key_chain))
+        .route("/verified-contacts", get(get_verified_contacts))
+        .route("/verified-contacts", put(update_verified_contacts))
 }
 
 #[derive(Debug, Deserialize)]
@@ -69,6 +78,9 @@ pub async fn upload_prekeys(
 #[derive(Debug, Serialize)]
 pub struct PrekeyCountResponse {
     pub count: i64,
+    pub low_water_mark: i64,
+    pub signed_prekey_created_at: Option<DateTime<Utc>>,
+    pub signed_prekey_stale: bool,
 }
 
 pub async fn get_prekey_count(
@@ -76,7 +88,38 @@ pub async fn get_prekey_count(
     auth: AuthUser,
 ) -> Result<Json<PrekeyCountResponse>> {
     let count = state.db.get_one_time_prekey_count(auth.user_id).await?;
-    Ok(Json(PrekeyCountResponse { count }))
+    let signed_prekey_created_at = state
+        .db
+        .get_user_prekeys(auth.user_id)
+        .await?
+        .map(|p| p.signed_prekey_created_at);
+    Ok(Json(PrekeyCountResponse {
+        count,
+        low_water_mark: state.config.prekeys.low_water_mark,
+        signed_prekey_stale: signed_prekey_created_at
+            .is_some_and(|created_at| is_signed_prekey_stale(&state, created_at)),
+        signed_prekey_created_at,
+    }))
+}
+
+#[derive(Default)]
+pub struct PrekeyMetrics {
+    pub bundles_served: AtomicU64,
+    pub bundles_without_otk: AtomicU64,
+}
+
+impl PrekeyMetrics {
+    pub fn log_metrics(&self) {
+        tracing::info!(
+            "Prekeys: bundles_served={}, bundles_without_otk={}",
+            self.bundles_served.load(Ordering::Relaxed),
+            self.bundles_without_otk.load(Ordering::Relaxed),
+        );
+    }
+}
+
+fn is_signed_prekey_stale(state: &AppState, created_at: DateTime<Utc>) -> bool {
+    Utc::now() - created_at > Duration::days(state.config.prekeys.signed_prekey_max_age_days)
 }
 
 pub async fn get_prekey_bundle(
@@ -89,9 +132,72 @@ pub async fn get_prekey_bundle(
         .get_prekey_bundle(user_id)
         .await?
         .ok_or(AppError::UserNotFound)?;
+
+    let metrics = &state.prekey_metrics;
+    metrics.bundles_served.fetch_add(1, Ordering::Relaxed);
+    if bundle.one_time_prekey.is_none() {
+        let total = metrics.bundles_without_otk.fetch_add(1, Ordering::Relaxed) + 1;
+        tracing::warn!(
+            "Served prekey bundle for {} without a one-time prekey ({} total)",
+            user_id,
+            total
+        );
+    }
+
+    let health_state = state.clone();
+    tokio::spawn(async move {
+        if let Err(e) = check_prekey_health(&health_state, user_id).await {
+            tracing::error!("Failed to check prekey health for {}: {:?}", user_id, e);
+        }
+    });
+
     Ok(Json(bundle))
 }
 
+/// Warns the owner when their one-time prekey pool is under the low-water mark
+/// or their signed prekey is older than the configured maximum age. Each
+/// warning is sent at most once per cooldown window.
+async fn check_prekey_health(state: &AppState, user_id: Uuid) -> Result<()> {
+    let config = &state.config.prekeys;
+    let cooldown_seconds = config.warning_cooldown_minutes * 60;
+
+    let remaining = state.db.get_one_time_prekey_count(user_id).await?;
+    if remaining < config.low_water_mark
+        && state
+            .db
+            .claim_prekey_warning(user_id, "low", cooldown_seconds)
+            .await?
+    {
+        let msg = WsMessage::PrekeysLow(PrekeysLowData {
+            remaining,
+            low_water_mark: config.low_water_mark,
+        });
+        if let Ok(json) = serde_json::to_string(&msg) {
+            state.subscriptions.send_to_user(user_id, &json).await;
+        }
+    }
+
+    if let Some(prekeys) = state.db.get_user_prekeys(user_id).await? {
+        if is_signed_prekey_stale(state, prekeys.signed_prekey_created_at)
+            && state
+                .db
+                .claim_prekey_warning(user_id, "stale", cooldown_seconds)
+                .await?
+        {
+            let msg = WsMessage::SignedPrekeyStale(SignedPrekeyStaleData {
+                signed_prekey_id: prekeys.signed_prekey_id,
+                created_at: prekeys.signed_prekey_created_at,
+                max_age_days: config.signed_prekey_max_age_days,
+            });
+            if let Ok(json) = serde_json::to_string(&msg) {
+                state.subscriptions.send_to_user(user_id, &json).await;
+            }
+        }
+    }
+
+    Ok(())
+}
+
 #[derive(Debug, Deserialize)]
 pub struct InitiateKeyExchangeRequest {
     pub to_user_id: Uuid,
@@ -230,3 +336,166 @@ pub async fn save_session(
         .await?;
     Ok(Json(serde_json::json!({ "success": true })))
 }
+
+/// The bytes the previous DSA key signs to vouch for a new key version:
+/// `identity_rotation:{user_id}:{version}:{sha256(kem)}:{sha256(dsa)}` with
+/// the digests hex encoded.
+pub fn continuity_message(
+    user_id: Uuid,
+    version: i32,
+    kem_public_key: &[u8],
+    dsa_public_key: &[u8],
+) -> Vec<u8> {
+    format!(
+        "identity_rotation:{}:{}:{}:{}",
+        user_id,
+        version,
+        hex::encode(Sha256::digest(kem_public_key)),
+        hex::encode(Sha256::digest(dsa_public_key))
+    )
+    .into_bytes()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RotateIdentityKeysRequest {
+    pub password: String,
+    #[serde(default)]
+    pub two_factor_code: Option<String>,
+    pub kem_public_key: Vec<u8>,
+    pub kem_encrypted_private: Vec<u8>,
+    pub dsa_public_key: Vec<u8>,
+    pub dsa_encrypted_private: Vec<u8>,
+    pub key_salt: Vec<u8>,
+    pub continuity_signature: Vec<u8>,
+}
+
+pub async fn rotate_identity_keys(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Json(req): Json<RotateIdentityKeysRequest>,
+) -> Result<Json<IdentityKeyRecord>> {
+    let user = state
+        .db
+        .get_user_by_id(auth.user_id)
+        .await?
+        .ok_or(AppError::UserNotFound)?;
+
+    super::auth::reauthenticate(&state, &user, &req.password, req.two_factor_code.as_deref())
+        .await?;
+
+    if req.dsa_public_key == user.dsa_public_key || req.kem_public_key == user.kem_public_key {
+        return Err(AppError::BadRequest(
+            "rotation must replace both identity keys".into(),
+        ));
+    }
+
+    let current = state
+        .db
+        .get_current_identity_key(user.id)
+        .await?
+        .ok_or_else(|| AppError::Internal(anyhow::anyhow!("identity key chain is empty")))?;
+    let version = current.version + 1;
+
+    let message = continuity_message(user.id, version, &req.kem_public_key, &req.dsa_public_key);
+    let signature_valid =
+        DsaKeyPair::verify(&current.dsa_public_key, &message, &req.continuity_signature)
+            .unwrap_or(false);
+    if !signature_valid {
+        return Err(AppError::BadRequest(
+            "continuity signature must be made with the current identity key".into(),
+        ));
+    }
+
+    let keys = UserKeys {
+        kem_public_key: req.kem_public_key,
+        kem_encrypted_private: req.kem_encrypted_private,
+        dsa_public_key: req.dsa_public_key,
+        dsa_encrypted_private: req.dsa_encrypted_private,
+        key_salt: req.key_salt,
+    };
+    let record = state
+        .db
+        .rotate_identity_keys(user.id, version, keys, req.continuity_signature)
+        .await?;
+
+    // Other devices still hold the old private keys and have to sign in again.
+    let revoked = state
+        .db
+        .delete_other_sessions(user.id, auth.session_id)
+        .await?;
+    state.subscriptions.revoke_sessions(&revoked).await;
+
+    crate::ws::broadcast_key_update(&state, record.clone()).await;
+
+    // Verified contacts are encrypted, so warn every contact and let their
+    // devices decide whether the change affects a verified conversation.
+    let contact_ids = state.db.get_user_contact_ids(user.id).await?;
+    let msg = WsMessage::IdentityKeyChanged(IdentityKeyChangedData {
+        user_id: user.id,
+        key_version: version,
+    });
+    if let Ok(json) = serde_json::to_string(&msg) {
+        state.subscriptions.send_to_users(&contact_ids, &json).await;
+        state
+            .subscriptions
+            .broadcast_presence_update(user.id, &json)
+            .await;
+    }
+
+    tracing::info!(
+        "User {} rotated identity keys to version {}",
+        user.id,
+        version
+    );
+    Ok(Json(record))
+}
+
+pub async fn get_identity_key_chain(
+    State(state): State<Arc<AppState>>,
+    _auth: AuthUser,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<Vec<IdentityKeyRecord>>> {
+    let chain = state.db.get_identity_key_chain(user_id).await?;
+    if chain.is_empty() {
+        return Err(AppError::UserNotFound);
+    }
+    Ok(Json(chain))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerifiedContacts {
+    pub encrypted_verified_contacts: Vec<u8>,
+}
+
+pub async fn get_verified_contacts(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+) -> Result<Json<VerifiedContacts>> {
+    let encrypted_verified_contacts = state
+        .db
+        .get_verified_contacts(auth.user_id)
+        .await?
+        .map(|v| v.encrypted_verified_contacts)
+        .unwrap_or_default();
+
+    Ok(Json(VerifiedContacts {
+        encrypted_verified_contacts,
+    }))
+}
+
+pub async fn update_verified_contacts(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Json(req): Json<VerifiedContacts>,
+) -> Result<Json<serde_json::Value>> {
+    state
+        .db
+        .update_verified_contacts(auth.user_id, req.encrypted_verified_contacts)
+        .await?;
+
+    if let Ok(json) = serde_json::to_string(&WsMessage::VerifiedContactsUpdated) {
+        state.subscriptions.send_to_user(auth.user_id, &json).await;
+    }
+
+    Ok(Json(serde_json::json!({ "success": true })))
+}
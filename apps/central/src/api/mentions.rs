@@ -0,0 +1,51 @@
+use axum::{
+    extract::{Query, State},
+    routing::get,
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::error::Result;
+use crate::AppState;
+
+use super::messages::{with_keys_and_threads, MessageWithKey};
+use super::middleware::AuthUser;
+
+pub fn routes() -> Router<Arc<AppState>> {
+    Router::new().route("/", get(get_mentions))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GetMentionsQuery {
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+    pub since: Option<DateTime<Utc>>,
+    pub before: Option<Uuid>,
+}
+
+fn default_limit() -> i64 {
+    50
+}
+
+pub async fn get_mentions(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Query(query): Query<GetMentionsQuery>,
+) -> Result<Json<Vec<MessageWithKey>>> {
+    let messages = state
+        .db
+        .get_mentioned_messages(
+            auth.user_id,
+            query.since,
+            query.before,
+            query.limit.clamp(1, 100),
+        )
+        .await?;
+
+    Ok(Json(
+        with_keys_and_threads(&state, auth.user_id, messages).await?,
+    ))
+}
@@ -6,7 +6,7 @@ use axum::{
 use chrono::Utc;
 use redis::AsyncCommands;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use uuid::Uuid;
 
@@ -17,11 +17,14 @@ async fn broadcast_to_members(state: &crate::AppState, conversation_id: Uuid, js
     }
 }
 
+use crate::db::{MessageCursor, MessageExtras, NewAttachment};
 use crate::error::{AppError, Result};
-use crate::models::{Message, MessageKey, MessageReaction};
+use crate::models::{
+    Message, MessageKey, MessageReaction, MessageRevision, MessageRow, MessageThread,
+};
 use crate::ws::{
     MessageDeletedData, MessageEditedData, MessagePinnedData, MessageUnpinnedData, NewMessageData,
-    ReactionAddedData, ReactionRemovedData, WsMessage,
+    ReactionAddedData, ReactionRemovedData, ThreadReplyData, ThreadUpdatedData, WsMessage,
 };
 use crate::AppState;
 
@@ -34,6 +37,10 @@ pub fn routes() -> Router<Arc<AppState>> {
         .route("/{conversation_id}/{message_id}", put(edit_message))
         .route("/{conversation_id}/{message_id}", delete(delete_message))
         .route("/{conversation_id}/{message_id}/key", get(get_message_key))
+        .route(
+            "/{conversation_id}/{message_id}/history",
+            get(get_message_history),
+        )
         .route(
             "/{conversation_id}/{message_id}/reactions",
             post(add_reaction),
@@ -45,6 +52,19 @@ pub fn routes() -> Router<Arc<AppState>> {
         .route("/{conversation_id}/{message_id}/pin", post(pin_message))
         .route("/{conversation_id}/{message_id}/unpin", post(unpin_message))
         .route("/{conversation_id}/pinned", get(get_pinned_messages))
+        .route("/{conversation_id}/{message_id}/thread", get(get_thread))
+        .route(
+            "/{conversation_id}/{message_id}/thread",
+            post(reply_to_thread),
+        )
+        .route(
+            "/{conversation_id}/{message_id}/thread/follow",
+            post(follow_thread),
+        )
+        .route(
+            "/{conversation_id}/{message_id}/thread/follow",
+            delete(unfollow_thread),
+        )
 }
 
 #[derive(Debug, Deserialize)]
@@ -52,56 +72,125 @@ pub struct GetMessagesQuery {
     #[serde(default = "default_limit")]
     pub limit: i64,
     pub before: Option<Uuid>,
+    pub after: Option<Uuid>,
+    pub around: Option<Uuid>,
 }
 
 fn default_limit() -> i64 {
     50
 }
 
+#[derive(Debug, Serialize)]
+pub struct MessagesResponse {
+    pub messages: Vec<MessageWithKey>,
+    /// Whether more messages exist in the direction being paged: older for
+    /// `before` and the latest page, newer for `after`, either for `around`.
+    pub has_more: bool,
+    pub has_more_before: bool,
+    pub has_more_after: bool,
+}
+
 #[derive(Debug, Serialize)]
 pub struct MessageWithKey {
     #[serde(flatten)]
     pub message: Message,
     pub encrypted_key: Option<Vec<u8>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thread: Option<MessageThread>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub mentioned_user_ids: Vec<Uuid>,
 }
 
-pub async fn get_messages(
-    State(state): State<Arc<AppState>>,
-    auth: AuthUser,
-    Path(conversation_id): Path<Uuid>,
-    Query(query): Query<GetMessagesQuery>,
-) -> Result<Json<Vec<MessageWithKey>>> {
-    state
-        .db
-        .get_conversation_routing(conversation_id, auth.user_id)
-        .await?
-        .ok_or(AppError::NotConversationMember)?;
-
-    let messages = state
-        .db
-        .get_messages(conversation_id, query.limit.min(100), query.before)
-        .await?;
-
+pub async fn with_keys_and_threads(
+    state: &AppState,
+    user_id: Uuid,
+    messages: Vec<Message>,
+) -> Result<Vec<MessageWithKey>> {
     let message_ids: Vec<Uuid> = messages.iter().map(|m| m.id).collect();
     let keys = state
         .db
-        .get_message_keys_for_user(&message_ids, auth.user_id)
+        .get_message_keys_for_user(&message_ids, user_id)
         .await?;
 
     let key_map: HashMap<Uuid, &MessageKey> = keys.iter().map(|k| (k.message_id, k)).collect();
 
-    let messages_with_keys: Vec<MessageWithKey> = messages
+    let threads = state.db.get_threads_for_messages(&message_ids).await?;
+    let mut thread_map: HashMap<Uuid, MessageThread> = threads
+        .into_iter()
+        .map(|t| (t.root_message_id, t))
+        .collect();
+
+    let mut mentions = state.db.get_mentions_for_messages(&message_ids).await?;
+
+    Ok(messages
         .into_iter()
         .map(|m| {
             let key = key_map.get(&m.id);
+            let thread = thread_map.remove(&m.id);
+            let mentioned_user_ids = mentions.remove(&m.id).unwrap_or_default();
             MessageWithKey {
                 message: m,
                 encrypted_key: key.map(|k| k.encrypted_key.clone()),
+                thread,
+                mentioned_user_ids,
             }
         })
-        .collect();
+        .collect())
+}
 
-    Ok(Json(messages_with_keys))
+pub async fn get_messages(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Path(conversation_id): Path<Uuid>,
+    Query(query): Query<GetMessagesQuery>,
+) -> Result<Json<MessagesResponse>> {
+    state
+        .db
+        .get_conversation_routing(conversation_id, auth.user_id)
+        .await?
+        .ok_or(AppError::NotConversationMember)?;
+
+    let cursor = match (query.before, query.after, query.around) {
+        (None, None, None) => MessageCursor::Latest,
+        (Some(id), None, None) => MessageCursor::Before(id),
+        (None, Some(id), None) => MessageCursor::After(id),
+        (None, None, Some(id)) => MessageCursor::Around(id),
+        _ => {
+            return Err(AppError::BadRequest(
+                "Only one of before, after or around may be given".into(),
+            ))
+        }
+    };
+
+    if let MessageCursor::Before(anchor)
+    | MessageCursor::After(anchor)
+    | MessageCursor::Around(anchor) = cursor
+    {
+        state
+            .db
+            .get_message(anchor)
+            .await?
+            .filter(|m| m.conversation_id == conversation_id)
+            .ok_or(AppError::NotFound("Message not found".into()))?;
+    }
+
+    let page = state
+        .db
+        .get_messages(conversation_id, query.limit.clamp(1, 100), cursor)
+        .await?;
+
+    let has_more = match cursor {
+        MessageCursor::Latest | MessageCursor::Before(_) => page.has_more_before,
+        MessageCursor::After(_) => page.has_more_after,
+        MessageCursor::Around(_) => page.has_more_before || page.has_more_after,
+    };
+
+    Ok(Json(MessagesResponse {
+        has_more,
+        has_more_before: page.has_more_before,
+        has_more_after: page.has_more_after,
+        messages: with_keys_and_threads(&state, auth.user_id, page.messages).await?,
+    }))
 }
 
 #[derive(Debug, Deserialize)]
@@ -128,6 +217,7 @@ pub struct SendMessageRequest {
     pub sender_chain_iteration: Option<i32>,
     pub message_keys: Option<Vec<MessageKeyData>>,
     pub attachment: Option<AttachmentMetadata>,
+    pub mentioned_user_ids: Option<Vec<Uuid>>,
 }
 
 #[derive(Debug, Serialize)]
@@ -148,7 +238,109 @@ pub async fn send_message(
         .await?
         .ok_or(AppError::NotConversationMember)?;
 
-    let spam_key = format!("spam:{}:{}", auth.user_id, conversation_id);
+    check_spam(&state, auth.user_id, conversation_id).await?;
+
+    let message = deliver_message(&state, auth.user_id, conversation_id, req, None).await?;
+
+    Ok(Json(SendMessageResponse {
+        id: message.id,
+        created_at: message.created_at,
+    }))
+}
+
+/// Stores a message with its keys and attachment, unhides the conversation
+/// for every member and fans the message out to them.
+pub async fn deliver_message(
+    state: &AppState,
+    sender_id: Uuid,
+    conversation_id: Uuid,
+    mut req: SendMessageRequest,
+    scheduled_message_id: Option<Uuid>,
+) -> Result<Message> {
+    req.expires_at = apply_disappearing_timer(state, conversation_id, req.expires_at).await?;
+    let mentions = match &req.mentioned_user_ids {
+        Some(ids) => validate_mentions(state, conversation_id, ids).await?,
+        None => Vec::new(),
+    };
+    let keys = request_message_keys(state, conversation_id, &req).await?;
+
+    let message = state
+        .db
+        .create_message(
+            conversation_id,
+            sender_id,
+            req.encrypted_content.clone(),
+            req.signature.clone(),
+            req.reply_to_id,
+            req.expires_at,
+            req.sender_chain_id,
+            req.sender_chain_iteration,
+            None,
+            MessageExtras {
+                scheduled_message_id,
+                ..message_extras(&req, &keys, &mentions)
+            },
+        )
+        .await?;
+
+    let ws_msg = WsMessage::NewMessage(NewMessageData {
+        id: message.id,
+        conversation_id,
+        sender_id,
+        encrypted_content: req.encrypted_content,
+        signature: req.signature,
+        reply_to_id: req.reply_to_id,
+        expires_at: req.expires_at,
+        sender_chain_id: req.sender_chain_id,
+        sender_chain_iteration: req.sender_chain_iteration,
+        message_type: "text".to_string(),
+        call_id: None,
+        call_duration_seconds: None,
+        pinned_at: None,
+        mentioned_user_ids: mentions,
+        created_at: message.created_at,
+    });
+    if let Ok(members) = state.db.get_conversation_members(conversation_id).await {
+        for member in &members {
+            let _ = state
+                .db
+                .unhide_conversation(conversation_id, member.user_id)
+                .await;
+        }
+
+        if let Ok(json) = serde_json::to_string(&ws_msg) {
+            let member_ids: Vec<Uuid> = members.iter().map(|m| m.user_id).collect();
+            state.subscriptions.send_to_users(&member_ids, &json).await;
+        }
+    }
+
+    Ok(message)
+}
+
+/// Applies the conversation's disappearing-message timer: messages without
+/// an expiry get one, and none may outlive the timer.
+pub async fn apply_disappearing_timer(
+    state: &AppState,
+    conversation_id: Uuid,
+    requested: Option<chrono::DateTime<chrono::Utc>>,
+) -> Result<Option<chrono::DateTime<chrono::Utc>>> {
+    let ttl = state
+        .db
+        .get_conversation(conversation_id)
+        .await?
+        .and_then(|c| c.message_ttl_seconds);
+
+    Ok(match ttl {
+        Some(ttl) => {
+            let limit = Utc::now() + chrono::Duration::seconds(ttl as i64);
+            Some(requested.map_or(limit, |r| r.min(limit)))
+        }
+        None => requested,
+    })
+}
+
+async fn check_spam(state: &AppState, user_id: Uuid, conversation_id: Uuid) -> Result<()> {
+    let spam_key = format!("spam:{}:{}", user_id, conversation_id);
     let mut conn = state
         .redis
         .get_multiplexed_async_connection()
@@ -207,91 +399,93 @@ pub async fn send_message(
         .await
         .map_err(|e| AppError::Internal(anyhow::anyhow!("Redis error: {}", e)))?;
 
-    let message = state
-        .db
-        .create_message(
-            conversation_id,
-            auth.user_id,
-            req.encrypted_content.clone(),
-            req.signature.clone(),
-            req.reply_to_id,
-            req.expires_at,
-            req.sender_chain_id,
-            req.sender_chain_iteration,
-        )
-        .await?;
+    Ok(())
+}
 
-    if let Some(keys) = &req.message_keys {
-        let conversation_members = state.db.get_conversation_members(conversation_id).await?;
-        let member_ids: std::collections::HashSet<Uuid> =
-            conversation_members.iter().map(|m| m.user_id).collect();
-
-        for key in keys {
-            if !member_ids.contains(&key.user_id) {
-                return Err(AppError::BadRequest(format!(
-                    "User {} is not a conversation member",
-                    key.user_id
-                )));
-            }
+/// Checks that every key is addressed to a conversation member.
+pub async fn validate_message_keys(
+    state: &AppState,
+    conversation_id: Uuid,
+    keys: &[MessageKeyData],
+) -> Result<Vec<(Uuid, Vec<u8>)>> {
+    let conversation_members = state.db.get_conversation_members(conversation_id).await?;
+    let member_ids: std::collections::HashSet<Uuid> =
+        conversation_members.iter().map(|m| m.user_id).collect();
+
+    for key in keys {
+        if !member_ids.contains(&key.user_id) {
+            return Err(AppError::BadRequest(format!(
+                "User {} is not a conversation member",
+                key.user_id
+            )));
         }
-
-        let key_data: Vec<(Uuid, Vec<u8>)> = keys
-            .iter()
-            .map(|k| (k.user_id, k.encrypted_key.clone()))
-            .collect();
-        state.db.store_message_keys(message.id, &key_data).await?;
     }
 
-    if let Some(attachment) = &req.attachment {
-        state
-            .db
-            .save_attachment(
-                message.id,
-                conversation_id,
-                auth.user_id,
-                &attachment.s3_key,
-                attachment.file_size,
-                attachment.encrypted_size,
-                &attachment.mime_type,
-                req.expires_at,
-            )
-            .await?;
+    Ok(keys
+        .iter()
+        .map(|k| (k.user_id, k.encrypted_key.clone()))
+        .collect())
+}
+
+/// Deduplicates mentioned users and checks that each is a conversation
+/// member.
+pub async fn validate_mentions(
+    state: &AppState,
+    conversation_id: Uuid,
+    user_ids: &[Uuid],
+) -> Result<Vec<Uuid>> {
+    if user_ids.is_empty() {
+        return Ok(Vec::new());
     }
 
-    let ws_msg = WsMessage::NewMessage(NewMessageData {
-        id: message.id,
-        conversation_id,
-        sender_id: auth.user_id,
-        encrypted_content: req.encrypted_content,
-        signature: req.signature,
-        reply_to_id: req.reply_to_id,
-        expires_at: req.expires_at,
-        sender_chain_id: req.sender_chain_id,
-        sender_chain_iteration: req.sender_chain_iteration,
-        message_type: "text".to_string(),
-        call_id: None,
-        call_duration_seconds: None,
-        pinned_at: None,
-        created_at: message.created_at,
-    });
-    if let Ok(members) = state.db.get_conversation_members(conversation_id).await {
-        for member in &members {
-            let _ = state
-                .db
-                .unhide_conversation(conversation_id, member.user_id)
-                .await;
+    let members = state.db.get_conversation_members(conversation_id).await?;
+    let member_ids: std::collections::HashSet<Uuid> = members.iter().map(|m| m.user_id).collect();
+
+    let mut seen = std::collections::HashSet::new();
+    let mut mentions = Vec::new();
+    for user_id in user_ids {
+        if !member_ids.contains(user_id) {
+            return Err(AppError::BadRequest(format!(
+                "User {} is not a conversation member",
+                user_id
+            )));
         }
-
-        if let Ok(json) = serde_json::to_string(&ws_msg) {
-            let member_ids: Vec<Uuid> = members.iter().map(|m| m.user_id).collect();
-            state.subscriptions.send_to_users(&member_ids, &json).await;
+        if seen.insert(*user_id) {
+            mentions.push(*user_id);
         }
     }
+    Ok(mentions)
+}
 
-    Ok(Json(SendMessageResponse {
-        id: message.id,
-        created_at: message.created_at,
-    }))
+/// Validates the request's keys up front so a bad key never leaves a
+/// message behind without them.
+async fn request_message_keys(
+    state: &AppState,
+    conversation_id: Uuid,
+    req: &SendMessageRequest,
+) -> Result<Vec<(Uuid, Vec<u8>)>> {
+    match &req.message_keys {
+        Some(keys) => validate_message_keys(state, conversation_id, keys).await,
+        None => Ok(Vec::new()),
+    }
+}
+
+fn message_extras<'a>(
+    req: &'a SendMessageRequest,
+    keys: &'a [(Uuid, Vec<u8>)],
+    mentions: &'a [Uuid],
+) -> MessageExtras<'a> {
+    MessageExtras {
+        keys,
+        attachment: req.attachment.as_ref().map(|a| NewAttachment {
+            s3_key: &a.s3_key,
+            file_size: a.file_size,
+            encrypted_size: a.encrypted_size,
+            mime_type: &a.mime_type,
+        }),
+        mentions,
+        scheduled_message_id: None,
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -337,6 +531,12 @@ pub async fn edit_message(
         return Err(AppError::Forbidden);
     }
 
+    let keep_history = state
+        .db
+        .get_conversation(conversation_id)
+        .await?
+        .is_some_and(|c| c.keep_edit_history);
+
     let edited_at = state
         .db
         .update_message(
@@ -344,28 +544,13 @@ pub async fn edit_message(
             auth.user_id,
             req.encrypted_content.clone(),
             req.signature.clone(),
+            keep_history,
         )
         .await?
         .ok_or(AppError::NotFound("Message not found".into()))?;
 
     if let Some(keys) = &req.message_keys {
-        let conversation_members = state.db.get_conversation_members(conversation_id).await?;
-        let member_ids: std::collections::HashSet<Uuid> =
-            conversation_members.iter().map(|m| m.user_id).collect();
-
-        for key in keys {
-            if !member_ids.contains(&key.user_id) {
-                return Err(AppError::BadRequest(format!(
-                    "User {} is not a conversation member",
-                    key.user_id
-                )));
-            }
-        }
-
-        let key_data: Vec<(Uuid, Vec<u8>)> = keys
-            .iter()
-            .map(|k| (k.user_id, k.encrypted_key.clone()))
-            .collect();
+        let key_data = validate_message_keys(&state, conversation_id, keys).await?;
         state.db.store_message_keys(message_id, &key_data).await?;
     }
 
@@ -383,6 +568,31 @@ pub async fn edit_message(
     Ok(Json(EditMessageResponse { edited_at }))
 }
 
+pub async fn get_message_history(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Path((conversation_id, message_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<Vec<MessageRevision>>> {
+    state
+        .db
+        .get_conversation_routing(conversation_id, auth.user_id)
+        .await?
+        .ok_or(AppError::NotConversationMember)?;
+
+    state
+        .db
+        .get_message(message_id)
+        .await?
+        .filter(|m| m.conversation_id == conversation_id)
+        .ok_or(AppError::NotFound("Message not found".into()))?;
+
+    let revisions = state
+        .db
+        .get_message_revisions(message_id, auth.user_id)
+        .await?;
+    Ok(Json(revisions))
+}
+
 pub async fn delete_message(
     State(state): State<Arc<AppState>>,
     auth: AuthUser,
@@ -394,6 +604,12 @@ pub async fn delete_message(
         .await?
         .ok_or(AppError::NotConversationMember)?;
 
+    let thread_root_id = state
+        .db
+        .get_message(message_id)
+        .await?
+        .and_then(|m| m.thread_root_id);
+
     let deleted = state.db.delete_message(message_id, auth.user_id).await?;
 
     if !deleted {
@@ -408,6 +624,19 @@ pub async fn delete_message(
         broadcast_to_members(&state, conversation_id, &json).await;
     }
 
+    if let Some(root_message_id) = thread_root_id {
+        let thread = state.db.get_thread(root_message_id).await?;
+        let ws_msg = WsMessage::ThreadUpdated(ThreadUpdatedData {
+            conversation_id,
+            root_message_id,
+            reply_count: thread.reply_count,
+            last_reply_at: thread.last_reply_at,
+        });
+        if let Ok(json) = serde_json::to_string(&ws_msg) {
+            broadcast_to_members(&state, conversation_id, &json).await;
+        }
+    }
+
     Ok(Json(SuccessResponse { success: true }))
 }
 
@@ -581,6 +810,7 @@ pub async fn pin_message(
         call_id: None,
         call_duration_seconds: None,
         pinned_at: None,
+        mentioned_user_ids: Vec::new(),
         created_at: sys_msg.created_at,
     });
 
@@ -639,24 +869,198 @@ pub async fn get_pinned_messages(
 
     let messages = state.db.get_pinned_messages(conversation_id).await?;
 
-    let message_ids: Vec<Uuid> = messages.iter().map(|m| m.id).collect();
-    let keys = state
+    Ok(Json(
+        with_keys_and_threads(&state, auth.user_id, messages).await?,
+    ))
+}
+
+async fn get_thread_root(
+    state: &AppState,
+    conversation_id: Uuid,
+    message_id: Uuid,
+) -> Result<MessageRow> {
+    let root = state
         .db
-        .get_message_keys_for_user(&message_ids, auth.user_id)
+        .get_message(message_id)
+        .await?
+        .filter(|m| m.conversation_id == conversation_id)
+        .ok_or(AppError::NotFound("Message not found".into()))?;
+
+    if root.thread_root_id.is_some() {
+        return Err(AppError::BadRequest(
+            "Threads cannot be started from a thread reply".into(),
+        ));
+    }
+
+    Ok(root)
+}
+
+#[derive(Debug, Serialize)]
+pub struct ThreadResponse {
+    pub thread: MessageThread,
+    pub following: bool,
+    pub replies: Vec<MessageWithKey>,
+}
+
+pub async fn get_thread(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Path((conversation_id, message_id)): Path<(Uuid, Uuid)>,
+    Query(query): Query<GetMessagesQuery>,
+) -> Result<Json<ThreadResponse>> {
+    state
+        .db
+        .get_conversation_routing(conversation_id, auth.user_id)
+        .await?
+        .ok_or(AppError::NotConversationMember)?;
+
+    let root = get_thread_root(&state, conversation_id, message_id).await?;
+
+    let thread = state.db.get_thread(root.id).await?;
+    let following = state.db.is_following_thread(root.id, auth.user_id).await?;
+    let replies = state
+        .db
+        .get_thread_replies(root.id, query.limit.min(100), query.before)
         .await?;
 
-    let key_map: HashMap<Uuid, &MessageKey> = keys.iter().map(|k| (k.message_id, k)).collect();
+    Ok(Json(ThreadResponse {
+        thread,
+        following,
+        replies: with_keys_and_threads(&state, auth.user_id, replies).await?,
+    }))
+}
 
-    let messages_with_keys: Vec<MessageWithKey> = messages
+pub async fn reply_to_thread(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Path((conversation_id, message_id)): Path<(Uuid, Uuid)>,
+    Json(mut req): Json<SendMessageRequest>,
+) -> Result<Json<SendMessageResponse>> {
+    state
+        .db
+        .get_conversation_routing(conversation_id, auth.user_id)
+        .await?
+        .ok_or(AppError::NotConversationMember)?;
+
+    let root = get_thread_root(&state, conversation_id, message_id).await?;
+
+    check_spam(&state, auth.user_id, conversation_id).await?;
+
+    req.expires_at = apply_disappearing_timer(&state, conversation_id, req.expires_at).await?;
+    let mentions = match &req.mentioned_user_ids {
+        Some(ids) => validate_mentions(&state, conversation_id, ids).await?,
+        None => Vec::new(),
+    };
+    let keys = request_message_keys(&state, conversation_id, &req).await?;
+
+    let message = state
+        .db
+        .create_message(
+            conversation_id,
+            auth.user_id,
+            req.encrypted_content.clone(),
+            req.signature.clone(),
+            req.reply_to_id,
+            req.expires_at,
+            req.sender_chain_id,
+            req.sender_chain_iteration,
+            Some(root.id),
+            message_extras(&req, &keys, &mentions),
+        )
+        .await?;
+
+    state.db.follow_thread(root.id, root.sender_id).await?;
+    state.db.follow_thread(root.id, auth.user_id).await?;
+
+    let thread = state.db.get_thread(root.id).await?;
+
+    // Followers get the reply itself; everyone else only sees the thread's
+    // counters move.
+    let followers: HashSet<Uuid> = state
+        .db
+        .get_thread_followers(root.id)
+        .await?
         .into_iter()
-        .map(|m| {
-            let key = key_map.get(&m.id);
-            MessageWithKey {
-                message: m,
-                encrypted_key: key.map(|k| k.encrypted_key.clone()),
-            }
-        })
         .collect();
+    let (following, others): (Vec<Uuid>, Vec<Uuid>) = state
+        .db
+        .get_conversation_members(conversation_id)
+        .await?
+        .into_iter()
+        .map(|m| m.user_id)
+        .partition(|id| followers.contains(id));
+
+    let ws_msg = WsMessage::ThreadReply(ThreadReplyData {
+        thread_root_id: root.id,
+        reply_count: thread.reply_count,
+        last_reply_at: thread.last_reply_at,
+        message: NewMessageData {
+            id: message.id,
+            conversation_id,
+            sender_id: auth.user_id,
+            encrypted_content: req.encrypted_content,
+            signature: req.signature,
+            reply_to_id: req.reply_to_id,
+            expires_at: req.expires_at,
+            sender_chain_id: req.sender_chain_id,
+            sender_chain_iteration: req.sender_chain_iteration,
+            message_type: "text".to_string(),
+            call_id: None,
+            call_duration_seconds: None,
+            pinned_at: None,
+            mentioned_user_ids: mentions,
+            created_at: message.created_at,
+        },
+    });
+    if let Ok(json) = serde_json::to_string(&ws_msg) {
+        state.subscriptions.send_to_users(&following, &json).await;
+    }
+
+    let ws_msg = WsMessage::ThreadUpdated(ThreadUpdatedData {
+        conversation_id,
+        root_message_id: root.id,
+        reply_count: thread.reply_count,
+        last_reply_at: thread.last_reply_at,
+    });
+    if let Ok(json) = serde_json::to_string(&ws_msg) {
+        state.subscriptions.send_to_users(&others, &json).await;
+    }
+
+    Ok(Json(SendMessageResponse {
+        id: message.id,
+        created_at: message.created_at,
+    }))
+}
 
-    Ok(Json(messages_with_keys))
+pub async fn follow_thread(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Path((conversation_id, message_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<SuccessResponse>> {
+    state
+        .db
+        .get_conversation_routing(conversation_id, auth.user_id)
+        .await?
+        .ok_or(AppError::NotConversationMember)?;
+
+    let root = get_thread_root(&state, conversation_id, message_id).await?;
+    state.db.follow_thread(root.id, auth.user_id).await?;
+
+    Ok(Json(SuccessResponse { success: true }))
+}
+
+pub async fn unfollow_thread(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Path((conversation_id, message_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<SuccessResponse>> {
+    state
+        .db
+        .get_conversation_routing(conversation_id, auth.user_id)
+        .await?
+        .ok_or(AppError::NotConversationMember)?;
+
+    state.db.unfollow_thread(message_id, auth.user_id).await?;
+
+    Ok(Json(SuccessResponse { success: true }))
 }
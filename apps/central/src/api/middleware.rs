@@ -41,10 +41,21 @@ impl FromRequestParts<Arc<AppState>> for AuthUser {
             .await?
             .ok_or(AppError::Unauthorized)?;
 
-        if session.expires_at < chrono::Utc::now() {
+        let now = chrono::Utc::now();
+        if session.expires_at < now || session.access_expires_at < now {
             return Err(AppError::Unauthorized);
         }
 
+        if session.last_seen_at < now - chrono::Duration::minutes(5) {
+            let state = Arc::clone(state);
+            let session_id = session.id;
+            tokio::spawn(async move {
+                if let Err(e) = state.db.touch_session(session_id).await {
+                    tracing::warn!("Failed to update session activity: {}", e);
+                }
+            });
+        }
+
         Ok(AuthUser {
             user_id: session.user_id,
             session_id: session.id,
@@ -4,20 +4,25 @@ mod audio_settings;
 mod auth;
 pub mod calls;
 mod conversations;
+mod device_link;
 mod discovery;
+mod exports;
 mod federation;
 mod friends;
 mod gifs;
 mod group_calls;
-mod keys;
-mod messages;
+pub mod keys;
+mod mentions;
+pub mod messages;
 pub mod middleware;
 mod preferences;
 mod profiles;
 pub mod rate_limit;
 mod recovery;
+mod scheduled_messages;
 mod servers;
 mod spotify;
+mod two_factor;
 mod uploads;
 
 use axum::{routing::get, Json, Router};
@@ -44,18 +49,23 @@ pub fn routes() -> Router<Arc<AppState>> {
         .nest("/attachments", attachments::routes())
         .nest("/audio-settings", audio_settings::routes())
         .nest("/auth", auth::routes())
+        .nest("/auth/2fa", two_factor::routes())
+        .nest("/auth/link", device_link::routes())
         .nest("/calls", calls::routes())
         .nest("/calls/group", group_calls::routes())
         .nest("/conversations", conversations::routes())
         .nest("/discovery", discovery::routes())
+        .nest("/exports", exports::routes())
         .nest("/federation", federation::routes())
         .nest("/friends", friends::routes())
         .nest("/gifs", gifs::routes())
         .nest("/keys", keys::routes())
+        .nest("/mentions", mentions::routes())
         .nest("/messages", messages::routes())
         .nest("/preferences", preferences::routes())
         .nest("/profiles", profiles::routes())
         .nest("/recovery", recovery::routes())
+        .nest("/scheduled-messages", scheduled_messages::routes())
         .nest("/servers", servers::routes())
         .nest("/spotify", spotify::routes())
         .nest("/uploads", uploads::routes())
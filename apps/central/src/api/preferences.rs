@@ -6,7 +6,10 @@ use axum::{
 use std::sync::Arc;
 
 use crate::error::Result;
-use crate::models::{PreferencesResponse, UpdatePreferencesRequest, UpdateSnowEffectRequest};
+use crate::models::{
+    PreferencesResponse, UpdatePreferencesRequest, UpdateReadReceiptsRequest,
+    UpdateSnowEffectRequest, UserPreferences,
+};
 use crate::AppState;
 
 use super::middleware::AuthUser;
@@ -16,6 +19,15 @@ pub fn routes() -> Router<Arc<AppState>> {
         .route("/", get(get_preferences))
         .route("/theme", put(update_theme))
         .route("/snow-effect", put(update_snow_effect))
+        .route("/read-receipts", put(update_read_receipts))
+}
+
+fn to_response(prefs: UserPreferences) -> PreferencesResponse {
+    PreferencesResponse {
+        theme: prefs.theme,
+        enable_snow_effect: prefs.enable_snow_effect,
+        send_read_receipts: prefs.send_read_receipts,
+    }
 }
 
 pub async fn update_snow_effect(
@@ -26,15 +38,30 @@ pub async fn update_snow_effect(
     let update_req = UpdatePreferencesRequest {
         theme: None,
         enable_snow_effect: Some(req.enabled),
+        send_read_receipts: None,
     };
     let prefs = state
         .db
         .update_preferences(auth.user_id, &update_req)
         .await?;
-    Ok(Json(PreferencesResponse {
-        theme: prefs.theme,
-        enable_snow_effect: prefs.enable_snow_effect,
-    }))
+    Ok(Json(to_response(prefs)))
+}
+
+pub async fn update_read_receipts(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Json(req): Json<UpdateReadReceiptsRequest>,
+) -> Result<Json<PreferencesResponse>> {
+    let update_req = UpdatePreferencesRequest {
+        theme: None,
+        enable_snow_effect: None,
+        send_read_receipts: Some(req.enabled),
+    };
+    let prefs = state
+        .db
+        .update_preferences(auth.user_id, &update_req)
+        .await?;
+    Ok(Json(to_response(prefs)))
 }
 
 pub async fn get_preferences(
@@ -42,10 +69,7 @@ pub async fn get_preferences(
     auth: AuthUser,
 ) -> Result<Json<PreferencesResponse>> {
     let prefs = state.db.get_or_create_preferences(auth.user_id).await?;
-    Ok(Json(PreferencesResponse {
-        theme: prefs.theme,
-        enable_snow_effect: prefs.enable_snow_effect,
-    }))
+    Ok(Json(to_response(prefs)))
 }
 
 pub async fn update_theme(
@@ -54,8 +78,5 @@ pub async fn update_theme(
     Json(req): Json<UpdatePreferencesRequest>,
 ) -> Result<Json<PreferencesResponse>> {
     let prefs = state.db.update_preferences(auth.user_id, &req).await?;
-    Ok(Json(PreferencesResponse {
-        theme: prefs.theme,
-        enable_snow_effect: prefs.enable_snow_effect,
-    }))
+    Ok(Json(to_response(prefs)))
 }
@@ -1,4 +1,5 @@
 use axum::{
+    body::Body,
     extract::{Request, State},
     http::{header::AUTHORIZATION, Method},
     middleware::Next,
@@ -7,10 +8,15 @@ use axum::{
 use sha2::{Digest, Sha256};
 use std::sync::Arc;
 
+use super::auth::RefreshRequest;
 use crate::{error::AppError, AppState};
 
+const MAX_REFRESH_BODY_BYTES: usize = 4096;
+
 pub enum RateLimitTier {
     Auth,
+    Refresh,
+    DeviceLink,
     Recovery,
     WebSocketConnect,
     Read,
@@ -22,6 +28,8 @@ impl RateLimitTier {
     pub fn limits(&self) -> (u32, u64, &'static str) {
         match self {
             RateLimitTier::Auth => (5, 60, "auth"),
+            RateLimitTier::Refresh => (10, 60, "refresh"),
+            RateLimitTier::DeviceLink => (40, 60, "link"),
             RateLimitTier::Recovery => (3, 60, "recovery"),
             RateLimitTier::WebSocketConnect => (10, 60, "ws"),
             RateLimitTier::Read => (300, 60, "read"),
@@ -31,7 +39,11 @@ impl RateLimitTier {
     }
 
     pub fn from_request(path: &str, method: &Method) -> Self {
-        if path.starts_with("/api/auth") {
+        if path == "/api/auth/refresh" {
+            RateLimitTier::Refresh
+        } else if device_link_id(path).is_some() {
+            RateLimitTier::DeviceLink
+        } else if path.starts_with("/api/auth") {
             RateLimitTier::Auth
         } else if path.starts_with("/api/recovery") {
             RateLimitTier::Recovery
@@ -47,15 +59,42 @@ impl RateLimitTier {
     }
 }
 
+/// The new device polls `/claim` until the link is approved and has no
+/// session yet, so link requests are counted per link rather than in the
+/// shared anonymous auth bucket.
+fn device_link_id(path: &str) -> Option<&str> {
+    path.strip_prefix("/api/auth/link/")?
+        .split('/')
+        .next()
+        .filter(|id| !id.is_empty())
+}
+
+/// Refresh requests carry no bearer token, so they are counted per refresh
+/// token rather than in the shared anonymous auth bucket.
+async fn refresh_token_identifier(request: Request) -> Result<(Request, String), AppError> {
+    let (parts, body) = request.into_parts();
+    let bytes = axum::body::to_bytes(body, MAX_REFRESH_BODY_BYTES)
+        .await
+        .map_err(|_| AppError::BadRequest("Request body too large".into()))?;
+
+    let identifier = serde_json::from_slice::<RefreshRequest>(&bytes)
+        .ok()
+        .and_then(|req| hex::decode(req.refresh_token).ok())
+        .map(|token| format!("refresh:{}", hex::encode(&Sha256::digest(&token)[..8])))
+        .unwrap_or_else(|| "anon".to_string());
+
+    Ok((Request::from_parts(parts, Body::from(bytes)), identifier))
+}
+
 pub async fn rate_limit_middleware(
     State(state): State<Arc<AppState>>,
     request: Request,
     next: Next,
 ) -> Result<Response, AppError> {
-    let path = request.uri().path();
+    let path = request.uri().path().to_string();
     let method = request.method().clone();
 
-    let tier = RateLimitTier::from_request(path, &method);
+    let tier = RateLimitTier::from_request(&path, &method);
     let (max_requests, window_seconds, tier_name) = tier.limits();
 
     let token = request
@@ -65,15 +104,22 @@ pub async fn rate_limit_middleware(
         .and_then(|h| h.strip_prefix("Bearer "))
         .map(|t| t.to_string());
 
-    let user_identifier = if let Some(token) = token {
-        if let Ok(token_bytes) = hex::decode(&token) {
-            let token_hash = Sha256::digest(&token_bytes);
-            format!("user:{}", hex::encode(token_hash))
+    let (request, user_identifier) = if matches!(tier, RateLimitTier::Refresh) {
+        refresh_token_identifier(request).await?
+    } else {
+        let identifier = if let Some(link_id) = device_link_id(&path) {
+            format!("link:{}", link_id)
+        } else if let Some(token) = token {
+            if let Ok(token_bytes) = hex::decode(&token) {
+                let token_hash = Sha256::digest(&token_bytes);
+                format!("user:{}", hex::encode(token_hash))
+            } else {
+                "anon".to_string()
+            }
         } else {
             "anon".to_string()
-        }
-    } else {
-        "anon".to_string()
+        };
+        (request, identifier)
     };
 
     let rate_key = format!(
@@ -1,18 +1,19 @@
 use axum::{
-    extract::State,
+    extract::{ConnectInfo, State},
+    http::HeaderMap,
     routing::{get, post},
     Json, Router,
 };
-use rand::RngCore;
 use serde::{Deserialize, Serialize};
-use sha2::{Digest, Sha256};
+use std::net::SocketAddr;
 use std::sync::Arc;
 
 use crate::crypto;
 use crate::error::{AppError, Result};
-use crate::models::{PublicUser, RecoveryKeys, UserKeys};
+use crate::models::{DeviceInfo, PublicUser, RecoveryKeys, UserKeys};
 use crate::AppState;
 
+use super::auth::{clean_device, ip_prefix, issue_tokens, SessionTokens};
 use super::middleware::AuthUser;
 
 pub fn routes() -> Router<Arc<AppState>> {
@@ -108,16 +109,23 @@ pub struct ResetPasswordRequest {
     pub recovery_dsa_encrypted_private: Vec<u8>,
     pub recovery_key_salt: Vec<u8>,
     pub recovery_proof_signature: Vec<u8>,
+    #[serde(default)]
+    pub device: Option<DeviceInfo>,
+    /// Required when the account has two-factor authentication enabled.
+    pub two_factor_code: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct ResetPasswordResponse {
     pub user: PublicUser,
-    pub token: String,
+    #[serde(flatten)]
+    pub tokens: SessionTokens,
 }
 
 pub async fn reset_password(
     State(state): State<Arc<AppState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
     Json(req): Json<ResetPasswordRequest>,
 ) -> Result<Json<ResetPasswordResponse>> {
     if req.new_password.len() < state.config.auth.password_min_length {
@@ -151,6 +159,16 @@ pub async fn reset_password(
         ));
     }
 
+    if state.db.is_two_factor_enabled(user.id).await? {
+        let code = req
+            .two_factor_code
+            .as_deref()
+            .ok_or_else(|| AppError::BadRequest("two-factor code required".into()))?;
+        if !super::two_factor::verify_second_factor(&state, user.id, code).await? {
+            return Err(AppError::InvalidTwoFactorCode);
+        }
+    }
+
     let new_password_hash =
         crypto::hash_password(&req.new_password, &state.config.crypto.to_argon2_config())?;
 
@@ -173,12 +191,18 @@ pub async fn reset_password(
         .reset_password_with_recovery(user.id, new_password_hash, new_keys, new_recovery_keys)
         .await?;
 
-    state.db.delete_user_sessions(user.id).await?;
+    let revoked = state.db.delete_user_sessions(user.id).await?;
+    state.subscriptions.revoke_sessions(&revoked).await;
 
-    let (token, token_hash) = generate_token();
+    let (tokens, token_hashes) = issue_tokens(&state.config.auth);
     state
         .db
-        .create_session(user.id, token_hash, state.config.auth.token_expiry_hours)
+        .create_session(
+            user.id,
+            &token_hashes,
+            &clean_device(req.device),
+            Some(ip_prefix(&state.config.server, &headers, peer)),
+        )
         .await?;
 
     Ok(Json(ResetPasswordResponse {
@@ -188,14 +212,6 @@ pub async fn reset_password(
             kem_public_key: req.kem_public_key,
             dsa_public_key: req.dsa_public_key,
         },
-        token,
+        tokens,
     }))
 }
-
-fn generate_token() -> (String, Vec<u8>) {
-    let mut token_bytes = [0u8; 32];
-    rand::thread_rng().fill_bytes(&mut token_bytes);
-    let token = hex::encode(token_bytes);
-    let token_hash = Sha256::digest(token_bytes).to_vec();
-    (token, token_hash)
-}
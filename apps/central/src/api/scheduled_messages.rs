@@ -0,0 +1,213 @@
+use axum::{
+    extract::{Path, State},
+    routing::{delete, get, post, put},
+    Json, Router,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::Deserialize;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::error::{AppError, Result};
+use crate::models::{NewScheduledMessage, ScheduledAttachment, ScheduledMessage};
+use crate::AppState;
+
+use super::messages::{
+    validate_mentions, validate_message_keys, MessageKeyData, SendMessageRequest, SuccessResponse,
+};
+use super::middleware::AuthUser;
+
+const MAX_PENDING_PER_USER: i64 = 100;
+const MAX_SCHEDULE_AHEAD_DAYS: i64 = 365;
+
+pub fn routes() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/{conversation_id}", get(get_scheduled_messages))
+        .route("/{conversation_id}", post(schedule_message))
+        .route("/{conversation_id}/{id}", put(edit_scheduled_message))
+        .route("/{conversation_id}/{id}", delete(cancel_scheduled_message))
+}
+
+fn validate_send_at(send_at: DateTime<Utc>, expires_at: Option<DateTime<Utc>>) -> Result<()> {
+    let now = Utc::now();
+    if send_at <= now {
+        return Err(AppError::BadRequest("send_at must be in the future".into()));
+    }
+    if send_at > now + Duration::days(MAX_SCHEDULE_AHEAD_DAYS) {
+        return Err(AppError::BadRequest(format!(
+            "Messages can be scheduled at most {} days ahead",
+            MAX_SCHEDULE_AHEAD_DAYS
+        )));
+    }
+    if expires_at.is_some_and(|e| e <= send_at) {
+        return Err(AppError::BadRequest(
+            "expires_at must be after send_at".into(),
+        ));
+    }
+    Ok(())
+}
+
+pub async fn get_scheduled_messages(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Path(conversation_id): Path<Uuid>,
+) -> Result<Json<Vec<ScheduledMessage>>> {
+    state
+        .db
+        .get_conversation_routing(conversation_id, auth.user_id)
+        .await?
+        .ok_or(AppError::NotConversationMember)?;
+
+    let scheduled = state
+        .db
+        .get_scheduled_messages(conversation_id, auth.user_id)
+        .await?;
+    Ok(Json(scheduled))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScheduleMessageRequest {
+    #[serde(flatten)]
+    pub message: SendMessageRequest,
+    pub send_at: DateTime<Utc>,
+}
+
+pub async fn schedule_message(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Path(conversation_id): Path<Uuid>,
+    Json(req): Json<ScheduleMessageRequest>,
+) -> Result<Json<ScheduledMessage>> {
+    state
+        .db
+        .get_conversation_routing(conversation_id, auth.user_id)
+        .await?
+        .ok_or(AppError::NotConversationMember)?;
+
+    validate_send_at(req.send_at, req.message.expires_at)?;
+
+    if state.db.count_scheduled_messages(auth.user_id).await? >= MAX_PENDING_PER_USER {
+        return Err(AppError::BadRequest(format!(
+            "You can have at most {} scheduled messages",
+            MAX_PENDING_PER_USER
+        )));
+    }
+
+    let keys = match &req.message.message_keys {
+        Some(keys) => validate_message_keys(&state, conversation_id, keys).await?,
+        None => Vec::new(),
+    };
+
+    let mentioned_user_ids = match &req.message.mentioned_user_ids {
+        Some(ids) => validate_mentions(&state, conversation_id, ids).await?,
+        None => Vec::new(),
+    };
+
+    let msg = req.message;
+    let scheduled = state
+        .db
+        .create_scheduled_message(
+            &NewScheduledMessage {
+                conversation_id,
+                sender_id: auth.user_id,
+                encrypted_content: msg.encrypted_content,
+                signature: msg.signature,
+                reply_to_id: msg.reply_to_id,
+                expires_at: msg.expires_at,
+                sender_chain_id: msg.sender_chain_id,
+                sender_chain_iteration: msg.sender_chain_iteration,
+                attachment: msg.attachment.map(|a| ScheduledAttachment {
+                    s3_key: a.s3_key,
+                    file_size: a.file_size,
+                    encrypted_size: a.encrypted_size,
+                    mime_type: a.mime_type,
+                }),
+                mentioned_user_ids,
+                send_at: req.send_at,
+            },
+            &keys,
+        )
+        .await?;
+
+    Ok(Json(scheduled))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EditScheduledMessageRequest {
+    pub encrypted_content: Option<Vec<u8>>,
+    pub signature: Option<Vec<u8>>,
+    pub message_keys: Option<Vec<MessageKeyData>>,
+    pub send_at: Option<DateTime<Utc>>,
+}
+
+pub async fn edit_scheduled_message(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Path((conversation_id, id)): Path<(Uuid, Uuid)>,
+    Json(req): Json<EditScheduledMessageRequest>,
+) -> Result<Json<ScheduledMessage>> {
+    state
+        .db
+        .get_conversation_routing(conversation_id, auth.user_id)
+        .await?
+        .ok_or(AppError::NotConversationMember)?;
+
+    let existing = state
+        .db
+        .get_scheduled_message(id)
+        .await?
+        .filter(|s| s.conversation_id == conversation_id && s.sender_id == auth.user_id)
+        .ok_or(AppError::NotFound("Scheduled message not found".into()))?;
+
+    let content = match (req.encrypted_content, req.signature) {
+        (Some(content), Some(signature)) => Some((content, signature)),
+        (None, None) => None,
+        _ => {
+            return Err(AppError::BadRequest(
+                "encrypted_content and signature must be updated together".into(),
+            ))
+        }
+    };
+
+    if let Some(send_at) = req.send_at {
+        validate_send_at(send_at, existing.expires_at)?;
+    }
+
+    let keys = match &req.message_keys {
+        Some(keys) => Some(validate_message_keys(&state, conversation_id, keys).await?),
+        None => None,
+    };
+
+    let scheduled = state
+        .db
+        .update_scheduled_message(id, auth.user_id, content, req.send_at, keys.as_deref())
+        .await?
+        .ok_or(AppError::NotFound("Scheduled message not found".into()))?;
+
+    Ok(Json(scheduled))
+}
+
+pub async fn cancel_scheduled_message(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Path((conversation_id, id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<SuccessResponse>> {
+    state
+        .db
+        .get_conversation_routing(conversation_id, auth.user_id)
+        .await?
+        .ok_or(AppError::NotConversationMember)?;
+
+    state
+        .db
+        .get_scheduled_message(id)
+        .await?
+        .filter(|s| s.conversation_id == conversation_id)
+        .ok_or(AppError::NotFound("Scheduled message not found".into()))?;
+
+    if !state.db.delete_scheduled_message(id, auth.user_id).await? {
+        return Err(AppError::NotFound("Scheduled message not found".into()));
+    }
+
+    Ok(Json(SuccessResponse { success: true }))
+}
@@ -0,0 +1,402 @@
+use axum::{
+    extract::State,
+    routing::{get, post},
+    Json, Router,
+};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::crypto;
+use crate::error::{AppError, Result};
+use crate::models::{DeviceInfo, TwoFactorStatus};
+use crate::totp;
+use crate::AppState;
+
+use super::auth::{start_session, LoginResponse};
+use super::middleware::AuthUser;
+
+const LOGIN_CHALLENGE_TTL_SECONDS: u64 = 300;
+const LOGIN_CHALLENGE_MAX_ATTEMPTS: u32 = 5;
+const SECOND_FACTOR_MAX_FAILURES: u32 = 10;
+const SECOND_FACTOR_FAILURE_WINDOW_SECONDS: u64 = 900;
+
+pub fn routes() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(get_status))
+        .route("/setup", post(setup))
+        .route("/enable", post(enable))
+        .route("/disable", post(disable))
+        .route("/backup-codes", post(regenerate_backup_codes))
+}
+
+fn totp_key(state: &AppState) -> Result<&[u8; 32]> {
+    state
+        .config
+        .auth
+        .totp_encryption_key
+        .as_ref()
+        .ok_or_else(|| {
+            AppError::BadRequest("two-factor authentication is not available on this server".into())
+        })
+}
+
+fn encrypt_secret(state: &AppState, secret: &[u8]) -> Result<Vec<u8>> {
+    Ok(confide_sdk::encrypt_aes_gcm(totp_key(state)?, secret)?)
+}
+
+fn decrypt_secret(state: &AppState, encrypted: &[u8]) -> Result<Vec<u8>> {
+    Ok(confide_sdk::decrypt_aes_gcm(totp_key(state)?, encrypted)?)
+}
+
+fn failures_key(user_id: Uuid) -> String {
+    format!("2fa_failures:{}", user_id)
+}
+
+/// Accepts either a current TOTP code or an unused backup code. Codes are
+/// consumed, so the same one never verifies twice. Wrong codes are counted
+/// per user across every caller, so guesses cannot be spread over fresh
+/// login challenges or other endpoints.
+pub async fn verify_second_factor(state: &AppState, user_id: Uuid, code: &str) -> Result<bool> {
+    let code = code.trim();
+    let Some(totp_row) = state.db.get_user_totp(user_id).await?.filter(|t| t.enabled) else {
+        return Ok(false);
+    };
+
+    let key = failures_key(user_id);
+    let mut conn = redis_conn(state).await?;
+    let failures: Option<u32> = redis::cmd("GET")
+        .arg(&key)
+        .query_async(&mut conn)
+        .await
+        .map_err(|e| AppError::Internal(anyhow::anyhow!("Redis error: {}", e)))?;
+    if failures.unwrap_or(0) >= SECOND_FACTOR_MAX_FAILURES {
+        return Err(AppError::TooManyRequests);
+    }
+
+    let verified = if totp::is_totp_code(code) {
+        let secret = decrypt_secret(state, &totp_row.encrypted_secret)?;
+        match totp::verify_code(&secret, code, chrono::Utc::now().timestamp()) {
+            Some(step) => state.db.consume_totp_step(user_id, step).await?,
+            None => false,
+        }
+    } else {
+        state
+            .db
+            .consume_backup_code(user_id, &totp::hash_backup_code(code))
+            .await?
+    };
+
+    let recorded: redis::RedisResult<()> = if verified {
+        redis::cmd("DEL").arg(&key).query_async(&mut conn).await
+    } else {
+        redis::pipe()
+            .cmd("INCR")
+            .arg(&key)
+            .ignore()
+            .cmd("EXPIRE")
+            .arg(&key)
+            .arg(SECOND_FACTOR_FAILURE_WINDOW_SECONDS)
+            .ignore()
+            .query_async(&mut conn)
+            .await
+    };
+    recorded.map_err(|e| AppError::Internal(anyhow::anyhow!("Redis error: {}", e)))?;
+
+    Ok(verified)
+}
+
+pub async fn get_status(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+) -> Result<Json<TwoFactorStatus>> {
+    let totp_row = state
+        .db
+        .get_user_totp(auth.user_id)
+        .await?
+        .filter(|t| t.enabled);
+
+    let backup_codes_remaining = match totp_row {
+        Some(_) => state.db.count_backup_codes(auth.user_id).await?,
+        None => 0,
+    };
+
+    Ok(Json(TwoFactorStatus {
+        available: state.config.auth.totp_encryption_key.is_some(),
+        enabled: totp_row.is_some(),
+        enabled_at: totp_row.and_then(|t| t.enabled_at),
+        backup_codes_remaining,
+    }))
+}
+
+#[derive(Debug, Serialize)]
+pub struct SetupResponse {
+    pub secret: String,
+    pub provisioning_uri: String,
+}
+
+pub async fn setup(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+) -> Result<Json<SetupResponse>> {
+    totp_key(&state)?;
+
+    let user = state
+        .db
+        .get_user_by_id(auth.user_id)
+        .await?
+        .ok_or(AppError::UserNotFound)?;
+
+    let secret = totp::generate_secret();
+    let stored = state
+        .db
+        .store_pending_totp(auth.user_id, encrypt_secret(&state, &secret)?)
+        .await?;
+    if !stored {
+        return Err(AppError::BadRequest(
+            "two-factor authentication is already enabled".into(),
+        ));
+    }
+
+    Ok(Json(SetupResponse {
+        secret: totp::encode_secret(&secret),
+        provisioning_uri: totp::provisioning_uri(
+            &state.config.auth.totp_issuer,
+            &user.username,
+            &secret,
+        ),
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CodeRequest {
+    pub code: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BackupCodesResponse {
+    pub backup_codes: Vec<String>,
+}
+
+pub async fn enable(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Json(req): Json<CodeRequest>,
+) -> Result<Json<BackupCodesResponse>> {
+    let totp_row = state
+        .db
+        .get_user_totp(auth.user_id)
+        .await?
+        .ok_or_else(|| AppError::BadRequest("two-factor setup has not been started".into()))?;
+    if totp_row.enabled {
+        return Err(AppError::BadRequest(
+            "two-factor authentication is already enabled".into(),
+        ));
+    }
+
+    let secret = decrypt_secret(&state, &totp_row.encrypted_secret)?;
+    let step = totp::verify_code(&secret, req.code.trim(), chrono::Utc::now().timestamp())
+        .ok_or(AppError::InvalidTwoFactorCode)?;
+
+    let backup_codes = totp::generate_backup_codes();
+    state
+        .db
+        .enable_totp(
+            auth.user_id,
+            step,
+            backup_codes
+                .iter()
+                .map(|c| totp::hash_backup_code(c))
+                .collect(),
+        )
+        .await?;
+
+    Ok(Json(BackupCodesResponse { backup_codes }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DisableRequest {
+    pub password: String,
+    pub code: String,
+}
+
+pub async fn disable(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Json(req): Json<DisableRequest>,
+) -> Result<Json<serde_json::Value>> {
+    let user = state
+        .db
+        .get_user_by_id(auth.user_id)
+        .await?
+        .ok_or(AppError::UserNotFound)?;
+
+    if !crypto::verify_password(&req.password, &user.password_hash)? {
+        return Err(AppError::InvalidCredentials);
+    }
+
+    if !verify_second_factor(&state, auth.user_id, &req.code).await? {
+        return Err(AppError::InvalidTwoFactorCode);
+    }
+
+    state.db.disable_totp(auth.user_id).await?;
+    Ok(Json(serde_json::json!({ "success": true })))
+}
+
+pub async fn regenerate_backup_codes(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Json(req): Json<CodeRequest>,
+) -> Result<Json<BackupCodesResponse>> {
+    if !verify_second_factor(&state, auth.user_id, &req.code).await? {
+        return Err(AppError::InvalidTwoFactorCode);
+    }
+
+    let backup_codes = totp::generate_backup_codes();
+    state
+        .db
+        .replace_backup_codes(
+            auth.user_id,
+            backup_codes
+                .iter()
+                .map(|c| totp::hash_backup_code(c))
+                .collect(),
+        )
+        .await?;
+
+    Ok(Json(BackupCodesResponse { backup_codes }))
+}
+
+/// A password-verified login waiting for its second factor.
+#[derive(Debug, Serialize, Deserialize)]
+struct PendingLogin {
+    user_id: Uuid,
+    device: DeviceInfo,
+    ip_prefix: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LoginChallenge {
+    pub two_factor_required: bool,
+    pub challenge: String,
+    pub expires_in: u64,
+}
+
+fn challenge_key(challenge: &str) -> String {
+    format!(
+        "login_2fa:{}",
+        hex::encode(Sha256::digest(challenge.as_bytes()))
+    )
+}
+
+async fn redis_conn(state: &AppState) -> Result<redis::aio::MultiplexedConnection> {
+    state
+        .redis
+        .get_multiplexed_async_connection()
+        .await
+        .map_err(|e| AppError::Internal(anyhow::anyhow!("Redis connection failed: {}", e)))
+}
+
+pub async fn create_login_challenge(
+    state: &AppState,
+    user_id: Uuid,
+    device: DeviceInfo,
+    ip_prefix: String,
+) -> Result<LoginChallenge> {
+    let mut challenge_bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut challenge_bytes);
+    let challenge = hex::encode(challenge_bytes);
+
+    let pending = serde_json::to_string(&PendingLogin {
+        user_id,
+        device,
+        ip_prefix,
+    })
+    .map_err(|e| AppError::Internal(e.into()))?;
+
+    let mut conn = redis_conn(state).await?;
+    redis::cmd("SET")
+        .arg(challenge_key(&challenge))
+        .arg(pending)
+        .arg("EX")
+        .arg(LOGIN_CHALLENGE_TTL_SECONDS)
+        .query_async::<()>(&mut conn)
+        .await
+        .map_err(|e| {
+            AppError::Internal(anyhow::anyhow!("Failed to store login challenge: {}", e))
+        })?;
+
+    Ok(LoginChallenge {
+        two_factor_required: true,
+        challenge,
+        expires_in: LOGIN_CHALLENGE_TTL_SECONDS,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CompleteLoginRequest {
+    pub challenge: String,
+    pub code: String,
+}
+
+pub async fn complete_login(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<CompleteLoginRequest>,
+) -> Result<Json<LoginResponse>> {
+    let key = challenge_key(&req.challenge);
+    let attempts_key = format!("{}:attempts", key);
+    let mut conn = redis_conn(&state).await?;
+
+    let pending: Option<String> = redis::cmd("GET")
+        .arg(&key)
+        .query_async(&mut conn)
+        .await
+        .map_err(|e| AppError::Internal(anyhow::anyhow!("Redis error: {}", e)))?;
+    let pending: PendingLogin = pending
+        .and_then(|p| serde_json::from_str(&p).ok())
+        .ok_or(AppError::Unauthorized)?;
+
+    let attempts: u32 = redis::pipe()
+        .cmd("INCR")
+        .arg(&attempts_key)
+        .cmd("EXPIRE")
+        .arg(&attempts_key)
+        .arg(LOGIN_CHALLENGE_TTL_SECONDS)
+        .ignore()
+        .query_async::<(u32,)>(&mut conn)
+        .await
+        .map(|(n,)| n)
+        .map_err(|e| AppError::Internal(anyhow::anyhow!("Redis error: {}", e)))?;
+    if attempts > LOGIN_CHALLENGE_MAX_ATTEMPTS {
+        let _: redis::RedisResult<()> = redis::cmd("DEL")
+            .arg(&key)
+            .arg(&attempts_key)
+            .query_async(&mut conn)
+            .await;
+        return Err(AppError::TooManyRequests);
+    }
+
+    if !verify_second_factor(&state, pending.user_id, &req.code).await? {
+        return Err(AppError::InvalidTwoFactorCode);
+    }
+
+    let claimed: Option<String> = redis::cmd("GETDEL")
+        .arg(&key)
+        .query_async(&mut conn)
+        .await
+        .map_err(|e| AppError::Internal(anyhow::anyhow!("Redis error: {}", e)))?;
+    if claimed.is_none() {
+        return Err(AppError::Unauthorized);
+    }
+
+    let user = state
+        .db
+        .get_user_by_id(pending.user_id)
+        .await?
+        .ok_or(AppError::UserNotFound)?;
+
+    let response = start_session(&state, user, &pending.device, pending.ip_prefix).await?;
+    Ok(Json(response))
+}
@@ -9,8 +9,10 @@ pub struct Config {
     pub auth: AuthConfig,
     pub messages: MessagesConfig,
     pub crypto: CryptoConfig,
+    pub prekeys: PrekeysConfig,
     pub websocket: WebSocketConfig,
     pub uploads: UploadsConfig,
+    pub exports: ExportsConfig,
     #[serde(default)]
     pub calls: CallsConfig,
     pub s3: S3Config,
@@ -21,6 +23,10 @@ pub struct ServerConfig {
     pub host: String,
     pub port: u16,
     pub allowed_origins: Vec<String>,
+    /// Number of reverse proxies in front of the server. Each appends the
+    /// address it received the request from to `X-Forwarded-For`, so only
+    /// that many hops from the right can be trusted. Zero ignores the header.
+    pub trusted_proxy_hops: usize,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -74,14 +80,23 @@ pub struct RedisConfig {
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct AuthConfig {
-    pub token_expiry_hours: u64,
+    pub access_token_expiry_minutes: u64,
+    pub refresh_token_expiry_days: u64,
     pub password_min_length: usize,
+    /// AES-256-GCM key protecting TOTP secrets at rest. Two-factor
+    /// enrollment is turned off while it is unset.
+    pub totp_encryption_key: Option<[u8; 32]>,
+    pub totp_issuer: String,
+    pub username_change_cooldown_days: u32,
+    /// How long a released username stays reserved for its previous owner.
+    pub username_reservation_days: u32,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct MessagesConfig {
     pub ttl_days: u32,
     pub cleanup_interval_hours: u64,
+    pub expired_cleanup_interval_seconds: u64,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -101,9 +116,30 @@ impl CryptoConfig {
     }
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct PrekeysConfig {
+    pub low_water_mark: i64,
+    pub signed_prekey_max_age_days: i64,
+    pub warning_cooldown_minutes: u64,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct WebSocketConfig {
     pub message_buffer_size: usize,
+    #[serde(default = "default_replay_max_events")]
+    pub replay_max_events: usize,
+    #[serde(default = "default_replay_ttl_hours")]
+    pub replay_ttl_hours: u64,
+    #[serde(default)]
+    pub cluster_enabled: bool,
+}
+
+fn default_replay_max_events() -> usize {
+    1000
+}
+
+fn default_replay_ttl_hours() -> u64 {
+    72
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -113,6 +149,16 @@ pub struct UploadsConfig {
     pub retention_days: u32,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExportsConfig {
+    /// Minimum time between two export requests from the same user.
+    pub cooldown_hours: u64,
+    /// How long a finished archive stays available for download.
+    pub retention_hours: u64,
+    /// Archives are built one per permit; further exports wait their turn.
+    pub max_concurrent: usize,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct S3Config {
     pub endpoint: String,
@@ -242,6 +288,10 @@ impl Config {
                 .split(',')
                 .map(|s| s.trim().to_string())
                 .collect(),
+            trusted_proxy_hops: env::var("TRUSTED_PROXY_HOPS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0),
         };
 
         let database = DatabaseConfig {
@@ -281,14 +331,28 @@ impl Config {
         };
 
         let auth = AuthConfig {
-            token_expiry_hours: env::var("AUTH_TOKEN_EXPIRY_HOURS")
+            access_token_expiry_minutes: env::var("AUTH_ACCESS_TOKEN_EXPIRY_MINUTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(15),
+            refresh_token_expiry_days: env::var("AUTH_REFRESH_TOKEN_EXPIRY_DAYS")
                 .ok()
                 .and_then(|v| v.parse().ok())
-                .unwrap_or(168),
+                .unwrap_or(30),
             password_min_length: env::var("AUTH_PASSWORD_MIN_LENGTH")
                 .ok()
                 .and_then(|v| v.parse().ok())
                 .unwrap_or(8),
+            totp_encryption_key: load_totp_encryption_key()?,
+            totp_issuer: env::var("AUTH_TOTP_ISSUER").unwrap_or_else(|_| "Confide".to_string()),
+            username_change_cooldown_days: env::var("AUTH_USERNAME_CHANGE_COOLDOWN_DAYS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(30),
+            username_reservation_days: env::var("AUTH_USERNAME_RESERVATION_DAYS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(14),
         };
 
         let messages = MessagesConfig {
@@ -300,6 +364,10 @@ impl Config {
                 .ok()
                 .and_then(|v| v.parse().ok())
                 .unwrap_or(24),
+            expired_cleanup_interval_seconds: env::var("MESSAGES_EXPIRED_CLEANUP_INTERVAL_SECONDS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(60),
         };
 
         let crypto = CryptoConfig {
@@ -317,11 +385,38 @@ impl Config {
                 .unwrap_or(4),
         };
 
+        let prekeys = PrekeysConfig {
+            low_water_mark: env::var("PREKEYS_LOW_WATER_MARK")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(20),
+            signed_prekey_max_age_days: env::var("PREKEYS_SIGNED_MAX_AGE_DAYS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(30),
+            warning_cooldown_minutes: env::var("PREKEYS_WARNING_COOLDOWN_MINUTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(15),
+        };
+
         let websocket = WebSocketConfig {
             message_buffer_size: env::var("WEBSOCKET_MESSAGE_BUFFER_SIZE")
                 .ok()
                 .and_then(|v| v.parse().ok())
                 .unwrap_or(128),
+            replay_max_events: env::var("WEBSOCKET_REPLAY_MAX_EVENTS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or_else(default_replay_max_events),
+            replay_ttl_hours: env::var("WEBSOCKET_REPLAY_TTL_HOURS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or_else(default_replay_ttl_hours),
+            cluster_enabled: env::var("WEBSOCKET_CLUSTER_ENABLED")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(false),
         };
 
         let uploads = UploadsConfig {
@@ -339,6 +434,21 @@ impl Config {
                 .unwrap_or(90),
         };
 
+        let exports = ExportsConfig {
+            cooldown_hours: env::var("EXPORTS_COOLDOWN_HOURS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(24),
+            retention_hours: env::var("EXPORTS_RETENTION_HOURS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(72),
+            max_concurrent: env::var("EXPORTS_MAX_CONCURRENT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(2),
+        };
+
         let s3 = S3Config {
             endpoint: env::var("S3_ENDPOINT")?,
             region: env::var("S3_REGION")?,
@@ -395,8 +505,10 @@ impl Config {
             auth,
             messages,
             crypto,
+            prekeys,
             websocket,
             uploads,
+            exports,
             calls,
             s3,
         })
@@ -408,3 +520,26 @@ impl Config {
         Self::load_from_env()
     }
 }
+
+fn load_totp_encryption_key() -> anyhow::Result<Option<[u8; 32]>> {
+    let Some(key_hex) = env::var("AUTH_TOTP_ENCRYPTION_KEY")
+        .ok()
+        .filter(|k| !k.is_empty())
+    else {
+        tracing::warn!("AUTH_TOTP_ENCRYPTION_KEY not set, two-factor enrollment is disabled");
+        return Ok(None);
+    };
+
+    let key_bytes = hex::decode(&key_hex)
+        .ok()
+        .filter(|b| b.len() == 32)
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "AUTH_TOTP_ENCRYPTION_KEY must be exactly 64 hexadecimal characters (32 bytes)"
+            )
+        })?;
+
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&key_bytes);
+    Ok(Some(key))
+}
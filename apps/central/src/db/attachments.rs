@@ -21,7 +21,7 @@ pub struct AttachmentRecord {
 impl Database {
     #[allow(clippy::too_many_arguments)]
     pub async fn save_attachment(
-        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         message_id: Uuid,
         conversation_id: Uuid,
         uploader_id: Uuid,
@@ -48,7 +48,7 @@ impl Database {
         .bind(encrypted_size)
         .bind(mime_type)
         .bind(expires_at)
-        .fetch_one(&self.pool)
+        .fetch_one(&mut **tx)
         .await?;
 
         Ok(id)
@@ -77,7 +77,6 @@ where
     Ok(value)
 }
 
-#[allow(dead_code)]
 pub async fn invalidate_cache(redis: &Client, pattern: &str) -> Result<()> {
     let mut conn = redis.get_multiplexed_async_connection().await?;
     let _: () = redis::cmd("DEL")
@@ -7,6 +7,7 @@ use crate::AppState;
 
 const CALL_CLEANUP_INTERVAL_SECS: u64 = 30;
 const CALL_REJOIN_WINDOW_SECS: i64 = 300;
+const EXPORT_CLEANUP_INTERVAL_SECS: u64 = 15 * 60;
 
 pub async fn run_cleanup_task(state: Arc<AppState>) {
     let interval_hours = state.config.messages.cleanup_interval_hours;
@@ -27,17 +28,6 @@ pub async fn run_cleanup_task(state: Arc<AppState>) {
             }
         }
 
-        match state.db.delete_expired_messages().await {
-            Ok(count) => {
-                if count > 0 {
-                    tracing::info!("cleaned up {} expired messages", count);
-                }
-            }
-            Err(e) => {
-                tracing::error!("failed to clean up expired messages: {:?}", e);
-            }
-        }
-
         match state.db.delete_expired_sessions().await {
             Ok(count) => {
                 if count > 0 {
@@ -72,6 +62,63 @@ pub async fn run_cleanup_task(state: Arc<AppState>) {
     }
 }
 
+pub async fn run_expired_message_cleanup_task(state: Arc<AppState>) {
+    let interval_secs = state.config.messages.expired_cleanup_interval_seconds;
+    let mut interval = interval(Duration::from_secs(interval_secs.max(1)));
+
+    loop {
+        interval.tick().await;
+
+        match state.db.delete_expired_messages().await {
+            Ok((count, s3_keys)) => {
+                if count > 0 {
+                    tracing::info!("cleaned up {} expired messages", count);
+                }
+                for s3_key in s3_keys {
+                    if let Err(e) = state.s3.delete_file(&s3_key).await {
+                        tracing::error!(
+                            "failed to delete expired attachment from S3 {}: {:?}",
+                            s3_key,
+                            e
+                        );
+                    }
+                }
+            }
+            Err(e) => {
+                tracing::error!("failed to clean up expired messages: {:?}", e);
+            }
+        }
+    }
+}
+
+pub async fn run_export_cleanup_task(state: Arc<AppState>) {
+    let mut interval = interval(Duration::from_secs(EXPORT_CLEANUP_INTERVAL_SECS));
+
+    loop {
+        interval.tick().await;
+
+        match state.db.cleanup_data_exports().await {
+            Ok(s3_keys) => {
+                if !s3_keys.is_empty() {
+                    tracing::info!("cleaned up {} expired data exports", s3_keys.len());
+                }
+                for s3_key in s3_keys {
+                    if let Err(e) = state.s3.delete_file(&s3_key).await {
+                        tracing::error!(
+                            "failed to delete expired data export from S3 {}: {:?}",
+                            s3_key,
+                            e
+                        );
+                    }
+                }
+            }
+            Err(e) => {
+                tracing::error!("failed to clean up data exports: {:?}", e);
+            }
+        }
+    }
+}
+
 pub async fn run_call_cleanup_task(state: Arc<AppState>) {
     let mut interval = interval(Duration::from_secs(CALL_CLEANUP_INTERVAL_SECS));
 
@@ -170,6 +170,21 @@ impl Database {
         .map_err(AppError::Internal)
     }
 
+    /// Every group the user belongs to, hidden ones included.
+    pub async fn get_user_groups(&self, user_id: Uuid) -> Result<Vec<Conversation>> {
+        let groups = sqlx::query_as::<_, Conversation>(
+            r#"
+            SELECT c.* FROM conversations c
+            JOIN conversation_members cm ON cm.conversation_id = c.id
+            WHERE cm.user_id = $1 AND c.conversation_type = 'group'
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(groups)
+    }
+
     pub async fn hide_conversation(&self, conversation_id: Uuid, user_id: Uuid) -> Result<()> {
         sqlx::query(
             "UPDATE conversation_members SET hidden = TRUE WHERE conversation_id = $1 AND user_id = $2",
@@ -230,6 +245,31 @@ impl Database {
         Ok(())
     }
 
+    /// Turns edit history on or off. Turning it off only stops new revisions
+    /// from being recorded; the ones already stored stay, since the other
+    /// participants may have relied on them.
+    pub async fn set_keep_edit_history(&self, conversation_id: Uuid, enabled: bool) -> Result<()> {
+        sqlx::query("UPDATE conversations SET keep_edit_history = $1 WHERE id = $2")
+            .bind(enabled)
+            .bind(conversation_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn set_message_ttl(
+        &self,
+        conversation_id: Uuid,
+        ttl_seconds: Option<i32>,
+    ) -> Result<()> {
+        sqlx::query("UPDATE conversations SET message_ttl_seconds = $1 WHERE id = $2")
+            .bind(ttl_seconds)
+            .bind(conversation_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
     pub async fn update_conversation_owner(
         &self,
         conversation_id: Uuid,
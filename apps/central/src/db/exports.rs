@@ -0,0 +1,217 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::error::{AppError, Result};
+use crate::models::{
+    Call, ConversationWithRouting, DataExport, ExportedAttachment, ExportedMessage, Upload,
+};
+
+use super::Database;
+
+/// Exports still being built this long after they started were interrupted
+/// and are failed.
+const STALE_EXPORT_MINUTES: i64 = 60;
+/// Exports that never left the queue were lost with the process that held
+/// them.
+const STALE_QUEUED_EXPORT_HOURS: i64 = 24;
+
+impl Database {
+    /// Starts a new export. Returns None while another export for the user
+    /// is still being built.
+    pub async fn create_data_export(&self, user_id: Uuid) -> Result<Option<DataExport>> {
+        let export = sqlx::query_as::<_, DataExport>(
+            r#"
+            INSERT INTO data_exports (user_id)
+            VALUES ($1)
+            ON CONFLICT (user_id) WHERE status = 'pending' DO NOTHING
+            RETURNING *
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(export)
+    }
+
+    pub async fn get_latest_data_export(&self, user_id: Uuid) -> Result<Option<DataExport>> {
+        let export = sqlx::query_as::<_, DataExport>(
+            "SELECT * FROM data_exports WHERE user_id = $1 ORDER BY created_at DESC LIMIT 1",
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(export)
+    }
+
+    pub async fn get_data_export(&self, id: Uuid, user_id: Uuid) -> Result<Option<DataExport>> {
+        let export = sqlx::query_as::<_, DataExport>(
+            "SELECT * FROM data_exports WHERE id = $1 AND user_id = $2",
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(export)
+    }
+
+    /// Marks a queued export as being built. Returns false when it was
+    /// failed while it waited.
+    pub async fn start_data_export(&self, id: Uuid) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE data_exports SET started_at = NOW() WHERE id = $1 AND status = 'pending'",
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn complete_data_export(
+        &self,
+        id: Uuid,
+        s3_key: &str,
+        size_bytes: i64,
+        expires_at: DateTime<Utc>,
+    ) -> Result<DataExport> {
+        let export = sqlx::query_as::<_, DataExport>(
+            r#"
+            UPDATE data_exports
+            SET status = 'ready', s3_key = $2, size_bytes = $3,
+                completed_at = NOW(), expires_at = $4
+            WHERE id = $1 AND status = 'pending'
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(s3_key)
+        .bind(size_bytes)
+        .bind(expires_at)
+        .fetch_optional(&self.pool)
+        .await?;
+        export.ok_or(AppError::NotFound(
+            "Data export is no longer pending".into(),
+        ))
+    }
+
+    pub async fn fail_data_export(&self, id: Uuid) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE data_exports SET status = 'failed', completed_at = NOW()
+            WHERE id = $1 AND status = 'pending'
+            "#,
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Removes expired exports and fails ones that never finished. Returns
+    /// the S3 keys of the archives that were dropped.
+    pub async fn cleanup_data_exports(&self) -> Result<Vec<String>> {
+        sqlx::query(
+            r#"
+            UPDATE data_exports SET status = 'failed', completed_at = NOW()
+            WHERE status = 'pending'
+              AND (started_at < NOW() - make_interval(mins => $1)
+                   OR (started_at IS NULL AND created_at < NOW() - make_interval(hours => $2)))
+            "#,
+        )
+        .bind(STALE_EXPORT_MINUTES as i32)
+        .bind(STALE_QUEUED_EXPORT_HOURS as i32)
+        .execute(&self.pool)
+        .await?;
+
+        let keys: Vec<Option<String>> = sqlx::query_scalar(
+            "DELETE FROM data_exports WHERE expires_at <= NOW() RETURNING s3_key",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(keys.into_iter().flatten().collect())
+    }
+
+    /// Every conversation the user belongs to, hidden ones included.
+    pub async fn get_export_conversations(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Vec<ConversationWithRouting>> {
+        let rows = sqlx::query_as::<_, ConversationWithRouting>(
+            r#"
+            SELECT c.id, c.conversation_type, c.encrypted_metadata, c.owner_id, c.created_at,
+                   cm.encrypted_sender_key, cm.encrypted_role
+            FROM conversations c
+            JOIN conversation_members cm ON c.id = cm.conversation_id
+            WHERE cm.user_id = $1
+            ORDER BY c.created_at
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows)
+    }
+
+    pub async fn get_export_messages(&self, user_id: Uuid) -> Result<Vec<ExportedMessage>> {
+        let messages = sqlx::query_as::<_, ExportedMessage>(
+            r#"
+            SELECT m.id, m.conversation_id, m.encrypted_content, m.signature, mk.encrypted_key,
+                   m.reply_to_id, m.thread_root_id, m.message_type, m.sender_chain_id,
+                   m.sender_chain_iteration, m.edited_at, m.created_at
+            FROM messages m
+            LEFT JOIN message_keys mk ON mk.message_id = m.id AND mk.user_id = m.sender_id
+            WHERE m.sender_id = $1
+            ORDER BY m.created_at
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(messages)
+    }
+
+    pub async fn get_export_attachments(&self, user_id: Uuid) -> Result<Vec<ExportedAttachment>> {
+        let attachments = sqlx::query_as::<_, ExportedAttachment>(
+            r#"
+            SELECT id, message_id, conversation_id, s3_key, file_size_bytes,
+                   encrypted_size_bytes, mime_type, created_at
+            FROM file_attachments
+            WHERE uploader_id = $1
+            ORDER BY created_at
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(attachments)
+    }
+
+    pub async fn get_export_uploads(&self, user_id: Uuid) -> Result<Vec<Upload>> {
+        let uploads = sqlx::query_as::<_, Upload>(
+            r#"
+            SELECT id, user_id, file_type, content_type, s3_key, file_size, created_at
+            FROM uploads
+            WHERE user_id = $1 AND s3_key IS NOT NULL
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(uploads)
+    }
+
+    /// Direct and group calls the user took part in.
+    pub async fn get_export_calls(&self, user_id: Uuid) -> Result<Vec<Call>> {
+        let calls = sqlx::query_as::<_, Call>(
+            r#"
+            SELECT * FROM calls
+            WHERE caller_id = $1 OR callee_id = $1 OR initiator_id = $1
+            OR id IN (SELECT call_id FROM group_call_participants WHERE user_id = $1)
+            ORDER BY created_at
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(calls)
+    }
+}
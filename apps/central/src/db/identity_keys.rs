@@ -0,0 +1,171 @@
+use uuid::Uuid;
+
+use crate::error::{AppError, Result};
+use crate::models::{IdentityKeyRecord, UserKeys, UserVerifiedContacts};
+
+use super::{cache, Database};
+
+impl Database {
+    pub async fn get_identity_key_chain(&self, user_id: Uuid) -> Result<Vec<IdentityKeyRecord>> {
+        let chain = sqlx::query_as::<_, IdentityKeyRecord>(
+            r#"
+            SELECT user_id, version, kem_public_key, dsa_public_key, continuity_signature, created_at
+            FROM identity_key_history
+            WHERE user_id = $1
+            ORDER BY version
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(chain)
+    }
+
+    pub async fn get_current_identity_key(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Option<IdentityKeyRecord>> {
+        let key = sqlx::query_as::<_, IdentityKeyRecord>(
+            r#"
+            SELECT user_id, version, kem_public_key, dsa_public_key, continuity_signature, created_at
+            FROM identity_key_history
+            WHERE user_id = $1
+            ORDER BY version DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(key)
+    }
+
+    pub(super) async fn insert_genesis_identity_key(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: Uuid,
+        kem_public_key: &[u8],
+        dsa_public_key: &[u8],
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO identity_key_history (user_id, version, kem_public_key, dsa_public_key)
+            VALUES ($1, 1, $2, $3)
+            "#,
+        )
+        .bind(user_id)
+        .bind(kem_public_key)
+        .bind(dsa_public_key)
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
+    /// Replaces the user's identity keys with `version` of the chain. Recovery
+    /// data and prekeys were bound to the old keys, so they are dropped and
+    /// must be set up again.
+    pub async fn rotate_identity_keys(
+        &self,
+        user_id: Uuid,
+        version: i32,
+        keys: UserKeys,
+        continuity_signature: Vec<u8>,
+    ) -> Result<IdentityKeyRecord> {
+        let mut tx = self.pool.begin().await?;
+
+        let record = sqlx::query_as::<_, IdentityKeyRecord>(
+            r#"
+            INSERT INTO identity_key_history
+                (user_id, version, kem_public_key, dsa_public_key, continuity_signature)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING user_id, version, kem_public_key, dsa_public_key, continuity_signature,
+                      created_at
+            "#,
+        )
+        .bind(user_id)
+        .bind(version)
+        .bind(&keys.kem_public_key)
+        .bind(&keys.dsa_public_key)
+        .bind(continuity_signature)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(ref db_err) if db_err.is_unique_violation() => {
+                AppError::BadRequest("identity keys were rotated concurrently".into())
+            }
+            _ => AppError::Database(e),
+        })?;
+
+        sqlx::query(
+            r#"
+            UPDATE users SET
+                kem_public_key = $2,
+                kem_encrypted_private = $3,
+                dsa_public_key = $4,
+                dsa_encrypted_private = $5,
+                key_salt = $6,
+                recovery_kem_encrypted_private = NULL,
+                recovery_dsa_encrypted_private = NULL,
+                recovery_key_salt = NULL,
+                recovery_setup_completed = FALSE
+            WHERE id = $1
+            "#,
+        )
+        .bind(user_id)
+        .bind(&keys.kem_public_key)
+        .bind(&keys.kem_encrypted_private)
+        .bind(&keys.dsa_public_key)
+        .bind(&keys.dsa_encrypted_private)
+        .bind(&keys.key_salt)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("DELETE FROM user_prekeys WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM one_time_prekeys WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        cache::invalidate_cache(&self.redis, &format!("user:public:{}", user_id))
+            .await
+            .ok();
+        Ok(record)
+    }
+
+    pub async fn get_verified_contacts(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Option<UserVerifiedContacts>> {
+        let contacts = sqlx::query_as::<_, UserVerifiedContacts>(
+            "SELECT * FROM user_verified_contacts WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(contacts)
+    }
+
+    pub async fn update_verified_contacts(
+        &self,
+        user_id: Uuid,
+        encrypted_verified_contacts: Vec<u8>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO user_verified_contacts (user_id, encrypted_verified_contacts, updated_at)
+            VALUES ($1, $2, NOW())
+            ON CONFLICT (user_id) DO UPDATE SET
+                encrypted_verified_contacts = $2, updated_at = NOW()
+            "#,
+        )
+        .bind(user_id)
+        .bind(encrypted_verified_contacts)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::error::Result;
+
+use super::Database;
+
+impl Database {
+    pub async fn store_mentions(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        message_id: Uuid,
+        conversation_id: Uuid,
+        user_ids: &[Uuid],
+    ) -> Result<()> {
+        if user_ids.is_empty() {
+            return Ok(());
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO message_mentions (message_id, user_id, conversation_id)
+            SELECT $1, unnest($2::uuid[]), $3
+            ON CONFLICT (message_id, user_id) DO NOTHING
+            "#,
+        )
+        .bind(message_id)
+        .bind(user_ids)
+        .bind(conversation_id)
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn get_mentions_for_messages(
+        &self,
+        message_ids: &[Uuid],
+    ) -> Result<HashMap<Uuid, Vec<Uuid>>> {
+        if message_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let rows = sqlx::query_as::<_, (Uuid, Uuid)>(
+            "SELECT message_id, user_id FROM message_mentions WHERE message_id = ANY($1)",
+        )
+        .bind(message_ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut mentions: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+        for (message_id, user_id) in rows {
+            mentions.entry(message_id).or_default().push(user_id);
+        }
+        Ok(mentions)
+    }
+}
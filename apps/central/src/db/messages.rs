@@ -1,11 +1,51 @@
 use chrono::{DateTime, Duration, Utc};
 use uuid::Uuid;
 
-use crate::error::Result;
-use crate::models::{Message, MessageKey, MessageReaction, MessageRow};
+use crate::error::{AppError, Result};
+use crate::models::{Message, MessageKey, MessageReaction, MessageRevision, MessageRow};
 
 use super::Database;
 
+#[derive(Debug, Clone, Copy)]
+pub enum MessageCursor {
+    Latest,
+    Before(Uuid),
+    After(Uuid),
+    Around(Uuid),
+}
+
+/// A window of the conversation timeline, newest first.
+pub struct MessagePage {
+    pub messages: Vec<Message>,
+    pub has_more_before: bool,
+    pub has_more_after: bool,
+}
+
+pub struct NewAttachment<'a> {
+    pub s3_key: &'a str,
+    pub file_size: i64,
+    pub encrypted_size: i64,
+    pub mime_type: &'a str,
+}
+
+/// Rows written in the same transaction as a new message. Keys and mentions
+/// must already be checked against the conversation's members.
+#[derive(Default)]
+pub struct MessageExtras<'a> {
+    pub keys: &'a [(Uuid, Vec<u8>)],
+    pub attachment: Option<NewAttachment<'a>>,
+    pub mentions: &'a [Uuid],
+    /// The scheduled message being sent, removed together with the insert so
+    /// it cannot be sent twice.
+    pub scheduled_message_id: Option<Uuid>,
+}
+
+enum Direction {
+    Older,
+    OlderInclusive,
+    Newer,
+}
+
 impl Database {
     #[allow(clippy::too_many_arguments)]
     pub async fn create_message(
@@ -18,11 +58,15 @@ impl Database {
         expires_at: Option<DateTime<Utc>>,
         sender_chain_id: Option<i32>,
         sender_chain_iteration: Option<i32>,
+        thread_root_id: Option<Uuid>,
+        extras: MessageExtras<'_>,
     ) -> Result<Message> {
+        let mut tx = self.pool.begin().await?;
+
         let row = sqlx::query_as::<_, MessageRow>(
             r#"
-            INSERT INTO messages (conversation_id, sender_id, encrypted_content, signature, reply_to_id, expires_at, sender_chain_id, sender_chain_iteration)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            INSERT INTO messages (conversation_id, sender_id, encrypted_content, signature, reply_to_id, expires_at, sender_chain_id, sender_chain_iteration, thread_root_id)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
             RETURNING *
             "#,
         )
@@ -34,9 +78,39 @@ impl Database {
         .bind(expires_at)
         .bind(sender_chain_id)
         .bind(sender_chain_iteration)
-        .fetch_one(&self.pool)
+        .bind(thread_root_id)
+        .fetch_one(&mut *tx)
         .await?;
 
+        if let Some(scheduled_id) = extras.scheduled_message_id {
+            let removed = sqlx::query("DELETE FROM scheduled_messages WHERE id = $1")
+                .bind(scheduled_id)
+                .execute(&mut *tx)
+                .await?;
+            if removed.rows_affected() == 0 {
+                return Err(AppError::NotFound("Scheduled message not found".into()));
+            }
+        }
+
+        Self::insert_message_keys(&mut tx, row.id, extras.keys).await?;
+        if let Some(attachment) = extras.attachment {
+            Self::save_attachment(
+                &mut tx,
+                row.id,
+                conversation_id,
+                sender_id,
+                attachment.s3_key,
+                attachment.file_size,
+                attachment.encrypted_size,
+                attachment.mime_type,
+                expires_at,
+            )
+            .await?;
+        }
+        Self::store_mentions(&mut tx, row.id, conversation_id, extras.mentions).await?;
+
+        tx.commit().await?;
+
         Ok(Message {
             id: row.id,
             conversation_id: row.conversation_id,
@@ -52,6 +126,7 @@ impl Database {
             call_id: row.call_id,
             call_duration_seconds: row.call_duration_seconds,
             pinned_at: row.pinned_at,
+            thread_root_id: row.thread_root_id,
             reactions: vec![],
             created_at: row.created_at,
         })
@@ -97,6 +172,7 @@ impl Database {
             call_id: row.call_id,
             call_duration_seconds: row.call_duration_seconds,
             pinned_at: row.pinned_at,
+            thread_root_id: row.thread_root_id,
             reactions: vec![],
             created_at: row.created_at,
         })
@@ -140,6 +216,7 @@ impl Database {
             call_id: row.call_id,
             call_duration_seconds: row.call_duration_seconds,
             pinned_at: row.pinned_at,
+            thread_root_id: row.thread_root_id,
             reactions: vec![],
             created_at: row.created_at,
         })
@@ -151,6 +228,26 @@ impl Database {
         sender_id: Uuid,
         message_type: &str,
         encrypted_content: Vec<u8>,
+    ) -> Result<Message> {
+        let mut tx = self.pool.begin().await?;
+        let message = Self::insert_system_message(
+            &mut tx,
+            conversation_id,
+            sender_id,
+            message_type,
+            encrypted_content,
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(message)
+    }
+
+    pub(super) async fn insert_system_message(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        conversation_id: Uuid,
+        sender_id: Uuid,
+        message_type: &str,
+        encrypted_content: Vec<u8>,
     ) -> Result<Message> {
         let row = sqlx::query_as::<_, MessageRow>(
             r#"
@@ -164,7 +261,7 @@ impl Database {
         .bind(encrypted_content)
         .bind(Vec::<u8>::new())
         .bind(message_type)
-        .fetch_one(&self.pool)
+        .fetch_one(&mut **tx)
         .await?;
 
         Ok(Message {
@@ -182,6 +279,7 @@ impl Database {
             call_id: row.call_id,
             call_duration_seconds: row.call_duration_seconds,
             pinned_at: row.pinned_at,
+            thread_root_id: row.thread_root_id,
             reactions: vec![],
             created_at: row.created_at,
         })
@@ -202,20 +300,130 @@ impl Database {
         &self,
         conversation_id: Uuid,
         limit: i64,
+        cursor: MessageCursor,
+    ) -> Result<MessagePage> {
+        let (mut older, has_more_before, mut newer, has_more_after) = match cursor {
+            MessageCursor::Latest => {
+                let (rows, more) = self
+                    .fetch_timeline(conversation_id, None, Direction::Older, limit)
+                    .await?;
+                (rows, more, Vec::new(), false)
+            }
+            MessageCursor::Before(anchor) => {
+                let (rows, more) = self
+                    .fetch_timeline(conversation_id, Some(anchor), Direction::Older, limit)
+                    .await?;
+                (rows, more, Vec::new(), true)
+            }
+            MessageCursor::After(anchor) => {
+                let (rows, more) = self
+                    .fetch_timeline(conversation_id, Some(anchor), Direction::Newer, limit)
+                    .await?;
+                (Vec::new(), true, rows, more)
+            }
+            MessageCursor::Around(anchor) => {
+                let newer_limit = limit / 2;
+                let (older, older_more) = self
+                    .fetch_timeline(
+                        conversation_id,
+                        Some(anchor),
+                        Direction::OlderInclusive,
+                        limit - newer_limit,
+                    )
+                    .await?;
+                let (newer, newer_more) = self
+                    .fetch_timeline(conversation_id, Some(anchor), Direction::Newer, newer_limit)
+                    .await?;
+                (older, older_more, newer, newer_more)
+            }
+        };
+
+        newer.reverse();
+        newer.append(&mut older);
+
+        Ok(MessagePage {
+            messages: self.attach_reactions(newer).await?,
+            has_more_before,
+            has_more_after,
+        })
+    }
+
+    /// Fetches up to `limit` timeline rows on one side of `anchor`, ordered
+    /// away from it, plus whether more rows exist beyond the last one.
+    async fn fetch_timeline(
+        &self,
+        conversation_id: Uuid,
+        anchor: Option<Uuid>,
+        direction: Direction,
+        limit: i64,
+    ) -> Result<(Vec<MessageRow>, bool)> {
+        if limit <= 0 {
+            return Ok((Vec::new(), false));
+        }
+
+        let (cmp, order) = match direction {
+            Direction::Older => ("<", "DESC"),
+            Direction::OlderInclusive => ("<=", "DESC"),
+            Direction::Newer => (">", "ASC"),
+        };
+
+        let mut rows = if let Some(anchor_id) = anchor {
+            sqlx::query_as::<_, MessageRow>(&format!(
+                r#"
+                SELECT * FROM messages
+                WHERE conversation_id = $1
+                AND thread_root_id IS NULL
+                AND (expires_at IS NULL OR expires_at > NOW())
+                AND created_at {cmp} (SELECT created_at FROM messages WHERE id = $2)
+                ORDER BY created_at {order}
+                LIMIT $3
+                "#,
+            ))
+            .bind(conversation_id)
+            .bind(anchor_id)
+            .bind(limit + 1)
+            .fetch_all(&self.pool)
+            .await?
+        } else {
+            sqlx::query_as::<_, MessageRow>(&format!(
+                r#"
+                SELECT * FROM messages
+                WHERE conversation_id = $1
+                AND thread_root_id IS NULL
+                AND (expires_at IS NULL OR expires_at > NOW())
+                ORDER BY created_at {order}
+                LIMIT $2
+                "#,
+            ))
+            .bind(conversation_id)
+            .bind(limit + 1)
+            .fetch_all(&self.pool)
+            .await?
+        };
+
+        let has_more = rows.len() as i64 > limit;
+        rows.truncate(limit as usize);
+        Ok((rows, has_more))
+    }
+
+    pub async fn get_thread_replies(
+        &self,
+        root_message_id: Uuid,
+        limit: i64,
         before: Option<Uuid>,
     ) -> Result<Vec<Message>> {
         let rows = if let Some(before_id) = before {
             sqlx::query_as::<_, MessageRow>(
                 r#"
                 SELECT * FROM messages
-                WHERE conversation_id = $1
+                WHERE thread_root_id = $1
                 AND (expires_at IS NULL OR expires_at > NOW())
                 AND created_at < (SELECT created_at FROM messages WHERE id = $2)
                 ORDER BY created_at DESC
                 LIMIT $3
                 "#,
             )
-            .bind(conversation_id)
+            .bind(root_message_id)
             .bind(before_id)
             .bind(limit)
             .fetch_all(&self.pool)
@@ -224,18 +432,57 @@ impl Database {
             sqlx::query_as::<_, MessageRow>(
                 r#"
                 SELECT * FROM messages
-                WHERE conversation_id = $1
+                WHERE thread_root_id = $1
                 AND (expires_at IS NULL OR expires_at > NOW())
                 ORDER BY created_at DESC
                 LIMIT $2
                 "#,
             )
-            .bind(conversation_id)
+            .bind(root_message_id)
             .bind(limit)
             .fetch_all(&self.pool)
             .await?
         };
 
+        self.attach_reactions(rows).await
+    }
+
+    /// Messages that mention `user_id` in conversations they still belong to,
+    /// newest first.
+    pub async fn get_mentioned_messages(
+        &self,
+        user_id: Uuid,
+        since: Option<DateTime<Utc>>,
+        before: Option<Uuid>,
+        limit: i64,
+    ) -> Result<Vec<Message>> {
+        let rows = sqlx::query_as::<_, MessageRow>(
+            r#"
+            SELECT m.* FROM message_mentions mm
+            JOIN conversation_members cm
+                ON cm.conversation_id = mm.conversation_id AND cm.user_id = mm.user_id
+            JOIN messages m ON m.id = mm.message_id
+            WHERE mm.user_id = $1
+            AND (m.expires_at IS NULL OR m.expires_at > NOW())
+            AND ($2::timestamptz IS NULL OR mm.created_at > $2)
+            AND ($3::uuid IS NULL OR mm.created_at < (
+                SELECT created_at FROM message_mentions WHERE message_id = $3 AND user_id = $1
+            ))
+            ORDER BY mm.created_at DESC
+            LIMIT $4
+            "#,
+        )
+        .bind(user_id)
+        .bind(since)
+        .bind(before)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        self.attach_reactions(rows).await
+    }
+
+    async fn attach_reactions(&self, rows: Vec<MessageRow>) -> Result<Vec<Message>> {
         let message_ids: Vec<Uuid> = rows.iter().map(|r| r.id).collect();
         let reactions = self.get_reactions_for_messages(&message_ids).await?;
 
@@ -262,6 +509,7 @@ impl Database {
                     call_id: row.call_id,
                     call_duration_seconds: row.call_duration_seconds,
                     pinned_at: row.pinned_at,
+                    thread_root_id: row.thread_root_id,
                     reactions: msg_reactions,
                     created_at: row.created_at,
                 }
@@ -288,14 +536,48 @@ impl Database {
         Ok(result.rows_affected() > 0)
     }
 
+    /// Replaces a message's content. When `keep_history` is set the previous
+    /// content, signature and recipient keys are saved as a revision first.
     pub async fn update_message(
         &self,
         message_id: Uuid,
         sender_id: Uuid,
         encrypted_content: Vec<u8>,
         signature: Vec<u8>,
+        keep_history: bool,
     ) -> Result<Option<DateTime<Utc>>> {
         let edited_at = Utc::now();
+        let mut tx = self.pool.begin().await?;
+
+        if keep_history {
+            let revision_id = sqlx::query_scalar::<_, Uuid>(
+                r#"
+                INSERT INTO message_revisions (message_id, encrypted_content, signature, created_at)
+                SELECT id, encrypted_content, signature, COALESCE(edited_at, created_at)
+                FROM messages
+                WHERE id = $1 AND sender_id = $2
+                RETURNING id
+                "#,
+            )
+            .bind(message_id)
+            .bind(sender_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+
+            if let Some(revision_id) = revision_id {
+                sqlx::query(
+                    r#"
+                    INSERT INTO message_revision_keys (revision_id, user_id, encrypted_key)
+                    SELECT $1, user_id, encrypted_key FROM message_keys WHERE message_id = $2
+                    "#,
+                )
+                .bind(revision_id)
+                .bind(message_id)
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+
         let result = sqlx::query(
             r#"
             UPDATE messages
@@ -308,9 +590,11 @@ impl Database {
         .bind(edited_at)
         .bind(message_id)
         .bind(sender_id)
-        .execute(&self.pool)
+        .execute(&mut *tx)
         .await?;
 
+        tx.commit().await?;
+
         if result.rows_affected() > 0 {
             Ok(Some(edited_at))
         } else {
@@ -318,6 +602,28 @@ impl Database {
         }
     }
 
+    pub async fn get_message_revisions(
+        &self,
+        message_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Vec<MessageRevision>> {
+        let revisions = sqlx::query_as::<_, MessageRevision>(
+            r#"
+            SELECT r.id, r.message_id, r.encrypted_content, r.signature, r.created_at, r.replaced_at,
+                   k.encrypted_key
+            FROM message_revisions r
+            LEFT JOIN message_revision_keys k ON k.revision_id = r.id AND k.user_id = $2
+            WHERE r.message_id = $1
+            ORDER BY r.created_at ASC
+            "#,
+        )
+        .bind(message_id)
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(revisions)
+    }
+
     pub async fn get_reactions_for_messages(
         &self,
         message_ids: &[Uuid],
@@ -386,12 +692,31 @@ impl Database {
         Ok(result.rows_affected())
     }
 
-    pub async fn delete_expired_messages(&self) -> Result<u64> {
-        let result =
-            sqlx::query("DELETE FROM messages WHERE expires_at IS NOT NULL AND expires_at < NOW()")
-                .execute(&self.pool)
-                .await?;
-        Ok(result.rows_affected())
+    /// Deletes expired messages and returns how many were removed together
+    /// with the S3 keys of their attachments.
+    pub async fn delete_expired_messages(&self) -> Result<(u64, Vec<String>)> {
+        let rows = sqlx::query_as::<_, (Uuid, Option<String>)>(
+            r#"
+            WITH deleted AS (
+                DELETE FROM messages
+                WHERE expires_at IS NOT NULL AND expires_at < NOW()
+                RETURNING id
+            )
+            SELECT d.id, fa.s3_key
+            FROM deleted d
+            LEFT JOIN file_attachments fa ON fa.message_id = d.id
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let count = rows
+            .iter()
+            .map(|(id, _)| *id)
+            .collect::<std::collections::HashSet<_>>()
+            .len() as u64;
+        let s3_keys = rows.into_iter().filter_map(|(_, key)| key).collect();
+        Ok((count, s3_keys))
     }
 
     pub async fn delete_expired_sessions(&self) -> Result<u64> {
@@ -405,6 +730,17 @@ impl Database {
         &self,
         message_id: Uuid,
         keys: &[(Uuid, Vec<u8>)],
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        Self::insert_message_keys(&mut tx, message_id, keys).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn insert_message_keys(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        message_id: Uuid,
+        keys: &[(Uuid, Vec<u8>)],
     ) -> Result<()> {
         if keys.is_empty() {
             return Ok(());
@@ -434,7 +770,7 @@ impl Database {
             " ON CONFLICT (message_id, user_id) DO UPDATE SET encrypted_key = EXCLUDED.encrypted_key"
         );
 
-        query_builder.build().execute(&self.pool).await?;
+        query_builder.build().execute(&mut **tx).await?;
 
         Ok(())
     }
@@ -517,38 +853,6 @@ impl Database {
         .fetch_all(&self.pool)
         .await?;
 
-        let message_ids: Vec<Uuid> = rows.iter().map(|r| r.id).collect();
-        let reactions = self.get_reactions_for_messages(&message_ids).await?;
-
-        let messages = rows
-            .into_iter()
-            .map(|row| {
-                let msg_reactions: Vec<MessageReaction> = reactions
-                    .iter()
-                    .filter(|r| r.message_id == row.id)
-                    .cloned()
-                    .collect();
-                Message {
-                    id: row.id,
-                    conversation_id: row.conversation_id,
-                    sender_id: row.sender_id,
-                    encrypted_content: row.encrypted_content,
-                    signature: row.signature,
-                    reply_to_id: row.reply_to_id,
-                    expires_at: row.expires_at,
-                    sender_chain_id: row.sender_chain_id,
-                    sender_chain_iteration: row.sender_chain_iteration,
-                    edited_at: row.edited_at,
-                    message_type: row.message_type,
-                    call_id: row.call_id,
-                    call_duration_seconds: row.call_duration_seconds,
-                    pinned_at: row.pinned_at,
-                    reactions: msg_reactions,
-                    created_at: row.created_at,
-                }
-            })
-            .collect();
-
-        Ok(messages)
+        self.attach_reactions(rows).await
     }
 }
@@ -6,19 +6,30 @@ mod calls;
 pub mod cleanup;
 mod conversations;
 mod discovery;
+mod exports;
 mod federation;
 mod friends;
 mod gifs;
 mod group_calls;
+mod identity_keys;
+mod mentions;
 mod messages;
+mod notification_settings;
 mod preferences;
 mod profiles;
+mod read_markers;
+mod scheduled_messages;
 mod servers;
 mod sessions;
 mod spotify;
+mod threads;
+mod two_factor;
 mod uploads;
 mod users;
 
+pub use messages::{MessageCursor, MessageExtras, NewAttachment};
+pub use users::{AccountDeletion, GroupHandover};
+
 use redis::Client as RedisClient;
 use sqlx::PgPool;
 
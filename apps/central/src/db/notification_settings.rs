@@ -0,0 +1,76 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::error::Result;
+use crate::models::{NotificationLevel, NotificationSettings};
+
+use super::Database;
+
+impl Database {
+    pub async fn get_notification_settings(
+        &self,
+        conversation_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Option<NotificationSettings>> {
+        let settings = sqlx::query_as::<_, NotificationSettings>(
+            r#"
+            SELECT conversation_id, level, muted_until, updated_at
+            FROM conversation_notification_settings
+            WHERE conversation_id = $1 AND user_id = $2
+            "#,
+        )
+        .bind(conversation_id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(settings)
+    }
+
+    /// Returns only the conversations where the user has changed the
+    /// defaults; anything missing notifies for all messages.
+    pub async fn get_user_notification_settings(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Vec<NotificationSettings>> {
+        let rows = sqlx::query_as::<_, NotificationSettings>(
+            r#"
+            SELECT s.conversation_id, s.level, s.muted_until, s.updated_at
+            FROM conversation_notification_settings s
+            JOIN conversation_members cm
+                ON cm.conversation_id = s.conversation_id AND cm.user_id = s.user_id
+            WHERE s.user_id = $1
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows)
+    }
+
+    pub async fn upsert_notification_settings(
+        &self,
+        conversation_id: Uuid,
+        user_id: Uuid,
+        level: NotificationLevel,
+        muted_until: Option<DateTime<Utc>>,
+    ) -> Result<NotificationSettings> {
+        let settings = sqlx::query_as::<_, NotificationSettings>(
+            r#"
+            INSERT INTO conversation_notification_settings (conversation_id, user_id, level, muted_until)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (conversation_id, user_id) DO UPDATE SET
+                level = EXCLUDED.level,
+                muted_until = EXCLUDED.muted_until,
+                updated_at = NOW()
+            RETURNING conversation_id, level, muted_until, updated_at
+            "#,
+        )
+        .bind(conversation_id)
+        .bind(user_id)
+        .bind(level)
+        .bind(muted_until)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(settings)
+    }
+}
@@ -7,7 +7,7 @@ use crate::models::{UpdatePreferencesRequest, UserPreferences};
 impl Database {
     pub async fn get_preferences(&self, user_id: Uuid) -> Result<Option<UserPreferences>, Error> {
         sqlx::query_as::<_, UserPreferences>(
-            r#"SELECT user_id, theme, enable_snow_effect, created_at, updated_at
+            r#"SELECT user_id, theme, enable_snow_effect, send_read_receipts, created_at, updated_at
                FROM user_preferences WHERE user_id = $1"#,
         )
         .bind(user_id)
@@ -21,7 +21,7 @@ impl Database {
             INSERT INTO user_preferences (user_id)
             VALUES ($1)
             ON CONFLICT (user_id) DO UPDATE SET updated_at = NOW()
-            RETURNING user_id, theme, enable_snow_effect, created_at, updated_at
+            RETURNING user_id, theme, enable_snow_effect, send_read_receipts, created_at, updated_at
             "#,
         )
         .bind(user_id)
@@ -36,19 +36,31 @@ impl Database {
     ) -> Result<UserPreferences, Error> {
         sqlx::query_as::<_, UserPreferences>(
             r#"
-            INSERT INTO user_preferences (user_id, theme, enable_snow_effect)
-            VALUES ($1, COALESCE($2, 'dark'), COALESCE($3, TRUE))
+            INSERT INTO user_preferences (user_id, theme, enable_snow_effect, send_read_receipts)
+            VALUES ($1, COALESCE($2, 'dark'), COALESCE($3, TRUE), COALESCE($4, TRUE))
             ON CONFLICT (user_id) DO UPDATE SET
                 theme = COALESCE($2, user_preferences.theme),
                 enable_snow_effect = COALESCE($3, user_preferences.enable_snow_effect),
+                send_read_receipts = COALESCE($4, user_preferences.send_read_receipts),
                 updated_at = NOW()
-            RETURNING user_id, theme, enable_snow_effect, created_at, updated_at
+            RETURNING user_id, theme, enable_snow_effect, send_read_receipts, created_at, updated_at
             "#,
         )
         .bind(user_id)
         .bind(&req.theme)
         .bind(req.enable_snow_effect)
+        .bind(req.send_read_receipts)
         .fetch_one(&self.pool)
         .await
     }
+
+    pub async fn sends_read_receipts(&self, user_id: Uuid) -> Result<bool, Error> {
+        let enabled = sqlx::query_scalar::<_, bool>(
+            "SELECT send_read_receipts FROM user_preferences WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(enabled.unwrap_or(true))
+    }
 }
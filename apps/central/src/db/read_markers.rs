@@ -0,0 +1,62 @@
+use uuid::Uuid;
+
+use crate::error::Result;
+use crate::models::{ConversationUnread, ReadMarker};
+
+use super::Database;
+
+impl Database {
+    /// Moves the user's read marker forward to `message_id`. Returns the new
+    /// marker, or `None` when the message is not newer than the current one.
+    pub async fn mark_conversation_read(
+        &self,
+        conversation_id: Uuid,
+        user_id: Uuid,
+        message_id: Uuid,
+    ) -> Result<Option<ReadMarker>> {
+        let marker = sqlx::query_as::<_, ReadMarker>(
+            r#"
+            INSERT INTO read_markers (conversation_id, user_id, last_read_message_id, last_read_at)
+            SELECT $1, $2, m.id, m.created_at
+            FROM messages m
+            WHERE m.id = $3 AND m.conversation_id = $1
+            ON CONFLICT (conversation_id, user_id) DO UPDATE SET
+                last_read_message_id = EXCLUDED.last_read_message_id,
+                last_read_at = EXCLUDED.last_read_at,
+                updated_at = NOW()
+            WHERE EXCLUDED.last_read_at > read_markers.last_read_at
+            RETURNING last_read_message_id, last_read_at
+            "#,
+        )
+        .bind(conversation_id)
+        .bind(user_id)
+        .bind(message_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(marker)
+    }
+
+    pub async fn get_unread_counts(&self, user_id: Uuid) -> Result<Vec<ConversationUnread>> {
+        let rows = sqlx::query_as::<_, ConversationUnread>(
+            r#"
+            SELECT cm.conversation_id, rm.last_read_message_id,
+                   (
+                       SELECT COUNT(*) FROM messages m
+                       WHERE m.conversation_id = cm.conversation_id
+                       AND m.sender_id <> cm.user_id
+                       AND m.thread_root_id IS NULL
+                       AND (m.expires_at IS NULL OR m.expires_at > NOW())
+                       AND m.created_at > COALESCE(rm.last_read_at, cm.joined_at)
+                   ) AS unread_count
+            FROM conversation_members cm
+            LEFT JOIN read_markers rm
+                ON rm.conversation_id = cm.conversation_id AND rm.user_id = cm.user_id
+            WHERE cm.user_id = $1 AND cm.hidden = FALSE
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.ws_pool)
+        .await?;
+        Ok(rows)
+    }
+}
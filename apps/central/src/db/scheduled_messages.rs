@@ -0,0 +1,291 @@
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::error::Result;
+use crate::models::{DueScheduledMessage, NewScheduledMessage, ScheduledMessage};
+
+use super::Database;
+
+/// A claim older than this is assumed to belong to a worker that died mid-send
+/// and is handed out again.
+const CLAIM_TIMEOUT_SECONDS: i64 = 300;
+
+impl Database {
+    pub async fn create_scheduled_message(
+        &self,
+        msg: &NewScheduledMessage,
+        keys: &[(Uuid, Vec<u8>)],
+    ) -> Result<ScheduledMessage> {
+        let mut tx = self.pool.begin().await?;
+
+        let attachment = msg.attachment.as_ref();
+        let scheduled = sqlx::query_as::<_, ScheduledMessage>(
+            r#"
+            INSERT INTO scheduled_messages
+            (conversation_id, sender_id, encrypted_content, signature, reply_to_id, expires_at,
+             sender_chain_id, sender_chain_iteration, attachment_s3_key, attachment_file_size,
+             attachment_encrypted_size, attachment_mime_type, mentioned_user_ids, send_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+            RETURNING *
+            "#,
+        )
+        .bind(msg.conversation_id)
+        .bind(msg.sender_id)
+        .bind(&msg.encrypted_content)
+        .bind(&msg.signature)
+        .bind(msg.reply_to_id)
+        .bind(msg.expires_at)
+        .bind(msg.sender_chain_id)
+        .bind(msg.sender_chain_iteration)
+        .bind(attachment.map(|a| a.s3_key.as_str()))
+        .bind(attachment.map(|a| a.file_size))
+        .bind(attachment.map(|a| a.encrypted_size))
+        .bind(attachment.map(|a| a.mime_type.as_str()))
+        .bind(&msg.mentioned_user_ids)
+        .bind(msg.send_at)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        for (user_id, encrypted_key) in keys {
+            sqlx::query(
+                r#"
+                INSERT INTO scheduled_message_keys (scheduled_message_id, user_id, encrypted_key)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (scheduled_message_id, user_id) DO UPDATE SET encrypted_key = EXCLUDED.encrypted_key
+                "#,
+            )
+            .bind(scheduled.id)
+            .bind(user_id)
+            .bind(encrypted_key)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(scheduled)
+    }
+
+    pub async fn get_scheduled_messages(
+        &self,
+        conversation_id: Uuid,
+        sender_id: Uuid,
+    ) -> Result<Vec<ScheduledMessage>> {
+        let rows = sqlx::query_as::<_, ScheduledMessage>(
+            r#"
+            SELECT * FROM scheduled_messages
+            WHERE conversation_id = $1 AND sender_id = $2
+            ORDER BY send_at ASC
+            "#,
+        )
+        .bind(conversation_id)
+        .bind(sender_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows)
+    }
+
+    pub async fn count_scheduled_messages(&self, sender_id: Uuid) -> Result<i64> {
+        let count = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM scheduled_messages WHERE sender_id = $1",
+        )
+        .bind(sender_id)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(count)
+    }
+
+    pub async fn get_scheduled_message(&self, id: Uuid) -> Result<Option<ScheduledMessage>> {
+        let row =
+            sqlx::query_as::<_, ScheduledMessage>("SELECT * FROM scheduled_messages WHERE id = $1")
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await?;
+        Ok(row)
+    }
+
+    /// Replaces the content and/or send time of a pending scheduled message.
+    /// When `keys` is given the stored recipient keys are replaced as well.
+    /// Editing a failed message queues it again; a message that is currently
+    /// being sent cannot be edited.
+    pub async fn update_scheduled_message(
+        &self,
+        id: Uuid,
+        sender_id: Uuid,
+        content: Option<(Vec<u8>, Vec<u8>)>,
+        send_at: Option<DateTime<Utc>>,
+        keys: Option<&[(Uuid, Vec<u8>)]>,
+    ) -> Result<Option<ScheduledMessage>> {
+        let mut tx = self.pool.begin().await?;
+
+        let (encrypted_content, signature) = content.unzip();
+        let scheduled = sqlx::query_as::<_, ScheduledMessage>(
+            r#"
+            UPDATE scheduled_messages SET
+                encrypted_content = COALESCE($3, encrypted_content),
+                signature = COALESCE($4, signature),
+                send_at = COALESCE($5, send_at),
+                failed_at = NULL,
+                failure_reason = NULL,
+                attempts = 0,
+                next_attempt_at = NULL,
+                updated_at = NOW()
+            WHERE id = $1 AND sender_id = $2 AND claimed_at IS NULL
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(sender_id)
+        .bind(encrypted_content)
+        .bind(signature)
+        .bind(send_at)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(scheduled) = scheduled else {
+            return Ok(None);
+        };
+
+        if let Some(keys) = keys {
+            sqlx::query("DELETE FROM scheduled_message_keys WHERE scheduled_message_id = $1")
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+
+            for (user_id, encrypted_key) in keys {
+                sqlx::query(
+                    r#"
+                    INSERT INTO scheduled_message_keys (scheduled_message_id, user_id, encrypted_key)
+                    VALUES ($1, $2, $3)
+                    ON CONFLICT (scheduled_message_id, user_id) DO UPDATE SET encrypted_key = EXCLUDED.encrypted_key
+                    "#,
+                )
+                .bind(id)
+                .bind(user_id)
+                .bind(encrypted_key)
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+
+        tx.commit().await?;
+        Ok(Some(scheduled))
+    }
+
+    pub async fn delete_scheduled_message(&self, id: Uuid, sender_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM scheduled_messages WHERE id = $1 AND sender_id = $2")
+            .bind(id)
+            .bind(sender_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Claims up to `limit` due scheduled messages and returns them with
+    /// their keys. Rows locked by another node are skipped, so each message
+    /// is handed out once; the caller must then complete, release or fail it.
+    pub async fn claim_due_scheduled_messages(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<DueScheduledMessage>> {
+        let mut tx = self.pool.begin().await?;
+
+        let rows = sqlx::query_as::<_, ScheduledMessage>(
+            r#"
+            UPDATE scheduled_messages SET claimed_at = NOW(), attempts = attempts + 1
+            WHERE id IN (
+                SELECT id FROM scheduled_messages
+                WHERE send_at <= NOW()
+                  AND failed_at IS NULL
+                  AND (next_attempt_at IS NULL OR next_attempt_at <= NOW())
+                  AND (claimed_at IS NULL OR claimed_at < NOW() - make_interval(secs => $2))
+                ORDER BY send_at ASC
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING *
+            "#,
+        )
+        .bind(limit)
+        .bind(CLAIM_TIMEOUT_SECONDS as f64)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        if rows.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let ids: Vec<Uuid> = rows.iter().map(|r| r.id).collect();
+        let key_rows = sqlx::query_as::<_, (Uuid, Uuid, Vec<u8>)>(
+            r#"
+            SELECT scheduled_message_id, user_id, encrypted_key
+            FROM scheduled_message_keys
+            WHERE scheduled_message_id = ANY($1)
+            "#,
+        )
+        .bind(&ids)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        let mut keys: HashMap<Uuid, Vec<(Uuid, Vec<u8>)>> = HashMap::new();
+        for (scheduled_id, user_id, encrypted_key) in key_rows {
+            keys.entry(scheduled_id)
+                .or_default()
+                .push((user_id, encrypted_key));
+        }
+
+        Ok(rows
+            .into_iter()
+            .map(|message| DueScheduledMessage {
+                keys: keys.remove(&message.id).unwrap_or_default(),
+                message,
+            })
+            .collect())
+    }
+
+    /// Removes a claimed message that was dropped without being sent. Sent
+    /// messages are removed by `create_message` in the same transaction.
+    pub async fn complete_scheduled_message(&self, id: Uuid) -> Result<()> {
+        sqlx::query("DELETE FROM scheduled_messages WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Hands a claimed message back to be retried after `retry_in_seconds`.
+    pub async fn release_scheduled_message(&self, id: Uuid, retry_in_seconds: i64) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE scheduled_messages
+            SET claimed_at = NULL, next_attempt_at = NOW() + make_interval(secs => $2)
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(retry_in_seconds as f64)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Parks a claimed message that cannot be sent as it stands. It stays
+    /// out of the queue until the sender edits or cancels it. Returns false
+    /// when the sender cancelled it in the meantime.
+    pub async fn fail_scheduled_message(&self, id: Uuid, reason: &str) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE scheduled_messages
+            SET claimed_at = NULL, failed_at = NOW(), failure_reason = $2
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(reason)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -23,6 +23,12 @@ impl Database {
                 signed_prekey_public = $2,
                 signed_prekey_signature = $3,
                 signed_prekey_id = $4,
+                signed_prekey_created_at = CASE
+                    WHEN user_prekeys.signed_prekey_id = $4
+                        AND user_prekeys.signed_prekey_public = $2
+                    THEN user_prekeys.signed_prekey_created_at
+                    ELSE NOW()
+                END,
                 updated_at = NOW()
             "#,
         )
@@ -121,6 +127,31 @@ impl Database {
         }))
     }
 
+    /// Returns true if no `kind` warning was sent to `user_id` within the last
+    /// `cooldown_seconds`, and starts a new cooldown window.
+    pub async fn claim_prekey_warning(
+        &self,
+        user_id: Uuid,
+        kind: &str,
+        cooldown_seconds: u64,
+    ) -> Result<bool> {
+        let mut conn = self
+            .redis
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| anyhow::anyhow!("Redis error: {}", e))?;
+        let set: Option<String> = redis::cmd("SET")
+            .arg(format!("prekey_warning:{}:{}", kind, user_id))
+            .arg("1")
+            .arg("NX")
+            .arg("EX")
+            .arg(cooldown_seconds.max(1))
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| anyhow::anyhow!("Redis error: {}", e))?;
+        Ok(set.is_some())
+    }
+
     pub async fn upsert_ratchet_session(
         &self,
         user_id: Uuid,
@@ -0,0 +1,91 @@
+use uuid::Uuid;
+
+use crate::error::Result;
+use crate::models::MessageThread;
+
+use super::Database;
+
+impl Database {
+    pub async fn get_thread(&self, root_message_id: Uuid) -> Result<MessageThread> {
+        let thread = sqlx::query_as::<_, MessageThread>(
+            r#"
+            SELECT $1 AS root_message_id, COUNT(*) AS reply_count, MAX(created_at) AS last_reply_at
+            FROM messages
+            WHERE thread_root_id = $1
+            AND (expires_at IS NULL OR expires_at > NOW())
+            "#,
+        )
+        .bind(root_message_id)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(thread)
+    }
+
+    pub async fn get_threads_for_messages(
+        &self,
+        message_ids: &[Uuid],
+    ) -> Result<Vec<MessageThread>> {
+        if message_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let threads = sqlx::query_as::<_, MessageThread>(
+            r#"
+            SELECT thread_root_id AS root_message_id, COUNT(*) AS reply_count, MAX(created_at) AS last_reply_at
+            FROM messages
+            WHERE thread_root_id = ANY($1)
+            AND (expires_at IS NULL OR expires_at > NOW())
+            GROUP BY thread_root_id
+            "#,
+        )
+        .bind(message_ids)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(threads)
+    }
+
+    pub async fn follow_thread(&self, root_message_id: Uuid, user_id: Uuid) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO thread_follows (root_message_id, user_id)
+            VALUES ($1, $2)
+            ON CONFLICT (root_message_id, user_id) DO NOTHING
+            "#,
+        )
+        .bind(root_message_id)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn unfollow_thread(&self, root_message_id: Uuid, user_id: Uuid) -> Result<()> {
+        sqlx::query("DELETE FROM thread_follows WHERE root_message_id = $1 AND user_id = $2")
+            .bind(root_message_id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_thread_followers(&self, root_message_id: Uuid) -> Result<Vec<Uuid>> {
+        let followers = sqlx::query_scalar::<_, Uuid>(
+            "SELECT user_id FROM thread_follows WHERE root_message_id = $1",
+        )
+        .bind(root_message_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(followers)
+    }
+
+    pub async fn is_following_thread(&self, root_message_id: Uuid, user_id: Uuid) -> Result<bool> {
+        let following = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM thread_follows WHERE root_message_id = $1 AND user_id = $2)",
+        )
+        .bind(root_message_id)
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(following)
+    }
+}
@@ -0,0 +1,165 @@
+use uuid::Uuid;
+
+use crate::error::Result;
+use crate::models::UserTotp;
+
+use super::Database;
+
+impl Database {
+    pub async fn get_user_totp(&self, user_id: Uuid) -> Result<Option<UserTotp>> {
+        let totp = sqlx::query_as::<_, UserTotp>("SELECT * FROM user_totp WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(totp)
+    }
+
+    pub async fn is_two_factor_enabled(&self, user_id: Uuid) -> Result<bool> {
+        let enabled: Option<bool> =
+            sqlx::query_scalar("SELECT enabled FROM user_totp WHERE user_id = $1")
+                .bind(user_id)
+                .fetch_optional(&self.pool)
+                .await?;
+        Ok(enabled.unwrap_or(false))
+    }
+
+    /// Stores a new secret awaiting confirmation. Returns false when 2FA is
+    /// already enabled, in which case the active secret is left untouched.
+    pub async fn store_pending_totp(
+        &self,
+        user_id: Uuid,
+        encrypted_secret: Vec<u8>,
+    ) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO user_totp (user_id, encrypted_secret)
+            VALUES ($1, $2)
+            ON CONFLICT (user_id) DO UPDATE SET
+                encrypted_secret = EXCLUDED.encrypted_secret,
+                last_used_step = NULL,
+                created_at = NOW()
+            WHERE user_totp.enabled = FALSE
+            "#,
+        )
+        .bind(user_id)
+        .bind(encrypted_secret)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn enable_totp(
+        &self,
+        user_id: Uuid,
+        verified_step: i64,
+        backup_code_hashes: Vec<Vec<u8>>,
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            UPDATE user_totp
+            SET enabled = TRUE, enabled_at = NOW(), last_used_step = $2
+            WHERE user_id = $1
+            "#,
+        )
+        .bind(user_id)
+        .bind(verified_step)
+        .execute(&mut *tx)
+        .await?;
+
+        Self::insert_backup_codes(&mut tx, user_id, backup_code_hashes).await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn replace_backup_codes(
+        &self,
+        user_id: Uuid,
+        backup_code_hashes: Vec<Vec<u8>>,
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        Self::insert_backup_codes(&mut tx, user_id, backup_code_hashes).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn insert_backup_codes(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: Uuid,
+        backup_code_hashes: Vec<Vec<u8>>,
+    ) -> Result<()> {
+        sqlx::query("DELETE FROM user_backup_codes WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut **tx)
+            .await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO user_backup_codes (user_id, code_hash)
+            SELECT $1, UNNEST($2::bytea[])
+            "#,
+        )
+        .bind(user_id)
+        .bind(backup_code_hashes)
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
+    /// Records `step` as used. Returns false if it, or a later step, was
+    /// already accepted, so a code cannot be replayed.
+    pub async fn consume_totp_step(&self, user_id: Uuid, step: i64) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE user_totp SET last_used_step = $2
+            WHERE user_id = $1 AND enabled = TRUE
+            AND (last_used_step IS NULL OR last_used_step < $2)
+            "#,
+        )
+        .bind(user_id)
+        .bind(step)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn consume_backup_code(&self, user_id: Uuid, code_hash: &[u8]) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE user_backup_codes SET used_at = NOW()
+            WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL
+            "#,
+        )
+        .bind(user_id)
+        .bind(code_hash)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn count_backup_codes(&self, user_id: Uuid) -> Result<i64> {
+        let count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM user_backup_codes WHERE user_id = $1 AND used_at IS NULL",
+        )
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(count)
+    }
+
+    pub async fn disable_totp(&self, user_id: Uuid) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM user_backup_codes WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM user_totp WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+}
@@ -99,6 +99,24 @@ impl Database {
         Ok(result.map(|(s3_key,)| s3_key))
     }
 
+    /// S3 keys of every avatar, banner and attachment the user uploaded, and
+    /// of any data export still stored for them.
+    pub async fn get_user_s3_keys(&self, user_id: Uuid) -> Result<Vec<String>> {
+        let keys: Vec<String> = sqlx::query_scalar(
+            r#"
+            SELECT s3_key FROM uploads WHERE user_id = $1 AND s3_key IS NOT NULL
+            UNION
+            SELECT s3_key FROM file_attachments WHERE uploader_id = $1
+            UNION
+            SELECT s3_key FROM data_exports WHERE user_id = $1 AND s3_key IS NOT NULL
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(keys)
+    }
+
     pub async fn delete_orphaned_uploads(&self, retention_days: u32) -> Result<Vec<String>> {
         let cutoff = Utc::now() - Duration::days(retention_days as i64);
 
@@ -1,12 +1,57 @@
-use chrono::{Duration, Utc};
+use chrono::{DateTime, Utc};
 use sqlx::Row;
 use uuid::Uuid;
 
 use crate::error::{AppError, Result};
-use crate::models::{PublicUser, RecoveryKeys, Session, User, UserKeys};
+use crate::models::{
+    DeviceInfo, Message, PublicUser, RecoveryKeys, RefreshOutcome, Session, SessionTokenHashes,
+    User, UserKeys, UsernameChange,
+};
 
 use super::{cache, Database};
 
+/// What has to change alongside an account deletion.
+#[derive(Default)]
+pub struct AccountDeletion {
+    pub handovers: Vec<GroupHandover>,
+    /// Owned groups with no other members left.
+    pub empty_groups: Vec<Uuid>,
+    pub owned_servers: Vec<Uuid>,
+}
+
+/// An owned group passing to another member.
+pub struct GroupHandover {
+    pub conversation_id: Uuid,
+    pub new_owner_id: Uuid,
+    /// Content of the `group_owner_changed` system message.
+    pub announcement: Vec<u8>,
+}
+
+/// A client whose refresh response was lost (timeout, crash, app killed) will
+/// retry with the token it already spent. For this long after the rotation,
+/// retrying the session's most recently spent token issues a fresh pair
+/// instead of counting as reuse. The trade-off is that a thief holding that
+/// token gets the same window.
+const REFRESH_RETRY_GRACE_SECONDS: i64 = 30;
+
+async fn is_refresh_retry(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    session_id: Uuid,
+    used_at: DateTime<Utc>,
+) -> Result<bool> {
+    if used_at <= Utc::now() - chrono::Duration::seconds(REFRESH_RETRY_GRACE_SECONDS) {
+        return Ok(false);
+    }
+    let spent_later = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS (SELECT 1 FROM refresh_tokens WHERE session_id = $1 AND used_at > $2)",
+    )
+    .bind(session_id)
+    .bind(used_at)
+    .fetch_one(&mut **tx)
+    .await?;
+    Ok(!spent_later)
+}
+
 impl Database {
     pub async fn create_user(
         &self,
@@ -14,6 +59,8 @@ impl Database {
         password_hash: Vec<u8>,
         keys: UserKeys,
     ) -> Result<User> {
+        let mut tx = self.pool.begin().await?;
+
         let user = sqlx::query_as::<_, User>(
             r#"
             INSERT INTO users (username, password_hash, kem_public_key, kem_encrypted_private,
@@ -29,7 +76,7 @@ impl Database {
         .bind(&keys.dsa_public_key)
         .bind(&keys.dsa_encrypted_private)
         .bind(&keys.key_salt)
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *tx)
         .await
         .map_err(|e| match e {
             sqlx::Error::Database(ref db_err)
@@ -43,9 +90,18 @@ impl Database {
         sqlx::query("INSERT INTO user_friends (user_id, encrypted_friends) VALUES ($1, $2)")
             .bind(user.id)
             .bind(Vec::<u8>::new())
-            .execute(&self.pool)
+            .execute(&mut *tx)
             .await?;
 
+        Self::insert_genesis_identity_key(
+            &mut tx,
+            user.id,
+            &keys.kem_public_key,
+            &keys.dsa_public_key,
+        )
+        .await?;
+
+        tx.commit().await?;
         Ok(user)
     }
 
@@ -127,25 +183,142 @@ impl Database {
     pub async fn create_session(
         &self,
         user_id: Uuid,
-        token_hash: Vec<u8>,
-        expiry_hours: u64,
+        tokens: &SessionTokenHashes,
+        device: &DeviceInfo,
+        ip_prefix: Option<String>,
     ) -> Result<Session> {
-        let expires_at = Utc::now() + Duration::hours(expiry_hours as i64);
+        let mut tx = self.pool.begin().await?;
+
         let session = sqlx::query_as::<_, Session>(
             r#"
-            INSERT INTO sessions (user_id, token_hash, expires_at)
-            VALUES ($1, $2, $3)
+            INSERT INTO sessions (user_id, token_hash, access_expires_at, expires_at, device_name, platform, client_version, ip_prefix)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
             RETURNING *
             "#,
         )
         .bind(user_id)
-        .bind(token_hash)
-        .bind(expires_at)
-        .fetch_one(&self.pool)
+        .bind(&tokens.access_token_hash)
+        .bind(tokens.access_expires_at)
+        .bind(tokens.expires_at)
+        .bind(&device.name)
+        .bind(&device.platform)
+        .bind(&device.client_version)
+        .bind(ip_prefix)
+        .fetch_one(&mut *tx)
         .await?;
+
+        sqlx::query("INSERT INTO refresh_tokens (session_id, token_hash) VALUES ($1, $2)")
+            .bind(session.id)
+            .bind(&tokens.refresh_token_hash)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
         Ok(session)
     }
 
+    /// Exchanges a refresh token for the new pair in `tokens`. Every refresh
+    /// token is single-use: presenting one that was already rotated revokes
+    /// the whole session, since either the client or an attacker holds a
+    /// stolen copy. The one exception is a prompt retry of the latest token,
+    /// see `REFRESH_RETRY_GRACE_SECONDS`.
+    pub async fn rotate_refresh_token(
+        &self,
+        refresh_token_hash: &[u8],
+        tokens: &SessionTokenHashes,
+    ) -> Result<RefreshOutcome> {
+        let mut tx = self.pool.begin().await?;
+
+        let row = sqlx::query(
+            r#"
+            SELECT rt.id, rt.used_at, s.id AS session_id, s.user_id, s.token_hash, s.expires_at
+            FROM refresh_tokens rt
+            JOIN sessions s ON s.id = rt.session_id
+            WHERE rt.token_hash = $1
+            FOR UPDATE OF rt, s
+            "#,
+        )
+        .bind(refresh_token_hash)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(row) = row else {
+            return Ok(RefreshOutcome::Invalid);
+        };
+        let session_id: Uuid = row.get("session_id");
+        let user_id: Uuid = row.get("user_id");
+        let old_access_hash: Vec<u8> = row.get("token_hash");
+
+        let used_at: Option<DateTime<Utc>> = row.get("used_at");
+        let is_retry = match used_at {
+            Some(used_at) => is_refresh_retry(&mut tx, session_id, used_at).await?,
+            None => false,
+        };
+
+        if used_at.is_some() && !is_retry {
+            sqlx::query("DELETE FROM sessions WHERE id = $1")
+                .bind(session_id)
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+
+            self.invalidate_session_cache(&old_access_hash).await;
+            return Ok(RefreshOutcome::Reused {
+                session_id,
+                user_id,
+            });
+        }
+
+        if row.get::<DateTime<Utc>, _>("expires_at") <= Utc::now() {
+            return Ok(RefreshOutcome::Invalid);
+        }
+
+        if is_retry {
+            // The client never received the pair issued last time; drop it.
+            sqlx::query("DELETE FROM refresh_tokens WHERE session_id = $1 AND used_at IS NULL")
+                .bind(session_id)
+                .execute(&mut *tx)
+                .await?;
+        } else {
+            sqlx::query("UPDATE refresh_tokens SET used_at = NOW() WHERE id = $1")
+                .bind(row.get::<Uuid, _>("id"))
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        sqlx::query("INSERT INTO refresh_tokens (session_id, token_hash) VALUES ($1, $2)")
+            .bind(session_id)
+            .bind(&tokens.refresh_token_hash)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query(
+            r#"
+            UPDATE sessions
+            SET token_hash = $2, access_expires_at = $3, expires_at = $4, last_seen_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(session_id)
+        .bind(&tokens.access_token_hash)
+        .bind(tokens.access_expires_at)
+        .bind(tokens.expires_at)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        self.invalidate_session_cache(&old_access_hash).await;
+        Ok(RefreshOutcome::Rotated)
+    }
+
+    async fn invalidate_session_cache(&self, token_hash: &[u8]) {
+        let token_hash_hex = hex::encode(&token_hash[..8.min(token_hash.len())]);
+        cache::invalidate_cache(&self.redis, &format!("session:{}", token_hash_hex))
+            .await
+            .ok();
+    }
+
     pub async fn get_session_by_token_hash(&self, token_hash: &[u8]) -> Result<Option<Session>> {
         let token_hash_hex = hex::encode(&token_hash[..8.min(token_hash.len())]);
         let cache_key = format!("session:{}", token_hash_hex);
@@ -177,11 +350,286 @@ impl Database {
         Ok(())
     }
 
-    pub async fn delete_user_sessions(&self, user_id: Uuid) -> Result<()> {
-        sqlx::query("DELETE FROM sessions WHERE user_id = $1")
+    /// Deletes every session of the user and returns their ids.
+    pub async fn delete_user_sessions(&self, user_id: Uuid) -> Result<Vec<Uuid>> {
+        let ids: Vec<Uuid> =
+            sqlx::query_scalar("DELETE FROM sessions WHERE user_id = $1 RETURNING id")
+                .bind(user_id)
+                .fetch_all(&self.pool)
+                .await?;
+
+        cache::invalidate_cache_pattern(&self.redis, "session:*")
+            .await
+            .ok();
+        Ok(ids)
+    }
+
+    pub async fn get_user_sessions(&self, user_id: Uuid) -> Result<Vec<Session>> {
+        let sessions = sqlx::query_as::<_, Session>(
+            r#"
+            SELECT * FROM sessions
+            WHERE user_id = $1 AND expires_at > NOW()
+            ORDER BY last_seen_at DESC
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(sessions)
+    }
+
+    /// Deletes one of the user's sessions. Returns false when no such session
+    /// belongs to the user.
+    pub async fn delete_user_session(&self, user_id: Uuid, session_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM sessions WHERE id = $1 AND user_id = $2")
+            .bind(session_id)
             .bind(user_id)
             .execute(&self.pool)
             .await?;
+
+        cache::invalidate_cache_pattern(&self.redis, "session:*")
+            .await
+            .ok();
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Deletes every session of the user except `keep_session_id` and returns
+    /// the ids that were removed.
+    pub async fn delete_other_sessions(
+        &self,
+        user_id: Uuid,
+        keep_session_id: Uuid,
+    ) -> Result<Vec<Uuid>> {
+        let ids: Vec<Uuid> =
+            sqlx::query_scalar("DELETE FROM sessions WHERE user_id = $1 AND id <> $2 RETURNING id")
+                .bind(user_id)
+                .bind(keep_session_id)
+                .fetch_all(&self.pool)
+                .await?;
+
+        cache::invalidate_cache_pattern(&self.redis, "session:*")
+            .await
+            .ok();
+        Ok(ids)
+    }
+
+    /// Everyone who shares a conversation or a pending friend request with
+    /// the user.
+    pub async fn get_user_contact_ids(&self, user_id: Uuid) -> Result<Vec<Uuid>> {
+        let ids: Vec<Uuid> = sqlx::query_scalar(
+            r#"
+            SELECT other.user_id FROM conversation_members mine
+            JOIN conversation_members other ON other.conversation_id = mine.conversation_id
+            WHERE mine.user_id = $1 AND other.user_id <> $1
+            UNION
+            SELECT to_user_id FROM friend_requests WHERE from_user_id = $1
+            UNION
+            SELECT from_user_id FROM friend_requests WHERE to_user_id = $1
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(ids)
+    }
+
+    /// Deletes the account together with the ownership changes it causes, so
+    /// a failure part way through leaves nothing half done. Returns the
+    /// removed session ids and the system messages announcing each handover.
+    pub async fn delete_user(
+        &self,
+        user_id: Uuid,
+        deletion: AccountDeletion,
+    ) -> Result<(Vec<Uuid>, Vec<Message>)> {
+        let mut tx = self.pool.begin().await?;
+
+        // The old owner's messages go with their account, so the new owner
+        // posts the announcement.
+        let mut announcements = Vec::with_capacity(deletion.handovers.len());
+        for handover in deletion.handovers {
+            sqlx::query("UPDATE conversations SET owner_id = $1 WHERE id = $2")
+                .bind(handover.new_owner_id)
+                .bind(handover.conversation_id)
+                .execute(&mut *tx)
+                .await?;
+
+            sqlx::query(
+                r#"
+                INSERT INTO group_events (conversation_id, event_type, actor_id, target_user_id)
+                VALUES ($1, 'owner_changed', $2, $3)
+                "#,
+            )
+            .bind(handover.conversation_id)
+            .bind(user_id)
+            .bind(handover.new_owner_id)
+            .execute(&mut *tx)
+            .await?;
+
+            let message = Self::insert_system_message(
+                &mut tx,
+                handover.conversation_id,
+                handover.new_owner_id,
+                "group_owner_changed",
+                handover.announcement,
+            )
+            .await?;
+            announcements.push(message);
+        }
+
+        sqlx::query("DELETE FROM conversations WHERE id = ANY($1)")
+            .bind(&deletion.empty_groups)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM registered_servers WHERE id = ANY($1)")
+            .bind(&deletion.owned_servers)
+            .execute(&mut *tx)
+            .await?;
+
+        let session_ids: Vec<Uuid> =
+            sqlx::query_scalar("DELETE FROM sessions WHERE user_id = $1 RETURNING id")
+                .bind(user_id)
+                .fetch_all(&mut *tx)
+                .await?;
+
+        sqlx::query("DELETE FROM users WHERE id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        cache::invalidate_cache_pattern(&self.redis, "session:*")
+            .await
+            .ok();
+        cache::invalidate_cache(&self.redis, &format!("user:public:{}", user_id))
+            .await
+            .ok();
+        cache::invalidate_cache(&self.redis, &format!("user:convos:{}", user_id))
+            .await
+            .ok();
+        Ok((session_ids, announcements))
+    }
+
+    /// Whether `username` was given up by another account within the last
+    /// `reservation_days` and is still held for them.
+    pub async fn is_username_reserved(
+        &self,
+        username: &str,
+        exclude_user_id: Option<Uuid>,
+        reservation_days: u32,
+    ) -> Result<bool> {
+        let reserved: bool = sqlx::query_scalar(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM username_history
+                WHERE old_username = $1
+                AND ($2::uuid IS NULL OR user_id <> $2)
+                AND changed_at > NOW() - make_interval(days => $3)
+            )
+            "#,
+        )
+        .bind(username)
+        .bind(exclude_user_id)
+        .bind(reservation_days as i32)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(reserved)
+    }
+
+    /// Renames the user and records the change. Fails with `TooManyRequests`
+    /// while the previous change is within `cooldown_days`.
+    pub async fn change_username(
+        &self,
+        user_id: Uuid,
+        new_username: &str,
+        cooldown_days: u32,
+    ) -> Result<UsernameChange> {
+        let mut tx = self.pool.begin().await?;
+
+        let old_username: String =
+            sqlx::query_scalar("SELECT username FROM users WHERE id = $1 FOR UPDATE")
+                .bind(user_id)
+                .fetch_optional(&mut *tx)
+                .await?
+                .ok_or(AppError::UserNotFound)?;
+        if old_username == new_username {
+            return Err(AppError::BadRequest("username is unchanged".into()));
+        }
+
+        let recently_changed: bool = sqlx::query_scalar(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM username_history
+                WHERE user_id = $1 AND changed_at > NOW() - make_interval(days => $2)
+            )
+            "#,
+        )
+        .bind(user_id)
+        .bind(cooldown_days as i32)
+        .fetch_one(&mut *tx)
+        .await?;
+        if recently_changed {
+            return Err(AppError::TooManyRequests);
+        }
+
+        sqlx::query("UPDATE users SET username = $2 WHERE id = $1")
+            .bind(user_id)
+            .bind(new_username)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| match e {
+                sqlx::Error::Database(ref db_err)
+                    if db_err.constraint() == Some("users_username_key") =>
+                {
+                    AppError::UserAlreadyExists
+                }
+                _ => AppError::Database(e),
+            })?;
+
+        let change = sqlx::query_as::<_, UsernameChange>(
+            r#"
+            INSERT INTO username_history (user_id, old_username, new_username)
+            VALUES ($1, $2, $3)
+            RETURNING *
+            "#,
+        )
+        .bind(user_id)
+        .bind(&old_username)
+        .bind(new_username)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        cache::invalidate_cache(&self.redis, &format!("user:public:{}", user_id))
+            .await
+            .ok();
+        Ok(change)
+    }
+
+    pub async fn get_username_history(&self, user_id: Uuid) -> Result<Vec<UsernameChange>> {
+        let history = sqlx::query_as::<_, UsernameChange>(
+            "SELECT * FROM username_history WHERE user_id = $1 ORDER BY changed_at DESC",
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(history)
+    }
+
+    /// Records activity on a session. Writes are skipped while the stored
+    /// timestamp is still recent.
+    pub async fn touch_session(&self, session_id: Uuid) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE sessions SET last_seen_at = NOW()
+            WHERE id = $1 AND last_seen_at < NOW() - INTERVAL '5 minutes'
+            "#,
+        )
+        .bind(session_id)
+        .execute(&self.pool)
+        .await?;
         Ok(())
     }
 
@@ -269,8 +717,6 @@ impl Database {
         .execute(&self.pool)
         .await?;
 
-        self.delete_user_sessions(user_id).await?;
-
         Ok(())
     }
 }
@@ -38,6 +38,9 @@ pub enum AppError {
     #[error("recovery not set up")]
     RecoveryNotSetup,
 
+    #[error("invalid two-factor code")]
+    InvalidTwoFactorCode,
+
     #[error("invalid request: {0}")]
     BadRequest(String),
 
@@ -74,6 +77,7 @@ impl IntoResponse for AppError {
             AppError::Forbidden => (StatusCode::FORBIDDEN, self.to_string()),
             AppError::TooManyRequests => (StatusCode::TOO_MANY_REQUESTS, self.to_string()),
             AppError::RecoveryNotSetup => (StatusCode::BAD_REQUEST, self.to_string()),
+            AppError::InvalidTwoFactorCode => (StatusCode::UNAUTHORIZED, self.to_string()),
             AppError::BadRequest(_) => (StatusCode::BAD_REQUEST, self.to_string()),
             AppError::Database(e) => {
                 tracing::error!("database error: {:?}", e);
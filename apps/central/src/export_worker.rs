@@ -0,0 +1,365 @@
+use chrono::{DateTime, Duration, Utc};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::sync::Arc;
+use tempfile::NamedTempFile;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+use crate::error::{AppError, Result};
+use crate::models::{CallResponse, DataExport};
+use crate::ws::{DataExportFailedData, DataExportReadyData, WsMessage};
+use crate::AppState;
+
+const EXPORT_FORMAT_VERSION: u32 = 1;
+/// Files waiting to be compressed. Small, since each may be a whole
+/// attachment.
+const ARCHIVE_QUEUE_SIZE: usize = 4;
+
+#[derive(Serialize)]
+struct Manifest {
+    format_version: u32,
+    export_id: Uuid,
+    user_id: Uuid,
+    created_at: DateTime<Utc>,
+    files: Vec<ManifestEntry>,
+}
+
+#[derive(Serialize)]
+struct ManifestEntry {
+    path: String,
+    size_bytes: u64,
+    sha256: String,
+}
+
+/// Account keys are included still wrapped by the user's password, so the
+/// archive can only be decrypted by its owner.
+#[derive(Serialize)]
+struct ExportedAccount {
+    id: Uuid,
+    username: String,
+    kem_public_key: Vec<u8>,
+    kem_encrypted_private: Vec<u8>,
+    dsa_public_key: Vec<u8>,
+    dsa_encrypted_private: Vec<u8>,
+    key_salt: Vec<u8>,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+struct ExportedFriends {
+    encrypted_friends: Vec<u8>,
+    updated_at: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+struct ExportedVerifiedContacts {
+    encrypted_verified_contacts: Vec<u8>,
+    updated_at: DateTime<Utc>,
+}
+
+/// Archives can hold every attachment a user has sent, so they are written
+/// to a temporary file rather than held in memory.
+struct Archive {
+    builder: tar::Builder<GzEncoder<File>>,
+    entries: Vec<ManifestEntry>,
+}
+
+impl Archive {
+    fn new(file: File) -> Self {
+        Self {
+            builder: tar::Builder::new(GzEncoder::new(file, Compression::default())),
+            entries: Vec::new(),
+        }
+    }
+
+    fn add_file(&mut self, path: &str, data: &[u8]) -> Result<()> {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(Utc::now().timestamp().max(0) as u64);
+        self.builder
+            .append_data(&mut header, path, data)
+            .map_err(|e| AppError::Internal(anyhow::anyhow!("Failed to write {}: {}", path, e)))?;
+
+        self.entries.push(ManifestEntry {
+            path: path.to_string(),
+            size_bytes: data.len() as u64,
+            sha256: hex::encode(Sha256::digest(data)),
+        });
+        Ok(())
+    }
+
+    /// Writes the manifest last so it can list every other file.
+    fn finish(mut self, export_id: Uuid, user_id: Uuid) -> Result<()> {
+        let manifest = Manifest {
+            format_version: EXPORT_FORMAT_VERSION,
+            export_id,
+            user_id,
+            created_at: Utc::now(),
+            files: std::mem::take(&mut self.entries),
+        };
+        self.add_file("manifest.json", &to_json(&manifest)?)?;
+
+        self.builder
+            .into_inner()
+            .and_then(|gz| gz.finish())
+            .and_then(|file| file.sync_all())
+            .map_err(|e| AppError::Internal(anyhow::anyhow!("Failed to finish archive: {}", e)))
+    }
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<Vec<u8>> {
+    serde_json::to_vec_pretty(value).map_err(|e| AppError::Internal(e.into()))
+}
+
+/// Compression and file writes block, so the `Archive` lives on a blocking
+/// thread and is fed files over a channel.
+struct ArchiveWriter {
+    tx: mpsc::Sender<(String, Vec<u8>)>,
+    task: JoinHandle<Result<()>>,
+}
+
+impl ArchiveWriter {
+    fn spawn(file: File, export_id: Uuid, user_id: Uuid) -> Self {
+        let (tx, mut rx) = mpsc::channel::<(String, Vec<u8>)>(ARCHIVE_QUEUE_SIZE);
+        let task = tokio::task::spawn_blocking(move || {
+            let mut archive = Archive::new(file);
+            while let Some((path, data)) = rx.blocking_recv() {
+                archive.add_file(&path, &data)?;
+            }
+            archive.finish(export_id, user_id)
+        });
+        Self { tx, task }
+    }
+
+    async fn add_file(&mut self, path: String, data: Vec<u8>) -> Result<()> {
+        if self.tx.send((path, data)).await.is_err() {
+            return Err(AppError::Internal(anyhow::anyhow!(
+                "Archive writer stopped"
+            )));
+        }
+        Ok(())
+    }
+
+    async fn add_json<T: Serialize>(&mut self, path: &str, value: &T) -> Result<()> {
+        self.add_file(path.to_string(), to_json(value)?).await
+    }
+
+    async fn finish(self) -> Result<()> {
+        drop(self.tx);
+        self.task
+            .await
+            .map_err(|e| AppError::Internal(anyhow::anyhow!("Archive writer panicked: {}", e)))?
+    }
+}
+
+pub async fn run_export(state: Arc<AppState>, export: DataExport) {
+    let export_id = export.id;
+    let user_id = export.user_id;
+
+    let Ok(_permit) = state.export_semaphore.clone().acquire_owned().await else {
+        return;
+    };
+    match state.db.start_data_export(export_id).await {
+        Ok(true) => {}
+        Ok(false) => {
+            tracing::info!("Data export {} was failed while queued", export_id);
+            return;
+        }
+        Err(e) => {
+            tracing::error!("Failed to start data export {}: {:?}", export_id, e);
+            return;
+        }
+    }
+
+    let msg = match build_and_store(&state, &export).await {
+        Ok(ready) => {
+            tracing::info!("Data export {} ready for user {}", export_id, user_id);
+            WsMessage::DataExportReady(DataExportReadyData {
+                export_id,
+                size_bytes: ready.size_bytes.unwrap_or_default(),
+                expires_at: ready.expires_at.unwrap_or_else(Utc::now),
+            })
+        }
+        Err(e) => {
+            tracing::error!("Data export {} failed: {:?}", export_id, e);
+            if let Err(e) = state.db.fail_data_export(export_id).await {
+                tracing::error!("Failed to mark data export {} failed: {:?}", export_id, e);
+            }
+            WsMessage::DataExportFailed(DataExportFailedData { export_id })
+        }
+    };
+
+    if let Ok(json) = serde_json::to_string(&msg) {
+        state.subscriptions.send_to_user(user_id, &json).await;
+    }
+}
+
+async fn build_and_store(state: &AppState, export: &DataExport) -> Result<DataExport> {
+    let archive = build_archive(state, export).await?;
+    let size_bytes = archive
+        .as_file()
+        .metadata()
+        .map_err(|e| AppError::Internal(e.into()))?
+        .len() as i64;
+
+    let s3_key = state
+        .s3
+        .upload_export(
+            &export.user_id.to_string(),
+            &export.id.to_string(),
+            archive.path(),
+        )
+        .await?;
+
+    let expires_at = Utc::now() + Duration::hours(state.config.exports.retention_hours as i64);
+    state
+        .db
+        .complete_data_export(export.id, &s3_key, size_bytes, expires_at)
+        .await
+}
+
+async fn build_archive(state: &AppState, export: &DataExport) -> Result<NamedTempFile> {
+    let file = NamedTempFile::new().map_err(|e| AppError::Internal(e.into()))?;
+    let mut archive = ArchiveWriter::spawn(
+        file.reopen().map_err(|e| AppError::Internal(e.into()))?,
+        export.id,
+        export.user_id,
+    );
+    // Files are queued before the writer has finished with earlier ones, so
+    // a write error can surface as a stopped writer; `finish` has the cause.
+    if let Err(e) = add_entries(state, export, &mut archive).await {
+        return Err(archive.finish().await.err().unwrap_or(e));
+    }
+    archive.finish().await?;
+    Ok(file)
+}
+
+async fn add_entries(
+    state: &AppState,
+    export: &DataExport,
+    archive: &mut ArchiveWriter,
+) -> Result<()> {
+    let user_id = export.user_id;
+    let user = state
+        .db
+        .get_user_by_id(user_id)
+        .await?
+        .ok_or(AppError::UserNotFound)?;
+
+    archive
+        .add_json(
+            "account.json",
+            &ExportedAccount {
+                id: user.id,
+                username: user.username,
+                kem_public_key: user.kem_public_key,
+                kem_encrypted_private: user.kem_encrypted_private,
+                dsa_public_key: user.dsa_public_key,
+                dsa_encrypted_private: user.dsa_encrypted_private,
+                key_salt: user.key_salt,
+                created_at: user.created_at,
+            },
+        )
+        .await?;
+    archive
+        .add_json("profile.json", &state.db.get_profile(user_id).await?)
+        .await?;
+    archive
+        .add_json(
+            "preferences.json",
+            &state.db.get_preferences(user_id).await?,
+        )
+        .await?;
+    archive
+        .add_json(
+            "friends.json",
+            &state
+                .db
+                .get_user_friends(user_id)
+                .await?
+                .map(|f| ExportedFriends {
+                    encrypted_friends: f.encrypted_friends,
+                    updated_at: f.updated_at,
+                }),
+        )
+        .await?;
+    archive
+        .add_json(
+            "verified_contacts.json",
+            &state
+                .db
+                .get_verified_contacts(user_id)
+                .await?
+                .map(|v| ExportedVerifiedContacts {
+                    encrypted_verified_contacts: v.encrypted_verified_contacts,
+                    updated_at: v.updated_at,
+                }),
+        )
+        .await?;
+    archive
+        .add_json(
+            "conversations.json",
+            &state.db.get_export_conversations(user_id).await?,
+        )
+        .await?;
+    archive
+        .add_json(
+            "messages.json",
+            &state.db.get_export_messages(user_id).await?,
+        )
+        .await?;
+
+    let calls: Vec<CallResponse> = state
+        .db
+        .get_export_calls(user_id)
+        .await?
+        .into_iter()
+        .map(CallResponse::from)
+        .collect();
+    archive.add_json("calls.json", &calls).await?;
+    archive
+        .add_json("activity.json", &state.db.get_user_activity(user_id).await?)
+        .await?;
+
+    for upload in state.db.get_export_uploads(user_id).await? {
+        match state.s3.download_file(&upload.s3_key).await {
+            Ok(data) => {
+                archive
+                    .add_file(format!("profile/{}", upload.file_type), data)
+                    .await?
+            }
+            Err(e) => tracing::warn!(
+                "Skipping {} in data export {}: {:?}",
+                upload.s3_key,
+                export.id,
+                e
+            ),
+        }
+    }
+
+    let attachments = state.db.get_export_attachments(user_id).await?;
+    for attachment in &attachments {
+        match state.s3.download_file(&attachment.s3_key).await {
+            Ok(data) => {
+                archive
+                    .add_file(format!("attachments/{}", attachment.id), data)
+                    .await?
+            }
+            Err(e) => tracing::warn!(
+                "Skipping attachment {} in data export {}: {:?}",
+                attachment.id,
+                export.id,
+                e
+            ),
+        }
+    }
+    archive.add_json("attachments.json", &attachments).await?;
+
+    Ok(())
+}
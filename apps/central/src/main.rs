@@ -3,16 +3,20 @@ mod config;
 mod crypto;
 mod db;
 mod error;
+mod export_worker;
 mod media;
 mod models;
 mod s3;
+mod scheduled_worker;
 mod spotify_worker;
+mod totp;
 mod ws;
 
 use axum::extract::DefaultBodyLimit;
 use axum::http::header;
 use axum::Router;
 use sqlx::postgres::PgPoolOptions;
+use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::net::TcpListener;
 use tokio::sync::Semaphore;
@@ -23,6 +27,8 @@ use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 use crate::config::Config;
 use crate::db::Database;
+use crate::ws::cluster::ClusterBus;
+use crate::ws::replay::EventLog;
 use crate::ws::subscriptions::SubscriptionManager;
 
 pub struct AppState {
@@ -30,8 +36,10 @@ pub struct AppState {
     pub redis: redis::Client,
     pub config: Config,
     pub upload_semaphore: Arc<Semaphore>,
+    pub export_semaphore: Arc<Semaphore>,
     pub s3: s3::S3Service,
     pub subscriptions: Arc<SubscriptionManager>,
+    pub prekey_metrics: api::keys::PrekeyMetrics,
 }
 
 #[tokio::main]
@@ -111,6 +119,7 @@ async fn main() -> anyhow::Result<()> {
     tracing::info!("connected to Redis");
 
     let upload_semaphore = Arc::new(Semaphore::new(config.uploads.max_concurrent_uploads));
+    let export_semaphore = Arc::new(Semaphore::new(config.exports.max_concurrent));
 
     let s3 = s3::S3Service::new(Arc::new(config.s3.clone())).await?;
     tracing::info!("S3 service initialized");
@@ -120,16 +129,42 @@ async fn main() -> anyhow::Result<()> {
         config.s3.max_file_size_bytes / 1024 / 1024
     );
 
-    let subscriptions = Arc::new(SubscriptionManager::new());
+    let mut subscriptions = SubscriptionManager::new();
+    if config.websocket.replay_max_events > 0 {
+        subscriptions = subscriptions.with_event_log(EventLog::new(
+            redis.clone(),
+            config.websocket.replay_max_events,
+            config.websocket.replay_ttl_hours * 3600,
+        ));
+    }
+    let cluster = if config.websocket.cluster_enabled {
+        let bus = Arc::new(ClusterBus::connect(redis.clone()).await?);
+        subscriptions = subscriptions.with_cluster(bus.clone());
+        tracing::info!("WebSocket cluster mode enabled (node {})", bus.node_id());
+        Some(bus)
+    } else {
+        None
+    };
+    let subscriptions = Arc::new(subscriptions);
     tracing::info!("Subscription manager initialized");
 
+    if let Some(bus) = cluster {
+        tokio::spawn(ws::cluster::run_node_heartbeat(bus.clone()));
+        tokio::spawn(ws::cluster::run_cluster_listener(
+            bus,
+            subscriptions.clone(),
+        ));
+    }
+
     let state = Arc::new(AppState {
         db: Database::new(api_pool, ws_pool, redis.clone()),
         redis,
         config: config.clone(),
         upload_semaphore,
+        export_semaphore,
         s3,
         subscriptions,
+        prekey_metrics: api::keys::PrekeyMetrics::default(),
     });
 
     let cleanup_state = state.clone();
@@ -137,6 +172,21 @@ async fn main() -> anyhow::Result<()> {
         db::cleanup::run_cleanup_task(cleanup_state).await;
     });
 
+    let expired_cleanup_state = state.clone();
+    tokio::spawn(async move {
+        db::cleanup::run_expired_message_cleanup_task(expired_cleanup_state).await;
+    });
+
+    let export_cleanup_state = state.clone();
+    tokio::spawn(async move {
+        db::cleanup::run_export_cleanup_task(export_cleanup_state).await;
+    });
+
+    let scheduled_state = state.clone();
+    tokio::spawn(async move {
+        scheduled_worker::run_scheduled_message_worker(scheduled_state).await;
+    });
+
     let call_cleanup_state = state.clone();
     tokio::spawn(async move {
         db::cleanup::run_call_cleanup_task(call_cleanup_state).await;
@@ -169,6 +219,15 @@ async fn main() -> anyhow::Result<()> {
         }
     });
 
+    let metrics_state = state.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(15 * 60));
+        loop {
+            interval.tick().await;
+            metrics_state.prekey_metrics.log_metrics();
+        }
+    });
+
     if config.calls.enabled {
         let relay_config = media::MediaRelayConfig {
             bind_addr: format!(
@@ -260,6 +319,10 @@ async fn main() -> anyhow::Result<()> {
     let listener = TcpListener::bind(&addr).await?;
     tracing::info!("server listening on {}", addr);
 
-    axum::serve(listener, app).await?;
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
     Ok(())
 }
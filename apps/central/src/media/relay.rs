@@ -36,6 +36,10 @@ struct ParticipantChannels {
     datagram_tx: mpsc::Sender<Bytes>,
 }
 
+/// QUIC relay for direct and group calls. Community servers run a fork of
+/// this relay (apps/server/src/media/relay.rs) with voice-channel state on
+/// top; the framing and token layout are shared, so changes to either must be
+/// made in both copies.
 pub struct MediaRelay {
     config: MediaRelayConfig,
     endpoint: Endpoint,
@@ -327,69 +331,38 @@ impl MediaRelay {
 
     async fn handle_outgoing_streams(
         connection: Connection,
-        mut audio_rx: mpsc::Receiver<Bytes>,
-        mut video_rx: mpsc::Receiver<Bytes>,
+        audio_rx: mpsc::Receiver<Bytes>,
+        video_rx: mpsc::Receiver<Bytes>,
     ) -> anyhow::Result<()> {
-        let conn_audio = connection.clone();
-        let conn_video = connection.clone();
-
-        let audio_task = tokio::spawn(async move {
-            let mut stream: Option<SendStream> = None;
-
-            while let Some(data) = audio_rx.recv().await {
-                let send = match &mut stream {
-                    Some(s) => s,
-                    None => {
-                        let (s, _) = conn_audio.open_bi().await?;
-                        stream = Some(s);
-                        stream.as_mut().unwrap()
-                    }
-                };
+        let audio_task = tokio::spawn(Self::forward_stream(connection.clone(), audio_rx));
+        let video_task = tokio::spawn(Self::forward_stream(connection, video_rx));
 
-                let len = (data.len() as u16).to_be_bytes();
-                if send.write_all(&len).await.is_err() {
-                    stream = None;
-                    continue;
-                }
-                if send.write_all(&data).await.is_err() {
-                    stream = None;
-                    continue;
-                }
-            }
+        tokio::select! {
+            r = audio_task => r??,
+            r = video_task => r??,
+        }
 
-            Ok::<_, anyhow::Error>(())
-        });
+        Ok(())
+    }
 
-        let video_task = tokio::spawn(async move {
-            let mut stream: Option<SendStream> = None;
+    /// Writes length-prefixed frames to a bi stream, reopening it after a
+    /// write failure.
+    async fn forward_stream(
+        connection: Connection,
+        mut rx: mpsc::Receiver<Bytes>,
+    ) -> anyhow::Result<()> {
+        let mut stream: Option<SendStream> = None;
 
-            while let Some(data) = video_rx.recv().await {
-                let send = match &mut stream {
-                    Some(s) => s,
-                    None => {
-                        let (s, _) = conn_video.open_bi().await?;
-                        stream = Some(s);
-                        stream.as_mut().unwrap()
-                    }
-                };
+        while let Some(data) = rx.recv().await {
+            let send = match stream {
+                Some(ref mut s) => s,
+                None => stream.insert(connection.open_bi().await?.0),
+            };
 
-                let len = (data.len() as u16).to_be_bytes();
-                if send.write_all(&len).await.is_err() {
-                    stream = None;
-                    continue;
-                }
-                if send.write_all(&data).await.is_err() {
-                    stream = None;
-                    continue;
-                }
+            let len = (data.len() as u16).to_be_bytes();
+            if send.write_all(&len).await.is_err() || send.write_all(&data).await.is_err() {
+                stream = None;
             }
-
-            Ok::<_, anyhow::Error>(())
-        });
-
-        tokio::select! {
-            r = audio_task => r??,
-            r = video_task => r??,
         }
 
         Ok(())
@@ -8,6 +8,8 @@ pub struct Conversation {
     pub conversation_type: String,
     pub encrypted_metadata: Option<Vec<u8>>,
     pub owner_id: Option<Uuid>,
+    pub keep_edit_history: bool,
+    pub message_ttl_seconds: Option<i32>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -62,6 +64,71 @@ pub struct ConversationWithRouting {
     pub created_at: DateTime<Utc>,
 }
 
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ConversationUnread {
+    pub conversation_id: Uuid,
+    pub last_read_message_id: Option<Uuid>,
+    pub unread_count: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConversationWithUnread {
+    #[serde(flatten)]
+    pub conversation: ConversationWithRouting,
+    pub last_read_message_id: Option<Uuid>,
+    pub unread_count: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationSettings {
+    pub keep_edit_history: bool,
+    pub message_ttl_seconds: Option<i32>,
+}
+
+impl From<&Conversation> for ConversationSettings {
+    fn from(conv: &Conversation) -> Self {
+        Self {
+            keep_edit_history: conv.keep_edit_history,
+            message_ttl_seconds: conv.message_ttl_seconds,
+        }
+    }
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ReadMarker {
+    pub last_read_message_id: Option<Uuid>,
+    pub last_read_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, Default)]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationLevel {
+    #[default]
+    All,
+    Mentions,
+    None,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct NotificationSettings {
+    pub conversation_id: Uuid,
+    pub level: NotificationLevel,
+    pub muted_until: Option<DateTime<Utc>>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl NotificationSettings {
+    pub fn default_for(conversation_id: Uuid) -> Self {
+        Self {
+            conversation_id,
+            level: NotificationLevel::All,
+            muted_until: None,
+            updated_at: Utc::now(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, sqlx::FromRow)]
 pub struct ConversationMemberWithUser {
     pub conversation_id: Uuid,
@@ -0,0 +1,57 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum DataExportStatus {
+    Pending,
+    Ready,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct DataExport {
+    pub id: Uuid,
+    #[serde(skip)]
+    pub user_id: Uuid,
+    pub status: DataExportStatus,
+    #[serde(skip)]
+    pub s3_key: Option<String>,
+    pub size_bytes: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// A message the user sent, with the copy of its key wrapped for them.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ExportedMessage {
+    pub id: Uuid,
+    pub conversation_id: Uuid,
+    pub encrypted_content: Vec<u8>,
+    pub signature: Vec<u8>,
+    pub encrypted_key: Option<Vec<u8>>,
+    pub reply_to_id: Option<Uuid>,
+    pub thread_root_id: Option<Uuid>,
+    pub message_type: String,
+    pub sender_chain_id: Option<i32>,
+    pub sender_chain_iteration: Option<i32>,
+    pub edited_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ExportedAttachment {
+    pub id: Uuid,
+    pub message_id: Uuid,
+    pub conversation_id: Uuid,
+    #[serde(skip)]
+    pub s3_key: String,
+    pub file_size_bytes: i64,
+    pub encrypted_size_bytes: i64,
+    pub mime_type: String,
+    pub created_at: DateTime<Utc>,
+}
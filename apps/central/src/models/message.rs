@@ -15,6 +15,7 @@ pub enum MessageType {
     ChannelPin,
     GroupCallStarted,
     GroupCallEnded,
+    DisappearingTimerChanged,
 }
 
 impl std::fmt::Display for MessageType {
@@ -27,6 +28,7 @@ impl std::fmt::Display for MessageType {
             MessageType::ChannelPin => write!(f, "channel_pin"),
             MessageType::GroupCallStarted => write!(f, "group_call_started"),
             MessageType::GroupCallEnded => write!(f, "group_call_ended"),
+            MessageType::DisappearingTimerChanged => write!(f, "disappearing_timer_changed"),
         }
     }
 }
@@ -42,6 +44,7 @@ impl std::str::FromStr for MessageType {
             "channel_pin" => Ok(MessageType::ChannelPin),
             "group_call_started" => Ok(MessageType::GroupCallStarted),
             "group_call_ended" => Ok(MessageType::GroupCallEnded),
+            "disappearing_timer_changed" => Ok(MessageType::DisappearingTimerChanged),
             _ => Err(format!("Unknown message type: {}", s)),
         }
     }
@@ -63,6 +66,7 @@ pub struct MessageRow {
     pub call_id: Option<Uuid>,
     pub call_duration_seconds: Option<i32>,
     pub pinned_at: Option<DateTime<Utc>>,
+    pub thread_root_id: Option<Uuid>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -82,6 +86,7 @@ pub struct Message {
     pub call_id: Option<Uuid>,
     pub call_duration_seconds: Option<i32>,
     pub pinned_at: Option<DateTime<Utc>>,
+    pub thread_root_id: Option<Uuid>,
     pub reactions: Vec<MessageReaction>,
     pub created_at: DateTime<Utc>,
 }
@@ -103,3 +108,14 @@ pub struct MessageKey {
     pub encrypted_key: Vec<u8>,
     pub created_at: DateTime<Utc>,
 }
+
+#[derive(Debug, Clone, sqlx::FromRow, Serialize)]
+pub struct MessageRevision {
+    pub id: Uuid,
+    pub message_id: Uuid,
+    pub encrypted_content: Vec<u8>,
+    pub signature: Vec<u8>,
+    pub created_at: DateTime<Utc>,
+    pub replaced_at: DateTime<Utc>,
+    pub encrypted_key: Option<Vec<u8>>,
+}
@@ -2,12 +2,16 @@ mod activity;
 mod audio_settings;
 mod call;
 mod conversation;
+mod export;
 mod federation;
 mod gif;
 mod message;
 mod preferences;
 mod profile;
+mod scheduled_message;
 mod session;
+mod thread;
+mod two_factor;
 mod upload;
 mod user;
 
@@ -15,11 +19,15 @@ pub use activity::*;
 pub use audio_settings::*;
 pub use call::*;
 pub use conversation::*;
+pub use export::*;
 pub use federation::*;
 pub use gif::*;
 pub use message::*;
 pub use preferences::*;
 pub use profile::*;
+pub use scheduled_message::*;
 pub use session::*;
+pub use thread::*;
+pub use two_factor::*;
 pub use upload::*;
 pub use user::*;
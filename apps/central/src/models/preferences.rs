@@ -7,6 +7,7 @@ pub struct UserPreferences {
     pub user_id: Uuid,
     pub theme: String,
     pub enable_snow_effect: bool,
+    pub send_read_receipts: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -15,6 +16,7 @@ pub struct UserPreferences {
 pub struct UpdatePreferencesRequest {
     pub theme: Option<String>,
     pub enable_snow_effect: Option<bool>,
+    pub send_read_receipts: Option<bool>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -22,8 +24,14 @@ pub struct UpdateSnowEffectRequest {
     pub enabled: bool,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct UpdateReadReceiptsRequest {
+    pub enabled: bool,
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct PreferencesResponse {
     pub theme: String,
     pub enable_snow_effect: bool,
+    pub send_read_receipts: bool,
 }
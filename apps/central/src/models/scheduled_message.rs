@@ -0,0 +1,63 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ScheduledMessage {
+    pub id: Uuid,
+    pub conversation_id: Uuid,
+    pub sender_id: Uuid,
+    pub encrypted_content: Vec<u8>,
+    pub signature: Vec<u8>,
+    pub reply_to_id: Option<Uuid>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub sender_chain_id: Option<i32>,
+    pub sender_chain_iteration: Option<i32>,
+    pub attachment_s3_key: Option<String>,
+    pub attachment_file_size: Option<i64>,
+    pub attachment_encrypted_size: Option<i64>,
+    pub attachment_mime_type: Option<String>,
+    pub mentioned_user_ids: Vec<Uuid>,
+    pub send_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    #[serde(skip_serializing)]
+    pub claimed_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing)]
+    pub attempts: i32,
+    #[serde(skip_serializing)]
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub failed_at: Option<DateTime<Utc>>,
+    pub failure_reason: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ScheduledAttachment {
+    pub s3_key: String,
+    pub file_size: i64,
+    pub encrypted_size: i64,
+    pub mime_type: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct NewScheduledMessage {
+    pub conversation_id: Uuid,
+    pub sender_id: Uuid,
+    pub encrypted_content: Vec<u8>,
+    pub signature: Vec<u8>,
+    pub reply_to_id: Option<Uuid>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub sender_chain_id: Option<i32>,
+    pub sender_chain_iteration: Option<i32>,
+    pub attachment: Option<ScheduledAttachment>,
+    pub mentioned_user_ids: Vec<Uuid>,
+    pub send_at: DateTime<Utc>,
+}
+
+/// A due scheduled message together with the per-recipient keys that were
+/// stored alongside it.
+#[derive(Debug, Clone)]
+pub struct DueScheduledMessage {
+    pub message: ScheduledMessage,
+    pub keys: Vec<(Uuid, Vec<u8>)>,
+}
@@ -9,6 +9,7 @@ pub struct UserPrekeys {
     pub signed_prekey_signature: Vec<u8>,
     pub signed_prekey_id: i32,
     pub updated_at: DateTime<Utc>,
+    pub signed_prekey_created_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone, sqlx::FromRow, Serialize)]
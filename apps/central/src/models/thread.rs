@@ -0,0 +1,10 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, sqlx::FromRow, Serialize)]
+pub struct MessageThread {
+    pub root_message_id: Uuid,
+    pub reply_count: i64,
+    pub last_reply_at: Option<DateTime<Utc>>,
+}
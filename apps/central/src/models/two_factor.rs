@@ -0,0 +1,22 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct UserTotp {
+    pub user_id: Uuid,
+    pub encrypted_secret: Vec<u8>,
+    pub enabled: bool,
+    pub last_used_step: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub enabled_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TwoFactorStatus {
+    /// False when the server has no TOTP encryption key configured.
+    pub available: bool,
+    pub enabled: bool,
+    pub enabled_at: Option<DateTime<Utc>>,
+    pub backup_codes_remaining: i64,
+}
@@ -61,6 +61,68 @@ pub struct Session {
     pub token_hash: Vec<u8>,
     pub expires_at: DateTime<Utc>,
     pub created_at: DateTime<Utc>,
+    pub device_name: Option<String>,
+    pub platform: Option<String>,
+    pub client_version: Option<String>,
+    pub ip_prefix: Option<String>,
+    pub last_seen_at: DateTime<Utc>,
+    pub access_expires_at: DateTime<Utc>,
+}
+
+/// Hashes and expiries of a freshly issued access/refresh token pair. The
+/// session itself lives until `expires_at`; each refresh pushes it forward.
+#[derive(Debug, Clone)]
+pub struct SessionTokenHashes {
+    pub access_token_hash: Vec<u8>,
+    pub access_expires_at: DateTime<Utc>,
+    pub refresh_token_hash: Vec<u8>,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug)]
+pub enum RefreshOutcome {
+    Rotated,
+    /// An already rotated refresh token was presented again; the session it
+    /// belonged to has been revoked.
+    Reused {
+        session_id: Uuid,
+        user_id: Uuid,
+    },
+    Invalid,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeviceInfo {
+    pub name: Option<String>,
+    pub platform: Option<String>,
+    pub client_version: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionInfo {
+    pub id: Uuid,
+    pub device_name: Option<String>,
+    pub platform: Option<String>,
+    pub client_version: Option<String>,
+    pub ip_prefix: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    pub current: bool,
+}
+
+impl SessionInfo {
+    pub fn from_session(session: Session, current_session_id: Uuid) -> Self {
+        Self {
+            id: session.id,
+            device_name: session.device_name,
+            platform: session.platform,
+            client_version: session.client_version,
+            ip_prefix: session.ip_prefix,
+            created_at: session.created_at,
+            last_seen_at: session.last_seen_at,
+            current: session.id == current_session_id,
+        }
+    }
 }
 
 #[derive(Debug, Clone, sqlx::FromRow)]
@@ -70,6 +132,13 @@ pub struct UserFriends {
     pub updated_at: DateTime<Utc>,
 }
 
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct UserVerifiedContacts {
+    pub user_id: Uuid,
+    pub encrypted_verified_contacts: Vec<u8>,
+    pub updated_at: DateTime<Utc>,
+}
+
 #[derive(Debug, Clone, Serialize, sqlx::FromRow)]
 pub struct FriendRequest {
     pub id: Uuid,
@@ -85,3 +154,25 @@ pub struct UserServers {
     pub encrypted_servers: Vec<u8>,
     pub updated_at: DateTime<Utc>,
 }
+
+#[derive(Debug, Clone, sqlx::FromRow, Serialize)]
+pub struct UsernameChange {
+    pub id: Uuid,
+    #[serde(skip_serializing)]
+    pub user_id: Uuid,
+    pub old_username: String,
+    pub new_username: String,
+    pub changed_at: DateTime<Utc>,
+}
+
+/// One link in a user's identity key chain. Every version after the first
+/// carries a signature by the DSA key it replaced.
+#[derive(Debug, Clone, sqlx::FromRow, Serialize)]
+pub struct IdentityKeyRecord {
+    pub user_id: Uuid,
+    pub version: i32,
+    pub kem_public_key: Vec<u8>,
+    pub dsa_public_key: Vec<u8>,
+    pub continuity_signature: Option<Vec<u8>>,
+    pub created_at: DateTime<Utc>,
+}
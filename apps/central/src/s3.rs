@@ -1,6 +1,7 @@
 use aws_config::BehaviorVersion;
 use aws_credential_types::Credentials;
 use aws_sdk_s3::presigning::PresigningConfig;
+use aws_sdk_s3::primitives::ByteStream;
 use aws_sdk_s3::Client;
 use std::sync::Arc;
 use std::time::Duration;
@@ -200,6 +201,39 @@ impl S3Service {
         Ok(())
     }
 
+    pub async fn upload_export(
+        &self,
+        user_id: &str,
+        export_id: &str,
+        path: &std::path::Path,
+    ) -> Result<String> {
+        let key = format!("exports/{}/{}.tar.gz", user_id, export_id);
+        let body = ByteStream::from_path(path)
+            .await
+            .map_err(|e| AppError::Internal(anyhow::anyhow!("Failed to read export: {}", e)))?;
+
+        self.client
+            .put_object()
+            .bucket(&self.config.bucket)
+            .key(&key)
+            .body(body)
+            .content_type("application/gzip")
+            .send()
+            .await
+            .map_err(|e| {
+                tracing::error!(
+                    "Failed to upload export to bucket '{}', key '{}': {}",
+                    self.config.bucket,
+                    key,
+                    e
+                );
+                AppError::Internal(anyhow::anyhow!("Failed to upload export: {}", e))
+            })?;
+
+        tracing::info!("Export uploaded successfully: {}", key);
+        Ok(key)
+    }
+
     pub async fn upload_profile_image(
         &self,
         user_id: &str,
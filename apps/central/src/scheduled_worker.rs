@@ -0,0 +1,192 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::time::{interval, Duration};
+use uuid::Uuid;
+
+use crate::api::messages::{
+    deliver_message, AttachmentMetadata, MessageKeyData, SendMessageRequest,
+};
+use crate::error::AppError;
+use crate::models::DueScheduledMessage;
+use crate::ws::{ScheduledMessageFailedData, WsMessage};
+use crate::AppState;
+
+const POLL_INTERVAL_SECONDS: u64 = 5;
+const BATCH_SIZE: i64 = 100;
+/// Transient failures are retried this many times in total, waiting
+/// `RETRY_BASE_SECONDS` doubled after each attempt, before the message is
+/// marked failed.
+const MAX_ATTEMPTS: i32 = 6;
+const RETRY_BASE_SECONDS: i64 = 30;
+
+pub async fn run_scheduled_message_worker(state: Arc<AppState>) {
+    tracing::info!("Scheduled message worker started");
+
+    let mut interval = interval(Duration::from_secs(POLL_INTERVAL_SECONDS));
+    loop {
+        interval.tick().await;
+
+        let due = match state.db.claim_due_scheduled_messages(BATCH_SIZE).await {
+            Ok(due) => due,
+            Err(e) => {
+                tracing::error!("Failed to claim scheduled messages: {:?}", e);
+                continue;
+            }
+        };
+
+        for scheduled in due {
+            let id = scheduled.message.id;
+            let conversation_id = scheduled.message.conversation_id;
+            let sender_id = scheduled.message.sender_id;
+            let attempts = scheduled.message.attempts;
+
+            let outcome = match send_scheduled_message(&state, scheduled).await {
+                Ok(()) => state.db.complete_scheduled_message(id).await,
+                Err(e) if is_transient(&e) && attempts < MAX_ATTEMPTS => {
+                    let retry_in = RETRY_BASE_SECONDS << (attempts - 1).clamp(0, 10);
+                    tracing::warn!(
+                        "Retrying scheduled message {} in {}s: {:?}",
+                        id,
+                        retry_in,
+                        e
+                    );
+                    state.db.release_scheduled_message(id, retry_in).await
+                }
+                Err(e) => {
+                    tracing::info!("Scheduled message {} failed: {}", id, e);
+                    fail_scheduled_message(&state, id, conversation_id, sender_id, e).await
+                }
+            };
+            if let Err(e) = outcome {
+                tracing::error!("Failed to settle scheduled message {}: {:?}", id, e);
+            }
+        }
+    }
+}
+
+/// Whether retrying the same message later could succeed. Constraint
+/// violations will fail the same way every time.
+fn is_transient(error: &AppError) -> bool {
+    match error {
+        AppError::Database(sqlx::Error::Database(db_err)) => !matches!(
+            db_err.kind(),
+            sqlx::error::ErrorKind::UniqueViolation
+                | sqlx::error::ErrorKind::ForeignKeyViolation
+                | sqlx::error::ErrorKind::NotNullViolation
+                | sqlx::error::ErrorKind::CheckViolation
+        ),
+        AppError::Database(_) | AppError::Internal(_) => true,
+        _ => false,
+    }
+}
+
+async fn fail_scheduled_message(
+    state: &AppState,
+    id: Uuid,
+    conversation_id: Uuid,
+    sender_id: Uuid,
+    error: AppError,
+) -> crate::error::Result<()> {
+    let reason = error.to_string();
+    if !state.db.fail_scheduled_message(id, &reason).await? {
+        return Ok(());
+    }
+
+    let msg = WsMessage::ScheduledMessageFailed(ScheduledMessageFailedData {
+        scheduled_message_id: id,
+        conversation_id,
+        reason,
+    });
+    if let Ok(json) = serde_json::to_string(&msg) {
+        state.subscriptions.send_to_user(sender_id, &json).await;
+    }
+    Ok(())
+}
+
+async fn send_scheduled_message(
+    state: &AppState,
+    scheduled: DueScheduledMessage,
+) -> crate::error::Result<()> {
+    let DueScheduledMessage { message: msg, keys } = scheduled;
+    let scheduled_message_id = msg.id;
+
+    if state
+        .db
+        .get_conversation_routing(msg.conversation_id, msg.sender_id)
+        .await?
+        .is_none()
+    {
+        tracing::info!(
+            "Dropping scheduled message {}: sender left the conversation",
+            msg.id
+        );
+        return Ok(());
+    }
+
+    let attachment = match (
+        msg.attachment_s3_key,
+        msg.attachment_file_size,
+        msg.attachment_encrypted_size,
+        msg.attachment_mime_type,
+    ) {
+        (Some(s3_key), Some(file_size), Some(encrypted_size), Some(mime_type)) => {
+            Some(AttachmentMetadata {
+                s3_key,
+                file_size,
+                encrypted_size,
+                mime_type,
+            })
+        }
+        _ => None,
+    };
+
+    // Members can leave between scheduling and sending. Their mentions and
+    // keys are dropped so the rest of the conversation still gets the message.
+    let members: HashSet<Uuid> = if msg.mentioned_user_ids.is_empty() && keys.is_empty() {
+        HashSet::new()
+    } else {
+        state
+            .db
+            .get_conversation_members(msg.conversation_id)
+            .await?
+            .into_iter()
+            .map(|m| m.user_id)
+            .collect()
+    };
+    let mentioned_user_ids: Vec<Uuid> = msg
+        .mentioned_user_ids
+        .into_iter()
+        .filter(|id| members.contains(id))
+        .collect();
+
+    let message_keys = (!keys.is_empty()).then(|| {
+        keys.into_iter()
+            .filter(|(user_id, _)| members.contains(user_id))
+            .map(|(user_id, encrypted_key)| MessageKeyData {
+                user_id,
+                encrypted_key,
+            })
+            .collect()
+    });
+
+    deliver_message(
+        state,
+        msg.sender_id,
+        msg.conversation_id,
+        SendMessageRequest {
+            encrypted_content: msg.encrypted_content,
+            signature: msg.signature,
+            reply_to_id: msg.reply_to_id,
+            expires_at: msg.expires_at,
+            sender_chain_id: msg.sender_chain_id,
+            sender_chain_iteration: msg.sender_chain_iteration,
+            message_keys,
+            attachment,
+            mentioned_user_ids: Some(mentioned_user_ids),
+        },
+        Some(scheduled_message_id),
+    )
+    .await?;
+
+    Ok(())
+}
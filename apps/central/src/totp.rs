@@ -0,0 +1,181 @@
+use hmac::{Hmac, Mac};
+use rand::{Rng, RngCore};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+type HmacSha1 = Hmac<Sha1>;
+
+const SECRET_LENGTH: usize = 20;
+const STEP_SECONDS: i64 = 30;
+const DIGITS: u32 = 6;
+/// Steps accepted on either side of the current one to absorb clock drift.
+const ALLOWED_DRIFT: i64 = 1;
+
+const BACKUP_CODE_COUNT: usize = 10;
+const BACKUP_CODE_LENGTH: usize = 10;
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+pub fn generate_secret() -> Vec<u8> {
+    let mut secret = vec![0u8; SECRET_LENGTH];
+    rand::thread_rng().fill_bytes(&mut secret);
+    secret
+}
+
+/// RFC 4648 base32 without padding, the form authenticator apps expect.
+pub fn encode_secret(secret: &[u8]) -> String {
+    let mut out = String::with_capacity(secret.len().div_ceil(5) * 8);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for &byte in secret {
+        buffer = (buffer << 8) | byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    out
+}
+
+pub fn provisioning_uri(issuer: &str, account: &str, secret: &[u8]) -> String {
+    format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+        urlencoding::encode(issuer),
+        urlencoding::encode(account),
+        encode_secret(secret),
+        urlencoding::encode(issuer),
+        DIGITS,
+        STEP_SECONDS
+    )
+}
+
+fn hotp(secret: &[u8], counter: u64) -> u32 {
+    let mut mac = HmacSha1::new_from_slice(secret).expect("HMAC can take key of any size");
+    mac.update(&counter.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let value = u32::from_be_bytes([
+        hash[offset] & 0x7f,
+        hash[offset + 1],
+        hash[offset + 2],
+        hash[offset + 3],
+    ]);
+    value % 10u32.pow(DIGITS)
+}
+
+/// Checks `code` against the steps around `unix_time`. Returns the matching
+/// step so callers can refuse to accept it twice.
+pub fn verify_code(secret: &[u8], code: &str, unix_time: i64) -> Option<i64> {
+    if !is_totp_code(code) {
+        return None;
+    }
+    let code: u32 = code.parse().ok()?;
+
+    let current = unix_time / STEP_SECONDS;
+    (current - ALLOWED_DRIFT..=current + ALLOWED_DRIFT)
+        .filter(|step| *step >= 0)
+        .find(|step| hotp(secret, *step as u64) == code)
+}
+
+pub fn is_totp_code(code: &str) -> bool {
+    code.len() == DIGITS as usize && code.bytes().all(|b| b.is_ascii_digit())
+}
+
+pub fn generate_backup_codes() -> Vec<String> {
+    let mut rng = rand::thread_rng();
+    (0..BACKUP_CODE_COUNT)
+        .map(|_| {
+            (0..BACKUP_CODE_LENGTH)
+                .map(|_| BASE32_ALPHABET[rng.gen_range(0..32)] as char)
+                .collect()
+        })
+        .collect()
+}
+
+/// Backup codes are compared case-insensitively and ignore dashes and spaces.
+pub fn hash_backup_code(code: &str) -> Vec<u8> {
+    let normalized: String = code
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    Sha256::digest(normalized.as_bytes()).to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RFC6238_SECRET: &[u8] = b"12345678901234567890";
+
+    /// RFC 6238 appendix B (SHA-1), truncated to our six digits.
+    const RFC6238_VECTORS: &[(i64, &str)] = &[
+        (59, "287082"),
+        (1111111109, "081804"),
+        (1111111111, "050471"),
+        (1234567890, "005924"),
+        (2000000000, "279037"),
+        (20000000000, "353130"),
+    ];
+
+    #[test]
+    fn test_rfc6238_vectors() {
+        for &(time, code) in RFC6238_VECTORS {
+            let step = time / STEP_SECONDS;
+            assert_eq!(format!("{:06}", hotp(RFC6238_SECRET, step as u64)), code);
+            assert_eq!(verify_code(RFC6238_SECRET, code, time), Some(step));
+        }
+    }
+
+    #[test]
+    fn test_verify_code_allows_one_step_of_drift() {
+        let (time, code) = RFC6238_VECTORS[1];
+        let step = time / STEP_SECONDS;
+
+        assert_eq!(
+            verify_code(RFC6238_SECRET, code, time - STEP_SECONDS),
+            Some(step)
+        );
+        assert_eq!(
+            verify_code(RFC6238_SECRET, code, time + STEP_SECONDS),
+            Some(step)
+        );
+        assert_eq!(
+            verify_code(RFC6238_SECRET, code, time + 2 * STEP_SECONDS),
+            None
+        );
+    }
+
+    #[test]
+    fn test_verify_code_rejects_malformed_codes() {
+        let (time, _) = RFC6238_VECTORS[0];
+        assert_eq!(verify_code(RFC6238_SECRET, "28708", time), None);
+        assert_eq!(verify_code(RFC6238_SECRET, "2870820", time), None);
+        assert_eq!(verify_code(RFC6238_SECRET, "28708a", time), None);
+    }
+
+    #[test]
+    fn test_encode_secret_rfc4648() {
+        assert_eq!(encode_secret(b"foobar"), "MZXW6YTBOI");
+        assert_eq!(
+            encode_secret(RFC6238_SECRET),
+            "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ"
+        );
+    }
+
+    #[test]
+    fn test_backup_code_hash_normalization() {
+        assert_eq!(
+            hash_backup_code("abcde-fghij"),
+            hash_backup_code("ABCDE FGHIJ")
+        );
+        assert_ne!(
+            hash_backup_code("ABCDEFGHIJ"),
+            hash_backup_code("ABCDEFGHIK")
+        );
+    }
+}
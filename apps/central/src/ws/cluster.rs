@@ -0,0 +1,291 @@
+use futures::StreamExt;
+use redis::aio::MultiplexedConnection;
+use redis::Client as RedisClient;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+use super::subscriptions::{PresenceInfo, SubscriptionManager};
+
+const CLUSTER_CHANNEL: &str = "ws:cluster";
+const ONLINE_USERS_KEY: &str = "ws:online";
+const NODE_TTL_SECONDS: u64 = 30;
+const NODE_HEARTBEAT_SECONDS: u64 = 10;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ClusterEvent {
+    Deliver {
+        user_id: Uuid,
+        msg: String,
+    },
+    Presence {
+        user_id: Uuid,
+        msg: String,
+    },
+    Online {
+        user_id: Uuid,
+        status: String,
+        custom_status: Option<String>,
+    },
+    Offline {
+        user_id: Uuid,
+    },
+    RevokeSessions {
+        session_ids: Vec<Uuid>,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ClusterEnvelope {
+    origin: Uuid,
+    #[serde(flatten)]
+    event: ClusterEvent,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OnlineEntry {
+    node_id: Uuid,
+    status: String,
+    custom_status: Option<String>,
+}
+
+/// Redis pub/sub bridge that lets several central nodes deliver WebSocket
+/// events to sockets held by each other.
+pub struct ClusterBus {
+    node_id: Uuid,
+    redis: RedisClient,
+    conn: MultiplexedConnection,
+}
+
+impl ClusterBus {
+    pub async fn connect(redis: RedisClient) -> anyhow::Result<Self> {
+        let conn = redis.get_multiplexed_async_connection().await?;
+        Ok(Self {
+            node_id: Uuid::new_v4(),
+            redis,
+            conn,
+        })
+    }
+
+    pub fn node_id(&self) -> Uuid {
+        self.node_id
+    }
+
+    fn node_key(node_id: Uuid) -> String {
+        format!("ws:node:{}", node_id)
+    }
+
+    pub async fn publish(&self, event: ClusterEvent) {
+        let envelope = ClusterEnvelope {
+            origin: self.node_id,
+            event,
+        };
+        let payload = match serde_json::to_string(&envelope) {
+            Ok(p) => p,
+            Err(e) => {
+                tracing::error!("Failed to serialize cluster event: {}", e);
+                return;
+            }
+        };
+
+        let mut conn = self.conn.clone();
+        let result: redis::RedisResult<()> = redis::cmd("PUBLISH")
+            .arg(CLUSTER_CHANNEL)
+            .arg(payload)
+            .query_async(&mut conn)
+            .await;
+        if let Err(e) = result {
+            tracing::error!("Failed to publish cluster event: {}", e);
+        }
+    }
+
+    pub async fn store_online(&self, user_id: Uuid, info: &PresenceInfo) {
+        let entry = OnlineEntry {
+            node_id: self.node_id,
+            status: info.status.clone(),
+            custom_status: info.custom_status.clone(),
+        };
+        let Ok(serialized) = serde_json::to_string(&entry) else {
+            return;
+        };
+
+        let mut conn = self.conn.clone();
+        let result: redis::RedisResult<()> = redis::cmd("HSET")
+            .arg(ONLINE_USERS_KEY)
+            .arg(user_id.to_string())
+            .arg(serialized)
+            .query_async(&mut conn)
+            .await;
+        if let Err(e) = result {
+            tracing::error!("Failed to store presence for user {}: {}", user_id, e);
+        }
+    }
+
+    /// Removes the user's shared presence entry if this node still owns it.
+    /// Returns false when the user has since connected through another node.
+    pub async fn remove_online(&self, user_id: Uuid) -> bool {
+        let script = redis::Script::new(
+            r#"
+            local entry = redis.call('HGET', KEYS[1], ARGV[1])
+            if not entry then
+                return 1
+            end
+            if cjson.decode(entry)['node_id'] ~= ARGV[2] then
+                return 0
+            end
+            redis.call('HDEL', KEYS[1], ARGV[1])
+            return 1
+            "#,
+        );
+
+        let mut conn = self.conn.clone();
+        let removed: redis::RedisResult<i32> = script
+            .key(ONLINE_USERS_KEY)
+            .arg(user_id.to_string())
+            .arg(self.node_id.to_string())
+            .invoke_async(&mut conn)
+            .await;
+
+        match removed {
+            Ok(removed) => removed == 1,
+            Err(e) => {
+                tracing::error!("Failed to remove presence for user {}: {}", user_id, e);
+                true
+            }
+        }
+    }
+
+    async fn load_online_users(&self, subscriptions: &SubscriptionManager) -> anyhow::Result<()> {
+        let mut conn = self.conn.clone();
+        let entries: HashMap<String, String> = redis::cmd("HGETALL")
+            .arg(ONLINE_USERS_KEY)
+            .query_async(&mut conn)
+            .await?;
+
+        let mut live_nodes: HashMap<Uuid, bool> = HashMap::new();
+        let mut loaded = 0;
+        for (user_id, entry) in entries {
+            let (Ok(user_id), Ok(entry)) = (
+                user_id.parse::<Uuid>(),
+                serde_json::from_str::<OnlineEntry>(&entry),
+            ) else {
+                continue;
+            };
+            if entry.node_id == self.node_id {
+                continue;
+            }
+
+            let alive = match live_nodes.get(&entry.node_id) {
+                Some(alive) => *alive,
+                None => {
+                    let alive: bool = redis::cmd("EXISTS")
+                        .arg(Self::node_key(entry.node_id))
+                        .query_async(&mut conn)
+                        .await?;
+                    live_nodes.insert(entry.node_id, alive);
+                    alive
+                }
+            };
+
+            if alive {
+                subscriptions.mirror_online(
+                    user_id,
+                    PresenceInfo {
+                        status: entry.status,
+                        custom_status: entry.custom_status,
+                    },
+                );
+                loaded += 1;
+            } else {
+                let _: () = redis::cmd("HDEL")
+                    .arg(ONLINE_USERS_KEY)
+                    .arg(user_id.to_string())
+                    .query_async(&mut conn)
+                    .await?;
+            }
+        }
+
+        tracing::info!("Loaded {} online users from other nodes", loaded);
+        Ok(())
+    }
+}
+
+pub async fn run_node_heartbeat(bus: Arc<ClusterBus>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(NODE_HEARTBEAT_SECONDS));
+    loop {
+        interval.tick().await;
+        let mut conn = bus.conn.clone();
+        let result: redis::RedisResult<()> = redis::cmd("SET")
+            .arg(ClusterBus::node_key(bus.node_id))
+            .arg(1)
+            .arg("EX")
+            .arg(NODE_TTL_SECONDS)
+            .query_async(&mut conn)
+            .await;
+        if let Err(e) = result {
+            tracing::error!("Failed to refresh cluster node heartbeat: {}", e);
+        }
+    }
+}
+
+pub async fn run_cluster_listener(bus: Arc<ClusterBus>, subscriptions: Arc<SubscriptionManager>) {
+    loop {
+        if let Err(e) = listen(&bus, &subscriptions).await {
+            tracing::error!("Cluster listener error: {}", e);
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+}
+
+async fn listen(bus: &ClusterBus, subscriptions: &SubscriptionManager) -> anyhow::Result<()> {
+    let mut pubsub = bus.redis.get_async_pubsub().await?;
+    pubsub.subscribe(CLUSTER_CHANNEL).await?;
+    bus.load_online_users(subscriptions).await?;
+
+    tracing::info!("Cluster listener subscribed as node {}", bus.node_id);
+
+    let mut messages = pubsub.on_message();
+    while let Some(msg) = messages.next().await {
+        let payload: String = match msg.get_payload() {
+            Ok(p) => p,
+            Err(_) => continue,
+        };
+        let envelope = match serde_json::from_str::<ClusterEnvelope>(&payload) {
+            Ok(e) => e,
+            Err(e) => {
+                tracing::warn!("Ignoring malformed cluster event: {}", e);
+                continue;
+            }
+        };
+        if envelope.origin == bus.node_id {
+            continue;
+        }
+
+        match envelope.event {
+            ClusterEvent::Deliver { user_id, msg } => subscriptions.deliver_local(user_id, &msg),
+            ClusterEvent::Presence { user_id, msg } => {
+                subscriptions.deliver_presence_local(user_id, &msg)
+            }
+            ClusterEvent::Online {
+                user_id,
+                status,
+                custom_status,
+            } => subscriptions.mirror_online(
+                user_id,
+                PresenceInfo {
+                    status,
+                    custom_status,
+                },
+            ),
+            ClusterEvent::Offline { user_id } => subscriptions.mirror_offline(user_id),
+            ClusterEvent::RevokeSessions { session_ids } => {
+                subscriptions.close_sessions_local(&session_ids)
+            }
+        }
+    }
+
+    Err(anyhow::anyhow!("cluster subscription closed"))
+}
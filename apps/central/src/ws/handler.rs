@@ -1,12 +1,18 @@
 use axum::extract::ws::{Message, WebSocket};
+use chrono::{DateTime, Utc};
+use futures::stream::SplitSink;
 use futures::{SinkExt, StreamExt};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::sync::Arc;
 use tokio::sync::mpsc;
 use uuid::Uuid;
 
+use crate::models::IdentityKeyRecord;
 use crate::AppState;
 
+use super::replay;
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(tag = "type", content = "data")]
 pub enum WsMessage {
@@ -18,6 +24,16 @@ pub enum WsMessage {
     MessageEdited(MessageEditedData),
     #[serde(rename = "message_pinned")]
     MessagePinned(MessagePinnedData),
+    #[serde(rename = "thread_reply")]
+    ThreadReply(ThreadReplyData),
+    #[serde(rename = "thread_updated")]
+    ThreadUpdated(ThreadUpdatedData),
+    #[serde(rename = "read_receipt")]
+    ReadReceipt(ReadReceiptData),
+    #[serde(rename = "conversation_settings_updated")]
+    ConversationSettingsUpdated(ConversationSettingsUpdatedData),
+    #[serde(rename = "notification_settings_updated")]
+    NotificationSettingsUpdated(crate::models::NotificationSettings),
     #[serde(rename = "message_unpinned")]
     MessageUnpinned(MessageUnpinnedData),
     #[serde(rename = "reaction_added")]
@@ -44,8 +60,30 @@ pub enum WsMessage {
     FriendAccepted(FriendAcceptedData),
     #[serde(rename = "friend_removed")]
     FriendRemoved(FriendRemovedData),
+    #[serde(rename = "account_deleted")]
+    AccountDeleted(AccountDeletedData),
+    #[serde(rename = "user_renamed")]
+    UserRenamed(UserRenamedData),
+    #[serde(rename = "data_export_ready")]
+    DataExportReady(DataExportReadyData),
+    #[serde(rename = "data_export_failed")]
+    DataExportFailed(DataExportFailedData),
+    #[serde(rename = "scheduled_message_failed")]
+    ScheduledMessageFailed(ScheduledMessageFailedData),
     #[serde(rename = "key_update")]
     KeyUpdate(KeyUpdateData),
+    #[serde(rename = "identity_key_changed")]
+    IdentityKeyChanged(IdentityKeyChangedData),
+    #[serde(rename = "verified_contacts_updated")]
+    VerifiedContactsUpdated,
+    #[serde(rename = "device_link_requested")]
+    DeviceLinkRequested(DeviceLinkRequestedData),
+    #[serde(rename = "device_link_completed")]
+    DeviceLinkCompleted(DeviceLinkCompletedData),
+    #[serde(rename = "prekeys_low")]
+    PrekeysLow(PrekeysLowData),
+    #[serde(rename = "signed_prekey_stale")]
+    SignedPrekeyStale(SignedPrekeyStaleData),
     #[serde(rename = "key_exchange")]
     KeyExchange(KeyExchangeData),
     #[serde(rename = "typing")]
@@ -64,6 +102,12 @@ pub enum WsMessage {
     Ping,
     #[serde(rename = "pong")]
     Pong,
+    #[serde(rename = "resumed")]
+    Resumed(ResumedData),
+    #[serde(rename = "reauth")]
+    Reauth(ReauthData),
+    #[serde(rename = "reauthenticated")]
+    Reauthenticated(ReauthenticatedData),
 
     #[serde(rename = "call_offer")]
     CallOffer(CallOfferData),
@@ -122,6 +166,28 @@ pub struct SubscribeUserData {
     pub user_id: Uuid,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ResumeData {
+    pub last_seq: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResumedData {
+    pub last_seq: u64,
+    pub replayed: usize,
+    pub truncated: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReauthData {
+    pub token: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReauthenticatedData {
+    pub expires_at: DateTime<Utc>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UpdatePresenceData {
     pub status: String,
@@ -143,6 +209,8 @@ pub struct NewMessageData {
     pub call_id: Option<Uuid>,
     pub call_duration_seconds: Option<i32>,
     pub pinned_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mentioned_user_ids: Vec<Uuid>,
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
 
@@ -176,6 +244,37 @@ pub struct MessageUnpinnedData {
     pub unpinner_id: Uuid,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ThreadReplyData {
+    pub thread_root_id: Uuid,
+    pub reply_count: i64,
+    pub last_reply_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub message: NewMessageData,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ThreadUpdatedData {
+    pub conversation_id: Uuid,
+    pub root_message_id: Uuid,
+    pub reply_count: i64,
+    pub last_reply_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConversationSettingsUpdatedData {
+    pub conversation_id: Uuid,
+    pub updated_by: Uuid,
+    pub settings: crate::models::ConversationSettings,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReadReceiptData {
+    pub conversation_id: Uuid,
+    pub user_id: Uuid,
+    pub last_read_message_id: Uuid,
+    pub read_at: chrono::DateTime<chrono::Utc>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ReactionAddedData {
     pub id: Uuid,
@@ -211,10 +310,85 @@ pub struct FriendRemovedData {
     pub by_user_id: Uuid,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AccountDeletedData {
+    pub user_id: Uuid,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserRenamedData {
+    pub user_id: Uuid,
+    pub old_username: String,
+    pub new_username: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DataExportReadyData {
+    pub export_id: Uuid,
+    pub size_bytes: i64,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DataExportFailedData {
+    pub export_id: Uuid,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScheduledMessageFailedData {
+    pub scheduled_message_id: Uuid,
+    pub conversation_id: Uuid,
+    pub reason: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct KeyUpdateData {
     pub user_id: Uuid,
     pub kem_public_key: Vec<u8>,
+    pub dsa_public_key: Vec<u8>,
+    pub key_version: i32,
+    /// Signature by the previous DSA key, see `GET /api/keys/identity/{user_id}`.
+    pub continuity_signature: Option<Vec<u8>>,
+}
+
+/// Sent to everyone who may have verified `user_id`. Devices check it against
+/// their verified contacts and mark the contact as unverified.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IdentityKeyChangedData {
+    pub user_id: Uuid,
+    pub key_version: i32,
+}
+
+/// Sent to the account owner when a new device scans a link QR code. The
+/// approving device decrypts `encrypted_transfer_key` with the link's
+/// ephemeral secret key.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeviceLinkRequestedData {
+    pub link_id: Uuid,
+    pub device_name: Option<String>,
+    pub platform: Option<String>,
+    pub encrypted_transfer_key: Vec<u8>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeviceLinkCompletedData {
+    pub link_id: Uuid,
+    pub device_name: Option<String>,
+}
+
+/// Sent to the owner when claims drop their one-time prekey pool under the
+/// configured low-water mark.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PrekeysLowData {
+    pub remaining: i64,
+    pub low_water_mark: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SignedPrekeyStaleData {
+    pub signed_prekey_id: i32,
+    pub created_at: DateTime<Utc>,
+    pub max_age_days: i64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -484,13 +658,50 @@ pub struct GroupCallSenderKeyData {
     pub sender_ephemeral_public: Vec<u8>,
 }
 
-pub async fn handle_socket(socket: WebSocket, state: Arc<AppState>, user_id: Uuid) {
+pub async fn handle_socket(
+    socket: WebSocket,
+    state: Arc<AppState>,
+    user_id: Uuid,
+    session_id: Uuid,
+    mut access_expires_at: DateTime<Utc>,
+    resume: Option<ResumeData>,
+) {
     tracing::info!("WebSocket connected for user {}", user_id);
 
     let (mut ws_sender, mut ws_receiver) = socket.split();
     let (tx, mut rx) = mpsc::channel::<String>(100);
 
-    state.subscriptions.add_connection(user_id, tx.clone());
+    let closer = state
+        .subscriptions
+        .add_connection(user_id, session_id, tx.clone());
+
+    // Live events keep arriving while the replay is written out. They are
+    // moved off the bounded queue as they come so none are dropped.
+    let mut pending = Vec::new();
+    let replayed_seq = replay_missed_events(
+        &state,
+        user_id,
+        resume,
+        &mut ws_sender,
+        &mut rx,
+        &mut pending,
+    )
+    .await;
+
+    let send_task = tokio::spawn(async move {
+        let mut pending = pending.into_iter();
+        while let Some(msg) = match pending.next() {
+            Some(msg) => Some(msg),
+            None => rx.recv().await,
+        } {
+            if replay::seq_of(&msg).is_some_and(|seq| seq <= replayed_seq) {
+                continue;
+            }
+            if ws_sender.send(Message::Text(msg.into())).await.is_err() {
+                break;
+            }
+        }
+    });
 
     let (status, custom_status) = state
         .db
@@ -499,7 +710,8 @@ pub async fn handle_socket(socket: WebSocket, state: Arc<AppState>, user_id: Uui
         .unwrap_or(("online".to_string(), None));
     state
         .subscriptions
-        .set_online(user_id, &status, custom_status.clone());
+        .set_online(user_id, &status, custom_status.clone())
+        .await;
 
     broadcast_presence(&state, user_id, true).await;
 
@@ -528,17 +740,27 @@ pub async fn handle_socket(socket: WebSocket, state: Arc<AppState>, user_id: Uui
         }
     }
 
-    let send_task = tokio::spawn(async move {
-        while let Some(msg) = rx.recv().await {
-            if ws_sender.send(Message::Text(msg.into())).await.is_err() {
+    let state_clone = state.clone();
+    let tx_clone = tx.clone();
+    loop {
+        let until_expiry = (access_expires_at - Utc::now())
+            .to_std()
+            .unwrap_or_default();
+        let msg = tokio::select! {
+            msg = ws_receiver.next() => msg,
+            _ = closer.notified() => {
+                tracing::info!("Session {} revoked, closing WebSocket for user {}", session_id, user_id);
                 break;
             }
-        }
-    });
+            _ = tokio::time::sleep(until_expiry) => {
+                tracing::info!("Access token expired, closing WebSocket for user {}", user_id);
+                break;
+            }
+        };
+        let Some(Ok(msg)) = msg else {
+            break;
+        };
 
-    let state_clone = state.clone();
-    let tx_clone = tx.clone();
-    while let Some(Ok(msg)) = ws_receiver.next().await {
         match msg {
             Message::Text(text) => {
                 const MAX_WS_MESSAGE_SIZE: usize = 64 * 1024;
@@ -550,8 +772,25 @@ pub async fn handle_socket(socket: WebSocket, state: Arc<AppState>, user_id: Uui
                     );
                     break;
                 }
-                if let Ok(ws_msg) = serde_json::from_str::<WsMessage>(&text) {
-                    handle_client_message(&state_clone, user_id, ws_msg, &tx_clone).await;
+                match serde_json::from_str::<WsMessage>(&text) {
+                    Ok(WsMessage::Reauth(data)) => {
+                        let Some(expires_at) =
+                            reauthenticate(&state_clone, session_id, &data.token).await
+                        else {
+                            tracing::warn!("WebSocket re-auth failed for user {}", user_id);
+                            break;
+                        };
+                        access_expires_at = expires_at;
+                        let reauth_msg =
+                            WsMessage::Reauthenticated(ReauthenticatedData { expires_at });
+                        if let Ok(json) = serde_json::to_string(&reauth_msg) {
+                            let _ = tx_clone.send(json).await;
+                        }
+                    }
+                    Ok(ws_msg) => {
+                        handle_client_message(&state_clone, user_id, ws_msg, &tx_clone).await;
+                    }
+                    Err(_) => {}
                 }
             }
             Message::Close(_) => break,
@@ -560,13 +799,102 @@ pub async fn handle_socket(socket: WebSocket, state: Arc<AppState>, user_id: Uui
     }
 
     send_task.abort();
-    state.subscriptions.remove_connection(user_id);
-    state.subscriptions.set_offline(user_id);
-    broadcast_presence(&state, user_id, false).await;
+    let last_connection = state
+        .subscriptions
+        .remove_connection(user_id, session_id, &tx, &closer);
+    if last_connection {
+        state.subscriptions.set_offline(user_id).await;
+        broadcast_presence(&state, user_id, false).await;
+    }
 
     tracing::debug!("WebSocket connection closed for user {}", user_id);
 }
 
+/// Checks a refreshed access token sent over an open socket. It has to
+/// belong to the session the socket was opened with; returns its expiry.
+async fn reauthenticate(state: &AppState, session_id: Uuid, token: &str) -> Option<DateTime<Utc>> {
+    let token_bytes = hex::decode(token).ok()?;
+    let token_hash = Sha256::digest(&token_bytes).to_vec();
+    let session = state
+        .db
+        .get_session_by_token_hash(&token_hash)
+        .await
+        .ok()??;
+
+    (session.id == session_id && session.access_expires_at > Utc::now())
+        .then_some(session.access_expires_at)
+}
+
+async fn replay_missed_events(
+    state: &AppState,
+    user_id: Uuid,
+    resume: Option<ResumeData>,
+    ws_sender: &mut SplitSink<WebSocket, Message>,
+    rx: &mut mpsc::Receiver<String>,
+    pending: &mut Vec<String>,
+) -> u64 {
+    let after = resume.map(|r| r.last_seq);
+    let batch = match buffering(
+        rx,
+        pending,
+        state.subscriptions.replay_after(user_id, after),
+    )
+    .await
+    {
+        Ok(Some(batch)) => batch,
+        Ok(None) => return 0,
+        Err(e) => {
+            tracing::error!("Failed to load missed events for user {}: {}", user_id, e);
+            return 0;
+        }
+    };
+
+    let replayed = batch.events.len();
+    for event in batch.events {
+        let sent = buffering(rx, pending, ws_sender.send(Message::Text(event.into()))).await;
+        if sent.is_err() {
+            return batch.last_seq;
+        }
+    }
+
+    if replayed > 0 {
+        tracing::debug!("Replayed {} missed events for user {}", replayed, user_id);
+    }
+
+    let resumed_msg = WsMessage::Resumed(ResumedData {
+        last_seq: batch.last_seq,
+        replayed,
+        truncated: batch.truncated,
+    });
+    if let Ok(json) = serde_json::to_string(&resumed_msg) {
+        let _ = buffering(rx, pending, ws_sender.send(Message::Text(json.into()))).await;
+    }
+
+    // Without a resume cursor nothing was replayed, so live events already
+    // queued for this connection must not be filtered out.
+    if after.is_some() {
+        batch.last_seq
+    } else {
+        0
+    }
+}
+
+/// Drives `fut` to completion while moving anything queued for the
+/// connection into `pending`, so the bounded queue never overflows.
+async fn buffering<F: std::future::Future>(
+    rx: &mut mpsc::Receiver<String>,
+    pending: &mut Vec<String>,
+    fut: F,
+) -> F::Output {
+    tokio::pin!(fut);
+    loop {
+        tokio::select! {
+            out = &mut fut => return out,
+            Some(live) = rx.recv() => pending.push(live),
+        }
+    }
+}
+
 async fn handle_client_message(
     state: &AppState,
     user_id: Uuid,
@@ -671,7 +999,8 @@ async fn handle_client_message(
             } else {
                 state
                     .subscriptions
-                    .set_online(user_id, &data.status, data.custom_status);
+                    .set_online(user_id, &data.status, data.custom_status)
+                    .await;
                 broadcast_presence(state, user_id, true).await;
             }
         }
@@ -726,7 +1055,8 @@ async fn broadcast_presence(state: &AppState, user_id: Uuid, online: bool) {
     }
 }
 
-pub async fn broadcast_key_update(state: &AppState, user_id: Uuid, kem_public_key: Vec<u8>) {
+pub async fn broadcast_key_update(state: &AppState, key: IdentityKeyRecord) {
+    let user_id = key.user_id;
     let conversations = match state.db.get_user_conversations(user_id).await {
         Ok(convs) => convs,
         Err(e) => {
@@ -740,7 +1070,10 @@ pub async fn broadcast_key_update(state: &AppState, user_id: Uuid, kem_public_ke
 
     let key_update_msg = WsMessage::KeyUpdate(KeyUpdateData {
         user_id,
-        kem_public_key,
+        kem_public_key: key.kem_public_key,
+        dsa_public_key: key.dsa_public_key,
+        key_version: key.version,
+        continuity_signature: key.continuity_signature,
     });
 
     if let Ok(json) = serde_json::to_string(&key_update_msg) {
@@ -752,6 +1085,10 @@ pub async fn broadcast_key_update(state: &AppState, user_id: Uuid, kem_public_ke
         {
             state.subscriptions.send_to_users(&user_ids, &json).await;
         }
+        state
+            .subscriptions
+            .broadcast_presence_update(user_id, &json)
+            .await;
     }
 }
 
@@ -1,5 +1,7 @@
 pub mod call_recovery;
+pub mod cluster;
 pub mod handler;
+pub mod replay;
 pub mod subscriptions;
 
 pub use handler::{
@@ -7,15 +9,19 @@ pub use handler::{
     send_call_end, send_call_key_complete, send_call_leave, send_call_media_ready, send_call_offer,
     send_call_reject, send_call_rejoin, send_group_call_ended, send_group_call_mute_update,
     send_group_call_participant_joined, send_group_call_participant_left, send_group_call_ring,
-    send_group_call_sender_key, send_new_message, CallAnswerData, CallCancelData, CallEndData,
-    CallKeyCompleteData, CallLeaveData, CallMediaReadyData, CallOfferData, CallRejectData,
-    CallRejoinData, FriendAcceptedData, FriendRemovedData, FriendRequestData, GroupCallEndedData,
+    send_group_call_sender_key, send_new_message, AccountDeletedData, CallAnswerData,
+    CallCancelData, CallEndData, CallKeyCompleteData, CallLeaveData, CallMediaReadyData,
+    CallOfferData, CallRejectData, CallRejoinData, ConversationSettingsUpdatedData,
+    DataExportFailedData, DataExportReadyData, DeviceLinkCompletedData, DeviceLinkRequestedData,
+    FriendAcceptedData, FriendRemovedData, FriendRequestData, GroupCallEndedData,
     GroupCallMuteUpdateData, GroupCallParticipantJoinedData, GroupCallParticipantLeftData,
     GroupCallRingData, GroupCallSenderKeyData, GroupCreatedData, GroupDeletedData,
     GroupMemberAddedData, GroupMemberLeftData, GroupMemberRemovedData, GroupMetadataUpdatedData,
-    GroupOwnerChangedData, KeyExchangeData, MessageDeletedData, MessageEditedData,
-    MessagePinnedData, MessageUnpinnedData, NewMessageData, PresenceData, ReactionAddedData,
-    ReactionRemovedData, WsMessage,
+    GroupOwnerChangedData, IdentityKeyChangedData, KeyExchangeData, MessageDeletedData,
+    MessageEditedData, MessagePinnedData, MessageUnpinnedData, NewMessageData, PrekeysLowData,
+    PresenceData, ReactionAddedData, ReactionRemovedData, ReadReceiptData,
+    ScheduledMessageFailedData, SignedPrekeyStaleData, ThreadReplyData, ThreadUpdatedData,
+    UserRenamedData, WsMessage,
 };
 
 use axum::{
@@ -47,6 +53,8 @@ struct AuthMessage {
 #[derive(Debug, Deserialize)]
 struct AuthData {
     token: String,
+    #[serde(default)]
+    resume: Option<handler::ResumeData>,
 }
 
 async fn ws_handler(
@@ -61,7 +69,7 @@ async fn ws_handler(
                 if let Message::Text(text) = msg {
                     if let Ok(auth_msg) = serde_json::from_str::<AuthMessage>(&text) {
                         if auth_msg.msg_type == "auth" {
-                            return Some(auth_msg.data.token);
+                            return Some(auth_msg.data);
                         }
                     }
                 }
@@ -70,8 +78,8 @@ async fn ws_handler(
         })
         .await;
 
-        let token = match auth_result {
-            Ok(Some(t)) => t,
+        let AuthData { token, resume } = match auth_result {
+            Ok(Some(data)) => data,
             _ => {
                 tracing::warn!("WebSocket auth timeout or missing auth message");
                 return;
@@ -88,15 +96,26 @@ async fn ws_handler(
 
         let token_hash = Sha256::digest(&token_bytes).to_vec();
         let session = match state.db.get_session_by_token_hash(&token_hash).await {
-            Ok(Some(s)) => s,
+            Ok(Some(s)) if s.access_expires_at > chrono::Utc::now() => s,
             _ => {
                 tracing::warn!("WebSocket invalid session");
                 return;
             }
         };
 
-        let user_id = session.user_id;
+        if let Err(e) = state.db.touch_session(session.id).await {
+            tracing::warn!("Failed to update session activity: {}", e);
+        }
+
         let socket = sender.reunite(receiver).expect("reunite failed");
-        handler::handle_socket(socket, state, user_id).await;
+        handler::handle_socket(
+            socket,
+            state,
+            session.user_id,
+            session.id,
+            session.access_expires_at,
+            resume,
+        )
+        .await;
     }))
 }
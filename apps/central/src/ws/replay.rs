@@ -0,0 +1,218 @@
+use redis::Client as RedisClient;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::error::{AppError, Result};
+
+const EPHEMERAL_EVENTS: &[&str] = &[
+    "typing",
+    "presence_update",
+    "presence_sync",
+    "activity_update",
+    "pong",
+    "resumed",
+];
+
+const EPHEMERAL_PREFIXES: &[&str] = &["call_", "group_call_"];
+
+/// Per-user event log backed by a Redis stream, used to replay events a
+/// client missed while its socket was down.
+pub struct EventLog {
+    redis: RedisClient,
+    max_events: usize,
+    ttl_seconds: u64,
+}
+
+pub struct ReplayBatch {
+    pub events: Vec<String>,
+    pub last_seq: u64,
+    pub truncated: bool,
+}
+
+impl EventLog {
+    pub fn new(redis: RedisClient, max_events: usize, ttl_seconds: u64) -> Self {
+        Self {
+            redis,
+            max_events,
+            ttl_seconds,
+        }
+    }
+
+    fn seq_key(user_id: Uuid) -> String {
+        format!("ws:seq:{}", user_id)
+    }
+
+    fn stream_key(user_id: Uuid) -> String {
+        format!("ws:events:{}", user_id)
+    }
+
+    /// Assigns the next sequence number for `user_id` and stores the event
+    /// under it. Returns the assigned sequence number.
+    pub async fn append(&self, user_id: Uuid, msg: &str) -> Result<u64> {
+        let mut conn = self
+            .redis
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| AppError::Internal(anyhow::anyhow!("Redis error: {}", e)))?;
+
+        let script = redis::Script::new(
+            r#"
+            local seq = redis.call('INCR', KEYS[1])
+            redis.call('XADD', KEYS[2], 'MAXLEN', '~', ARGV[2], seq .. '-0', 'event', ARGV[1])
+            redis.call('EXPIRE', KEYS[1], ARGV[3])
+            redis.call('EXPIRE', KEYS[2], ARGV[3])
+            return seq
+            "#,
+        );
+
+        let seq: u64 = script
+            .key(Self::seq_key(user_id))
+            .key(Self::stream_key(user_id))
+            .arg(msg)
+            .arg(self.max_events)
+            .arg(self.ttl_seconds)
+            .invoke_async(&mut conn)
+            .await
+            .map_err(|e| AppError::Internal(anyhow::anyhow!("Redis error: {}", e)))?;
+
+        Ok(seq)
+    }
+
+    /// Returns every retained event with a sequence number greater than
+    /// `after`, already tagged with its `seq`. `truncated` is set when some of
+    /// the requested events have been trimmed or expired.
+    pub async fn read_after(&self, user_id: Uuid, after: Option<u64>) -> Result<ReplayBatch> {
+        let mut conn = self
+            .redis
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| AppError::Internal(anyhow::anyhow!("Redis error: {}", e)))?;
+
+        let last_seq: Option<u64> = redis::cmd("GET")
+            .arg(Self::seq_key(user_id))
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| AppError::Internal(anyhow::anyhow!("Redis error: {}", e)))?;
+        let last_seq = last_seq.unwrap_or(0);
+
+        let after = match after {
+            Some(after) if after < last_seq => after,
+            _ => {
+                return Ok(ReplayBatch {
+                    events: Vec::new(),
+                    last_seq,
+                    truncated: after.is_some_and(|a| a > last_seq),
+                })
+            }
+        };
+
+        let entries: Vec<(String, HashMap<String, String>)> = redis::cmd("XRANGE")
+            .arg(Self::stream_key(user_id))
+            .arg(format!("{}-0", after + 1))
+            .arg("+")
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| AppError::Internal(anyhow::anyhow!("Redis error: {}", e)))?;
+
+        let mut events = Vec::with_capacity(entries.len());
+        let mut first_seq = None;
+        let mut replayed_seq = after;
+        for (id, mut fields) in entries {
+            let Some(seq) = id.split('-').next().and_then(|s| s.parse::<u64>().ok()) else {
+                continue;
+            };
+            let Some(event) = fields.remove("event") else {
+                continue;
+            };
+            first_seq.get_or_insert(seq);
+            replayed_seq = replayed_seq.max(seq);
+            events.push(with_seq(&event, seq));
+        }
+
+        let truncated = first_seq.is_none_or(|first| first > after + 1);
+
+        Ok(ReplayBatch {
+            events,
+            last_seq: replayed_seq.max(last_seq),
+            truncated,
+        })
+    }
+}
+
+fn event_type(msg: &str) -> Option<&str> {
+    let rest = msg.strip_prefix("{\"type\":\"")?;
+    rest.split('"').next()
+}
+
+pub fn is_replayable(msg: &str) -> bool {
+    match event_type(msg) {
+        Some(t) => {
+            !EPHEMERAL_EVENTS.contains(&t) && !EPHEMERAL_PREFIXES.iter().any(|p| t.starts_with(p))
+        }
+        None => false,
+    }
+}
+
+pub fn with_seq(msg: &str, seq: u64) -> String {
+    match msg.strip_prefix('{') {
+        Some(rest) => format!("{{\"seq\":{},{}", seq, rest),
+        None => msg.to_string(),
+    }
+}
+
+pub fn seq_of(msg: &str) -> Option<u64> {
+    let rest = msg.strip_prefix("{\"seq\":")?;
+    let end = rest.find(',')?;
+    rest[..end].parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ws::handler::{MessageDeletedData, ResumedData, WsMessage};
+
+    // `event_type` and `seq_of` read the raw JSON by prefix, which relies on
+    // serde writing the `type` tag first for adjacently tagged enums.
+    fn message_deleted() -> String {
+        serde_json::to_string(&WsMessage::MessageDeleted(MessageDeletedData {
+            message_id: Uuid::nil(),
+            conversation_id: Uuid::nil(),
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_type_tag_is_serialized_first() {
+        assert_eq!(event_type(&message_deleted()), Some("message_deleted"));
+        assert_eq!(
+            event_type(&serde_json::to_string(&WsMessage::Ping).unwrap()),
+            Some("ping")
+        );
+    }
+
+    #[test]
+    fn test_is_replayable() {
+        assert!(is_replayable(&message_deleted()));
+
+        let resumed = WsMessage::Resumed(ResumedData {
+            last_seq: 1,
+            replayed: 0,
+            truncated: false,
+        });
+        assert!(!is_replayable(&serde_json::to_string(&resumed).unwrap()));
+        assert!(!is_replayable(r#"{"type":"call_offer","data":{}}"#));
+        assert!(!is_replayable("not json"));
+    }
+
+    #[test]
+    fn test_with_seq_round_trips() {
+        let tagged = with_seq(&message_deleted(), 42);
+        assert_eq!(seq_of(&tagged), Some(42));
+        assert_eq!(seq_of(&message_deleted()), None);
+
+        let value: serde_json::Value = serde_json::from_str(&tagged).unwrap();
+        assert_eq!(value["seq"], 42);
+        assert_eq!(value["type"], "message_deleted");
+        assert!(serde_json::from_str::<WsMessage>(&tagged).is_ok());
+    }
+}
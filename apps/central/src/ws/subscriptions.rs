@@ -1,9 +1,13 @@
 use dashmap::DashMap;
 use std::collections::HashSet;
 use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
-use tokio::sync::mpsc;
+use std::sync::Arc;
+use tokio::sync::{mpsc, Notify};
 use uuid::Uuid;
 
+use super::cluster::{ClusterBus, ClusterEvent};
+use super::replay::{self, EventLog, ReplayBatch};
+
 pub type MessageSender = mpsc::Sender<String>;
 
 #[derive(Clone, Debug)]
@@ -21,10 +25,13 @@ pub struct SubscriptionMetrics {
 
 pub struct SubscriptionManager {
     user_senders: DashMap<Uuid, Vec<MessageSender>>,
+    session_closers: DashMap<Uuid, Vec<Arc<Notify>>>,
     conversation_subs: DashMap<Uuid, HashSet<Uuid>>,
     user_conversations: DashMap<Uuid, HashSet<Uuid>>,
     presence_subs: DashMap<Uuid, HashSet<Uuid>>,
     online_users: DashMap<Uuid, PresenceInfo>,
+    event_log: Option<EventLog>,
+    cluster: Option<Arc<ClusterBus>>,
     pub metrics: SubscriptionMetrics,
 }
 
@@ -32,15 +39,46 @@ impl SubscriptionManager {
     pub fn new() -> Self {
         Self {
             user_senders: DashMap::new(),
+            session_closers: DashMap::new(),
             conversation_subs: DashMap::new(),
             user_conversations: DashMap::new(),
             presence_subs: DashMap::new(),
             online_users: DashMap::new(),
+            event_log: None,
+            cluster: None,
             metrics: SubscriptionMetrics::default(),
         }
     }
 
-    pub fn add_connection(&self, user_id: Uuid, sender: MessageSender) {
+    pub fn with_event_log(mut self, event_log: EventLog) -> Self {
+        self.event_log = Some(event_log);
+        self
+    }
+
+    pub fn with_cluster(mut self, cluster: Arc<ClusterBus>) -> Self {
+        self.cluster = Some(cluster);
+        self
+    }
+
+    pub async fn replay_after(
+        &self,
+        user_id: Uuid,
+        after: Option<u64>,
+    ) -> crate::error::Result<Option<ReplayBatch>> {
+        match &self.event_log {
+            Some(log) => Ok(Some(log.read_after(user_id, after).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Registers a socket for `user_id` opened with `session_id`. The returned
+    /// handle is notified when the session is revoked.
+    pub fn add_connection(
+        &self,
+        user_id: Uuid,
+        session_id: Uuid,
+        sender: MessageSender,
+    ) -> Arc<Notify> {
         self.user_senders.entry(user_id).or_default().push(sender);
         self.metrics
             .active_connections
@@ -50,17 +88,50 @@ impl SubscriptionManager {
             self.metrics.active_users.fetch_add(1, Ordering::Relaxed);
         }
 
+        let closer = Arc::new(Notify::new());
+        self.session_closers
+            .entry(session_id)
+            .or_default()
+            .push(closer.clone());
+
         tracing::debug!("Added connection for user {}", user_id);
+        closer
     }
 
-    pub fn remove_connection(&self, user_id: Uuid) {
-        if let Some((_, senders)) = self.user_senders.remove(&user_id) {
+    /// Removes a single socket. Returns true when it was the user's last
+    /// connection on this node.
+    pub fn remove_connection(
+        &self,
+        user_id: Uuid,
+        session_id: Uuid,
+        sender: &MessageSender,
+        closer: &Arc<Notify>,
+    ) -> bool {
+        if let Some(mut senders) = self.user_senders.get_mut(&user_id) {
+            let before = senders.len();
+            senders.retain(|s| !s.same_channel(sender));
             self.metrics
                 .active_connections
-                .fetch_sub(senders.len(), Ordering::Relaxed);
-            self.metrics.active_users.fetch_sub(1, Ordering::Relaxed);
+                .fetch_sub(before - senders.len(), Ordering::Relaxed);
         }
 
+        if let Some(mut closers) = self.session_closers.get_mut(&session_id) {
+            closers.retain(|c| !Arc::ptr_eq(c, closer));
+        }
+        self.session_closers
+            .remove_if(&session_id, |_, closers| closers.is_empty());
+
+        if self
+            .user_senders
+            .remove_if(&user_id, |_, senders| senders.is_empty())
+            .is_none()
+        {
+            tracing::debug!("Removed connection for user {}", user_id);
+            return false;
+        }
+
+        self.metrics.active_users.fetch_sub(1, Ordering::Relaxed);
+
         if let Some((_, conv_ids)) = self.user_conversations.remove(&user_id) {
             for conv_id in conv_ids {
                 if let Some(mut subs) = self.conversation_subs.get_mut(&conv_id) {
@@ -70,6 +141,36 @@ impl SubscriptionManager {
         }
 
         tracing::debug!("Removed all connections for user {}", user_id);
+        true
+    }
+
+    /// Closes every socket opened with one of `session_ids`, on this node and
+    /// on the rest of the cluster.
+    pub async fn revoke_sessions(&self, session_ids: &[Uuid]) {
+        if session_ids.is_empty() {
+            return;
+        }
+
+        self.close_sessions_local(session_ids);
+
+        if let Some(cluster) = &self.cluster {
+            cluster
+                .publish(ClusterEvent::RevokeSessions {
+                    session_ids: session_ids.to_vec(),
+                })
+                .await;
+        }
+    }
+
+    pub fn close_sessions_local(&self, session_ids: &[Uuid]) {
+        for session_id in session_ids {
+            if let Some((_, closers)) = self.session_closers.remove(session_id) {
+                for closer in closers {
+                    closer.notify_one();
+                }
+                tracing::debug!("Closed connections for revoked session {}", session_id);
+            }
+        }
     }
 
     pub fn subscribe_conversation(&self, user_id: Uuid, conv_id: Uuid) {
@@ -98,18 +199,47 @@ impl SubscriptionManager {
         );
     }
 
-    pub fn set_online(&self, user_id: Uuid, status: &str, custom_status: Option<String>) {
-        self.online_users.insert(
-            user_id,
-            PresenceInfo {
-                status: status.to_string(),
-                custom_status,
-            },
-        );
+    pub async fn set_online(&self, user_id: Uuid, status: &str, custom_status: Option<String>) {
+        let info = PresenceInfo {
+            status: status.to_string(),
+            custom_status,
+        };
+        self.online_users.insert(user_id, info.clone());
+
+        if let Some(cluster) = &self.cluster {
+            cluster.store_online(user_id, &info).await;
+            cluster
+                .publish(ClusterEvent::Online {
+                    user_id,
+                    status: info.status,
+                    custom_status: info.custom_status,
+                })
+                .await;
+        }
+    }
+
+    pub async fn set_offline(&self, user_id: Uuid) {
+        match &self.cluster {
+            Some(cluster) => {
+                if cluster.remove_online(user_id).await {
+                    self.online_users.remove(&user_id);
+                    cluster.publish(ClusterEvent::Offline { user_id }).await;
+                }
+            }
+            None => {
+                self.online_users.remove(&user_id);
+            }
+        }
+    }
+
+    pub fn mirror_online(&self, user_id: Uuid, info: PresenceInfo) {
+        self.online_users.insert(user_id, info);
     }
 
-    pub fn set_offline(&self, user_id: Uuid) {
-        self.online_users.remove(&user_id);
+    pub fn mirror_offline(&self, user_id: Uuid) {
+        if !self.user_senders.contains_key(&user_id) {
+            self.online_users.remove(&user_id);
+        }
     }
 
     pub fn is_online(&self, user_id: Uuid) -> bool {
@@ -125,6 +255,31 @@ impl SubscriptionManager {
     }
 
     pub async fn send_to_user(&self, user_id: Uuid, msg: &str) {
+        let sequenced = match &self.event_log {
+            Some(log) if replay::is_replayable(msg) => match log.append(user_id, msg).await {
+                Ok(seq) => Some(replay::with_seq(msg, seq)),
+                Err(e) => {
+                    tracing::error!("Failed to persist event for user {}: {}", user_id, e);
+                    None
+                }
+            },
+            _ => None,
+        };
+        let msg = sequenced.as_deref().unwrap_or(msg);
+
+        self.deliver_local(user_id, msg);
+
+        if let Some(cluster) = &self.cluster {
+            cluster
+                .publish(ClusterEvent::Deliver {
+                    user_id,
+                    msg: msg.to_string(),
+                })
+                .await;
+        }
+    }
+
+    pub fn deliver_local(&self, user_id: Uuid, msg: &str) {
         if let Some(senders) = self.user_senders.get(&user_id) {
             for sender in senders.iter() {
                 if sender.try_send(msg.to_string()).is_ok() {
@@ -166,9 +321,22 @@ impl SubscriptionManager {
     }
 
     pub async fn broadcast_presence_update(&self, user_id: Uuid, msg: &str) {
+        self.deliver_presence_local(user_id, msg);
+
+        if let Some(cluster) = &self.cluster {
+            cluster
+                .publish(ClusterEvent::Presence {
+                    user_id,
+                    msg: msg.to_string(),
+                })
+                .await;
+        }
+    }
+
+    pub fn deliver_presence_local(&self, user_id: Uuid, msg: &str) {
         if let Some(watchers) = self.presence_subs.get(&user_id) {
             for watcher_id in watchers.iter() {
-                self.send_to_user(*watcher_id, msg).await;
+                self.deliver_local(*watcher_id, msg);
             }
         }
     }
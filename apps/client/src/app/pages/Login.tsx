@@ -1,6 +1,6 @@
 import { useState } from "react";
 import { useNavigate, Link } from "react-router-dom";
-import { useAuth } from "@/context/AuthContext";
+import { TwoFactorRequiredError, useAuth } from "@/context/AuthContext";
 import { FontAwesomeIcon } from "@fortawesome/react-fontawesome";
 
 export function Login() {
@@ -8,7 +8,9 @@ export function Login() {
   const [password, setPassword] = useState("");
   const [error, setError] = useState("");
   const [isLoading, setIsLoading] = useState(false);
-  const { login } = useAuth();
+  const [challenge, setChallenge] = useState<string | null>(null);
+  const [twoFactorCode, setTwoFactorCode] = useState("");
+  const { login, completeTwoFactorLogin } = useAuth();
   const navigate = useNavigate();
 
   const handleSubmit = async (e: React.FormEvent) => {
@@ -17,9 +19,17 @@ export function Login() {
     setIsLoading(true);
 
     try {
-      await login(username, password);
+      if (challenge) {
+        await completeTwoFactorLogin(challenge, twoFactorCode, password);
+      } else {
+        await login(username, password);
+      }
       navigate("/chat");
     } catch (err) {
+      if (err instanceof TwoFactorRequiredError) {
+        setChallenge(err.challenge);
+        return;
+      }
       setError(err instanceof Error ? err.message : "Login failed");
     } finally {
       setIsLoading(false);
@@ -72,13 +82,30 @@ export function Login() {
             />
           </div>
 
+          {challenge && (
+            <div>
+              <input
+                id="two-factor-code"
+                type="text"
+                value={twoFactorCode}
+                onChange={(e) => setTwoFactorCode(e.target.value)}
+                placeholder="Authentication or backup code"
+                required
+                autoFocus
+                disabled={isLoading}
+                autoComplete="one-time-code"
+                className="w-full px-4 py-3 bg-white/5 border border-white/10 rounded-lg text-foreground placeholder:text-muted-foreground focus:outline-none focus:ring-1 focus:ring-primary focus:border-primary disabled:opacity-50 disabled:cursor-not-allowed transition-all"
+              />
+            </div>
+          )}
+
           <button
             type="submit"
             disabled={isLoading}
             className="w-full px-4 py-3 bg-primary text-primary-foreground rounded-lg font-semibold hover:bg-primary/90 transition-all disabled:opacity-50 disabled:cursor-not-allowed flex items-center justify-center gap-2 shadow-lg shadow-primary/20"
           >
             {isLoading && <FontAwesomeIcon icon="spinner" className="w-4 h-4" spin />}
-            {isLoading ? "Signing in..." : "Sign in"}
+            {isLoading ? "Signing in..." : challenge ? "Verify" : "Sign in"}
           </button>
         </form>
 
@@ -12,6 +12,7 @@ export function ResetPassword() {
   const [recoveryKeyInput, setRecoveryKeyInput] = useState("");
   const [newPassword, setNewPassword] = useState("");
   const [confirmPassword, setConfirmPassword] = useState("");
+  const [twoFactorCode, setTwoFactorCode] = useState("");
   const [error, setError] = useState("");
   const [isLoading, setIsLoading] = useState(false);
 
@@ -143,10 +144,12 @@ export function ResetPassword() {
         recovery_kem_encrypted_private: newRecoveryData.recovery_kem_encrypted_private,
         recovery_dsa_encrypted_private: newRecoveryData.recovery_dsa_encrypted_private,
         recovery_key_salt: newRecoveryData.recovery_key_salt,
+        two_factor_code: twoFactorCode || undefined,
       });
 
       await secureKeyStore.saveAuthToken(response.token);
-      httpClient.setAuthToken(response.token);
+      await secureKeyStore.saveRefreshToken(response.refresh_token);
+      httpClient.setSessionTokens(response);
 
       navigate("/login", {
         replace: true,
@@ -297,6 +300,25 @@ export function ResetPassword() {
             />
           </div>
 
+          <div className="space-y-2">
+            <label htmlFor="twoFactorCode" className="text-sm font-medium text-foreground">
+              Two-Factor Code
+            </label>
+            <input
+              id="twoFactorCode"
+              type="text"
+              value={twoFactorCode}
+              onChange={(e) => setTwoFactorCode(e.target.value)}
+              placeholder="Authentication or backup code"
+              disabled={isLoading}
+              autoComplete="one-time-code"
+              className="w-full px-4 py-3 bg-white/5 border border-white/10 rounded-lg text-foreground placeholder:text-muted-foreground focus:outline-none focus:ring-1 focus:ring-primary focus:border-primary disabled:opacity-50 disabled:cursor-not-allowed transition-all"
+            />
+            <p className="text-xs text-muted-foreground">
+              Only required if two-factor authentication is enabled
+            </p>
+          </div>
+
           <div className="flex gap-2">
             <button
               type="button"
@@ -10,7 +10,14 @@ import { ConnectionStatus } from "../ui/ConnectionStatus";
 import { PinnedMessages } from "./PinnedMessages";
 
 export function ChatHeader() {
-  const { activeChat, showProfilePanel, setShowProfilePanel, setVerifyModal } = useChat();
+  const {
+    activeChat,
+    showProfilePanel,
+    setShowProfilePanel,
+    setVerifyModal,
+    isVerified,
+    keyChangedContacts,
+  } = useChat();
   const { getUserPresence, subscribeToUsers, isWsConnected, isOnline } = usePresence();
   const { user, keys } = useAuth();
   const { callState, initiateCall } = useCall();
@@ -29,6 +36,9 @@ export function ChatHeader() {
   const displayStatus = userIsOnline ? presence?.status || "online" : "offline";
 
   const canVerify = activeChat.theirIdentityKey && activeChat.theirIdentityKey.length > 0 && keys;
+  const keyChanged = keyChangedContacts.has(activeChat.visitorId);
+  const verified =
+    !!activeChat.theirIdentityKey && isVerified(activeChat.visitorId, activeChat.theirIdentityKey);
   const isDm = !activeChat.isGroup;
   const hasDsaKey = activeChat.theirDsaKey && activeChat.theirDsaKey.length > 0;
   const canCall = isDm && keys && callState.status === "idle" && hasDsaKey;
@@ -124,10 +134,25 @@ export function ChatHeader() {
           {canVerify && (
             <button
               onClick={handleVerify}
-              className="p-2 rounded-lg transition-colors hover:bg-secondary text-muted-foreground hover:text-foreground"
-              title="View safety number"
+              className={`p-2 rounded-lg transition-colors hover:bg-secondary ${
+                keyChanged
+                  ? "text-destructive"
+                  : verified
+                    ? "text-primary"
+                    : "text-muted-foreground hover:text-foreground"
+              }`}
+              title={
+                keyChanged
+                  ? "Identity key changed - verify again"
+                  : verified
+                    ? "Verified - view safety number"
+                    : "View safety number"
+              }
             >
-              <FontAwesomeIcon icon="shield" className="w-5 h-5" />
+              <FontAwesomeIcon
+                icon={keyChanged ? "triangle-exclamation" : verified ? "shield-halved" : "shield"}
+                className="w-5 h-5"
+              />
             </button>
           )}
           <button
@@ -0,0 +1,68 @@
+import { useState } from "react";
+import { FontAwesomeIcon } from "@fortawesome/react-fontawesome";
+import { Dialog, DialogContent, DialogHeader, DialogTitle, DialogFooter } from "../ui/dialog";
+import { Button } from "../ui/button";
+import type { PendingDeviceLink } from "../../context/AuthContext";
+
+interface DeviceLinkApprovalModalProps {
+  request: PendingDeviceLink;
+  onApprove: (linkId: string) => Promise<void>;
+  onReject: (linkId: string) => Promise<void>;
+}
+
+export function DeviceLinkApprovalModal({
+  request,
+  onApprove,
+  onReject,
+}: DeviceLinkApprovalModalProps) {
+  const [busy, setBusy] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  const handle = async (action: (linkId: string) => Promise<void>) => {
+    setBusy(true);
+    setError(null);
+    try {
+      await action(request.linkId);
+    } catch (err) {
+      console.error("Failed to answer device link:", err);
+      setError("Something went wrong. The link may have expired.");
+      setBusy(false);
+    }
+  };
+
+  const deviceLabel = [request.deviceName, request.platform].filter(Boolean).join(" · ");
+
+  return (
+    <Dialog open={true} onOpenChange={(open) => !open && !busy && handle(onReject)}>
+      <DialogContent className="max-w-md bg-card border-border">
+        <DialogHeader>
+          <DialogTitle>Link a new device?</DialogTitle>
+        </DialogHeader>
+
+        <div className="space-y-4">
+          <div className="flex items-center gap-3 text-muted-foreground">
+            <FontAwesomeIcon icon="shield" className="w-6 h-6 text-primary shrink-0" />
+            <p className="text-sm">
+              <strong className="text-foreground">{deviceLabel || "An unknown device"}</strong>{" "}
+              scanned your link code and is asking for your account keys.
+            </p>
+          </div>
+          <p className="text-xs text-muted-foreground">
+            Only approve if you just scanned the code yourself. The device will be able to read
+            all of your messages.
+          </p>
+          {error && <p className="text-sm text-destructive">{error}</p>}
+        </div>
+
+        <DialogFooter>
+          <Button variant="outline" disabled={busy} onClick={() => handle(onReject)}>
+            Deny
+          </Button>
+          <Button disabled={busy} onClick={() => handle(onApprove)}>
+            Approve
+          </Button>
+        </DialogFooter>
+      </DialogContent>
+    </Dialog>
+  );
+}
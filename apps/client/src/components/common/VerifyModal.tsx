@@ -11,6 +11,9 @@ interface VerifyModalProps {
   theirUsername: string;
   ourIdentityKey: number[];
   theirIdentityKey: number[];
+  isVerified: boolean;
+  keyChanged: boolean;
+  onVerifiedChange: (verified: boolean) => void;
 }
 
 export function VerifyModal({
@@ -20,6 +23,9 @@ export function VerifyModal({
   theirUsername,
   ourIdentityKey,
   theirIdentityKey,
+  isVerified,
+  keyChanged,
+  onVerifiedChange,
 }: VerifyModalProps) {
   const [safetyNumber, setSafetyNumber] = useState<string>("");
   const [loading, setLoading] = useState(true);
@@ -94,6 +100,15 @@ export function VerifyModal({
           </div>
         ) : (
           <div className="space-y-4">
+            {keyChanged && (
+              <div className="flex items-center gap-3 rounded-lg bg-destructive/10 p-3 text-sm text-destructive">
+                <FontAwesomeIcon icon="triangle-exclamation" className="w-5 h-5 shrink-0" />
+                <p>
+                  {theirUsername}'s identity key changed since you verified it. Compare the new
+                  safety number before trusting this conversation again.
+                </p>
+              </div>
+            )}
             <div className="flex items-center gap-3 text-muted-foreground">
               <FontAwesomeIcon icon="shield" className="w-6 h-6 text-primary shrink-0" />
               <p className="text-sm">
@@ -131,6 +146,14 @@ export function VerifyModal({
         )}
 
         <DialogFooter>
+          {!loading && !showInfo && (
+            <Button
+              variant={isVerified ? "outline" : "default"}
+              onClick={() => onVerifiedChange(!isVerified)}
+            >
+              {isVerified ? "Clear verification" : "Mark as verified"}
+            </Button>
+          )}
           <Button variant="outline" onClick={onClose}>
             Done
           </Button>
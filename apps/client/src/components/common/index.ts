@@ -2,6 +2,7 @@ export { Avatar, getInitials } from "./Avatar";
 export { ContextMenu } from "./ContextMenu";
 export { GifModal } from "./GifModal";
 export { VerifyModal } from "./VerifyModal";
+export { DeviceLinkApprovalModal } from "./DeviceLinkApprovalModal";
 export { ImageCropper } from "./ImageCropper";
 export { SnowEffect } from "./SnowEffect";
 export { KeyboardShortcutsModal } from "./KeyboardShortcutsModal";
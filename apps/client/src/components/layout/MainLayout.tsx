@@ -16,6 +16,7 @@ import { GroupContextMenu } from "../groups/GroupContextMenu";
 import { ProfileModal } from "../profile/ProfileModal";
 import { ProfileSidePanel } from "../profile/ProfileSidePanel";
 import { VerifyModal } from "../common/VerifyModal";
+import { DeviceLinkApprovalModal } from "../common/DeviceLinkApprovalModal";
 import { toast } from "sonner";
 import { useDropzone } from "../../hooks/useDropzone";
 import { Button } from "../ui/button";
@@ -37,7 +38,7 @@ import { useOnboarding } from "../../hooks/useOnboarding";
 import { WelcomeScreen } from "@/features/onboarding";
 
 export function MainLayout() {
-  const { user, keys } = useAuth();
+  const { user, keys, pendingDeviceLink, approveDeviceLink, rejectDeviceLink } = useAuth();
   const { activeServer, activeChannel, setActiveServer } = useServer();
   const [showDiscovery, setShowDiscovery] = useState(false);
   const [confirmGroupAction, setConfirmGroupAction] = useState<{
@@ -69,6 +70,9 @@ export function MainLayout() {
     closeDm,
     verifyModal,
     setVerifyModal,
+    isVerified,
+    keyChangedContacts,
+    setContactVerified,
     error,
     setError,
     successMessage,
@@ -480,6 +484,24 @@ export function MainLayout() {
           theirUsername={verifyModal.friendUsername}
           ourIdentityKey={keys.kem_public_key}
           theirIdentityKey={verifyModal.theirIdentityKey}
+          isVerified={isVerified(verifyModal.friendId, verifyModal.theirIdentityKey)}
+          keyChanged={keyChangedContacts.has(verifyModal.friendId)}
+          onVerifiedChange={(verified) =>
+            setContactVerified(verifyModal.friendId, verifyModal.theirIdentityKey, verified).catch(
+              (err) => {
+                console.error("Failed to update verification:", err);
+                setError("Failed to update verification");
+              }
+            )
+          }
+        />
+      )}
+
+      {pendingDeviceLink && (
+        <DeviceLinkApprovalModal
+          request={pendingDeviceLink}
+          onApprove={approveDeviceLink}
+          onReject={rejectDeviceLink}
         />
       )}
 
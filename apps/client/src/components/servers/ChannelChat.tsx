@@ -5,11 +5,38 @@ import { usePresence } from "../../context/PresenceContext";
 import { FontAwesomeIcon } from "@fortawesome/react-fontawesome";
 import { cryptoService } from "../../core/crypto/crypto";
 import type { FederatedMember as Member } from "../../features/servers/federatedClient";
-import type { WsMessage as ServerMessage } from "../../core/network/wsTypes";
-import type { EncryptedMessage } from "../../features/servers/types";
+import type { WsMessage } from "../../core/network/wsTypes";
+import type {
+  EncryptedMessage,
+  ChannelWsMessageEdited,
+  ChannelWsReactionAdded,
+  ChannelWsReactionRemoved,
+  ChannelWsMessagePinned,
+  ChannelWsMessageUnpinned,
+} from "../../features/servers/types";
 import { MemberProfileCard } from "./MemberProfileCard";
 import { ChannelMessage, ChannelInputForm, TypingIndicator, type DisplayMessage } from "./channel";
 
+// Channel events share type names with the DM events in the central union but
+// carry channel-shaped payloads.
+type ChannelEvent =
+  | Exclude<
+      WsMessage,
+      {
+        type:
+          | "message_edited"
+          | "reaction_added"
+          | "reaction_removed"
+          | "message_pinned"
+          | "message_unpinned";
+      }
+    >
+  | ChannelWsMessageEdited
+  | ChannelWsReactionAdded
+  | ChannelWsReactionRemoved
+  | ChannelWsMessagePinned
+  | ChannelWsMessageUnpinned;
+
 export function ChannelChat() {
   const [selectedProfileId, setSelectedProfileId] = useState<string | null>(null);
   const [profilePosition, setProfilePosition] = useState({ x: 0, y: 0 });
@@ -26,8 +53,11 @@ export function ChannelChat() {
   const [isSending, setIsSending] = useState(false);
   const [error, setError] = useState<string | null>(null);
   const [typingUsers, setTypingUsers] = useState<Map<string, string>>(new Map());
+  const [editingMessageId, setEditingMessageId] = useState<string | null>(null);
 
   const messagesEndRef = useRef<HTMLDivElement>(null);
+  const messagesRef = useRef<DisplayMessage[]>([]);
+  messagesRef.current = messages;
   const typingTimeoutRef = useRef<number | null>(null);
   const typingClearTimeoutsRef = useRef<Map<string, number>>(new Map());
 
@@ -76,14 +106,22 @@ export function ChannelChat() {
   };
 
   const decryptMessage = async (msg: EncryptedMessage): Promise<DisplayMessage> => {
+    const base = {
+      id: msg.id,
+      senderId: msg.sender_id,
+      senderName: msg.sender_username,
+      senderDsaPublicKey: msg.sender_dsa_public_key,
+      createdAt: msg.created_at,
+      editedAt: msg.updated_at ?? null,
+      pinnedAt: msg.pinned_at ?? null,
+      reactions: msg.reactions ?? [],
+      isMine: myMember?.id === msg.sender_id,
+    };
+
     if (!keys || !myMember) {
       return {
-        id: msg.id,
-        senderId: msg.sender_id,
-        senderName: msg.sender_username,
+        ...base,
         content: "[Unable to decrypt - not logged in]",
-        createdAt: msg.created_at,
-        isMine: myMember?.id === msg.sender_id,
         verified: false,
         decryptionFailed: true,
       };
@@ -93,12 +131,8 @@ export function ChannelChat() {
       const channelKey = await getChannelKey(msg.channel_id);
       if (!channelKey) {
         return {
-          id: msg.id,
-          senderId: msg.sender_id,
-          senderName: msg.sender_username,
+          ...base,
           content: "[Unable to decrypt - no channel key]",
-          createdAt: msg.created_at,
-          isMine: myMember?.id === msg.sender_id,
           verified: false,
           decryptionFailed: true,
         };
@@ -124,24 +158,16 @@ export function ChannelChat() {
       }
 
       return {
-        id: msg.id,
-        senderId: msg.sender_id,
-        senderName: msg.sender_username,
+        ...base,
         content,
-        createdAt: msg.created_at,
-        isMine: myMember?.id === msg.sender_id,
         verified,
         decryptionFailed: false,
       };
     } catch (err) {
       console.error("Decryption failed for message:", msg.id, err);
       return {
-        id: msg.id,
-        senderId: msg.sender_id,
-        senderName: msg.sender_username,
+        ...base,
         content: "[Decryption failed]",
-        createdAt: msg.created_at,
-        isMine: myMember?.id === msg.sender_id,
         verified: false,
         decryptionFailed: true,
       };
@@ -182,7 +208,7 @@ export function ChannelChat() {
     if (!federatedWs || !activeChannel || !keys || !myMember) return;
     federatedWs.subscribeChannel(activeChannel.id);
 
-    const unsubscribe = federatedWs.onMessage(async (message: ServerMessage) => {
+    const unsubscribe = federatedWs.onMessage(async (message: ChannelEvent) => {
       switch (message.type) {
         case "new_message": {
           if (message.data.channel_id !== activeChannel.id) return;
@@ -245,6 +271,86 @@ export function ChannelChat() {
           setMessages((prev) => prev.filter((m) => m.id !== message.data.message_id));
           break;
         }
+        case "message_edited": {
+          const edit = message.data;
+          if (edit.channel_id !== activeChannel.id) return;
+          const target = messagesRef.current.find((m) => m.id === edit.message_id);
+          if (!target) return;
+          const updated = await decryptMessage({
+            id: target.id,
+            channel_id: edit.channel_id,
+            sender_id: target.senderId,
+            sender_username: target.senderName,
+            sender_dsa_public_key: target.senderDsaPublicKey,
+            encrypted_content: edit.encrypted_content,
+            signature: edit.signature,
+            created_at: target.createdAt,
+            updated_at: edit.updated_at,
+          });
+          setMessages((prev) =>
+            prev.map((m) =>
+              m.id === updated.id
+                ? {
+                    ...m,
+                    content: updated.content,
+                    editedAt: updated.editedAt,
+                    verified: updated.verified,
+                    decryptionFailed: updated.decryptionFailed,
+                  }
+                : m
+            )
+          );
+          break;
+        }
+        case "reaction_added": {
+          const reaction = message.data;
+          if (reaction.channel_id !== activeChannel.id) return;
+          setMessages((prev) =>
+            prev.map((m) => {
+              if (m.id !== reaction.message_id) return m;
+              const reactions = m.reactions ?? [];
+              if (reactions.some((r) => r.id === reaction.id)) return m;
+              return {
+                ...m,
+                reactions: [...reactions, { ...reaction, created_at: new Date().toISOString() }],
+              };
+            })
+          );
+          break;
+        }
+        case "reaction_removed": {
+          const reaction = message.data;
+          if (reaction.channel_id !== activeChannel.id) return;
+          setMessages((prev) =>
+            prev.map((m) =>
+              m.id === reaction.message_id
+                ? {
+                    ...m,
+                    reactions: (m.reactions ?? []).filter(
+                      (r) => !(r.member_id === reaction.member_id && r.emoji === reaction.emoji)
+                    ),
+                  }
+                : m
+            )
+          );
+          break;
+        }
+        case "message_pinned": {
+          if (message.data.channel_id !== activeChannel.id) return;
+          const { message_id, pinned_at } = message.data;
+          setMessages((prev) =>
+            prev.map((m) => (m.id === message_id ? { ...m, pinnedAt: pinned_at } : m))
+          );
+          break;
+        }
+        case "message_unpinned": {
+          if (message.data.channel_id !== activeChannel.id) return;
+          const { message_id } = message.data;
+          setMessages((prev) =>
+            prev.map((m) => (m.id === message_id ? { ...m, pinnedAt: null } : m))
+          );
+          break;
+        }
       }
     });
 
@@ -257,6 +363,10 @@ export function ChannelChat() {
     };
   }, [federatedWs, activeChannel, keys, myMember]);
 
+  useEffect(() => {
+    setEditingMessageId(null);
+  }, [activeChannel]);
+
   const handleInputChange = (e: React.ChangeEvent<HTMLInputElement>) => {
     setNewMessage(e.target.value);
     if (federatedWs && activeChannel && e.target.value.trim()) {
@@ -283,10 +393,18 @@ export function ChannelChat() {
       const contentBytes = cryptoService.stringToBytes(newMessage.trim());
       const encryptedContent = await cryptoService.encryptWithKey(channelKey, contentBytes);
       const signature = await cryptoService.dsaSign(keys.dsa_secret_key, encryptedContent);
-      await federatedClient.sendMessage(activeChannel.id, {
-        encrypted_content: encryptedContent,
-        signature,
-      });
+      if (editingMessageId) {
+        await federatedClient.editMessage(activeChannel.id, editingMessageId, {
+          encrypted_content: encryptedContent,
+          signature,
+        });
+        setEditingMessageId(null);
+      } else {
+        await federatedClient.sendMessage(activeChannel.id, {
+          encrypted_content: encryptedContent,
+          signature,
+        });
+      }
       setNewMessage("");
       if (!federatedWs?.isConnected()) await loadMessages();
     } catch (err) {
@@ -297,6 +415,55 @@ export function ChannelChat() {
     }
   };
 
+  const handleStartEdit = (message: DisplayMessage) => {
+    setEditingMessageId(message.id);
+    setNewMessage(message.content);
+  };
+
+  const handleCancelEdit = () => {
+    setEditingMessageId(null);
+    setNewMessage("");
+  };
+
+  const handleDeleteMessage = async (messageId: string) => {
+    if (!activeChannel || !federatedClient) return;
+    try {
+      await federatedClient.deleteMessage(activeChannel.id, messageId);
+      setMessages((prev) => prev.filter((m) => m.id !== messageId));
+    } catch (err) {
+      console.error("Failed to delete message:", err);
+    }
+  };
+
+  const handleTogglePin = async (message: DisplayMessage) => {
+    if (!activeChannel || !federatedClient) return;
+    try {
+      if (message.pinnedAt) {
+        await federatedClient.unpinMessage(activeChannel.id, message.id);
+      } else {
+        await federatedClient.pinMessage(activeChannel.id, message.id);
+      }
+    } catch (err) {
+      console.error("Failed to update pin:", err);
+    }
+  };
+
+  const handleToggleReaction = async (message: DisplayMessage, emoji: string) => {
+    if (!activeChannel || !federatedClient || !myMember) return;
+    const reacted = message.reactions?.some(
+      (r) => r.member_id === myMember.id && r.emoji === emoji
+    );
+    try {
+      if (reacted) {
+        await federatedClient.removeReaction(activeChannel.id, message.id, emoji);
+      } else {
+        await federatedClient.addReaction(activeChannel.id, message.id, emoji);
+      }
+    } catch (err) {
+      console.error("Failed to update reaction:", err);
+    }
+  };
+
   if (!activeChannel) return null;
 
   if (error) {
@@ -380,7 +547,12 @@ export function ChannelChat() {
                 index={idx}
                 messages={messages}
                 member={members.find((m) => m.id === msg.senderId)}
+                myMemberId={myMember?.id}
                 onProfileClick={handleProfileClick}
+                onEdit={handleStartEdit}
+                onDelete={handleDeleteMessage}
+                onTogglePin={handleTogglePin}
+                onToggleReaction={handleToggleReaction}
               />
             ))}
             <div ref={messagesEndRef} />
@@ -390,6 +562,14 @@ export function ChannelChat() {
 
       <div className="h-auto min-h-[68px] px-8 py-4 flex-none z-20 relative">
         <TypingIndicator typingUsers={typingUsers} />
+        {editingMessageId && (
+          <div className="flex items-center justify-between text-xs text-muted-foreground mb-1 px-1">
+            <span>Editing message</span>
+            <button type="button" onClick={handleCancelEdit} className="hover:text-foreground">
+              Cancel
+            </button>
+          </div>
+        )}
         <ChannelInputForm
           channelName={activeChannel.name}
           value={newMessage}
@@ -400,6 +580,7 @@ export function ChannelChat() {
             setNewMessage((prev) => prev + emoji.native);
             if (federatedWs && activeChannel) federatedWs.sendTyping(activeChannel.id);
           }}
+          onCancel={editingMessageId ? handleCancelEdit : undefined}
           disabled={!myMember}
           isSending={isSending}
         />
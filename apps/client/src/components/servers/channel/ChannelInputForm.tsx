@@ -10,6 +10,7 @@ interface ChannelInputFormProps {
   onSubmit: (e: React.FormEvent) => void;
   onGifSelect: (url: string) => void;
   onEmojiSelect: (emoji: { native: string }) => void;
+  onCancel?: () => void;
   disabled: boolean;
   isSending: boolean;
 }
@@ -21,6 +22,7 @@ export function ChannelInputForm({
   onSubmit,
   onGifSelect,
   onEmojiSelect,
+  onCancel,
   disabled,
   isSending,
 }: ChannelInputFormProps) {
@@ -77,6 +79,9 @@ export function ChannelInputForm({
           if (e.key === "Enter" && !e.shiftKey) {
             e.preventDefault();
             onSubmit(e);
+          } else if (e.key === "Escape" && onCancel) {
+            e.preventDefault();
+            onCancel();
           }
         }}
         placeholder={`Message #${channelName}`}
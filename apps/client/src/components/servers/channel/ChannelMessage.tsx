@@ -1,6 +1,10 @@
 import { FontAwesomeIcon } from "@fortawesome/react-fontawesome";
 import { cn } from "../../../lib/utils";
 import { UserAvatar } from "../../ui/user-avatar";
+import { Popover, PopoverContent, PopoverTrigger } from "../../ui/popover";
+import { EmojiPicker } from "../../chat/EmojiPicker";
+import { useServer } from "../../../context/server";
+import { Permissions, hasPermission } from "../../../features/servers/permissions";
 import type { FederatedMember as Member } from "../../../features/servers/federatedClient";
 import { type DisplayMessage, shouldShowHeader, formatDate } from "./helpers";
 
@@ -9,7 +13,12 @@ interface ChannelMessageProps {
   index: number;
   messages: DisplayMessage[];
   member: Member | undefined;
+  myMemberId: string | undefined;
   onProfileClick: (memberId: string, event: React.MouseEvent) => void;
+  onEdit: (message: DisplayMessage) => void;
+  onDelete: (messageId: string) => void;
+  onTogglePin: (message: DisplayMessage) => void;
+  onToggleReaction: (message: DisplayMessage, emoji: string) => void;
 }
 
 export function ChannelMessage({
@@ -17,9 +26,24 @@ export function ChannelMessage({
   index,
   messages,
   member,
+  myMemberId,
   onProfileClick,
+  onEdit,
+  onDelete,
+  onTogglePin,
+  onToggleReaction,
 }: ChannelMessageProps) {
+  const { myPermissions } = useServer();
   const showHeader = shouldShowHeader(message, index, messages);
+  const canManage = hasPermission(myPermissions, Permissions.MANAGE_MESSAGES);
+
+  const reactionGroups = new Map<string, { count: number; mine: boolean }>();
+  for (const reaction of message.reactions ?? []) {
+    const group = reactionGroups.get(reaction.emoji) ?? { count: 0, mine: false };
+    group.count += 1;
+    group.mine = group.mine || reaction.member_id === myMemberId;
+    reactionGroups.set(reaction.emoji, group);
+  }
 
   return (
     <div
@@ -50,6 +74,11 @@ export function ChannelMessage({
               {member?.display_name || message.senderName}
             </span>
             <span className="text-xs text-muted-foreground">{formatDate(message.createdAt)}</span>
+            {message.pinnedAt && (
+              <span title="Pinned">
+                <FontAwesomeIcon icon="thumbtack" className="w-3 h-3 text-muted-foreground" />
+              </span>
+            )}
             {message.decryptionFailed && (
               <span title="Decryption failed">
                 <FontAwesomeIcon icon="triangle-exclamation" className="w-3 h-3 text-destructive" />
@@ -81,8 +110,90 @@ export function ChannelMessage({
           ) : (
             message.content
           )}
+          {message.editedAt && (
+            <span
+              className="ml-1 text-[10px] text-muted-foreground"
+              title={formatDate(message.editedAt)}
+            >
+              (edited)
+            </span>
+          )}
         </div>
+
+        {reactionGroups.size > 0 && (
+          <div className="flex flex-wrap gap-1 mt-1">
+            {Array.from(reactionGroups.entries()).map(([emoji, group]) => (
+              <button
+                key={emoji}
+                type="button"
+                onClick={() => onToggleReaction(message, emoji)}
+                className={cn(
+                  "flex items-center gap-1 px-1.5 py-0.5 rounded-md text-xs border transition-colors",
+                  group.mine
+                    ? "bg-primary/15 border-primary/40 text-foreground"
+                    : "bg-secondary border-transparent text-muted-foreground hover:border-border"
+                )}
+              >
+                <span>{emoji}</span>
+                <span>{group.count}</span>
+              </button>
+            ))}
+          </div>
+        )}
       </div>
+
+      {!message.decryptionFailed && (
+        <div className="absolute -top-3 right-8 hidden group-hover:flex items-center gap-0.5 rounded-md bg-card border border-border p-0.5 shadow-sm">
+          <Popover>
+            <PopoverTrigger asChild>
+              <button
+                type="button"
+                className="p-1.5 rounded text-muted-foreground hover:text-foreground hover:bg-white/10"
+                title="Add reaction"
+              >
+                <FontAwesomeIcon icon="face-smile" className="w-3.5 h-3.5" />
+              </button>
+            </PopoverTrigger>
+            <PopoverContent
+              side="top"
+              align="end"
+              className="w-auto p-0 border-none bg-transparent shadow-none"
+            >
+              <EmojiPicker onSelect={(emoji) => onToggleReaction(message, emoji.native)} />
+            </PopoverContent>
+          </Popover>
+          {message.isMine && (
+            <button
+              type="button"
+              onClick={() => onEdit(message)}
+              className="p-1.5 rounded text-muted-foreground hover:text-foreground hover:bg-white/10"
+              title="Edit"
+            >
+              <FontAwesomeIcon icon="pen" className="w-3.5 h-3.5" />
+            </button>
+          )}
+          {canManage && (
+            <button
+              type="button"
+              onClick={() => onTogglePin(message)}
+              className="p-1.5 rounded text-muted-foreground hover:text-foreground hover:bg-white/10"
+              title={message.pinnedAt ? "Unpin" : "Pin"}
+            >
+              <FontAwesomeIcon icon="thumbtack" className="w-3.5 h-3.5" />
+            </button>
+          )}
+          {(message.isMine || canManage) && (
+            <button
+              type="button"
+              onClick={() => onDelete(message.id)}
+              className="p-1.5 rounded text-muted-foreground hover:text-destructive hover:bg-white/10"
+              title="Delete"
+            >
+              <FontAwesomeIcon icon="trash" className="w-3.5 h-3.5" />
+            </button>
+          )}
+        </div>
+      )}
     </div>
   );
 }
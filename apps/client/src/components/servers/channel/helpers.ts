@@ -1,9 +1,15 @@
+import type { MessageReaction } from "../../../features/servers/types";
+
 export interface DisplayMessage {
   id: string;
   senderId: string;
   senderName: string;
+  senderDsaPublicKey?: number[];
   content: string;
   createdAt: string;
+  editedAt?: string | null;
+  pinnedAt?: string | null;
+  reactions?: MessageReaction[];
   isMine: boolean;
   verified: boolean;
   decryptionFailed: boolean;
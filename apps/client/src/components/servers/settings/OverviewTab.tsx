@@ -135,11 +135,11 @@ export function OverviewTab({
             value={messageRetention}
             onChange={(e) => setMessageRetention(e.target.value)}
             disabled={isLoading}
-            placeholder="e.g. 30d, 1y, forever"
+            placeholder="e.g. 30d, 6mo, 1y, forever"
             className="bg-secondary/50 border-0 h-11"
           />
           <p className="text-xs text-muted-foreground">
-            How long to keep messages before deletion (e.g. '30d', '1y')
+            How long to keep messages before deletion (e.g. '30d', '6mo', '1y', 'forever')
           </p>
         </div>
 
@@ -58,6 +58,11 @@ export const PERMISSION_GROUPS = [
         value: Permissions.MANAGE_CHANNELS,
         description: "Allows members to create, edit, and delete channels.",
       },
+      {
+        name: "View Audit Log",
+        value: Permissions.VIEW_AUDIT_LOG,
+        description: "Allows members to see the log of moderation and settings changes.",
+      },
     ],
   },
   {
@@ -110,4 +115,19 @@ export const PERMISSION_GROUPS = [
       },
     ],
   },
+  {
+    name: "Voice Channel Permissions",
+    permissions: [
+      {
+        name: "Connect",
+        value: Permissions.CONNECT,
+        description: "Allows members to join voice channels.",
+      },
+      {
+        name: "Speak",
+        value: Permissions.SPEAK,
+        description: "Allows members to talk in voice channels.",
+      },
+    ],
+  },
 ];
@@ -24,14 +24,14 @@ export function useServerSettings({ serverId, serverName, onClose }: UseServerSe
   const [isDiscoverable, setIsDiscoverable] = useState(false);
   const [maxUsers, setMaxUsers] = useState(100);
   const [maxUploadSize, setMaxUploadSize] = useState(100);
-  const [messageRetention, setMessageRetention] = useState("30d");
+  const [messageRetention, setMessageRetention] = useState("forever");
   const [initialSettings, setInitialSettings] = useState({
     name: serverName,
     description: "",
     isDiscoverable: false,
     maxUsers: 100,
     maxUploadSize: 100,
-    messageRetention: "30d",
+    messageRetention: "forever",
   });
 
   const [showCreateRole, setShowCreateRole] = useState(false);
@@ -60,14 +60,14 @@ export function useServerSettings({ serverId, serverName, onClose }: UseServerSe
       setIsDiscoverable(info.is_discoverable || false);
       setMaxUsers(info.max_users || 100);
       setMaxUploadSize(info.max_upload_size_mb || 100);
-      setMessageRetention(info.message_retention || "30d");
+      setMessageRetention(info.message_retention || "forever");
       setInitialSettings({
         name: info.name,
         description: info.description || "",
         isDiscoverable: info.is_discoverable || false,
         maxUsers: info.max_users || 100,
         maxUploadSize: info.max_upload_size_mb || 100,
-        messageRetention: info.message_retention || "30d",
+        messageRetention: info.message_retention || "forever",
       });
     } catch (error) {
       console.error("Failed to load server settings:", error);
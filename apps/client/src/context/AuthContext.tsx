@@ -1,12 +1,29 @@
-import { createContext, useContext, useState, useEffect, useCallback, ReactNode } from "react";
+import {
+  createContext,
+  useContext,
+  useState,
+  useEffect,
+  useCallback,
+  useRef,
+  ReactNode,
+} from "react";
 import { authService } from "../core/auth/AuthService";
 import { cryptoService } from "../core/crypto/crypto";
 import { profileService } from "../features/profiles/profiles";
 import { recoveryService } from "../core/auth/RecoveryService";
 import { keyService } from "../core/crypto/KeyService";
 import { httpClient } from "../core/network/HttpClient";
+import { centralWebSocketService } from "../core/network/CentralWebSocketService";
 import { preferenceService } from "../features/settings/preferences";
 import { secureKeyStore } from "../core/crypto/SecureKeyStore";
+import { continuityMessage } from "../core/crypto/identityChain";
+import {
+  decryptDeviceLinkPayload,
+  encodeDeviceLinkQr,
+  encryptDeviceLinkPayload,
+  generateEphemeralKeyPair,
+  parseDeviceLinkQr,
+} from "../core/auth/deviceLink";
 import type { PublicUser, LoginResponse } from "../core/auth/types";
 import type { DecryptedKeys, SignedPrekey, OneTimePrekey } from "../core/crypto/crypto";
 import type { UserProfile } from "../features/profiles/types";
@@ -27,17 +44,51 @@ interface RegisterResult {
   keys: DecryptedKeys;
 }
 
+export interface PendingDeviceLink {
+  linkId: string;
+  deviceName: string | null;
+  platform: string | null;
+  encryptedTransferKey: number[];
+}
+
+export interface DeviceLinkOffer {
+  linkId: string;
+  qrValue: string;
+  expiresIn: number;
+}
+
+export class TwoFactorRequiredError extends Error {
+  constructor(public readonly challenge: string) {
+    super("Two-factor code required");
+    this.name = "TwoFactorRequiredError";
+  }
+}
+
 interface AuthContextType extends AuthState {
   login: (username: string, password: string) => Promise<LoginResponse>;
+  completeTwoFactorLogin: (
+    challenge: string,
+    code: string,
+    password: string
+  ) => Promise<LoginResponse>;
   register: (username: string, password: string) => Promise<RegisterResult>;
   logout: () => Promise<void>;
   refreshProfile: () => Promise<void>;
   refreshPreferences: () => Promise<void>;
+  changeUsername: (username: string) => Promise<void>;
+  rotateIdentityKeys: (password: string, twoFactorCode?: string) => Promise<void>;
   checkRecoveryStatus: () => Promise<boolean>;
   completeRecoverySetup: () => void;
+  pendingDeviceLink: PendingDeviceLink | null;
+  startDeviceLink: () => Promise<DeviceLinkOffer>;
+  approveDeviceLink: (linkId: string) => Promise<void>;
+  rejectDeviceLink: (linkId: string) => Promise<void>;
+  linkWithDevice: (qrValue: string, signal?: AbortSignal) => Promise<LoginResponse>;
 }
 
 const AUTH_STORAGE_KEY = "confide_auth_state";
+// Stays well under the server's per-link limit of 40 requests a minute.
+const DEVICE_LINK_POLL_INTERVAL_MS = 2000;
 
 interface StoredPrekeys {
   signedPrekey: SignedPrekey;
@@ -110,6 +161,46 @@ async function addOneTimePrekeySecrets(newPrekeys: OneTimePrekey[]) {
   await secureKeyStore.savePrekeySecrets(existing);
 }
 
+const PREKEY_POOL_SIZE = 100;
+
+// Tops the one-time prekey pool back up once it is under the server's
+// low-water mark, and replaces the signed prekey when the server reports it
+// stale.
+async function replenishPrekeys(dsaSecretKey: number[], forceRotateSignedPrekey = false) {
+  const { count, low_water_mark, signed_prekey_stale } = await keyService.getPrekeyCount();
+  const storedPrekeys = await loadPrekeySecrets();
+  const rotateSignedPrekey = forceRotateSignedPrekey || signed_prekey_stale || !storedPrekeys;
+
+  if (count >= low_water_mark && !rotateSignedPrekey) return;
+
+  const signedPrekey =
+    rotateSignedPrekey || !storedPrekeys
+      ? await cryptoService.generateSignedPrekey(dsaSecretKey)
+      : storedPrekeys.signedPrekey;
+  const needed = Math.max(0, PREKEY_POOL_SIZE - count);
+  const newPrekeys = needed > 0 ? await cryptoService.generateOneTimePrekeys(needed) : [];
+
+  if (!storedPrekeys) {
+    await savePrekeySecrets(signedPrekey, newPrekeys);
+  } else {
+    if (rotateSignedPrekey) {
+      storedPrekeys.signedPrekey = signedPrekey;
+      await secureKeyStore.savePrekeySecrets(storedPrekeys);
+    }
+    await addOneTimePrekeySecrets(newPrekeys);
+  }
+
+  await keyService.uploadPrekeys({
+    signed_prekey_public: signedPrekey.public_key,
+    signed_prekey_signature: signedPrekey.signature,
+    signed_prekey_id: signedPrekey.prekey_id,
+    one_time_prekeys: newPrekeys.map((p) => ({
+      prekey_id: p.prekey_id,
+      public_key: p.public_key,
+    })),
+  });
+}
+
 export async function getPrekeySecrets(): Promise<StoredPrekeys | null> {
   try {
     return loadPrekeySecrets();
@@ -157,10 +248,13 @@ export function AuthProvider({ children }: { children: ReactNode }) {
   useEffect(() => {
     const loadAuth = async () => {
       const token = await secureKeyStore.loadAuthToken();
+      const refreshToken = await secureKeyStore.loadRefreshToken();
       const savedAuth = await loadAuthFromStorage();
 
       if (token && savedAuth) {
         httpClient.setAuthToken(token);
+        httpClient.setRefreshToken(refreshToken);
+        await httpClient.refreshSession();
         setState({
           user: savedAuth.user,
           keys: savedAuth.keys,
@@ -190,10 +284,19 @@ export function AuthProvider({ children }: { children: ReactNode }) {
   }, [fetchProfile, fetchPreferences]);
 
   const login = async (username: string, password: string) => {
-    const response = await authService.login({ username, password });
-    await secureKeyStore.saveAuthToken(response.token);
-    httpClient.setAuthToken(response.token);
+    const result = await authService.login({ username, password });
+    if (!("token" in result)) {
+      throw new TwoFactorRequiredError(result.challenge);
+    }
+    return finishLogin(result, password);
+  };
+
+  const completeTwoFactorLogin = async (challenge: string, code: string, password: string) => {
+    const response = await authService.completeTwoFactorLogin(challenge, code);
+    return finishLogin(response, password);
+  };
 
+  const finishLogin = async (response: LoginResponse, password: string) => {
     const decryptedKeys = await cryptoService.decryptKeys(
       password,
       response.user.kem_public_key,
@@ -202,6 +305,13 @@ export function AuthProvider({ children }: { children: ReactNode }) {
       response.dsa_encrypted_private,
       response.key_salt
     );
+    return establishSession(response, decryptedKeys);
+  };
+
+  const establishSession = async (response: LoginResponse, decryptedKeys: DecryptedKeys) => {
+    await secureKeyStore.saveAuthToken(response.token);
+    await secureKeyStore.saveRefreshToken(response.refresh_token);
+    httpClient.setSessionTokens(response);
 
     await saveAuthToStorage(response.user, decryptedKeys);
 
@@ -218,34 +328,7 @@ export function AuthProvider({ children }: { children: ReactNode }) {
     fetchPreferences();
 
     try {
-      const { count } = await keyService.getPrekeyCount();
-      const storedPrekeys = await loadPrekeySecrets();
-
-      if (count < 20 || !storedPrekeys) {
-        const signedPrekey =
-          storedPrekeys?.signedPrekey ||
-          (await cryptoService.generateSignedPrekey(decryptedKeys.dsa_secret_key));
-        const needed = Math.max(0, 100 - count);
-        const newPrekeys = needed > 0 ? await cryptoService.generateOneTimePrekeys(needed) : [];
-
-        if (!storedPrekeys) {
-          await savePrekeySecrets(signedPrekey, newPrekeys);
-        } else if (newPrekeys.length > 0) {
-          await addOneTimePrekeySecrets(newPrekeys);
-        }
-
-        if (newPrekeys.length > 0) {
-          await keyService.uploadPrekeys({
-            signed_prekey_public: signedPrekey.public_key,
-            signed_prekey_signature: signedPrekey.signature,
-            signed_prekey_id: signedPrekey.prekey_id,
-            one_time_prekeys: newPrekeys.map((p) => ({
-              prekey_id: p.prekey_id,
-              public_key: p.public_key,
-            })),
-          });
-        }
-      }
+      await replenishPrekeys(decryptedKeys.dsa_secret_key);
     } catch (err) {
       console.error("Failed to check/replenish prekeys:", err);
     }
@@ -253,6 +336,40 @@ export function AuthProvider({ children }: { children: ReactNode }) {
     return response;
   };
 
+  const linkWithDevice = async (qrValue: string, signal?: AbortSignal) => {
+    const qr = parseDeviceLinkQr(qrValue);
+    if (!qr) {
+      throw new Error("Invalid device link code");
+    }
+
+    const transferKey = await cryptoService.generateConversationKey();
+    const encryptedTransferKey = await cryptoService.encryptForRecipient(
+      qr.ephemeralPublicKey,
+      transferKey
+    );
+    const { claim_token, expires_in } = await authService.requestDeviceLink(
+      qr.linkId,
+      encryptedTransferKey
+    );
+
+    const deadline = Date.now() + expires_in * 1000;
+    while (Date.now() < deadline) {
+      if (signal?.aborted) {
+        throw new Error("Device link cancelled");
+      }
+      const result = await authService.claimDeviceLink(qr.linkId, claim_token);
+      if (result.status === "approved") {
+        const payload = await decryptDeviceLinkPayload(transferKey, result.encrypted_keys);
+        if (payload.prekeys) {
+          await secureKeyStore.savePrekeySecrets(payload.prekeys);
+        }
+        return establishSession(result, payload.keys);
+      }
+      await new Promise((r) => setTimeout(r, DEVICE_LINK_POLL_INTERVAL_MS));
+    }
+    throw new Error("Device link expired");
+  };
+
   const register = async (username: string, password: string): Promise<RegisterResult> => {
     const yieldToMain = () => new Promise((r) => setTimeout(r, 0));
 
@@ -270,7 +387,8 @@ export function AuthProvider({ children }: { children: ReactNode }) {
     });
 
     await secureKeyStore.saveAuthToken(response.token);
-    httpClient.setAuthToken(response.token);
+    await secureKeyStore.saveRefreshToken(response.refresh_token);
+    httpClient.setSessionTokens(response);
     await yieldToMain();
 
     const decryptedKeys = await cryptoService.decryptKeys(
@@ -344,6 +462,13 @@ export function AuthProvider({ children }: { children: ReactNode }) {
     });
   }, []);
 
+  useEffect(() => {
+    return httpClient.onSessionRefreshed(async (tokens) => {
+      await secureKeyStore.saveAuthToken(tokens.token);
+      await secureKeyStore.saveRefreshToken(tokens.refresh_token);
+    });
+  }, []);
+
   useEffect(() => {
     const handleUnauthorized = () => {
       logout();
@@ -363,6 +488,166 @@ export function AuthProvider({ children }: { children: ReactNode }) {
     await fetchPreferences();
   }, [fetchPreferences]);
 
+  const applyUsername = useCallback((username: string) => {
+    setState((prev) => {
+      if (!prev.user || prev.user.username === username) return prev;
+      const user = { ...prev.user, username };
+      localStorage.setItem(AUTH_STORAGE_KEY, JSON.stringify({ user }));
+      return { ...prev, user };
+    });
+  }, []);
+
+  const changeUsername = useCallback(
+    async (username: string) => {
+      const change = await authService.changeUsername(username);
+      applyUsername(change.new_username);
+    },
+    [applyUsername]
+  );
+
+  const rotateIdentityKeys = useCallback(
+    async (password: string, twoFactorCode?: string) => {
+      const { user, keys } = state;
+      if (!user || !keys) throw new Error("Not authenticated");
+
+      const encryptedKeys = await cryptoService.generateKeys(password);
+      const newKeys = await cryptoService.decryptKeys(
+        password,
+        encryptedKeys.kem_public_key,
+        encryptedKeys.kem_encrypted_private,
+        encryptedKeys.dsa_public_key,
+        encryptedKeys.dsa_encrypted_private,
+        encryptedKeys.key_salt
+      );
+
+      const chain = await keyService.getIdentityKeyChain(user.id);
+      const version = chain[chain.length - 1].version + 1;
+      const continuitySignature = await cryptoService.dsaSign(
+        keys.dsa_secret_key,
+        await continuityMessage(user.id, version, newKeys.kem_public_key, newKeys.dsa_public_key)
+      );
+
+      await keyService.rotateIdentityKeys({
+        password,
+        two_factor_code: twoFactorCode || undefined,
+        ...encryptedKeys,
+        continuity_signature: continuitySignature,
+      });
+
+      const updatedUser = {
+        ...user,
+        kem_public_key: newKeys.kem_public_key,
+        dsa_public_key: newKeys.dsa_public_key,
+      };
+      await saveAuthToStorage(updatedUser, newKeys);
+      setState((prev) => ({
+        ...prev,
+        user: updatedUser,
+        keys: newKeys,
+        needsRecoverySetup: true,
+      }));
+
+      // Prekeys were signed by the old identity key and have been dropped.
+      const signedPrekey = await cryptoService.generateSignedPrekey(newKeys.dsa_secret_key);
+      const oneTimePrekeys = await cryptoService.generateOneTimePrekeys(100);
+      await savePrekeySecrets(signedPrekey, oneTimePrekeys);
+      await keyService.uploadPrekeys({
+        signed_prekey_public: signedPrekey.public_key,
+        signed_prekey_signature: signedPrekey.signature,
+        signed_prekey_id: signedPrekey.prekey_id,
+        one_time_prekeys: oneTimePrekeys.map((p) => ({
+          prekey_id: p.prekey_id,
+          public_key: p.public_key,
+        })),
+      });
+    },
+    [state]
+  );
+
+  useEffect(() => {
+    const userId = state.user?.id;
+    if (!userId) return;
+
+    return centralWebSocketService.onMessage((message) => {
+      if (message.type === "user_renamed" && message.data.user_id === userId) {
+        applyUsername(message.data.new_username);
+      }
+    });
+  }, [state.user?.id, applyUsername]);
+
+  const dsaSecretKey = state.keys?.dsa_secret_key;
+  useEffect(() => {
+    if (!dsaSecretKey) return;
+
+    return centralWebSocketService.onMessage((message) => {
+      if (message.type === "prekeys_low" || message.type === "signed_prekey_stale") {
+        replenishPrekeys(dsaSecretKey, message.type === "signed_prekey_stale").catch((err) =>
+          console.error("Failed to replenish prekeys:", err)
+        );
+      }
+    });
+  }, [dsaSecretKey]);
+
+  const [pendingDeviceLink, setPendingDeviceLink] = useState<PendingDeviceLink | null>(null);
+  const deviceLinkSecrets = useRef(new Map<string, number[]>());
+
+  useEffect(() => {
+    if (!state.user?.id) return;
+
+    return centralWebSocketService.onMessage((message) => {
+      if (message.type === "device_link_requested") {
+        if (!deviceLinkSecrets.current.has(message.data.link_id)) return;
+        setPendingDeviceLink({
+          linkId: message.data.link_id,
+          deviceName: message.data.device_name,
+          platform: message.data.platform,
+          encryptedTransferKey: message.data.encrypted_transfer_key,
+        });
+      } else if (message.type === "device_link_completed") {
+        deviceLinkSecrets.current.delete(message.data.link_id);
+        setPendingDeviceLink((prev) => (prev?.linkId === message.data.link_id ? null : prev));
+      }
+    });
+  }, [state.user?.id]);
+
+  const startDeviceLink = useCallback(async (): Promise<DeviceLinkOffer> => {
+    const ephemeral = await generateEphemeralKeyPair();
+    const { link_id, expires_in } = await authService.createDeviceLink(ephemeral.publicKey);
+    deviceLinkSecrets.current.set(link_id, ephemeral.secretKey);
+    setTimeout(() => deviceLinkSecrets.current.delete(link_id), expires_in * 1000);
+
+    return {
+      linkId: link_id,
+      qrValue: encodeDeviceLinkQr({ linkId: link_id, ephemeralPublicKey: ephemeral.publicKey }),
+      expiresIn: expires_in,
+    };
+  }, []);
+
+  const approveDeviceLink = useCallback(
+    async (linkId: string) => {
+      const ephemeralSecretKey = deviceLinkSecrets.current.get(linkId);
+      if (!state.keys || !ephemeralSecretKey || pendingDeviceLink?.linkId !== linkId) {
+        throw new Error("No pending device link to approve");
+      }
+
+      const encryptedKeys = await encryptDeviceLinkPayload(
+        ephemeralSecretKey,
+        pendingDeviceLink.encryptedTransferKey,
+        { keys: state.keys, prekeys: await getPrekeySecrets() }
+      );
+      await authService.approveDeviceLink(linkId, encryptedKeys);
+      deviceLinkSecrets.current.delete(linkId);
+      setPendingDeviceLink(null);
+    },
+    [state.keys, pendingDeviceLink]
+  );
+
+  const rejectDeviceLink = useCallback(async (linkId: string) => {
+    deviceLinkSecrets.current.delete(linkId);
+    setPendingDeviceLink((prev) => (prev?.linkId === linkId ? null : prev));
+    await authService.rejectDeviceLink(linkId);
+  }, []);
+
   const checkRecoveryStatus = useCallback(async () => {
     try {
       const status = await recoveryService.getRecoveryStatus();
@@ -382,12 +667,20 @@ export function AuthProvider({ children }: { children: ReactNode }) {
       value={{
         ...state,
         login,
+        completeTwoFactorLogin,
         register,
         logout,
         refreshProfile,
         refreshPreferences,
+        changeUsername,
+        rotateIdentityKeys,
         checkRecoveryStatus,
         completeRecoverySetup,
+        pendingDeviceLink,
+        startDeviceLink,
+        approveDeviceLink,
+        rejectDeviceLink,
+        linkWithDevice,
       }}
     >
       {children}
@@ -6,6 +6,7 @@ import { conversationService } from "../../features/chat/conversations";
 import { useFriends } from "../../hooks/useFriends";
 import { useChatMessages } from "../../hooks/chat";
 import { useGroups } from "../../hooks/useGroups";
+import { useVerifiedContacts } from "../../hooks/useVerifiedContacts";
 import type { Friend, DmPreview } from "../../types/index";
 import type { ChatContextType } from "./types";
 import { useChatUIState } from "./useChatUIState";
@@ -23,6 +24,7 @@ export function ChatProvider({ children }: { children: ReactNode }) {
   const friendsLogic = useFriends();
   const chatLogic = useChatMessages(friendsLogic.friendsList);
   const groupsLogic = useGroups();
+  const verification = useVerifiedContacts();
   const loadGroupPreviews = groupsLogic.loadGroupPreviews;
 
   const [isConnected, setIsConnected] = useState(centralWebSocketService.isConnected());
@@ -47,9 +49,11 @@ export function ChatProvider({ children }: { children: ReactNode }) {
     userKeys: keys,
     activeChat: chatLogic.activeChat,
     friendsLogic: {
+      friendsList: friendsLogic.friendsList,
       loadFriendRequests: friendsLogic.loadFriendRequests,
       onFriendAccepted: friendsLogic.onFriendAccepted,
       onFriendRemoved: friendsLogic.onFriendRemoved,
+      onFriendRenamed: friendsLogic.onFriendRenamed,
       setFriendsList: friendsLogic.setFriendsList,
     },
     chatLogic: {
@@ -130,6 +134,7 @@ export function ChatProvider({ children }: { children: ReactNode }) {
 
     ...friendsLogic,
     ...chatLogic,
+    ...verification,
 
     isConnected,
     hasConnectedOnce,
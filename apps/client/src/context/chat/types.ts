@@ -15,6 +15,7 @@ import type {
   DmPreview,
   SystemMessageType,
 } from "../../types/index";
+import type { VerifiedContact } from "../../features/friends";
 
 export interface VerifyModalData {
   friendId: string;
@@ -74,6 +75,10 @@ export interface ChatContextType {
   setShowProfilePanel: (show: boolean) => void;
   verifyModal: VerifyModalData | null;
   setVerifyModal: (data: VerifyModalData | null) => void;
+  verifiedContacts: VerifiedContact[];
+  keyChangedContacts: Set<string>;
+  isVerified: (userId: string, identityKey: number[]) => boolean;
+  setContactVerified: (userId: string, identityKey: number[], verified: boolean) => Promise<void>;
 
   error: string;
   setError: (error: string) => void;
@@ -2,6 +2,7 @@ import { useEffect } from "react";
 import { centralWebSocketService } from "../../core/network/CentralWebSocketService";
 import { cryptoService } from "../../core/crypto/crypto";
 import { keyService } from "../../core/crypto/KeyService";
+import { verifyKeyUpdate } from "../../core/crypto/identityChain";
 import { getPrekeySecrets } from "../AuthContext";
 import {
   initNotifications,
@@ -9,6 +10,7 @@ import {
   NotificationService,
 } from "../../utils/notifications";
 import { messageService } from "../../features/chat/messages";
+import { conversationService } from "../../features/chat/conversations";
 import type { DecryptedMessage, ActiveChat, DmPreview } from "../../types/index";
 import type { Reaction } from "../../features/chat/types";
 
@@ -17,9 +19,11 @@ interface UseChatWebSocketParams {
   userKeys: { kem_secret_key: number[] } | null;
   activeChat: ActiveChat | null;
   friendsLogic: {
+    friendsList: { id: string; dsa_public_key?: number[] }[];
     loadFriendRequests: () => Promise<void>;
     onFriendAccepted: (byUserId: string, byUsername: string) => void;
     onFriendRemoved: (byUserId: string) => void;
+    onFriendRenamed: (userId: string, username: string) => void;
     setFriendsList: React.Dispatch<React.SetStateAction<any[]>>;
   };
   chatLogic: {
@@ -67,6 +71,15 @@ export function useChatWebSocket({
     };
   }, [setIsConnected, setHasConnectedOnce]);
 
+  useEffect(() => {
+    if (!userId) return;
+
+    conversationService
+      .getAllNotificationSettings()
+      .then((settings) => NotificationService.setConversationRules(settings))
+      .catch((error) => console.error("Failed to load notification settings:", error));
+  }, [userId]);
+
   useEffect(() => {
     const initializeNotifications = async () => {
       try {
@@ -91,16 +104,55 @@ export function useChatWebSocket({
         case "friend_removed":
           friendsLogic.onFriendRemoved(message.data.by_user_id);
           break;
-        case "key_update":
-          friendsLogic.setFriendsList((prev) =>
-            prev.map((f) => {
-              if (f.id === message.data.user_id) {
-                return { ...f, kem_public_key: message.data.kem_public_key };
+        case "account_deleted":
+          friendsLogic.onFriendRemoved(message.data.user_id);
+          loadGroupPreviews();
+          break;
+        case "user_renamed":
+          if (message.data.user_id === userId) break;
+          friendsLogic.onFriendRenamed(message.data.user_id, message.data.new_username);
+          chatLogic.setDmPreviews((prev) =>
+            prev.map((p) =>
+              p.visitorId === message.data.user_id
+                ? { ...p, visitorUsername: message.data.new_username }
+                : p
+            )
+          );
+          chatLogic.setActiveChat((prev) =>
+            prev && prev.visitorId === message.data.user_id
+              ? { ...prev, visitorUsername: message.data.new_username }
+              : prev
+          );
+          break;
+        case "key_update": {
+          const update = message.data;
+          const trusted = friendsLogic.friendsList.find(
+            (f) => f.id === update.user_id
+          )?.dsa_public_key;
+          verifyKeyUpdate(update, trusted)
+            .then((valid) => {
+              if (!valid) {
+                console.warn(
+                  `Ignoring key update for ${update.user_id}: no valid continuity proof`
+                );
+                return;
               }
-              return f;
+              friendsLogic.setFriendsList((prev) =>
+                prev.map((f) => {
+                  if (f.id === update.user_id) {
+                    return {
+                      ...f,
+                      kem_public_key: update.kem_public_key,
+                      dsa_public_key: update.dsa_public_key,
+                    };
+                  }
+                  return f;
+                })
+              );
             })
-          );
+            .catch((error) => console.error("Failed to verify key update:", error));
           break;
+        }
         case "new_message":
           if (message.data.conversation_id) {
             chatLogic.handleIncomingMessage({
@@ -380,6 +432,9 @@ export function useChatWebSocket({
             }
           }
           break;
+        case "notification_settings_updated":
+          NotificationService.updateConversationRule(message.data);
+          break;
         case "group_deleted":
           {
             const { conversation_id } = message.data;
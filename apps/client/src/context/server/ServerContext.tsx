@@ -22,11 +22,13 @@ import {
   type FederatedMember as Member,
 } from "../../features/servers/federatedClient";
 import { FederatedWebSocketService as FederatedWsClient } from "../../features/servers/federatedWebSocket";
+import type { FederatedSessionTokens } from "../../features/servers/federation";
 import type { ServerContextType, RoleEventCallback } from "./types";
 import {
   saveLastChannelForServer,
   loadFederatedServersFromDB,
   migrateFromLocalStorage,
+  saveFederatedServersToDB,
 } from "./storage";
 import { FEDERATED_SERVERS_KEY } from "./types";
 import { useFederatedServerData } from "./useFederatedServerData";
@@ -40,6 +42,7 @@ export function ServerProvider({ children }: { children: ReactNode }) {
   const [servers, setServers] = useState<DecryptedServer[]>([]);
   const [federatedServers, setFederatedServers] = useState<FederatedServer[]>([]);
   const [federatedServersLoaded, setFederatedServersLoaded] = useState(false);
+  const federatedServersRef = useRef<FederatedServer[]>([]);
   const [activeServer, setActiveServerState] = useState<AnyServer | null>(null);
   const [activeChannel, setActiveChannelState] = useState<DecryptedChannel | null>(null);
   const activeServerRef = useRef<AnyServer | null>(null);
@@ -65,6 +68,32 @@ export function ServerProvider({ children }: { children: ReactNode }) {
     setServers([]);
   };
 
+  useEffect(() => {
+    federatedServersRef.current = federatedServers;
+  }, [federatedServers]);
+
+  const handleSessionRefreshed = useCallback(
+    (serverId: string, tokens: FederatedSessionTokens) => {
+      if (activeServerRef.current?.id === serverId) {
+        federatedWsRef.current?.reauthenticate(tokens.session_token);
+      }
+
+      const updated = federatedServersRef.current.map((s) =>
+        s.id === serverId
+          ? { ...s, session_token: tokens.session_token, refresh_token: tokens.refresh_token }
+          : s
+      );
+      federatedServersRef.current = updated;
+      setFederatedServers(updated);
+      if (keys) {
+        saveFederatedServersToDB(updated, Array.from(keys.kem_secret_key)).catch((err) =>
+          console.error("Failed to save refreshed server session:", err)
+        );
+      }
+    },
+    [keys]
+  );
+
   const { loadFederatedServerData, distributeKeysToMember } = useFederatedServerData({
     keys,
     setIsLoading,
@@ -73,6 +102,7 @@ export function ServerProvider({ children }: { children: ReactNode }) {
     setActiveChannel,
     federatedClientRef,
     myMemberRef,
+    onSessionRefreshed: handleSessionRefreshed,
   });
 
   const notifyRoleEvent = useCallback(
@@ -92,6 +122,7 @@ export function ServerProvider({ children }: { children: ReactNode }) {
       setActiveServerState(server);
       activeServerRef.current = server;
       setActiveChannelState(null);
+      federatedClientRef.current?.close();
       federatedClientRef.current = null;
       setMyPermissions(0);
 
@@ -163,16 +194,21 @@ export function ServerProvider({ children }: { children: ReactNode }) {
     loadFederatedServerData,
   });
 
-  const { joinServerByDomain, registerAndJoinServer, leaveServer, deleteServer } =
-    useServerMembership({
-      user,
-      keys,
-      federatedServers,
-      activeServer,
-      setFederatedServers,
-      setActiveServer,
-      setActiveChannel: () => setActiveChannelState(null),
-    });
+  const {
+    joinServerByDomain,
+    registerAndJoinServer,
+    leaveServer,
+    deleteServer,
+    announceUsernameChange,
+  } = useServerMembership({
+    user,
+    keys,
+    federatedServers,
+    activeServer,
+    setFederatedServers,
+    setActiveServer,
+    setActiveChannel: () => setActiveChannelState(null),
+  });
 
   const reloadServerData = useCallback(async () => {
     if (!activeServer) return;
@@ -224,10 +260,15 @@ export function ServerProvider({ children }: { children: ReactNode }) {
         case "role_deleted":
           notifyRoleEvent(message.data.server_id);
           break;
+        case "user_renamed":
+          if (message.data.user_id === user?.id) {
+            announceUsernameChange(message.data.new_username);
+          }
+          break;
       }
     });
     return () => unsubscribe();
-  }, [notifyRoleEvent]);
+  }, [notifyRoleEvent, user?.id, announceUsernameChange]);
 
   const value: ServerContextType = {
     servers,
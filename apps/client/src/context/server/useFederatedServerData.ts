@@ -1,4 +1,4 @@
-import { useCallback, type MutableRefObject } from "react";
+import { useCallback, useRef, type MutableRefObject } from "react";
 import { cryptoService } from "../../core/crypto/crypto";
 import type {
   DecryptedCategory,
@@ -9,6 +9,7 @@ import {
   FederatedServerClient,
   type FederatedMember as Member,
 } from "../../features/servers/federatedClient";
+import type { FederatedSessionTokens } from "../../features/servers/federation";
 import type { WsMember } from "../../features/servers/federatedWebSocket";
 import { getLastChannelForServer } from "./storage";
 import { encryptChannelKeyForMember } from "../../features/servers/channelEncryption";
@@ -21,6 +22,7 @@ interface UseFederatedServerDataParams {
   setActiveChannel: (channel: DecryptedChannel | null) => void;
   federatedClientRef: MutableRefObject<FederatedServerClient | null>;
   myMemberRef: MutableRefObject<Member | null>;
+  onSessionRefreshed: (serverId: string, tokens: FederatedSessionTokens) => void;
 }
 
 export function useFederatedServerData({
@@ -31,7 +33,12 @@ export function useFederatedServerData({
   setActiveChannel,
   federatedClientRef,
   myMemberRef,
+  onSessionRefreshed,
 }: UseFederatedServerDataParams) {
+  // Refresh tokens are single-use, so the copy on a server object captured
+  // before the last refresh must not be replayed.
+  const refreshedSessions = useRef(new Map<string, FederatedSessionTokens>());
+
   const distributeKeysToMember = useCallback(
     async (client: FederatedServerClient, newMember: WsMember | Member, myMember: Member) => {
       if (!keys) return;
@@ -65,15 +72,32 @@ export function useFederatedServerData({
 
   const loadFederatedServerData = useCallback(
     async (server: FederatedServer, autoSelectChannel: boolean = false) => {
-      if (!server.session_token) {
+      const latest = refreshedSessions.current.get(server.id);
+      const sessionToken = latest?.session_token ?? server.session_token;
+      const refreshToken = latest?.refresh_token ?? server.refresh_token;
+      if (!sessionToken) {
         console.error("No session token for federated server");
         return;
       }
 
       setIsLoading(true);
       try {
-        const client = new FederatedServerClient(server.domain, server.session_token);
+        const client = new FederatedServerClient(
+          server.domain,
+          sessionToken,
+          refreshToken
+            ? {
+                refreshToken,
+                onRefreshed: (tokens) => {
+                  refreshedSessions.current.set(server.id, tokens);
+                  onSessionRefreshed(server.id, tokens);
+                },
+              }
+            : undefined
+        );
+        federatedClientRef.current?.close();
         federatedClientRef.current = client;
+        await client.refreshSession();
 
         const [categoriesRes, channelsRes, myMember] = await Promise.all([
           client.getCategories(),
@@ -125,7 +149,15 @@ export function useFederatedServerData({
         setIsLoading(false);
       }
     },
-    [setIsLoading, setCategories, setChannels, setActiveChannel, federatedClientRef, myMemberRef]
+    [
+      setIsLoading,
+      setCategories,
+      setChannels,
+      setActiveChannel,
+      federatedClientRef,
+      myMemberRef,
+      onSessionRefreshed,
+    ]
   );
 
   return { loadFederatedServerData, distributeKeysToMember };
@@ -5,10 +5,16 @@ import { FederatedServerClient } from "../../features/servers/federatedClient";
 import { saveFederatedServersToDB } from "./storage";
 import type { RegisterServerData } from "./types";
 import { createChannelEncryptionPayload } from "../../features/servers/channelEncryption";
+import { cryptoService } from "../../core/crypto/crypto";
 
 interface UseServerMembershipParams {
   user: { id: string; username: string } | null;
-  keys: { kem_public_key: number[]; kem_secret_key: number[]; dsa_public_key: number[] } | null;
+  keys: {
+    kem_public_key: number[];
+    kem_secret_key: number[];
+    dsa_public_key: number[];
+    dsa_secret_key: number[];
+  } | null;
   federatedServers: FederatedServer[];
   activeServer: AnyServer | null;
   setFederatedServers: React.Dispatch<React.SetStateAction<FederatedServer[]>>;
@@ -51,6 +57,7 @@ export function useServerMembership({
           description: tokenResponse.server_info.description,
           icon_url: tokenResponse.server_info.icon_url,
           session_token: joinResponse.session_token,
+          refresh_token: joinResponse.refresh_token,
           member_id: joinResponse.member_id,
           isFederated: true,
         };
@@ -82,6 +89,7 @@ export function useServerMembership({
           name: result.server_name,
           description: "",
           session_token: result.session_token,
+          refresh_token: result.refresh_token,
           member_id: result.member_id,
           is_owner: true,
           isFederated: true,
@@ -182,5 +190,42 @@ export function useServerMembership({
     [keys, federatedServers, activeServer, setFederatedServers, setActiveServer, setActiveChannel]
   );
 
-  return { joinServerByDomain, registerAndJoinServer, leaveServer, deleteServer };
+  const announceUsernameChange = useCallback(
+    async (username: string) => {
+      if (!user || !keys) return;
+
+      const timestamp = Math.floor(Date.now() / 1000);
+      const signature = await cryptoService.dsaSign(
+        keys.dsa_secret_key,
+        cryptoService.stringToBytes(`user_renamed:${user.id}:${username}:${timestamp}`)
+      );
+
+      const results = await Promise.allSettled(
+        federatedServers.map((server) =>
+          new FederatedServerClient(server.domain, server.session_token || "").syncUsername({
+            username,
+            timestamp,
+            signature,
+          })
+        )
+      );
+      results.forEach((result, i) => {
+        if (result.status === "rejected") {
+          console.error(
+            `Failed to sync username with ${federatedServers[i].domain}:`,
+            result.reason
+          );
+        }
+      });
+    },
+    [user, keys, federatedServers]
+  );
+
+  return {
+    joinServerByDomain,
+    registerAndJoinServer,
+    leaveServer,
+    deleteServer,
+    announceUsernameChange,
+  };
 }
@@ -1,40 +1,165 @@
+import { hostname, platform } from "@tauri-apps/plugin-os";
 import { httpClient } from "../network/HttpClient";
 import type {
   AuthResponse,
+  BackupCodesResponse,
+  ClaimDeviceLinkResponse,
+  CreateDeviceLinkResponse,
+  DeviceInfo,
   KeysResponse,
   LoginRequest,
   LoginResponse,
+  LoginResult,
   PublicUser,
   RegisterRequest,
+  RequestDeviceLinkResponse,
+  SessionInfo,
+  TwoFactorSetupResponse,
+  TwoFactorStatus,
+  UsernameChange,
 } from "./types";
 import { SuccessResponse } from "@/types/common";
+import { CLIENT_VERSION } from "@/config";
 
 class AuthService {
+  private async getDeviceInfo(): Promise<DeviceInfo> {
+    let name: string | null = null;
+    let os: string | null = null;
+    try {
+      os = platform();
+      name = await hostname();
+    } catch {}
+    return { name, platform: os, client_version: CLIENT_VERSION };
+  }
+
   public async register(data: RegisterRequest): Promise<AuthResponse> {
-    const response = await httpClient.post<AuthResponse>("/auth/register", data);
-    httpClient.setAuthToken(response.token);
+    const response = await httpClient.post<AuthResponse>("/auth/register", {
+      ...data,
+      device: data.device ?? (await this.getDeviceInfo()),
+    });
+    httpClient.setSessionTokens(response);
+    return response;
+  }
+
+  public async login(data: LoginRequest): Promise<LoginResult> {
+    const response = await httpClient.post<LoginResult>("/auth/login", {
+      ...data,
+      device: data.device ?? (await this.getDeviceInfo()),
+    });
+    if ("token" in response) {
+      httpClient.setSessionTokens(response);
+    }
     return response;
   }
 
-  public async login(data: LoginRequest): Promise<LoginResponse> {
-    const response = await httpClient.post<LoginResponse>("/auth/login", data);
-    httpClient.setAuthToken(response.token);
+  public async completeTwoFactorLogin(challenge: string, code: string): Promise<LoginResponse> {
+    const response = await httpClient.post<LoginResponse>("/auth/login/2fa", { challenge, code });
+    httpClient.setSessionTokens(response);
     return response;
   }
 
+  public async getTwoFactorStatus(): Promise<TwoFactorStatus> {
+    return httpClient.get<TwoFactorStatus>("/auth/2fa");
+  }
+
+  public async setupTwoFactor(): Promise<TwoFactorSetupResponse> {
+    return httpClient.post<TwoFactorSetupResponse>("/auth/2fa/setup");
+  }
+
+  public async enableTwoFactor(code: string): Promise<BackupCodesResponse> {
+    return httpClient.post<BackupCodesResponse>("/auth/2fa/enable", { code });
+  }
+
+  public async disableTwoFactor(password: string, code: string): Promise<SuccessResponse> {
+    return httpClient.post<SuccessResponse>("/auth/2fa/disable", { password, code });
+  }
+
+  public async regenerateBackupCodes(code: string): Promise<BackupCodesResponse> {
+    return httpClient.post<BackupCodesResponse>("/auth/2fa/backup-codes", { code });
+  }
+
   public async getMe(): Promise<PublicUser> {
     return httpClient.get<PublicUser>("/auth/me");
   }
 
+  public async changeUsername(username: string): Promise<UsernameChange> {
+    return httpClient.put<UsernameChange>("/auth/username", { username });
+  }
+
+  public async getUsernameHistory(): Promise<UsernameChange[]> {
+    return httpClient.get<UsernameChange[]>("/auth/username/history");
+  }
+
   public async logout(): Promise<SuccessResponse> {
     const response = await httpClient.post<SuccessResponse>("/auth/logout");
     httpClient.setAuthToken(null);
     return response;
   }
 
+  public async deleteAccount(password: string, twoFactorCode?: string): Promise<SuccessResponse> {
+    const response = await httpClient.del<SuccessResponse>("/auth/me", {
+      password,
+      two_factor_code: twoFactorCode || undefined,
+    });
+    httpClient.setAuthToken(null);
+    return response;
+  }
+
+  public async logoutOthers(): Promise<{ revoked: number }> {
+    return httpClient.post<{ revoked: number }>("/auth/logout-others");
+  }
+
+  public async getSessions(): Promise<SessionInfo[]> {
+    return httpClient.get<SessionInfo[]>("/auth/sessions");
+  }
+
+  public async revokeSession(sessionId: string): Promise<SuccessResponse> {
+    return httpClient.del<SuccessResponse>(`/auth/sessions/${sessionId}`);
+  }
+
   public async getKeys(): Promise<KeysResponse> {
     return httpClient.get<KeysResponse>("/auth/keys");
   }
+
+  public async createDeviceLink(ephemeralPublicKey: number[]): Promise<CreateDeviceLinkResponse> {
+    return httpClient.post<CreateDeviceLinkResponse>("/auth/link", {
+      ephemeral_public_key: ephemeralPublicKey,
+    });
+  }
+
+  public async requestDeviceLink(
+    linkId: string,
+    encryptedTransferKey: number[]
+  ): Promise<RequestDeviceLinkResponse> {
+    return httpClient.post<RequestDeviceLinkResponse>(`/auth/link/${linkId}/request`, {
+      encrypted_transfer_key: encryptedTransferKey,
+      device: await this.getDeviceInfo(),
+    });
+  }
+
+  public async approveDeviceLink(linkId: string, encryptedKeys: number[]): Promise<SuccessResponse> {
+    return httpClient.post<SuccessResponse>(`/auth/link/${linkId}/approve`, {
+      encrypted_keys: encryptedKeys,
+    });
+  }
+
+  public async rejectDeviceLink(linkId: string): Promise<SuccessResponse> {
+    return httpClient.post<SuccessResponse>(`/auth/link/${linkId}/reject`);
+  }
+
+  public async claimDeviceLink(
+    linkId: string,
+    claimToken: string
+  ): Promise<ClaimDeviceLinkResponse> {
+    const response = await httpClient.post<ClaimDeviceLinkResponse>(
+      `/auth/link/${linkId}/claim`,
+      { claim_token: claimToken }
+    );
+    if (response.status === "approved") {
+      httpClient.setSessionTokens(response);
+    }
+    return response;
+  }
 }
 
 export const authService = new AuthService();
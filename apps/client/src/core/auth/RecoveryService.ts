@@ -28,6 +28,7 @@ export interface ResetPasswordRequest {
   recovery_kem_encrypted_private: number[];
   recovery_dsa_encrypted_private: number[];
   recovery_key_salt: number[];
+  two_factor_code?: string;
 }
 
 export interface ResetPasswordResponse {
@@ -38,6 +39,8 @@ export interface ResetPasswordResponse {
     dsa_public_key: number[];
   };
   token: string;
+  refresh_token: string;
+  expires_in: number;
 }
 
 class RecoveryService {
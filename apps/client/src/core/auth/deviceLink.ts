@@ -0,0 +1,60 @@
+import { cryptoService } from "../crypto/crypto";
+import type { DecryptedKeys } from "../crypto/crypto";
+
+const QR_PREFIX = "confide-link:v1:";
+
+export interface DeviceLinkQr {
+  linkId: string;
+  ephemeralPublicKey: number[];
+}
+
+// Everything a linked device needs to act as the account: the identity keys and
+// the current prekey secrets, so both devices answer to the same signed prekey.
+export interface DeviceLinkPayload {
+  keys: DecryptedKeys;
+  prekeys: unknown | null;
+}
+
+export function encodeDeviceLinkQr(qr: DeviceLinkQr): string {
+  return `${QR_PREFIX}${qr.linkId}:${cryptoService.bytesToHex(qr.ephemeralPublicKey)}`;
+}
+
+export function parseDeviceLinkQr(value: string): DeviceLinkQr | null {
+  if (!value.startsWith(QR_PREFIX)) return null;
+  const [linkId, publicKeyHex] = value.slice(QR_PREFIX.length).split(":");
+  if (!linkId || !publicKeyHex || !/^[0-9a-f]+$/i.test(publicKeyHex)) return null;
+  return { linkId, ephemeralPublicKey: cryptoService.hexToBytes(publicKeyHex) };
+}
+
+// One-time prekeys are plain KEM key pairs, so a single fresh one serves as the
+// link's ephemeral key.
+export async function generateEphemeralKeyPair(): Promise<{
+  publicKey: number[];
+  secretKey: number[];
+}> {
+  const [prekey] = await cryptoService.generateOneTimePrekeys(1);
+  return { publicKey: prekey.public_key, secretKey: prekey.secret_key };
+}
+
+export async function encryptDeviceLinkPayload(
+  ephemeralSecretKey: number[],
+  encryptedTransferKey: number[],
+  payload: DeviceLinkPayload
+): Promise<number[]> {
+  const transferKey = await cryptoService.decryptFromSender(
+    ephemeralSecretKey,
+    encryptedTransferKey
+  );
+  return cryptoService.encryptWithKey(
+    transferKey,
+    cryptoService.stringToBytes(JSON.stringify(payload))
+  );
+}
+
+export async function decryptDeviceLinkPayload(
+  transferKey: number[],
+  encryptedKeys: number[]
+): Promise<DeviceLinkPayload> {
+  const bytes = await cryptoService.decryptWithKey(transferKey, encryptedKeys);
+  return JSON.parse(cryptoService.bytesToString(bytes)) as DeviceLinkPayload;
+}
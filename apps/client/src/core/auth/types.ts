@@ -5,19 +5,47 @@ export interface PublicUser {
   dsa_public_key: number[];
 }
 
-export interface AuthResponse {
-  user: PublicUser;
+export interface SessionTokens {
   token: string;
+  refresh_token: string;
+  expires_in: number;
 }
 
-export interface LoginResponse {
+export interface AuthResponse extends SessionTokens {
+  user: PublicUser;
+}
+
+export interface LoginResponse extends SessionTokens {
   user: PublicUser;
-  token: string;
   kem_encrypted_private: number[];
   dsa_encrypted_private: number[];
   key_salt: number[];
 }
 
+export interface TwoFactorChallenge {
+  two_factor_required: true;
+  challenge: string;
+  expires_in: number;
+}
+
+export type LoginResult = LoginResponse | TwoFactorChallenge;
+
+export interface TwoFactorStatus {
+  available: boolean;
+  enabled: boolean;
+  enabled_at: string | null;
+  backup_codes_remaining: number;
+}
+
+export interface TwoFactorSetupResponse {
+  secret: string;
+  provisioning_uri: string;
+}
+
+export interface BackupCodesResponse {
+  backup_codes: string[];
+}
+
 export interface KeysResponse {
   kem_encrypted_private: number[];
   dsa_encrypted_private: number[];
@@ -32,9 +60,71 @@ export interface RegisterRequest {
   dsa_public_key: number[];
   dsa_encrypted_private: number[];
   key_salt: number[];
+  device?: DeviceInfo;
 }
 
 export interface LoginRequest {
   username: string;
   password: string;
+  device?: DeviceInfo;
+}
+
+export interface DeviceInfo {
+  name: string | null;
+  platform: string | null;
+  client_version: string | null;
+}
+
+export interface UsernameChange {
+  id: string;
+  old_username: string;
+  new_username: string;
+  changed_at: string;
+}
+
+export interface SessionInfo {
+  id: string;
+  device_name: string | null;
+  platform: string | null;
+  client_version: string | null;
+  ip_prefix: string | null;
+  created_at: string;
+  last_seen_at: string;
+  current: boolean;
+}
+
+export interface CreateDeviceLinkResponse {
+  link_id: string;
+  expires_in: number;
+}
+
+export interface RequestDeviceLinkResponse {
+  claim_token: string;
+  expires_in: number;
+}
+
+export interface LinkedSession extends LoginResponse {
+  encrypted_keys: number[];
+}
+
+export type ClaimDeviceLinkResponse =
+  | { status: "pending" }
+  | ({ status: "approved" } & LinkedSession);
+
+export interface WsDeviceLinkRequested {
+  type: "device_link_requested";
+  data: {
+    link_id: string;
+    device_name: string | null;
+    platform: string | null;
+    encrypted_transfer_key: number[];
+  };
+}
+
+export interface WsDeviceLinkCompleted {
+  type: "device_link_completed";
+  data: {
+    link_id: string;
+    device_name: string | null;
+  };
 }
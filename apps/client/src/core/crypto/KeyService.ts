@@ -10,6 +10,7 @@ import type {
   SessionResponse,
   UploadPrekeysRequest,
 } from "../../features/chat/types";
+import type { IdentityKeyRecord, RotateIdentityKeysRequest } from "./types";
 import type { SuccessResponse } from "@/types/common";
 
 class KeyService {
@@ -51,6 +52,14 @@ class KeyService {
   ): Promise<SuccessResponse> {
     return httpClient.post<SuccessResponse>(`/keys/sessions/${conversationId}/${peerId}`, data);
   }
+
+  public async rotateIdentityKeys(data: RotateIdentityKeysRequest): Promise<IdentityKeyRecord> {
+    return httpClient.post<IdentityKeyRecord>("/keys/identity", data);
+  }
+
+  public async getIdentityKeyChain(userId: string): Promise<IdentityKeyRecord[]> {
+    return httpClient.get<IdentityKeyRecord[]>(`/keys/identity/${userId}`);
+  }
 }
 
 export const keyService = new KeyService();
@@ -52,6 +52,18 @@ class SecureKeyStore {
     await store.save();
   }
 
+  async saveRefreshToken(token: string): Promise<void> {
+    const store = await this.getStore();
+    await store.set("refresh_token", token);
+    await store.save();
+  }
+
+  async loadRefreshToken(): Promise<string | null> {
+    const store = await this.getStore();
+    const token = await store.get<string>("refresh_token");
+    return token || null;
+  }
+
   async hasAuthToken(): Promise<boolean> {
     const store = await this.getStore();
     return await store.has("auth_token");
@@ -79,6 +91,7 @@ class SecureKeyStore {
     const store = await this.getStore();
     await store.delete("user_keys");
     await store.delete("auth_token");
+    await store.delete("refresh_token");
     await store.delete("prekey_secrets");
     await store.save();
   }
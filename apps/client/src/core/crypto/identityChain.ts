@@ -0,0 +1,96 @@
+import { cryptoService } from "./crypto";
+import { keyService } from "./KeyService";
+import type { IdentityKeyRecord } from "./types";
+
+async function sha256Hex(data: number[]): Promise<string> {
+  const digest = await crypto.subtle.digest("SHA-256", new Uint8Array(data));
+  return cryptoService.bytesToHex(Array.from(new Uint8Array(digest)));
+}
+
+/** The bytes the previous DSA key signs to vouch for a new identity key version. */
+export async function continuityMessage(
+  userId: string,
+  version: number,
+  kemPublicKey: number[],
+  dsaPublicKey: number[]
+): Promise<number[]> {
+  const kemHash = await sha256Hex(kemPublicKey);
+  const dsaHash = await sha256Hex(dsaPublicKey);
+  return cryptoService.stringToBytes(
+    `identity_rotation:${userId}:${version}:${kemHash}:${dsaHash}`
+  );
+}
+
+function sameKey(a: number[], b: number[]): boolean {
+  return a.length === b.length && a.every((byte, i) => byte === b[i]);
+}
+
+/**
+ * Checks that every version in the chain is signed by the one before it. When
+ * `trustedDsaPublicKey` is given, the chain must also pass through that key, so
+ * a chain the server made up from scratch is rejected.
+ */
+export async function verifyIdentityKeyChain(
+  chain: IdentityKeyRecord[],
+  trustedDsaPublicKey?: number[]
+): Promise<boolean> {
+  if (chain.length === 0) return false;
+
+  let anchored = !trustedDsaPublicKey;
+  for (let i = 0; i < chain.length; i++) {
+    const link = chain[i];
+    if (link.version !== i + 1) return false;
+
+    if (i > 0) {
+      const previous = chain[i - 1];
+      if (!link.continuity_signature) return false;
+      const message = await continuityMessage(
+        link.user_id,
+        link.version,
+        link.kem_public_key,
+        link.dsa_public_key
+      );
+      const valid = await cryptoService.dsaVerify(
+        previous.dsa_public_key,
+        message,
+        link.continuity_signature
+      );
+      if (!valid) return false;
+    }
+
+    if (trustedDsaPublicKey && sameKey(link.dsa_public_key, trustedDsaPublicKey)) {
+      anchored = true;
+    }
+  }
+  return anchored;
+}
+
+/**
+ * Whether a `key_update` is a legitimate rotation of a key we already trust,
+ * rather than a key substituted by the server.
+ */
+export async function verifyKeyUpdate(
+  update: {
+    user_id: string;
+    kem_public_key: number[];
+    dsa_public_key: number[];
+    key_version: number;
+  },
+  trustedDsaPublicKey?: number[]
+): Promise<boolean> {
+  if (!trustedDsaPublicKey || sameKey(update.dsa_public_key, trustedDsaPublicKey)) {
+    return true;
+  }
+
+  const chain = await keyService.getIdentityKeyChain(update.user_id);
+  const latest = chain[chain.length - 1];
+  if (
+    !latest ||
+    latest.version !== update.key_version ||
+    !sameKey(latest.dsa_public_key, update.dsa_public_key) ||
+    !sameKey(latest.kem_public_key, update.kem_public_key)
+  ) {
+    return false;
+  }
+  return verifyIdentityKeyChain(chain, trustedDsaPublicKey);
+}
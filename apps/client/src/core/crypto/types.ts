@@ -12,6 +12,9 @@ export interface UploadPrekeysRequest {
 
 export interface PrekeyCountResponse {
   count: number;
+  low_water_mark: number;
+  signed_prekey_created_at: string | null;
+  signed_prekey_stale: boolean;
 }
 
 export interface OneTimePrekeyInfo {
@@ -59,11 +62,51 @@ export interface SaveSessionRequest {
   encrypted_state: number[];
 }
 
+export interface IdentityKeyRecord {
+  user_id: string;
+  version: number;
+  kem_public_key: number[];
+  dsa_public_key: number[];
+  continuity_signature: number[] | null;
+  created_at: string;
+}
+
+export interface RotateIdentityKeysRequest {
+  password: string;
+  two_factor_code?: string;
+  kem_public_key: number[];
+  kem_encrypted_private: number[];
+  dsa_public_key: number[];
+  dsa_encrypted_private: number[];
+  key_salt: number[];
+  continuity_signature: number[];
+}
+
 export interface WsKeyUpdate {
   type: "key_update";
   data: {
     user_id: string;
     kem_public_key: number[];
+    dsa_public_key: number[];
+    key_version: number;
+    continuity_signature: number[] | null;
+  };
+}
+
+export interface WsPrekeysLow {
+  type: "prekeys_low";
+  data: {
+    remaining: number;
+    low_water_mark: number;
+  };
+}
+
+export interface WsSignedPrekeyStale {
+  type: "signed_prekey_stale";
+  data: {
+    signed_prekey_id: number;
+    created_at: string;
+    max_age_days: number;
   };
 }
 
@@ -60,16 +60,48 @@ interface OutgoingAuth {
   type: "auth";
   data: {
     token: string;
+    resume?: {
+      last_seq: number;
+    };
   };
 }
 
-type InternalOutgoingMessage = OutgoingMessage | OutgoingAuth;
+interface OutgoingReauth {
+  type: "reauth";
+  data: {
+    token: string;
+  };
+}
+
+type InternalOutgoingMessage = OutgoingMessage | OutgoingAuth | OutgoingReauth;
 
 class CentralWebSocketService extends BaseWebSocket<WsMessage, InternalOutgoingMessage> {
+  private lastSeq: number | null = null;
+  private lastSeqToken: string | null = null;
+
   constructor() {
     super();
     this.shouldPing = true;
     this.pingIntervalMs = 25000;
+    this.onMessage((message) => this.trackSeq(message));
+    httpClient.onSessionRefreshed((tokens) => this.reauthenticate(tokens.token));
+  }
+
+  /** Hands a refreshed access token to the open socket so it is not closed at expiry. */
+  private reauthenticate(token: string): void {
+    this.lastSeqToken = token;
+    this.send({ type: "reauth", data: { token } });
+  }
+
+  private trackSeq(message: WsMessage): void {
+    if (message.type === "resumed") {
+      this.lastSeq = message.data.last_seq;
+      return;
+    }
+    const seq = (message as { seq?: number }).seq;
+    if (typeof seq === "number" && (this.lastSeq === null || seq > this.lastSeq)) {
+      this.lastSeq = seq;
+    }
   }
 
   protected getPingMessage(): InternalOutgoingMessage {
@@ -87,7 +119,12 @@ class CentralWebSocketService extends BaseWebSocket<WsMessage, InternalOutgoingM
       this.disconnect();
       return;
     }
-    this.send({ type: "auth", data: { token } });
+    if (this.lastSeqToken !== token) {
+      this.lastSeq = null;
+      this.lastSeqToken = token;
+    }
+    const resume = this.lastSeq !== null ? { last_seq: this.lastSeq } : undefined;
+    this.send({ type: "auth", data: { token, resume } });
   }
 
   sendTyping(conversationId: string, isTyping: boolean): void {
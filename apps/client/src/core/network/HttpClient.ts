@@ -1,5 +1,11 @@
 import { fetch } from "@tauri-apps/plugin-http";
 import { CENTRAL_API_URL } from "../../config";
+import type { SessionTokens } from "../auth/types";
+
+/** Refresh this long before the access token expires so sockets never see it lapse. */
+const REFRESH_MARGIN_SECONDS = 60;
+
+type SessionRefreshedListener = (tokens: SessionTokens) => void;
 
 export class ApiError extends Error {
   constructor(
@@ -13,6 +19,10 @@ export class ApiError extends Error {
 
 class HttpClient {
   private authToken: string | null = null;
+  private refreshToken: string | null = null;
+  private refreshInFlight: Promise<boolean> | null = null;
+  private refreshTimer: ReturnType<typeof setTimeout> | null = null;
+  private refreshListeners: Set<SessionRefreshedListener> = new Set();
   private isLoggingOut = false;
   private baseUrl: string;
 
@@ -24,7 +34,76 @@ class HttpClient {
     this.authToken = token;
     if (token) {
       this.isLoggingOut = false;
+    } else {
+      this.setRefreshToken(null);
+    }
+  }
+
+  public setRefreshToken(token: string | null) {
+    this.refreshToken = token;
+    if (!token && this.refreshTimer) {
+      clearTimeout(this.refreshTimer);
+      this.refreshTimer = null;
+    }
+  }
+
+  public setSessionTokens(tokens: SessionTokens) {
+    this.setAuthToken(tokens.token);
+    this.setRefreshToken(tokens.refresh_token);
+    this.scheduleRefresh(tokens.expires_in);
+  }
+
+  public onSessionRefreshed(listener: SessionRefreshedListener): () => void {
+    this.refreshListeners.add(listener);
+    return () => {
+      this.refreshListeners.delete(listener);
+    };
+  }
+
+  private scheduleRefresh(expiresIn: number) {
+    if (this.refreshTimer) {
+      clearTimeout(this.refreshTimer);
     }
+    const delaySeconds = Math.max(expiresIn - REFRESH_MARGIN_SECONDS, 5);
+    this.refreshTimer = setTimeout(() => {
+      this.refreshTimer = null;
+      this.refreshSession();
+    }, delaySeconds * 1000);
+  }
+
+  /**
+   * Exchanges the refresh token for a new token pair. Concurrent callers share
+   * one request, since a refresh token can only be used once.
+   */
+  public async refreshSession(): Promise<boolean> {
+    if (!this.refreshToken) {
+      return false;
+    }
+    if (!this.refreshInFlight) {
+      const refreshToken = this.refreshToken;
+      this.refreshInFlight = (async () => {
+        try {
+          const response = await fetch(`${this.baseUrl}/auth/refresh`, {
+            method: "POST",
+            headers: { "Content-Type": "application/json" },
+            body: JSON.stringify({ refresh_token: refreshToken }),
+          });
+          if (!response.ok) {
+            return false;
+          }
+          const tokens: SessionTokens = await response.json();
+          this.setSessionTokens(tokens);
+          this.refreshListeners.forEach((listener) => listener(tokens));
+          return true;
+        } catch (err) {
+          console.error("[Client] Failed to refresh session:", err);
+          return false;
+        } finally {
+          this.refreshInFlight = null;
+        }
+      })();
+    }
+    return this.refreshInFlight;
   }
 
   public getAuthToken(): string | null {
@@ -85,53 +164,46 @@ class HttpClient {
     return JSON.parse(text);
   }
 
+  private async request<T>(
+    method: string,
+    endpoint: string,
+    body?: unknown,
+    retried = false
+  ): Promise<T> {
+    const response = await fetch(`${this.baseUrl}${endpoint}`, {
+      method,
+      headers: this.getHeaders(),
+      body: body ? JSON.stringify(body) : undefined,
+    });
+    if (response.status === 401 && !retried && (await this.refreshSession())) {
+      return this.request<T>(method, endpoint, body, true);
+    }
+    return this.handleResponse<T>(response);
+  }
+
   public async get<T>(endpoint: string, params?: Record<string, string>): Promise<T> {
-    let url = `${this.baseUrl}${endpoint}`;
+    let url = endpoint;
     if (params) {
       const searchParams = new URLSearchParams(params);
       url += `?${searchParams.toString()}`;
     }
-    const response = await fetch(url, {
-      method: "GET",
-      headers: this.getHeaders(),
-    });
-    return this.handleResponse<T>(response);
+    return this.request<T>("GET", url);
   }
 
   public async post<T, B = unknown>(endpoint: string, body?: B): Promise<T> {
-    const response = await fetch(`${this.baseUrl}${endpoint}`, {
-      method: "POST",
-      headers: this.getHeaders(),
-      body: body ? JSON.stringify(body) : undefined,
-    });
-    return this.handleResponse<T>(response);
+    return this.request<T>("POST", endpoint, body);
   }
 
   public async put<T, B = unknown>(endpoint: string, body?: B): Promise<T> {
-    const response = await fetch(`${this.baseUrl}${endpoint}`, {
-      method: "PUT",
-      headers: this.getHeaders(),
-      body: body ? JSON.stringify(body) : undefined,
-    });
-    return this.handleResponse<T>(response);
+    return this.request<T>("PUT", endpoint, body);
   }
 
   public async patch<T, B = unknown>(endpoint: string, body?: B): Promise<T> {
-    const response = await fetch(`${this.baseUrl}${endpoint}`, {
-      method: "PATCH",
-      headers: this.getHeaders(),
-      body: body ? JSON.stringify(body) : undefined,
-    });
-    return this.handleResponse<T>(response);
+    return this.request<T>("PATCH", endpoint, body);
   }
 
   public async del<T, B = unknown>(endpoint: string, body?: B): Promise<T> {
-    const response = await fetch(`${this.baseUrl}${endpoint}`, {
-      method: "DELETE",
-      headers: this.getHeaders(),
-      body: body ? JSON.stringify(body) : undefined,
-    });
-    return this.handleResponse<T>(response);
+    return this.request<T>("DELETE", endpoint, body);
   }
 }
 
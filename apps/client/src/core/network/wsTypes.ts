@@ -1,4 +1,9 @@
-import { WsKeyExchange, WsKeyUpdate } from "../crypto/types";
+import {
+  WsKeyExchange,
+  WsKeyUpdate,
+  WsPrekeysLow,
+  WsSignedPrekeyStale,
+} from "../crypto/types";
 import {
   WsCallAnswer,
   WsCallCancel,
@@ -13,6 +18,8 @@ import {
   WsCallRejoin,
 } from "../../features/calls/types";
 import {
+  WsConversationSettingsUpdated,
+  WsNotificationSettingsUpdated,
   WsGroupCreated,
   WsGroupDeleted,
   WsGroupMemberAdded,
@@ -27,6 +34,10 @@ import {
   WsNewMessage,
   WsReactionAdded,
   WsReactionRemoved,
+  WsReadReceipt,
+  WsThreadReply,
+  WsThreadUpdated,
+  WsScheduledMessageFailed,
   WsTyping,
   WsTypingStart,
   WsTypingStop,
@@ -35,10 +46,38 @@ import {
   WsRoleUpdated,
   WsRoleDeleted,
 } from "../../features/chat/types";
-import { WsFriendAccepted, WsFriendRemoved, WsFriendRequest } from "../../features/friends/types";
+import {
+  WsAccountDeleted,
+  WsFriendAccepted,
+  WsFriendRemoved,
+  WsFriendRequest,
+  WsUserRenamed,
+} from "../../features/friends/types";
+import {
+  WsIdentityKeyChanged,
+  WsVerifiedContactsUpdated,
+} from "../../features/friends/verifiedContacts";
+import { WsDeviceLinkCompleted, WsDeviceLinkRequested } from "../auth/types";
 import { WsActivityUpdate } from "../../features/profiles/types";
+import { WsDataExportFailed, WsDataExportReady } from "../../features/settings/dataExport";
 import { WsPresence, WsPresenceSync, WsUpdatePresence } from "@/types/common";
 
+export interface WsResumed {
+  type: "resumed";
+  data: {
+    last_seq: number;
+    replayed: number;
+    truncated: boolean;
+  };
+}
+
+export interface WsReauthenticated {
+  type: "reauthenticated";
+  data: {
+    expires_at: string;
+  };
+}
+
 export type WsMessage =
   | WsNewMessage
   | WsMessageDeleted
@@ -47,10 +86,26 @@ export type WsMessage =
   | WsMessageUnpinned
   | WsReactionAdded
   | WsReactionRemoved
+  | WsReadReceipt
+  | WsConversationSettingsUpdated
+  | WsNotificationSettingsUpdated
+  | WsThreadReply
+  | WsThreadUpdated
+  | WsScheduledMessageFailed
   | WsFriendRequest
   | WsFriendAccepted
   | WsFriendRemoved
+  | WsAccountDeleted
+  | WsUserRenamed
+  | WsIdentityKeyChanged
+  | WsVerifiedContactsUpdated
+  | WsDataExportReady
+  | WsDataExportFailed
   | WsKeyUpdate
+  | WsDeviceLinkRequested
+  | WsDeviceLinkCompleted
+  | WsPrekeysLow
+  | WsSignedPrekeyStale
   | WsGroupCreated
   | WsGroupMemberAdded
   | WsGroupMemberRemoved
@@ -80,4 +135,6 @@ export type WsMessage =
   | WsCallLeave
   | WsCallRejoin
   | WsCallMuteUpdate
-  | WsActivityUpdate;
+  | WsActivityUpdate
+  | WsResumed
+  | WsReauthenticated;
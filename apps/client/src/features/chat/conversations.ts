@@ -2,7 +2,10 @@ import { httpClient } from "../../core/network/HttpClient";
 import type {
   AddMemberRequest,
   ConversationResponse,
+  ConversationSettings,
   ConversationWithRouting,
+  NotificationSettings,
+  UpdateNotificationSettingsRequest,
   CreateConversationRequest,
   CreateDmRequest,
   MemberResponse,
@@ -33,6 +36,44 @@ class ConversationService {
   public async hideConversation(conversationId: string): Promise<SuccessResponse> {
     return httpClient.post<SuccessResponse>(`/conversations/${conversationId}/hide`, {});
   }
+
+  public async getSettings(conversationId: string): Promise<ConversationSettings> {
+    return httpClient.get<ConversationSettings>(`/conversations/${conversationId}/settings`);
+  }
+
+  public async updateSettings(
+    conversationId: string,
+    settings: Partial<ConversationSettings>
+  ): Promise<ConversationSettings> {
+    return httpClient.patch<ConversationSettings>(
+      `/conversations/${conversationId}/settings`,
+      settings
+    );
+  }
+
+  public async getAllNotificationSettings(): Promise<NotificationSettings[]> {
+    return httpClient.get<NotificationSettings[]>("/conversations/notifications");
+  }
+
+  public async getNotificationSettings(conversationId: string): Promise<NotificationSettings> {
+    return httpClient.get<NotificationSettings>(`/conversations/${conversationId}/notifications`);
+  }
+
+  public async updateNotificationSettings(
+    conversationId: string,
+    settings: UpdateNotificationSettingsRequest
+  ): Promise<NotificationSettings> {
+    return httpClient.put<NotificationSettings>(
+      `/conversations/${conversationId}/notifications`,
+      settings
+    );
+  }
+
+  public async markRead(conversationId: string, messageId: string): Promise<SuccessResponse> {
+    return httpClient.post<SuccessResponse>(`/conversations/${conversationId}/read`, {
+      message_id: messageId,
+    });
+  }
 }
 
 export const conversationService = new ConversationService();
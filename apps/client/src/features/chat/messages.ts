@@ -2,12 +2,18 @@ import { httpClient } from "../../core/network/HttpClient";
 import type {
   GetMessagesQuery,
   Message,
+  MessagesPage,
   SendMessageRequest,
   SendMessageResponse,
   EditMessageRequest,
   EditMessageResponse,
   AddReactionRequest,
   ReactionResponse,
+  ScheduleMessageRequest,
+  ScheduledMessage,
+  EditScheduledMessageRequest,
+  MessageRevision,
+  GetMentionsQuery,
 } from "./types";
 import { SuccessResponse } from "@/types/common";
 
@@ -17,6 +23,14 @@ export interface MessageKeyResponse {
 
 class MessageService {
   public async getMessages(conversationId: string, query?: GetMessagesQuery): Promise<Message[]> {
+    const page = await this.getMessagesPage(conversationId, query);
+    return page.messages;
+  }
+
+  public async getMessagesPage(
+    conversationId: string,
+    query?: GetMessagesQuery
+  ): Promise<MessagesPage> {
     const params: Record<string, string> = {};
     if (query?.limit !== undefined) {
       params.limit = query.limit.toString();
@@ -24,7 +38,13 @@ class MessageService {
     if (query?.before) {
       params.before = query.before;
     }
-    return httpClient.get<Message[]>(`/messages/${conversationId}`, params);
+    if (query?.after) {
+      params.after = query.after;
+    }
+    if (query?.around) {
+      params.around = query.around;
+    }
+    return httpClient.get<MessagesPage>(`/messages/${conversationId}`, params);
   }
 
   public async sendMessage(
@@ -74,6 +94,13 @@ class MessageService {
     return httpClient.get<MessageKeyResponse>(`/messages/${conversationId}/${messageId}/key`);
   }
 
+  public async getMessageHistory(
+    conversationId: string,
+    messageId: string
+  ): Promise<MessageRevision[]> {
+    return httpClient.get<MessageRevision[]>(`/messages/${conversationId}/${messageId}/history`);
+  }
+
   public async pinMessage(conversationId: string, messageId: string): Promise<SuccessResponse> {
     return httpClient.post<SuccessResponse>(`/messages/${conversationId}/${messageId}/pin`, {});
   }
@@ -85,6 +112,49 @@ class MessageService {
   public async getPinnedMessages(conversationId: string): Promise<Message[]> {
     return httpClient.get<Message[]>(`/messages/${conversationId}/pinned`);
   }
+
+  public async getMentions(query?: GetMentionsQuery): Promise<Message[]> {
+    const params: Record<string, string> = {};
+    if (query?.limit !== undefined) {
+      params.limit = query.limit.toString();
+    }
+    if (query?.since) {
+      params.since = query.since;
+    }
+    if (query?.before) {
+      params.before = query.before;
+    }
+    return httpClient.get<Message[]>("/mentions", params);
+  }
+
+  public async getScheduledMessages(conversationId: string): Promise<ScheduledMessage[]> {
+    return httpClient.get<ScheduledMessage[]>(`/scheduled-messages/${conversationId}`);
+  }
+
+  public async scheduleMessage(
+    conversationId: string,
+    data: ScheduleMessageRequest
+  ): Promise<ScheduledMessage> {
+    return httpClient.post<ScheduledMessage>(`/scheduled-messages/${conversationId}`, data);
+  }
+
+  public async editScheduledMessage(
+    conversationId: string,
+    scheduledId: string,
+    data: EditScheduledMessageRequest
+  ): Promise<ScheduledMessage> {
+    return httpClient.put<ScheduledMessage>(
+      `/scheduled-messages/${conversationId}/${scheduledId}`,
+      data
+    );
+  }
+
+  public async cancelScheduledMessage(
+    conversationId: string,
+    scheduledId: string
+  ): Promise<SuccessResponse> {
+    return httpClient.del<SuccessResponse>(`/scheduled-messages/${conversationId}/${scheduledId}`);
+  }
 }
 
 export const messageService = new MessageService();
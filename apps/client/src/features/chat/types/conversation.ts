@@ -10,6 +10,8 @@ export interface ConversationWithRouting {
   encrypted_sender_key: number[];
   encrypted_role: number[];
   created_at: string;
+  last_read_message_id?: string | null;
+  unread_count?: number;
 }
 
 export interface ConversationResponse {
@@ -50,3 +52,22 @@ export interface AddMemberRequest {
   encrypted_sender_key: number[];
   encrypted_role: number[];
 }
+
+export type NotificationLevel = "all" | "mentions" | "none";
+
+export interface NotificationSettings {
+  conversation_id: string;
+  level: NotificationLevel;
+  muted_until: string | null;
+  updated_at: string;
+}
+
+export interface UpdateNotificationSettingsRequest {
+  level: NotificationLevel;
+  muted_until: string | null;
+}
+
+export interface ConversationSettings {
+  keep_edit_history: boolean;
+  message_ttl_seconds: number | null;
+}
@@ -10,6 +10,9 @@ export interface UploadPrekeysRequest {
 
 export interface PrekeyCountResponse {
   count: number;
+  low_water_mark: number;
+  signed_prekey_created_at: string | null;
+  signed_prekey_stale: boolean;
 }
 
 export interface PreKeyBundle {
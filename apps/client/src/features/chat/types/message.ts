@@ -32,6 +32,7 @@ export interface Message {
   call_id?: string | null;
   call_duration_seconds?: number | null;
   pinned_at?: string | null;
+  mentioned_user_ids?: string[];
 }
 
 export interface MessageKeyData {
@@ -55,6 +56,49 @@ export interface SendMessageRequest {
   sender_chain_iteration?: number | null;
   message_keys?: MessageKeyData[];
   attachment?: AttachmentMetadata;
+  mentioned_user_ids?: string[];
+}
+
+export interface ScheduleMessageRequest extends SendMessageRequest {
+  send_at: string;
+}
+
+export interface EditScheduledMessageRequest {
+  encrypted_content?: number[];
+  signature?: number[];
+  message_keys?: MessageKeyData[];
+  send_at?: string;
+}
+
+export interface ScheduledMessage {
+  id: string;
+  conversation_id: string;
+  sender_id: string;
+  encrypted_content: number[];
+  signature: number[];
+  reply_to_id: string | null;
+  expires_at: string | null;
+  sender_chain_id: number | null;
+  sender_chain_iteration: number | null;
+  attachment_s3_key: string | null;
+  attachment_file_size: number | null;
+  attachment_encrypted_size: number | null;
+  attachment_mime_type: string | null;
+  send_at: string;
+  created_at: string;
+  updated_at: string;
+  failed_at: string | null;
+  failure_reason: string | null;
+}
+
+export interface MessageRevision {
+  id: string;
+  message_id: string;
+  encrypted_content: number[];
+  signature: number[];
+  created_at: string;
+  replaced_at: string;
+  encrypted_key: number[] | null;
 }
 
 export interface SendMessageResponse {
@@ -87,4 +131,19 @@ export interface ReactionResponse {
 export interface GetMessagesQuery {
   limit?: number;
   before?: string;
+  after?: string;
+  around?: string;
+}
+
+export interface GetMentionsQuery {
+  limit?: number;
+  since?: string;
+  before?: string;
+}
+
+export interface MessagesPage {
+  messages: Message[];
+  has_more: boolean;
+  has_more_before: boolean;
+  has_more_after: boolean;
 }
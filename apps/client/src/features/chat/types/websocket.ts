@@ -1,3 +1,5 @@
+import type { ConversationSettings, NotificationSettings } from "./conversation";
+
 export interface WsNewMessage {
   type: "new_message";
   data: {
@@ -17,6 +19,7 @@ export interface WsNewMessage {
     call_id?: string | null;
     call_duration_seconds?: number | null;
     pinned_at?: string | null;
+    mentioned_user_ids?: string[];
     sender_username: string;
   };
 }
@@ -60,6 +63,59 @@ export interface WsMessageUnpinned {
   };
 }
 
+export interface WsThreadReply {
+  type: "thread_reply";
+  data: {
+    thread_root_id: string;
+    reply_count: number;
+    last_reply_at: string | null;
+    message: Omit<WsNewMessage["data"], "sender_username">;
+  };
+}
+
+export interface WsThreadUpdated {
+  type: "thread_updated";
+  data: {
+    conversation_id: string;
+    root_message_id: string;
+    reply_count: number;
+    last_reply_at: string | null;
+  };
+}
+
+export interface WsScheduledMessageFailed {
+  type: "scheduled_message_failed";
+  data: {
+    scheduled_message_id: string;
+    conversation_id: string;
+    reason: string;
+  };
+}
+
+export interface WsNotificationSettingsUpdated {
+  type: "notification_settings_updated";
+  data: NotificationSettings;
+}
+
+export interface WsConversationSettingsUpdated {
+  type: "conversation_settings_updated";
+  data: {
+    conversation_id: string;
+    updated_by: string;
+    settings: ConversationSettings;
+  };
+}
+
+export interface WsReadReceipt {
+  type: "read_receipt";
+  data: {
+    conversation_id: string;
+    user_id: string;
+    last_read_message_id: string;
+    read_at: string;
+  };
+}
+
 export interface WsReactionAdded {
   type: "reaction_added";
   data: {
@@ -1,2 +1,3 @@
 export * from "./friends";
 export * from "./types";
+export * from "./verifiedContacts";
@@ -63,3 +63,19 @@ export interface WsFriendRemoved {
     by_user_id: string;
   };
 }
+
+export interface WsUserRenamed {
+  type: "user_renamed";
+  data: {
+    user_id: string;
+    old_username: string;
+    new_username: string;
+  };
+}
+
+export interface WsAccountDeleted {
+  type: "account_deleted";
+  data: {
+    user_id: string;
+  };
+}
@@ -0,0 +1,55 @@
+import { httpClient } from "../../core/network/HttpClient";
+import { cryptoService } from "../../core/crypto/crypto";
+import type { SuccessResponse } from "@/types/common";
+
+/**
+ * A contact whose safety number was compared for the given identity key.
+ * `key_changed_at` is set once their key changes, until they are verified again.
+ */
+export interface VerifiedContact {
+  user_id: string;
+  identity_key: number[];
+  verified_at: string;
+  key_changed_at?: string;
+}
+
+interface VerifiedContactsResponse {
+  encrypted_verified_contacts: number[];
+}
+
+export interface WsIdentityKeyChanged {
+  type: "identity_key_changed";
+  data: {
+    user_id: string;
+    key_version: number;
+  };
+}
+
+export interface WsVerifiedContactsUpdated {
+  type: "verified_contacts_updated";
+}
+
+class VerifiedContactsService {
+  public async load(kemSecretKey: number[]): Promise<VerifiedContact[]> {
+    const response = await httpClient.get<VerifiedContactsResponse>("/keys/verified-contacts");
+    if (response.encrypted_verified_contacts.length === 0) return [];
+
+    const decrypted = await cryptoService.decryptData(
+      kemSecretKey,
+      response.encrypted_verified_contacts
+    );
+    return JSON.parse(cryptoService.bytesToString(decrypted));
+  }
+
+  public async save(kemSecretKey: number[], contacts: VerifiedContact[]): Promise<SuccessResponse> {
+    const encrypted = await cryptoService.encryptData(
+      kemSecretKey,
+      cryptoService.stringToBytes(JSON.stringify(contacts))
+    );
+    return httpClient.put<SuccessResponse>("/keys/verified-contacts", {
+      encrypted_verified_contacts: encrypted,
+    });
+  }
+}
+
+export const verifiedContactsService = new VerifiedContactsService();
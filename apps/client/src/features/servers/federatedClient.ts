@@ -1,4 +1,12 @@
 import { fetch } from "@tauri-apps/plugin-http";
+import type { FederatedSessionTokens } from "./federation";
+import type {
+  AuditLogPage,
+  AuditLogQuery,
+  ChannelAttachment,
+  VoiceJoinResponse,
+  VoiceParticipant,
+} from "./types";
 
 export interface FederatedCategory {
   id: string;
@@ -15,6 +23,8 @@ export interface FederatedChannel {
   name: string;
   description: string;
   position: number;
+  message_retention?: string | null;
+  channel_type?: "text" | "voice";
   created_at: string;
 }
 
@@ -37,13 +47,63 @@ export interface KeyDistribution {
   encrypted_key: number[];
 }
 
+export interface FederatedSessionRefresh {
+  refreshToken: string;
+  onRefreshed: (tokens: FederatedSessionTokens) => void;
+}
+
 export class FederatedServerClient {
   private baseUrl: string;
   private token: string;
+  private session: FederatedSessionRefresh | null;
+  private refreshInFlight: Promise<boolean> | null = null;
+  private refreshTimer: ReturnType<typeof setTimeout> | null = null;
 
-  constructor(domain: string, token: string) {
+  constructor(domain: string, token: string, session?: FederatedSessionRefresh) {
     this.baseUrl = this.resolveUrl(domain);
     this.token = token;
+    this.session = session ?? null;
+  }
+
+  /** Stops renewing the session once this client is no longer in use. */
+  public close(): void {
+    this.session = null;
+    if (this.refreshTimer) {
+      clearTimeout(this.refreshTimer);
+      this.refreshTimer = null;
+    }
+  }
+
+  public async refreshSession(): Promise<boolean> {
+    const session = this.session;
+    if (!session) return false;
+    if (!this.refreshInFlight) {
+      this.refreshInFlight = (async () => {
+        try {
+          const response = await fetch(`${this.baseUrl}/api/auth/refresh`, {
+            method: "POST",
+            headers: { "Content-Type": "application/json" },
+            body: JSON.stringify({ refresh_token: session.refreshToken }),
+          });
+          if (!response.ok) return false;
+          const tokens: FederatedSessionTokens = await response.json();
+          this.token = tokens.session_token;
+          session.refreshToken = tokens.refresh_token;
+          session.onRefreshed(tokens);
+          if (this.refreshTimer) clearTimeout(this.refreshTimer);
+          this.refreshTimer = setTimeout(
+            () => this.refreshSession(),
+            Math.max(tokens.expires_in - 60, 5) * 1000
+          );
+          return true;
+        } catch {
+          return false;
+        } finally {
+          this.refreshInFlight = null;
+        }
+      })();
+    }
+    return this.refreshInFlight;
   }
 
   private resolveUrl(domain: string): string {
@@ -54,7 +114,7 @@ export class FederatedServerClient {
         : `https://${domain}`;
   }
 
-  private async fetch<T>(path: string, options: RequestInit = {}): Promise<T> {
+  private async fetch<T>(path: string, options: RequestInit = {}, retried = false): Promise<T> {
     const url = `${this.baseUrl}/api${path}`;
     const headers = {
       "Content-Type": "application/json",
@@ -63,6 +123,9 @@ export class FederatedServerClient {
     };
 
     const response = await fetch(url, { ...options, headers });
+    if (response.status === 401 && !retried && (await this.refreshSession())) {
+      return this.fetch<T>(path, options, true);
+    }
     if (!response.ok) {
       const text = await response.text();
       console.error(
@@ -115,6 +178,7 @@ export class FederatedServerClient {
     category_id?: string;
     description?: string;
     position: number;
+    channel_type?: "text" | "voice";
     key_distributions?: KeyDistribution[];
   }): Promise<FederatedChannel> {
     return this.fetch<FederatedChannel>("/channels", {
@@ -158,6 +222,15 @@ export class FederatedServerClient {
     return this.fetch<any>("/server/info");
   }
 
+  public async getAuditLog(query: AuditLogQuery = {}): Promise<AuditLogPage> {
+    const params = new URLSearchParams();
+    for (const [key, value] of Object.entries(query)) {
+      if (value !== undefined) params.set(key, String(value));
+    }
+    const search = params.toString();
+    return this.fetch<AuditLogPage>(`/server/audit-log${search ? `?${search}` : ""}`);
+  }
+
   public async getMyPermissions(): Promise<number> {
     const response = await this.fetch<{ permissions: number }>("/members/me/permissions");
     return response.permissions;
@@ -254,6 +327,18 @@ export class FederatedServerClient {
     });
   }
 
+  /** Asks the server to pick up a username change confirmed by Central. */
+  async syncUsername(notice: {
+    username: string;
+    timestamp: number;
+    signature: number[];
+  }): Promise<FederatedMember> {
+    return this.fetch<FederatedMember>("/members/me/username", {
+      method: "POST",
+      body: JSON.stringify(notice),
+    });
+  }
+
   async distributeChannelKeys(channelId: string, distributions: KeyDistribution[]): Promise<void> {
     return this.fetch<void>(`/channels/${channelId}/keys`, {
       method: "POST",
@@ -277,4 +362,107 @@ export class FederatedServerClient {
       body: JSON.stringify(data),
     });
   }
+
+  async editMessage(
+    channelId: string,
+    messageId: string,
+    data: { encrypted_content: number[]; signature: number[] }
+  ): Promise<any> {
+    return this.fetch<any>(`/messages/${channelId}/${messageId}`, {
+      method: "PUT",
+      body: JSON.stringify(data),
+    });
+  }
+
+  async deleteMessage(channelId: string, messageId: string): Promise<void> {
+    await this.fetch<any>(`/messages/${channelId}/${messageId}`, { method: "DELETE" });
+  }
+
+  async getPinnedMessages(channelId: string): Promise<any[]> {
+    return this.fetch<any[]>(`/messages/${channelId}/pins`);
+  }
+
+  async pinMessage(channelId: string, messageId: string): Promise<void> {
+    await this.fetch<any>(`/messages/${channelId}/${messageId}/pin`, { method: "POST" });
+  }
+
+  async unpinMessage(channelId: string, messageId: string): Promise<void> {
+    await this.fetch<any>(`/messages/${channelId}/${messageId}/pin`, { method: "DELETE" });
+  }
+
+  async addReaction(channelId: string, messageId: string, emoji: string): Promise<any> {
+    return this.fetch<any>(`/messages/${channelId}/${messageId}/reactions`, {
+      method: "POST",
+      body: JSON.stringify({ emoji }),
+    });
+  }
+
+  // The body must already be encrypted; the server stores it as an opaque blob.
+  async uploadAttachment(
+    channelId: string,
+    encryptedData: Uint8Array,
+    filename: string,
+    mimeType: string
+  ): Promise<ChannelAttachment> {
+    return this.fetch<ChannelAttachment>("/uploads", {
+      method: "POST",
+      headers: {
+        "Content-Type": "application/octet-stream",
+        "x-channel-id": channelId,
+        "x-filename": filename,
+        "x-mime-type": mimeType,
+      },
+      body: encryptedData,
+    });
+  }
+
+  async downloadAttachment(uploadId: string, retried = false): Promise<Uint8Array> {
+    const response = await fetch(`${this.baseUrl}/api/uploads/${uploadId}`, {
+      headers: { Authorization: `Bearer ${this.token}` },
+    });
+    if (response.status === 401 && !retried && (await this.refreshSession())) {
+      return this.downloadAttachment(uploadId, true);
+    }
+    if (!response.ok) {
+      throw new Error(`Attachment download failed: ${response.status}`);
+    }
+    return new Uint8Array(await response.arrayBuffer());
+  }
+
+  async deleteAttachment(uploadId: string): Promise<void> {
+    await this.fetch<any>(`/uploads/${uploadId}`, { method: "DELETE" });
+  }
+
+  async removeReaction(channelId: string, messageId: string, emoji: string): Promise<void> {
+    await this.fetch<any>(
+      `/messages/${channelId}/${messageId}/reactions/${encodeURIComponent(emoji)}`,
+      { method: "DELETE" }
+    );
+  }
+
+  async getVoiceStates(): Promise<VoiceParticipant[]> {
+    return this.fetch<VoiceParticipant[]>("/voice/states");
+  }
+
+  async getVoiceParticipants(channelId: string): Promise<VoiceParticipant[]> {
+    return this.fetch<VoiceParticipant[]>(`/voice/${channelId}/participants`);
+  }
+
+  async joinVoice(channelId: string): Promise<VoiceJoinResponse> {
+    return this.fetch<VoiceJoinResponse>(`/voice/${channelId}/join`, { method: "POST" });
+  }
+
+  async leaveVoice(channelId: string): Promise<void> {
+    await this.fetch<any>(`/voice/${channelId}/leave`, { method: "POST" });
+  }
+
+  async updateVoiceState(
+    channelId: string,
+    state: { self_muted?: boolean; self_deafened?: boolean }
+  ): Promise<VoiceParticipant> {
+    return this.fetch<VoiceParticipant>(`/voice/${channelId}/state`, {
+      method: "PATCH",
+      body: JSON.stringify(state),
+    });
+  }
 }
@@ -30,6 +30,12 @@ export class FederatedWebSocketService extends BaseWebSocket<any, any> {
     console.log(`Disconnected from federated server: ${this.domain}`);
   }
 
+  /** Switches the open socket to a refreshed session token; reconnects use it too. */
+  public reauthenticate(token: string) {
+    this.token = token;
+    this.send({ type: "reauth", token });
+  }
+
   public subscribeChannel(channelId: string) {
     this.send({ type: "subscribe_channel", channel_id: channelId });
   }
@@ -19,10 +19,15 @@ export interface RegisterServerRequest {
   user_id: string;
 }
 
-export interface RegisterServerResponse {
+export interface FederatedSessionTokens {
+  session_token: string;
+  refresh_token: string;
+  expires_in: number;
+}
+
+export interface RegisterServerResponse extends FederatedSessionTokens {
   server_id: string;
   member_id: string;
-  session_token: string;
   server_name: string;
 }
 
@@ -53,7 +58,7 @@ class FederationService {
       dsa_public_key: number[];
     },
     password?: string
-  ): Promise<{ session_token: string; member_id: string }> {
+  ): Promise<FederatedSessionTokens & { member_id: string }> {
     const serverUrl = this.resolveServerUrl(serverDomain);
 
     const response = await fetch(`${serverUrl}/api/auth/login`, {
@@ -12,6 +12,9 @@ export const Permissions = {
   ADMINISTRATOR: 1 << 9,
   MANAGE_ROLES: 1 << 10,
   VIEW_CHANNELS: 1 << 11,
+  CONNECT: 1 << 12,
+  SPEAK: 1 << 13,
+  VIEW_AUDIT_LOG: 1 << 14,
 } as const;
 
 export function hasPermission(userPerms: number, required: number): boolean {
@@ -121,6 +121,7 @@ export interface FederatedServer {
   description?: string;
   icon_url?: string;
   session_token?: string;
+  refresh_token?: string;
   member_id?: string;
   is_owner?: boolean;
   isFederated: true;
@@ -224,4 +225,160 @@ export interface EncryptedMessage {
   signature?: number[];
   reply_to_id?: string;
   created_at: string;
+  updated_at?: string | null;
+  pinned_at?: string | null;
+  reactions?: MessageReaction[];
+  attachments?: ChannelAttachment[];
+}
+
+export interface ChannelAttachment {
+  id: string;
+  uploader_id: string | null;
+  channel_id: string | null;
+  message_id: string | null;
+  filename: string;
+  content_type: string;
+  file_size: number;
+  created_at: string;
+}
+
+export interface MessageReaction {
+  id: string;
+  message_id: string;
+  member_id: string;
+  emoji: string;
+  created_at: string;
+}
+
+export interface ChannelWsMessageEdited {
+  type: "message_edited";
+  data: {
+    channel_id: string;
+    message_id: string;
+    encrypted_content: number[];
+    signature: number[];
+    updated_at: string;
+  };
+}
+
+export interface ChannelWsReactionAdded {
+  type: "reaction_added";
+  data: {
+    id: string;
+    channel_id: string;
+    message_id: string;
+    member_id: string;
+    emoji: string;
+  };
+}
+
+export interface ChannelWsReactionRemoved {
+  type: "reaction_removed";
+  data: {
+    channel_id: string;
+    message_id: string;
+    member_id: string;
+    emoji: string;
+  };
+}
+
+export interface ChannelWsMessagePinned {
+  type: "message_pinned";
+  data: {
+    channel_id: string;
+    message_id: string;
+    pinned_by: string;
+    pinned_at: string;
+  };
+}
+
+export interface ChannelWsMessageUnpinned {
+  type: "message_unpinned";
+  data: {
+    channel_id: string;
+    message_id: string;
+  };
+}
+
+export interface VoiceParticipant {
+  channel_id: string;
+  member_id: string;
+  username: string;
+  can_speak: boolean;
+  self_muted: boolean;
+  self_deafened: boolean;
+  joined_at: string;
+}
+
+export interface VoiceJoinResponse {
+  relay_endpoint: string;
+  relay_token: number[];
+  expires_at: string;
+  participants: VoiceParticipant[];
+}
+
+export interface ChannelWsVoiceParticipantJoined {
+  type: "voice_participant_joined";
+  data: VoiceParticipant;
+}
+
+export interface ChannelWsVoiceParticipantLeft {
+  type: "voice_participant_left";
+  data: {
+    channel_id: string;
+    member_id: string;
+  };
+}
+
+export interface ChannelWsVoiceStateUpdated {
+  type: "voice_state_updated";
+  data: VoiceParticipant;
+}
+
+export type AuditAction =
+  | "member_kick"
+  | "member_ban"
+  | "member_unban"
+  | "member_role_add"
+  | "member_role_remove"
+  | "role_create"
+  | "role_update"
+  | "role_delete"
+  | "role_reorder"
+  | "category_create"
+  | "category_update"
+  | "category_delete"
+  | "channel_create"
+  | "channel_update"
+  | "channel_delete"
+  | "permission_override_set"
+  | "permission_override_delete"
+  | "invite_create"
+  | "server_update"
+  | "server_password_set"
+  | "server_password_remove";
+
+export interface AuditLogEntry {
+  id: string;
+  actor_id: string;
+  action: AuditAction;
+  target_id: string | null;
+  before_state: unknown | null;
+  after_state: unknown | null;
+  reason: string | null;
+  created_at: string;
+}
+
+export interface AuditLogPage {
+  entries: AuditLogEntry[];
+  next_before: string | null;
+}
+
+export interface AuditLogQuery {
+  limit?: number;
+  /** Id of the last entry of the previous page. */
+  before?: string;
+  action?: AuditAction;
+  actor_id?: string;
+  target_id?: string;
 }
@@ -0,0 +1,50 @@
+import { httpClient } from "../../core/network/HttpClient";
+
+export type DataExportStatus = "pending" | "ready" | "failed";
+
+export interface DataExport {
+  id: string;
+  status: DataExportStatus;
+  size_bytes: number | null;
+  created_at: string;
+  started_at: string | null;
+  completed_at: string | null;
+  expires_at: string | null;
+}
+
+export interface DataExportDownload {
+  url: string;
+  expires_in: number;
+}
+
+export interface WsDataExportReady {
+  type: "data_export_ready";
+  data: {
+    export_id: string;
+    size_bytes: number;
+    expires_at: string;
+  };
+}
+
+export interface WsDataExportFailed {
+  type: "data_export_failed";
+  data: {
+    export_id: string;
+  };
+}
+
+class DataExportService {
+  public async requestExport(): Promise<DataExport> {
+    return httpClient.post<DataExport>("/exports");
+  }
+
+  public async getLatestExport(): Promise<DataExport | null> {
+    return httpClient.get<DataExport | null>("/exports");
+  }
+
+  public async getDownloadUrl(exportId: string): Promise<DataExportDownload> {
+    return httpClient.get<DataExportDownload>(`/exports/${exportId}/download`);
+  }
+}
+
+export const dataExportService = new DataExportService();
@@ -1,2 +1,3 @@
 export * from "./components";
 export { preferenceService } from "./preferences";
+export { dataExportService } from "./dataExport";
@@ -3,6 +3,7 @@ import { httpClient } from "../../core/network/HttpClient";
 export interface UserPreferences {
   theme: string;
   enable_snow_effect?: boolean;
+  send_read_receipts?: boolean;
 }
 
 class PreferenceService {
@@ -17,6 +18,10 @@ class PreferenceService {
   public async updateSnowEffect(enabled: boolean): Promise<void> {
     return httpClient.put<void>("/preferences/snow-effect", { enabled });
   }
+
+  public async updateReadReceipts(enabled: boolean): Promise<void> {
+    return httpClient.put<void>("/preferences/read-receipts", { enabled });
+  }
 }
 
 export const preferenceService = new PreferenceService();
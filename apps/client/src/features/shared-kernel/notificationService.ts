@@ -52,6 +52,14 @@ export interface NotificationPreferences {
   persistentNotifications: boolean;
 }
 
+export type ConversationNotificationLevel = "all" | "mentions" | "none";
+
+export interface ConversationNotificationRule {
+  conversation_id: string;
+  level: ConversationNotificationLevel;
+  muted_until: string | null;
+}
+
 class NotificationServiceClass {
   private initialized = false;
   private preferences: NotificationPreferences = {
@@ -69,6 +77,7 @@ class NotificationServiceClass {
   };
 
   private sounds: Record<string, HTMLAudioElement> = {};
+  private conversationRules = new Map<string, ConversationNotificationRule>();
 
   async initialize(): Promise<void> {
     if (this.initialized) return;
@@ -210,11 +219,45 @@ class NotificationServiceClass {
     }
   }
 
+  setConversationRules(rules: ConversationNotificationRule[]): void {
+    this.conversationRules = new Map(rules.map((rule) => [rule.conversation_id, rule]));
+  }
+
+  updateConversationRule(rule: ConversationNotificationRule): void {
+    this.conversationRules.set(rule.conversation_id, rule);
+  }
+
+  shouldNotifyForConversation(conversationId: string, mentioned = false): boolean {
+    const rule = this.conversationRules.get(conversationId);
+    if (!rule) return true;
+
+    if (rule.muted_until && new Date(rule.muted_until).getTime() > Date.now()) {
+      return false;
+    }
+
+    switch (rule.level) {
+      case "none":
+        return false;
+      case "mentions":
+        return mentioned;
+      default:
+        return true;
+    }
+  }
+
   async showMessageNotification(
     senderName: string,
     message: string,
-    options: Partial<NotificationOptions> = {}
+    options: Partial<NotificationOptions> = {},
+    mentioned = false
   ): Promise<string | null> {
+    if (
+      options.conversationId &&
+      !this.shouldNotifyForConversation(options.conversationId, mentioned)
+    ) {
+      return null;
+    }
+
     const notificationOptions: NotificationOptions = {
       title: `@${senderName}`,
       body: this.formatMessageBody(message),
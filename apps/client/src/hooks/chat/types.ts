@@ -47,6 +47,7 @@ export interface IncomingMessageData {
   message_type?: string;
   call_duration_seconds?: number | null;
   pinned_at?: string | null;
+  mentioned_user_ids?: string[];
 }
 
 export interface AttachmentMeta {
@@ -65,10 +65,24 @@ export function useIncomingMessages({
           });
 
           const senderFriend = friendsList.find((f) => f.id === msgData.sender_id);
+          const notificationContext = {
+            conversationId: msgData.conversation_id,
+            mentioned: !!userId && !!msgData.mentioned_user_ids?.includes(userId),
+          };
           if (senderFriend) {
-            showMessageNotification(senderFriend.username, "sent you a message", profileStatus);
+            showMessageNotification(
+              senderFriend.username,
+              "sent you a message",
+              profileStatus,
+              notificationContext
+            );
           } else {
-            showMessageNotification("Someone", "sent you a message", profileStatus);
+            showMessageNotification(
+              "Someone",
+              "sent you a message",
+              profileStatus,
+              notificationContext
+            );
           }
         }
         setDmPreviews((prev) => {
@@ -298,6 +298,31 @@ export function useFriends() {
     [keys, queryClient]
   );
 
+  const onFriendRenamed = useCallback(
+    async (userId: string, username: string) => {
+      if (!keys) return;
+      if (!friendsListRef.current.some((f) => f.id === userId)) return;
+
+      const updatedFriends = friendsListRef.current.map((f) =>
+        f.id === userId ? { ...f, username } : f
+      );
+      setFriendsList(updatedFriends);
+
+      try {
+        const jsonData = JSON.stringify(updatedFriends);
+        const encryptedFriends = await cryptoService.encryptData(
+          keys.kem_secret_key,
+          cryptoService.stringToBytes(jsonData)
+        );
+        await friendService.updateFriends({ encrypted_friends: encryptedFriends });
+        queryClient.invalidateQueries({ queryKey: queryKeys.friends.list() });
+      } catch (err) {
+        console.error("Failed to sync friend rename:", err);
+      }
+    },
+    [keys, queryClient]
+  );
+
   return {
     friendsList,
     setFriendsList,
@@ -319,5 +344,6 @@ export function useFriends() {
     removeFriend,
     onFriendAccepted,
     onFriendRemoved,
+    onFriendRenamed,
   };
 }
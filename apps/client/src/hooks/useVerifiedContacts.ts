@@ -0,0 +1,99 @@
+import { useState, useCallback, useEffect, useMemo, useRef } from "react";
+import { useAuth } from "../context/AuthContext";
+import { centralWebSocketService } from "../core/network/CentralWebSocketService";
+import { verifiedContactsService, type VerifiedContact } from "../features/friends";
+
+function sameKey(a: number[], b: number[]): boolean {
+  return a.length === b.length && a.every((byte, i) => byte === b[i]);
+}
+
+export function useVerifiedContacts() {
+  const { keys } = useAuth();
+  const [verifiedContacts, setVerifiedContacts] = useState<VerifiedContact[]>([]);
+  const verifiedRef = useRef<VerifiedContact[]>([]);
+
+  useEffect(() => {
+    verifiedRef.current = verifiedContacts;
+  }, [verifiedContacts]);
+
+  const loadVerifiedContacts = useCallback(async () => {
+    if (!keys) return;
+    try {
+      setVerifiedContacts(await verifiedContactsService.load(keys.kem_secret_key));
+    } catch (err) {
+      console.error("Failed to load verified contacts:", err);
+    }
+  }, [keys]);
+
+  const saveVerifiedContacts = useCallback(
+    async (contacts: VerifiedContact[]) => {
+      if (!keys) return;
+      setVerifiedContacts(contacts);
+      await verifiedContactsService.save(keys.kem_secret_key, contacts);
+    },
+    [keys]
+  );
+
+  const keyChangedContacts = useMemo(
+    () => new Set(verifiedContacts.filter((c) => c.key_changed_at).map((c) => c.user_id)),
+    [verifiedContacts]
+  );
+
+  const isVerified = useCallback(
+    (userId: string, identityKey: number[]) =>
+      verifiedContacts.some(
+        (c) => c.user_id === userId && !c.key_changed_at && sameKey(c.identity_key, identityKey)
+      ),
+    [verifiedContacts]
+  );
+
+  const setContactVerified = useCallback(
+    async (userId: string, identityKey: number[], verified: boolean) => {
+      const others = verifiedRef.current.filter((c) => c.user_id !== userId);
+      const updated = verified
+        ? [
+            ...others,
+            { user_id: userId, identity_key: identityKey, verified_at: new Date().toISOString() },
+          ]
+        : others;
+      await saveVerifiedContacts(updated);
+    },
+    [saveVerifiedContacts]
+  );
+
+  useEffect(() => {
+    loadVerifiedContacts();
+  }, [loadVerifiedContacts]);
+
+  useEffect(() => {
+    return centralWebSocketService.onMessage((message) => {
+      switch (message.type) {
+        case "verified_contacts_updated":
+          loadVerifiedContacts();
+          break;
+        case "identity_key_changed": {
+          const userId = message.data.user_id;
+          const contact = verifiedRef.current.find((c) => c.user_id === userId);
+          if (!contact || contact.key_changed_at) break;
+
+          // The flag is stored in the synced blob, so devices that were offline
+          // see the warning too.
+          const updated = verifiedRef.current.map((c) =>
+            c.user_id === userId ? { ...c, key_changed_at: new Date().toISOString() } : c
+          );
+          saveVerifiedContacts(updated).catch((err) =>
+            console.error("Failed to flag changed identity key:", err)
+          );
+          break;
+        }
+      }
+    });
+  }, [loadVerifiedContacts, saveVerifiedContacts]);
+
+  return {
+    verifiedContacts,
+    keyChangedContacts,
+    isVerified,
+    setContactVerified,
+  };
+}
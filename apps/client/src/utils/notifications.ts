@@ -47,18 +47,32 @@ export function playFriendRequestSound() {
 export async function showMessageNotification(
   senderName: string,
   message: string,
-  userStatus?: string
+  userStatus?: string,
+  context: { conversationId?: string; mentioned?: boolean } = {}
 ) {
   if (!legacyInitialized) {
     await initNotifications();
   }
 
+  if (
+    context.conversationId &&
+    !NotificationService.shouldNotifyForConversation(context.conversationId, context.mentioned)
+  ) {
+    return;
+  }
+
   const isDND = userStatus === "dnd";
 
   try {
-    const notificationId = await NotificationService.showMessageNotification(senderName, message, {
-      priority: isDND ? NotificationPriority.Low : NotificationPriority.Normal,
-    });
+    const notificationId = await NotificationService.showMessageNotification(
+      senderName,
+      message,
+      {
+        priority: isDND ? NotificationPriority.Low : NotificationPriority.Normal,
+        conversationId: context.conversationId,
+      },
+      context.mentioned
+    );
 
     if (notificationId) {
     } else if (!isDND) {
@@ -0,0 +1,13 @@
+ALTER TABLE sessions ADD COLUMN access_expires_at TIMESTAMPTZ;
+UPDATE sessions SET access_expires_at = expires_at;
+ALTER TABLE sessions ALTER COLUMN access_expires_at SET NOT NULL;
+
+CREATE TABLE refresh_tokens (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    session_id UUID NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+    token_hash BYTEA NOT NULL UNIQUE,
+    used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_refresh_tokens_session_id ON refresh_tokens(session_id);
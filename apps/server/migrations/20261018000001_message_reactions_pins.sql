@@ -0,0 +1,15 @@
+CREATE TABLE message_reactions (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    message_id UUID NOT NULL REFERENCES messages(id) ON DELETE CASCADE,
+    member_id UUID NOT NULL REFERENCES members(id) ON DELETE CASCADE,
+    emoji TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (message_id, member_id, emoji)
+);
+
+CREATE INDEX idx_message_reactions_message_id ON message_reactions(message_id);
+
+ALTER TABLE messages ADD COLUMN pinned_at TIMESTAMPTZ;
+ALTER TABLE messages ADD COLUMN pinned_by UUID REFERENCES members(id) ON DELETE SET NULL;
+
+CREATE INDEX idx_messages_pinned ON messages(channel_id, pinned_at DESC) WHERE pinned_at IS NOT NULL;
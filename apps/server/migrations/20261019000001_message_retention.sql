@@ -0,0 +1,10 @@
+ALTER TABLE text_channels ADD COLUMN message_retention TEXT;
+
+-- Uploads outlive their message so the cleanup task can remove the stored file
+-- before dropping the row.
+ALTER TABLE uploads DROP CONSTRAINT uploads_message_id_fkey;
+ALTER TABLE uploads ADD CONSTRAINT uploads_message_id_fkey
+    FOREIGN KEY (message_id) REFERENCES messages(id) ON DELETE SET NULL;
+
+CREATE INDEX idx_uploads_orphaned ON uploads(created_at) WHERE message_id IS NULL;
+CREATE INDEX idx_messages_channel_created_at ON messages(channel_id, created_at);
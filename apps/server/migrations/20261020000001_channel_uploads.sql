@@ -0,0 +1,3 @@
+ALTER TABLE uploads ADD COLUMN channel_id UUID REFERENCES text_channels(id) ON DELETE SET NULL;
+
+CREATE INDEX idx_uploads_message_id ON uploads(message_id) WHERE message_id IS NOT NULL;
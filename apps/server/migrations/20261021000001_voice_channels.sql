@@ -0,0 +1,2 @@
+ALTER TABLE text_channels ADD COLUMN channel_type TEXT NOT NULL DEFAULT 'text'
+    CHECK (channel_type IN ('text', 'voice'));
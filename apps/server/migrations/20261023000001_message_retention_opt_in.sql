@@ -0,0 +1,5 @@
+-- Retention was never enforced before the cleanup task, so the old '30d'
+-- default would start purging history nobody chose to expire. Servers keep
+-- everything until an admin picks a retention period.
+ALTER TABLE server_identity ALTER COLUMN message_retention SET DEFAULT 'forever';
+UPDATE server_identity SET message_retention = 'forever' WHERE message_retention = '30d';
@@ -0,0 +1,4 @@
+-- The audit log pages on (created_at, id) so entries sharing a timestamp
+-- are neither skipped nor repeated.
+DROP INDEX idx_audit_log_created_at;
+CREATE INDEX idx_audit_log_created_at_id ON audit_log(created_at DESC, id DESC);
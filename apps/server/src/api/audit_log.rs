@@ -0,0 +1,140 @@
+use axum::{
+    extract::{Query, State},
+    http::HeaderMap,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::db::AuditLogFilter;
+use crate::error::{AppError, Result};
+use crate::models::{permissions, AuditAction, AuditLogEntry};
+use crate::AppState;
+
+use super::middleware::AuthMember;
+
+const MAX_REASON_CHARS: usize = 512;
+
+/// Moderators can attach a reason to any admin request with this header.
+pub const REASON_HEADER: &str = "x-audit-log-reason";
+
+pub fn reason_from_headers(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(REASON_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+}
+
+/// One admin action, with optional snapshots of the target before and after.
+pub struct AuditEvent {
+    action: AuditAction,
+    target_id: Option<Uuid>,
+    before: Option<JsonValue>,
+    after: Option<JsonValue>,
+    reason: Option<String>,
+}
+
+impl AuditEvent {
+    pub fn new(action: AuditAction, target_id: impl Into<Option<Uuid>>) -> Self {
+        Self {
+            action,
+            target_id: target_id.into(),
+            before: None,
+            after: None,
+            reason: None,
+        }
+    }
+
+    pub fn before<T: Serialize>(mut self, value: &T) -> Self {
+        self.before = serde_json::to_value(value).ok().filter(|v| !v.is_null());
+        self
+    }
+
+    pub fn after<T: Serialize>(mut self, value: &T) -> Self {
+        self.after = serde_json::to_value(value).ok().filter(|v| !v.is_null());
+        self
+    }
+
+    pub fn reason(mut self, reason: Option<String>) -> Self {
+        self.reason = reason
+            .map(|r| r.trim().chars().take(MAX_REASON_CHARS).collect::<String>())
+            .filter(|r| !r.is_empty());
+        self
+    }
+
+    /// A failed write fails the request, so an admin action never succeeds
+    /// without leaving an entry behind.
+    pub async fn record(self, state: &AppState, actor_id: Uuid) -> Result<()> {
+        if let Err(e) = state
+            .db
+            .append_audit_log(
+                actor_id,
+                self.action,
+                self.target_id,
+                self.before,
+                self.after,
+                self.reason,
+            )
+            .await
+        {
+            tracing::error!("Failed to write audit log entry {:?}: {}", self.action, e);
+            return Err(e);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GetAuditLogQuery {
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+    /// Id of the last entry of the previous page.
+    pub before: Option<Uuid>,
+    pub action: Option<AuditAction>,
+    pub actor_id: Option<Uuid>,
+    pub target_id: Option<Uuid>,
+}
+
+fn default_limit() -> i64 {
+    50
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditLogResponse {
+    pub entries: Vec<AuditLogEntry>,
+    /// Pass as `before` to fetch the next page; absent on the last page.
+    pub next_before: Option<Uuid>,
+}
+
+pub async fn get_audit_log(
+    State(state): State<Arc<AppState>>,
+    auth: AuthMember,
+    Query(query): Query<GetAuditLogQuery>,
+) -> Result<Json<AuditLogResponse>> {
+    let perms = state.db.get_member_permissions(auth.member_id).await?;
+    if !permissions::has_permission(perms, permissions::VIEW_AUDIT_LOG) {
+        return Err(AppError::Forbidden);
+    }
+
+    let page = state
+        .db
+        .get_audit_log(
+            AuditLogFilter {
+                action: query.action,
+                actor_id: query.actor_id,
+                target_id: query.target_id,
+                before: query.before,
+            },
+            query.limit.clamp(1, 100),
+        )
+        .await?;
+
+    let next_before = page.entries.last().filter(|_| page.has_more).map(|e| e.id);
+
+    Ok(Json(AuditLogResponse {
+        entries: page.entries,
+        next_before,
+    }))
+}
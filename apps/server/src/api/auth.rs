@@ -4,8 +4,10 @@ use sha2::{Digest, Sha256};
 use std::sync::Arc;
 use uuid::Uuid;
 
+use crate::config::AuthConfig;
 use crate::error::{AppError, Result};
 use crate::federation::verify_federation_token;
+use crate::models::{RefreshOutcome, SessionTokenHashes};
 use crate::ws::types::ServerMessage;
 use crate::AppState;
 
@@ -15,6 +17,7 @@ use super::server::verify_password;
 pub fn routes() -> Router<Arc<AppState>> {
     Router::new()
         .route("/login", post(federated_login))
+        .route("/refresh", post(refresh))
         .route("/logout", post(logout))
 }
 
@@ -28,7 +31,8 @@ pub struct FederatedLoginRequest {
 #[derive(Debug, Serialize)]
 pub struct LoginResponse {
     pub member_id: Uuid,
-    pub session_token: String,
+    #[serde(flatten)]
+    pub tokens: SessionTokens,
     pub is_new_member: bool,
 }
 
@@ -64,6 +68,30 @@ pub async fn federated_login(
     }
 
     let (member, is_new) = match state.db.get_member_by_central_id(user_info.user_id).await? {
+        // Keys change when the user rotates them on Central, and messages are
+        // verified against the copy held here.
+        Some(m)
+            if m.username != user_info.username
+                || m.kem_public_key != user_info.kem_public_key
+                || m.dsa_public_key != user_info.dsa_public_key =>
+        {
+            let m = state
+                .db
+                .update_member_identity(
+                    m.id,
+                    &user_info.username,
+                    &user_info.kem_public_key,
+                    &user_info.dsa_public_key,
+                )
+                .await?;
+
+            state
+                .ws
+                .broadcast_all(ServerMessage::MemberUpdated { member: m.clone() })
+                .await;
+
+            (m, false)
+        }
         Some(m) => (m, false),
         None => {
             if let Some(password_hash) = &identity.password_hash {
@@ -105,20 +133,47 @@ pub async fn federated_login(
         }
     };
 
-    let (session_token, token_hash) = generate_session_token();
-    let expires_at = chrono::Utc::now() + chrono::Duration::hours(24 * 30);
-    state
-        .db
-        .create_session(member.id, token_hash, expires_at)
-        .await?;
+    let (tokens, token_hashes) = issue_session_tokens(&state.config.auth);
+    state.db.create_session(member.id, &token_hashes).await?;
 
     Ok(Json(LoginResponse {
         member_id: member.id,
-        session_token,
+        tokens,
         is_new_member: is_new,
     }))
 }
 
+#[derive(Debug, Deserialize)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+pub async fn refresh(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<RefreshRequest>,
+) -> Result<Json<SessionTokens>> {
+    let token_bytes = hex::decode(&req.refresh_token).map_err(|_| AppError::Unauthorized)?;
+    let refresh_token_hash = Sha256::digest(&token_bytes).to_vec();
+
+    let (tokens, token_hashes) = issue_session_tokens(&state.config.auth);
+    match state
+        .db
+        .rotate_refresh_token(&refresh_token_hash, &token_hashes)
+        .await?
+    {
+        RefreshOutcome::Rotated => Ok(Json(tokens)),
+        RefreshOutcome::Reused { member_id } => {
+            tracing::warn!(
+                "Refresh token reuse for member {}, session revoked",
+                member_id
+            );
+            state.ws.remove_connection(member_id).await;
+            Err(AppError::Unauthorized)
+        }
+        RefreshOutcome::Invalid => Err(AppError::Unauthorized),
+    }
+}
+
 pub async fn logout(
     State(state): State<Arc<AppState>>,
     auth: AuthMember,
@@ -127,6 +182,35 @@ pub async fn logout(
     Ok(Json(serde_json::json!({ "success": true })))
 }
 
+/// `session_token` authenticates requests for `expires_in` seconds and is
+/// renewed by exchanging `refresh_token` at `/api/auth/refresh`.
+#[derive(Debug, Serialize)]
+pub struct SessionTokens {
+    pub session_token: String,
+    pub refresh_token: String,
+    pub expires_in: u64,
+}
+
+pub fn issue_session_tokens(config: &AuthConfig) -> (SessionTokens, SessionTokenHashes) {
+    let (session_token, access_token_hash) = generate_session_token();
+    let (refresh_token, refresh_token_hash) = generate_session_token();
+    let now = chrono::Utc::now();
+    let expires_in = config.access_token_expiry_minutes * 60;
+
+    let hashes = SessionTokenHashes {
+        access_token_hash,
+        access_expires_at: now + chrono::Duration::seconds(expires_in as i64),
+        refresh_token_hash,
+        expires_at: now + chrono::Duration::days(config.refresh_token_expiry_days as i64),
+    };
+    let tokens = SessionTokens {
+        session_token,
+        refresh_token,
+        expires_in,
+    };
+    (tokens, hashes)
+}
+
 fn generate_session_token() -> (String, Vec<u8>) {
     use rand::RngCore;
     let mut token_bytes = [0u8; 32];
@@ -1,5 +1,6 @@
 use axum::{
     extract::{Path, State},
+    http::HeaderMap,
     routing::{delete, get, patch, post},
     Json, Router,
 };
@@ -8,9 +9,13 @@ use std::sync::Arc;
 use uuid::Uuid;
 
 use crate::error::{AppError, Result};
-use crate::models::{permissions, Category, ChannelPermissionOverride, Invite, TextChannel};
+use crate::models::{
+    permissions, AuditAction, Category, ChannelPermissionOverride, ChannelType, Invite,
+    RetentionPolicy, TextChannel,
+};
 use crate::AppState;
 
+use super::audit_log::{reason_from_headers, AuditEvent};
 use super::middleware::AuthMember;
 
 pub fn routes() -> Router<Arc<AppState>> {
@@ -56,10 +61,18 @@ pub struct CreateCategoryRequest {
 pub async fn create_category(
     State(state): State<Arc<AppState>>,
     auth: AuthMember,
+    headers: HeaderMap,
     Json(req): Json<CreateCategoryRequest>,
 ) -> Result<Json<Category>> {
     check_permission(&state, auth.member_id, permissions::MANAGE_CHANNELS).await?;
     let category = state.db.create_category(req.name, req.position).await?;
+
+    AuditEvent::new(AuditAction::CategoryCreate, category.id)
+        .after(&category)
+        .reason(reason_from_headers(&headers))
+        .record(&state, auth.member_id)
+        .await?;
+
     Ok(Json(category))
 }
 
@@ -80,26 +93,53 @@ pub struct UpdateCategoryRequest {
 pub async fn update_category(
     State(state): State<Arc<AppState>>,
     auth: AuthMember,
+    headers: HeaderMap,
     Path(id): Path<Uuid>,
     Json(req): Json<UpdateCategoryRequest>,
 ) -> Result<Json<Category>> {
     check_permission(&state, auth.member_id, permissions::MANAGE_CHANNELS).await?;
+    let before = state
+        .db
+        .get_category(id)
+        .await?
+        .ok_or(AppError::NotFound("Category not found".into()))?;
     state.db.update_category(id, req.name, req.position).await?;
     let category = state
         .db
         .get_category(id)
         .await?
         .ok_or(AppError::NotFound("Category not found".into()))?;
+
+    AuditEvent::new(AuditAction::CategoryUpdate, id)
+        .before(&before)
+        .after(&category)
+        .reason(reason_from_headers(&headers))
+        .record(&state, auth.member_id)
+        .await?;
+
     Ok(Json(category))
 }
 
 pub async fn delete_category(
     State(state): State<Arc<AppState>>,
     auth: AuthMember,
+    headers: HeaderMap,
     Path(id): Path<Uuid>,
 ) -> Result<Json<()>> {
     check_permission(&state, auth.member_id, permissions::MANAGE_CHANNELS).await?;
+    let category = state
+        .db
+        .get_category(id)
+        .await?
+        .ok_or(AppError::NotFound("Category not found".into()))?;
     state.db.delete_category(id).await?;
+
+    AuditEvent::new(AuditAction::CategoryDelete, id)
+        .before(&category)
+        .reason(reason_from_headers(&headers))
+        .record(&state, auth.member_id)
+        .await?;
+
     Ok(Json(()))
 }
 
@@ -115,18 +155,27 @@ pub struct CreateChannelRequest {
     pub name: String,
     pub description: Option<String>,
     pub position: i32,
+    #[serde(default)]
+    pub channel_type: ChannelType,
     pub key_distributions: Option<Vec<KeyDistribution>>,
 }
 
 pub async fn create_channel(
     State(state): State<Arc<AppState>>,
     auth: AuthMember,
+    headers: HeaderMap,
     Json(req): Json<CreateChannelRequest>,
 ) -> Result<Json<TextChannel>> {
     check_permission(&state, auth.member_id, permissions::MANAGE_CHANNELS).await?;
     let channel = state
         .db
-        .create_channel(req.category_id, req.name, req.description, req.position)
+        .create_channel(
+            req.category_id,
+            req.name,
+            req.description,
+            req.position,
+            req.channel_type,
+        )
         .await?;
 
     if let Some(distributions) = req.key_distributions {
@@ -140,6 +189,12 @@ pub async fn create_channel(
             .await?;
     }
 
+    AuditEvent::new(AuditAction::ChannelCreate, channel.id)
+        .after(&channel)
+        .reason(reason_from_headers(&headers))
+        .record(&state, auth.member_id)
+        .await?;
+
     Ok(Json(channel))
 }
 
@@ -157,34 +212,85 @@ pub struct UpdateChannelRequest {
     pub description: Option<String>,
     pub category_id: Option<Option<Uuid>>,
     pub position: Option<i32>,
+    /// Overrides the server-wide retention for this channel; an empty string
+    /// clears the override.
+    pub message_retention: Option<String>,
 }
 
 pub async fn update_channel(
     State(state): State<Arc<AppState>>,
     auth: AuthMember,
+    headers: HeaderMap,
     Path(id): Path<Uuid>,
     Json(req): Json<UpdateChannelRequest>,
 ) -> Result<Json<TextChannel>> {
     check_permission(&state, auth.member_id, permissions::MANAGE_CHANNELS).await?;
+
+    let message_retention = match req.message_retention {
+        Some(retention) if retention.trim().is_empty() => Some(None),
+        Some(retention) => {
+            if RetentionPolicy::parse(&retention).is_none() {
+                return Err(AppError::BadRequest("Invalid message retention".into()));
+            }
+            Some(Some(retention.trim().to_string()))
+        }
+        None => None,
+    };
+
+    let before = state
+        .db
+        .get_channel(id)
+        .await?
+        .ok_or(AppError::NotFound("Channel not found".into()))?;
+
     state
         .db
-        .update_channel(id, req.name, req.description, req.category_id, req.position)
+        .update_channel(
+            id,
+            req.name,
+            req.description,
+            req.category_id,
+            req.position,
+            message_retention,
+        )
         .await?;
     let channel = state
         .db
         .get_channel(id)
         .await?
         .ok_or(AppError::NotFound("Channel not found".into()))?;
+
+    AuditEvent::new(AuditAction::ChannelUpdate, id)
+        .before(&before)
+        .after(&channel)
+        .reason(reason_from_headers(&headers))
+        .record(&state, auth.member_id)
+        .await?;
+
     Ok(Json(channel))
 }
 
 pub async fn delete_channel(
     State(state): State<Arc<AppState>>,
     auth: AuthMember,
+    headers: HeaderMap,
     Path(id): Path<Uuid>,
 ) -> Result<Json<()>> {
     check_permission(&state, auth.member_id, permissions::MANAGE_CHANNELS).await?;
+    let channel = state
+        .db
+        .get_channel(id)
+        .await?
+        .ok_or(AppError::NotFound("Channel not found".into()))?;
     state.db.delete_channel(id).await?;
+    super::voice::close_channel(&state, id).await;
+
+    AuditEvent::new(AuditAction::ChannelDelete, id)
+        .before(&channel)
+        .reason(reason_from_headers(&headers))
+        .record(&state, auth.member_id)
+        .await?;
+
     Ok(Json(()))
 }
 
@@ -203,6 +309,7 @@ pub struct InviteResponse {
 pub async fn create_invite(
     State(state): State<Arc<AppState>>,
     auth: AuthMember,
+    headers: HeaderMap,
     Json(req): Json<CreateInviteRequest>,
 ) -> Result<Json<InviteResponse>> {
     check_permission(&state, auth.member_id, permissions::CREATE_INVITE).await?;
@@ -217,6 +324,12 @@ pub async fn create_invite(
         .create_invite(code.clone(), auth.member_id, req.max_uses, expires_at)
         .await?;
 
+    AuditEvent::new(AuditAction::InviteCreate, invite.id)
+        .after(&invite)
+        .reason(reason_from_headers(&headers))
+        .record(&state, auth.member_id)
+        .await?;
+
     Ok(Json(InviteResponse { code, invite }))
 }
 
@@ -324,6 +437,21 @@ pub async fn get_permission_overrides(
     Ok(Json(overrides))
 }
 
+async fn find_override(
+    state: &AppState,
+    channel_id: Uuid,
+    role_id: Option<Uuid>,
+    member_id: Option<Uuid>,
+) -> Result<Option<ChannelPermissionOverride>> {
+    let overrides = state
+        .db
+        .get_channel_permission_overrides(channel_id)
+        .await?;
+    Ok(overrides
+        .into_iter()
+        .find(|o| o.role_id == role_id && o.member_id == member_id))
+}
+
 #[derive(Debug, Deserialize)]
 pub struct SetPermissionOverrideRequest {
     pub role_id: Option<Uuid>,
@@ -335,6 +463,7 @@ pub struct SetPermissionOverrideRequest {
 pub async fn set_permission_override(
     State(state): State<Arc<AppState>>,
     auth: AuthMember,
+    headers: HeaderMap,
     Path(channel_id): Path<Uuid>,
     Json(req): Json<SetPermissionOverrideRequest>,
 ) -> Result<Json<ChannelPermissionOverride>> {
@@ -351,6 +480,8 @@ pub async fn set_permission_override(
         ));
     }
 
+    let before = find_override(&state, channel_id, req.role_id, req.member_id).await?;
+
     let override_record = state
         .db
         .set_channel_permission_override(
@@ -362,6 +493,15 @@ pub async fn set_permission_override(
         )
         .await?;
 
+    super::voice::refresh_permissions(&state).await;
+
+    AuditEvent::new(AuditAction::PermissionOverrideSet, channel_id)
+        .before(&before)
+        .after(&override_record)
+        .reason(reason_from_headers(&headers))
+        .record(&state, auth.member_id)
+        .await?;
+
     Ok(Json(override_record))
 }
 
@@ -374,13 +514,24 @@ pub struct DeletePermissionOverrideRequest {
 pub async fn delete_permission_override(
     State(state): State<Arc<AppState>>,
     auth: AuthMember,
+    headers: HeaderMap,
     Path(channel_id): Path<Uuid>,
     Json(req): Json<DeletePermissionOverrideRequest>,
 ) -> Result<Json<()>> {
     check_permission(&state, auth.member_id, permissions::MANAGE_CHANNELS).await?;
+    let before = find_override(&state, channel_id, req.role_id, req.member_id).await?;
     state
         .db
         .delete_channel_permission_override(channel_id, req.role_id, req.member_id)
         .await?;
+
+    super::voice::refresh_permissions(&state).await;
+
+    AuditEvent::new(AuditAction::PermissionOverrideDelete, channel_id)
+        .before(&before)
+        .reason(reason_from_headers(&headers))
+        .record(&state, auth.member_id)
+        .await?;
+
     Ok(Json(()))
 }
@@ -1,16 +1,22 @@
 use axum::{
     extract::{Path, State},
+    http::HeaderMap,
     routing::{get, post},
     Json, Router,
 };
+use confide_sdk::crypto::keys::DsaKeyPair;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 use std::sync::Arc;
 use uuid::Uuid;
 
 use crate::error::{AppError, Result};
-use crate::models::{permissions, Ban, Member};
+use crate::federation::fetch_central_user;
+use crate::models::{permissions, AuditAction, Ban, Member};
+use crate::ws::types::ServerMessage;
 use crate::AppState;
 
+use super::audit_log::{reason_from_headers, AuditEvent};
 use super::middleware::AuthMember;
 
 pub fn routes() -> Router<Arc<AppState>> {
@@ -19,6 +25,7 @@ pub fn routes() -> Router<Arc<AppState>> {
         .route("/me", get(get_me))
         .route("/me/permissions", get(get_my_permissions))
         .route("/me/leave", post(leave_server))
+        .route("/me/username", post(sync_username))
         .route("/roles", get(get_all_member_roles))
         .route("/{id}", get(get_member))
         .route("/{id}/roles", get(get_member_roles))
@@ -110,6 +117,135 @@ pub async fn get_all_member_roles(
     Ok(Json(result))
 }
 
+/// How far a rename notice's timestamp may drift from the server clock.
+const RENAME_NOTICE_MAX_SKEW_SECONDS: i64 = 300;
+
+/// A rename announced by the member's client, signed with their identity key
+/// over `user_renamed:{central_user_id}:{username}:{timestamp}`.
+#[derive(Debug, Deserialize)]
+pub struct RenameNotice {
+    pub username: String,
+    pub timestamp: i64,
+    pub signature: Vec<u8>,
+}
+
+pub async fn sync_username(
+    State(state): State<Arc<AppState>>,
+    auth: AuthMember,
+    Json(req): Json<RenameNotice>,
+) -> Result<Json<Member>> {
+    let mut member = state
+        .db
+        .get_member(auth.member_id)
+        .await?
+        .ok_or(AppError::NotFound("Member not found".into()))?;
+
+    if (chrono::Utc::now().timestamp() - req.timestamp).abs() > RENAME_NOTICE_MAX_SKEW_SECONDS {
+        return Err(AppError::BadRequest("Rename notice has expired".into()));
+    }
+
+    let message = format!(
+        "user_renamed:{}:{}:{}",
+        member.central_user_id, req.username, req.timestamp
+    );
+    if !verify_member_signature(&state, &mut member, message.as_bytes(), &req.signature).await {
+        return Err(AppError::BadRequest("Signature verification failed".into()));
+    }
+
+    // The signature proves the notice came from the member; Central is the
+    // authority on which name they actually hold.
+    let user_info = fetch_central_user(
+        &state.http_client,
+        &state.db,
+        &state.config,
+        member.central_user_id,
+    )
+    .await
+    .map_err(AppError::Federation)?;
+    if user_info.username != req.username || user_info.dsa_public_key != member.dsa_public_key {
+        return Err(AppError::BadRequest(
+            "Rename notice does not match Central".into(),
+        ));
+    }
+
+    if member.username == req.username {
+        return Ok(Json(member));
+    }
+
+    let member = state
+        .db
+        .update_member_username(member.id, &req.username)
+        .await?;
+    state
+        .ws
+        .broadcast_all(ServerMessage::MemberUpdated {
+            member: member.clone(),
+        })
+        .await;
+
+    Ok(Json(member))
+}
+
+/// Checks a signature made with the member's identity key. A member who
+/// rotated their keys on Central signs with a key this server has not seen
+/// yet, so a mismatch refreshes the stored keys from Central and checks again.
+pub async fn verify_member_signature(
+    state: &AppState,
+    member: &mut Member,
+    message: &[u8],
+    signature: &[u8],
+) -> bool {
+    if DsaKeyPair::verify(&member.dsa_public_key, message, signature).unwrap_or(false) {
+        return true;
+    }
+
+    let user_info = match fetch_central_user(
+        &state.http_client,
+        &state.db,
+        &state.config,
+        member.central_user_id,
+    )
+    .await
+    {
+        Ok(info) => info,
+        Err(e) => {
+            tracing::warn!("Could not refresh keys for member {}: {}", member.id, e);
+            return false;
+        }
+    };
+    if user_info.dsa_public_key == member.dsa_public_key
+        || !DsaKeyPair::verify(&user_info.dsa_public_key, message, signature).unwrap_or(false)
+    {
+        return false;
+    }
+
+    match state
+        .db
+        .update_member_identity(
+            member.id,
+            &user_info.username,
+            &user_info.kem_public_key,
+            &user_info.dsa_public_key,
+        )
+        .await
+    {
+        Ok(updated) => {
+            *member = updated;
+            state
+                .ws
+                .broadcast_all(ServerMessage::MemberUpdated {
+                    member: member.clone(),
+                })
+                .await;
+            true
+        }
+        Err(e) => {
+            tracing::error!("Failed to store new keys for member {}: {}", member.id, e);
+            false
+        }
+    }
+}
+
 pub async fn leave_server(
     State(state): State<Arc<AppState>>,
     auth: AuthMember,
@@ -133,6 +269,7 @@ pub async fn leave_server(
 
     state.db.delete_member(auth.member_id).await?;
     state.db.delete_member_sessions(auth.member_id).await?;
+    super::voice::disconnect_member(&state, auth.member_id).await;
 
     Ok(Json(serde_json::json!({ "success": true })))
 }
@@ -150,9 +287,17 @@ pub async fn get_member(
     Ok(Json(member))
 }
 
+fn member_snapshot(member: &Member) -> serde_json::Value {
+    json!({
+        "username": member.username,
+        "central_user_id": member.central_user_id,
+    })
+}
+
 pub async fn kick_member(
     State(state): State<Arc<AppState>>,
     auth: AuthMember,
+    headers: HeaderMap,
     Path(id): Path<Uuid>,
 ) -> Result<Json<()>> {
     let perms = state.db.get_member_permissions(auth.member_id).await?;
@@ -164,19 +309,30 @@ pub async fn kick_member(
         return Err(AppError::BadRequest("Cannot kick yourself".into()));
     }
 
+    let member = state
+        .db
+        .get_member(id)
+        .await?
+        .ok_or(AppError::NotFound("Member not found".into()))?;
+
     let identity = state.db.get_server_identity().await?;
     if let Some(id_inner) = identity {
         if let Some(owner_id) = id_inner.owner_user_id {
-            let member = state.db.get_member(id).await?;
-            if let Some(m) = member {
-                if m.central_user_id == owner_id {
-                    return Err(AppError::BadRequest("Cannot kick the owner".into()));
-                }
+            if member.central_user_id == owner_id {
+                return Err(AppError::BadRequest("Cannot kick the owner".into()));
             }
         }
     }
 
     state.db.delete_member(id).await?;
+    super::voice::disconnect_member(&state, id).await;
+
+    AuditEvent::new(AuditAction::MemberKick, id)
+        .before(&member_snapshot(&member))
+        .reason(reason_from_headers(&headers))
+        .record(&state, auth.member_id)
+        .await?;
+
     Ok(Json(()))
 }
 
@@ -226,6 +382,14 @@ pub async fn ban_member(
         .await?;
 
     state.db.delete_member(id).await?;
+    super::voice::disconnect_member(&state, id).await;
+
+    AuditEvent::new(AuditAction::MemberBan, id)
+        .before(&member_snapshot(&member))
+        .after(&ban)
+        .reason(ban.reason.clone())
+        .record(&state, auth.member_id)
+        .await?;
 
     Ok(Json(BanResponse { ban }))
 }
@@ -233,6 +397,7 @@ pub async fn ban_member(
 pub async fn unban_member(
     State(state): State<Arc<AppState>>,
     auth: AuthMember,
+    headers: HeaderMap,
     Path(central_user_id): Path<Uuid>,
 ) -> Result<Json<()>> {
     let perms = state.db.get_member_permissions(auth.member_id).await?;
@@ -241,6 +406,12 @@ pub async fn unban_member(
     }
 
     state.db.unban_user(central_user_id).await?;
+
+    AuditEvent::new(AuditAction::MemberUnban, central_user_id)
+        .reason(reason_from_headers(&headers))
+        .record(&state, auth.member_id)
+        .await?;
+
     Ok(Json(()))
 }
 
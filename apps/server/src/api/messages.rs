@@ -3,26 +3,46 @@ use axum::{
     routing::{delete, get, post},
     Json, Router,
 };
-use confide_sdk::crypto::keys::DsaKeyPair;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
 use uuid::Uuid;
 
 use crate::error::{AppError, Result};
-use crate::models::{permissions, Message};
-use crate::ws::types::ServerMessage;
+use crate::models::{permissions, ChannelType, Member, Message, MessageReaction, Upload};
+use crate::ws::types::{
+    MessageDeletedData, MessageEditedData, MessagePinnedData, MessageUnpinnedData,
+    ReactionAddedData, ReactionRemovedData, ServerMessage,
+};
 use crate::AppState;
 
 use super::middleware::AuthMember;
 
 const MAX_ENCRYPTED_MESSAGE_BYTES: usize = 256 * 1024;
 const MAX_SIGNATURE_BYTES: usize = 8 * 1024;
+const MAX_EMOJI_BYTES: usize = 64;
+const MAX_REACTIONS_PER_MESSAGE: i64 = 20;
+const MAX_PINS_PER_CHANNEL: i64 = 50;
+const MAX_ATTACHMENTS_PER_MESSAGE: usize = 10;
 
 pub fn routes() -> Router<Arc<AppState>> {
     Router::new()
         .route("/{channel_id}", post(send_message))
         .route("/{channel_id}", get(get_messages))
-        .route("/{channel_id}/{message_id}", delete(delete_message))
+        .route("/{channel_id}/pins", get(get_pinned_messages))
+        .route(
+            "/{channel_id}/{message_id}",
+            delete(delete_message).put(edit_message),
+        )
+        .route("/{channel_id}/{message_id}/reactions", post(add_reaction))
+        .route(
+            "/{channel_id}/{message_id}/reactions/{emoji}",
+            delete(remove_reaction),
+        )
+        .route(
+            "/{channel_id}/{message_id}/pin",
+            post(pin_message).delete(unpin_message),
+        )
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -36,6 +56,91 @@ pub struct MessageWithKey {
     pub signature: Vec<u8>,
     pub reply_to_id: Option<Uuid>,
     pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub pinned_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub reactions: Vec<MessageReaction>,
+    pub attachments: Vec<Upload>,
+}
+
+impl MessageWithKey {
+    fn new(
+        message: Message,
+        sender: Member,
+        reactions: Vec<MessageReaction>,
+        attachments: Vec<Upload>,
+    ) -> Self {
+        Self {
+            id: message.id,
+            channel_id: message.channel_id,
+            sender_id: message.sender_id,
+            sender_username: sender.username,
+            sender_dsa_public_key: sender.dsa_public_key,
+            encrypted_content: message.encrypted_content,
+            signature: message.signature,
+            reply_to_id: message.reply_to_id,
+            created_at: message.created_at,
+            updated_at: message.updated_at,
+            pinned_at: message.pinned_at,
+            reactions,
+            attachments,
+        }
+    }
+}
+
+/// Attaches reactions and uploads to a page of messages.
+async fn with_details(
+    state: &AppState,
+    messages: Vec<(Message, Member)>,
+) -> Result<Vec<MessageWithKey>> {
+    let message_ids: Vec<Uuid> = messages.iter().map(|(m, _)| m.id).collect();
+    let mut reactions: HashMap<Uuid, Vec<MessageReaction>> = HashMap::new();
+    for reaction in state.db.get_reactions_for_messages(&message_ids).await? {
+        reactions
+            .entry(reaction.message_id)
+            .or_default()
+            .push(reaction);
+    }
+    let mut attachments: HashMap<Uuid, Vec<Upload>> = HashMap::new();
+    for upload in state.db.get_uploads_for_messages(&message_ids).await? {
+        if let Some(message_id) = upload.message_id {
+            attachments.entry(message_id).or_default().push(upload);
+        }
+    }
+
+    Ok(messages
+        .into_iter()
+        .map(|(msg, sender)| {
+            let message_reactions = reactions.remove(&msg.id).unwrap_or_default();
+            let message_attachments = attachments.remove(&msg.id).unwrap_or_default();
+            MessageWithKey::new(msg, sender, message_reactions, message_attachments)
+        })
+        .collect())
+}
+
+/// Loads `message_id` and checks it belongs to `channel_id`.
+async fn get_channel_message(
+    state: &AppState,
+    channel_id: Uuid,
+    message_id: Uuid,
+) -> Result<Message> {
+    let message = state
+        .db
+        .get_message(message_id)
+        .await?
+        .ok_or(AppError::NotFound("Message not found".into()))?;
+
+    if message.channel_id != channel_id {
+        return Err(AppError::NotFound("Message not found".into()));
+    }
+    Ok(message)
+}
+
+async fn require_permission(state: &AppState, member_id: Uuid, permission: i64) -> Result<()> {
+    let perms = state.db.get_member_permissions(member_id).await?;
+    if !permissions::has_permission(perms, permission) {
+        return Err(AppError::Forbidden);
+    }
+    Ok(())
 }
 
 #[derive(Debug, Deserialize)]
@@ -43,6 +148,9 @@ pub struct SendMessageRequest {
     pub encrypted_content: Vec<u8>,
     pub signature: Vec<u8>,
     pub reply_to_id: Option<Uuid>,
+    /// Uploads made for this channel that the message should reference.
+    #[serde(default)]
+    pub attachment_ids: Vec<Uuid>,
 }
 
 #[derive(Debug, Serialize)]
@@ -75,28 +183,50 @@ pub async fn send_message(
         return Err(AppError::BadRequest("Invalid signature size".into()));
     }
 
-    let sender = state
+    let mut sender = state
         .db
         .get_member(auth.member_id)
         .await?
         .ok_or(AppError::NotFound("Member not found".into()))?;
 
-    let signature_valid = DsaKeyPair::verify(
-        &sender.dsa_public_key,
+    let signature_valid = super::members::verify_member_signature(
+        &state,
+        &mut sender,
         &req.encrypted_content,
         &req.signature,
     )
-    .unwrap_or(false);
+    .await;
 
     if !signature_valid {
         return Err(AppError::BadRequest("Signature verification failed".into()));
     }
 
-    state
+    let channel = state
         .db
         .get_channel(channel_id)
         .await?
         .ok_or(AppError::NotFound("Channel not found".into()))?;
+    if channel.channel_type != ChannelType::Text {
+        return Err(AppError::BadRequest(
+            "Messages cannot be sent to a voice channel".into(),
+        ));
+    }
+
+    let mut attachment_ids = req.attachment_ids;
+    attachment_ids.sort_unstable();
+    attachment_ids.dedup();
+    if attachment_ids.len() > MAX_ATTACHMENTS_PER_MESSAGE {
+        return Err(AppError::BadRequest("Too many attachments".into()));
+    }
+    if !attachment_ids.is_empty() {
+        let pending = state
+            .db
+            .count_pending_uploads(&attachment_ids, auth.member_id, channel_id)
+            .await?;
+        if pending != attachment_ids.len() as i64 {
+            return Err(AppError::BadRequest("Invalid attachment".into()));
+        }
+    }
 
     let message = state
         .db
@@ -111,18 +241,17 @@ pub async fn send_message(
 
     tracing::debug!("Message created with id={}", message.id);
 
-    let broadcast_message = MessageWithKey {
-        id: message.id,
-        channel_id: message.channel_id,
-        sender_id: message.sender_id,
-        sender_username: sender.username.clone(),
-        sender_dsa_public_key: sender.dsa_public_key.clone(),
-        encrypted_content: message.encrypted_content.clone(),
-        signature: message.signature.clone(),
-        reply_to_id: message.reply_to_id,
-        created_at: message.created_at,
+    let attachments = if attachment_ids.is_empty() {
+        Vec::new()
+    } else {
+        state
+            .db
+            .attach_uploads(message.id, &attachment_ids, auth.member_id)
+            .await?
     };
 
+    let broadcast_message = MessageWithKey::new(message.clone(), sender, Vec::new(), attachments);
+
     let connections = state.ws.get_subscribed_members(channel_id).await;
     for member_id in connections {
         state
@@ -174,22 +303,7 @@ pub async fn get_messages(
         .get_channel_messages_with_senders(channel_id, limit, query.before)
         .await?;
 
-    let messages_with_keys = messages_with_senders
-        .into_iter()
-        .map(|(msg, sender)| MessageWithKey {
-            id: msg.id,
-            channel_id: msg.channel_id,
-            sender_id: msg.sender_id,
-            sender_username: sender.username,
-            sender_dsa_public_key: sender.dsa_public_key,
-            encrypted_content: msg.encrypted_content,
-            signature: msg.signature,
-            reply_to_id: msg.reply_to_id,
-            created_at: msg.created_at,
-        })
-        .collect();
-
-    Ok(Json(messages_with_keys))
+    Ok(Json(with_details(&state, messages_with_senders).await?))
 }
 
 pub async fn delete_message(
@@ -197,23 +311,282 @@ pub async fn delete_message(
     auth: AuthMember,
     Path((channel_id, message_id)): Path<(Uuid, Uuid)>,
 ) -> Result<Json<()>> {
-    let message = state
+    let message = get_channel_message(&state, channel_id, message_id).await?;
+
+    if message.sender_id != auth.member_id {
+        require_permission(&state, auth.member_id, permissions::MANAGE_MESSAGES).await?;
+    }
+
+    state.db.delete_message(message_id).await?;
+
+    state
+        .ws
+        .broadcast_to_channel(
+            channel_id,
+            ServerMessage::MessageDeleted {
+                data: MessageDeletedData {
+                    channel_id,
+                    message_id,
+                },
+            },
+        )
+        .await;
+
+    Ok(Json(()))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EditMessageRequest {
+    pub encrypted_content: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+/// Replaces the content of the caller's own message. The new ciphertext must
+/// carry a fresh signature from the sender, like a new message.
+pub async fn edit_message(
+    State(state): State<Arc<AppState>>,
+    auth: AuthMember,
+    Path((channel_id, message_id)): Path<(Uuid, Uuid)>,
+    Json(req): Json<EditMessageRequest>,
+) -> Result<Json<MessageResponse>> {
+    if req.encrypted_content.is_empty() || req.encrypted_content.len() > MAX_ENCRYPTED_MESSAGE_BYTES
+    {
+        return Err(AppError::BadRequest("Invalid message size".into()));
+    }
+    if req.signature.is_empty() || req.signature.len() > MAX_SIGNATURE_BYTES {
+        return Err(AppError::BadRequest("Invalid signature size".into()));
+    }
+
+    let message = get_channel_message(&state, channel_id, message_id).await?;
+    if message.sender_id != auth.member_id {
+        return Err(AppError::Forbidden);
+    }
+    require_permission(&state, auth.member_id, permissions::SEND_MESSAGES).await?;
+
+    let mut sender = state
         .db
-        .get_message(message_id)
+        .get_member(auth.member_id)
         .await?
-        .ok_or(AppError::NotFound("Message not found".into()))?;
+        .ok_or(AppError::NotFound("Member not found".into()))?;
 
-    if message.channel_id != channel_id {
-        return Err(AppError::NotFound("Message not found".into()));
+    let signature_valid = super::members::verify_member_signature(
+        &state,
+        &mut sender,
+        &req.encrypted_content,
+        &req.signature,
+    )
+    .await;
+
+    if !signature_valid {
+        return Err(AppError::BadRequest("Signature verification failed".into()));
     }
 
-    if message.sender_id != auth.member_id {
-        let perms = state.db.get_member_permissions(auth.member_id).await?;
-        if !permissions::has_permission(perms, permissions::MANAGE_MESSAGES) {
-            return Err(AppError::Forbidden);
-        }
+    let message = state
+        .db
+        .update_message_content(message_id, req.encrypted_content, req.signature)
+        .await?;
+
+    if let Some(updated_at) = message.updated_at {
+        state
+            .ws
+            .broadcast_to_channel(
+                channel_id,
+                ServerMessage::MessageEdited {
+                    data: MessageEditedData {
+                        channel_id,
+                        message_id,
+                        encrypted_content: message.encrypted_content.clone(),
+                        signature: message.signature.clone(),
+                        updated_at,
+                    },
+                },
+            )
+            .await;
     }
 
-    state.db.delete_message(message_id).await?;
+    Ok(Json(MessageResponse { message }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddReactionRequest {
+    pub emoji: String,
+}
+
+pub async fn add_reaction(
+    State(state): State<Arc<AppState>>,
+    auth: AuthMember,
+    Path((channel_id, message_id)): Path<(Uuid, Uuid)>,
+    Json(req): Json<AddReactionRequest>,
+) -> Result<Json<MessageReaction>> {
+    require_permission(&state, auth.member_id, permissions::READ_MESSAGES).await?;
+
+    let emoji = req.emoji.trim();
+    if emoji.is_empty() || emoji.len() > MAX_EMOJI_BYTES || emoji.chars().any(char::is_whitespace) {
+        return Err(AppError::BadRequest("Invalid emoji".into()));
+    }
+
+    get_channel_message(&state, channel_id, message_id).await?;
+
+    // Joining an existing reaction never adds a new emoji, so only a new one
+    // can hit the cap.
+    if state
+        .db
+        .count_other_reaction_emojis(message_id, emoji)
+        .await?
+        >= MAX_REACTIONS_PER_MESSAGE
+    {
+        return Err(AppError::BadRequest(
+            "This message has too many different reactions".into(),
+        ));
+    }
+
+    let reaction = state
+        .db
+        .add_reaction(message_id, auth.member_id, emoji)
+        .await?
+        .ok_or(AppError::BadRequest("Already reacted".into()))?;
+
+    state
+        .ws
+        .broadcast_to_channel(
+            channel_id,
+            ServerMessage::ReactionAdded {
+                data: ReactionAddedData {
+                    id: reaction.id,
+                    channel_id,
+                    message_id,
+                    member_id: auth.member_id,
+                    emoji: reaction.emoji.clone(),
+                },
+            },
+        )
+        .await;
+
+    Ok(Json(reaction))
+}
+
+pub async fn remove_reaction(
+    State(state): State<Arc<AppState>>,
+    auth: AuthMember,
+    Path((channel_id, message_id, emoji)): Path<(Uuid, Uuid, String)>,
+) -> Result<Json<()>> {
+    get_channel_message(&state, channel_id, message_id).await?;
+
+    if !state
+        .db
+        .remove_reaction(message_id, auth.member_id, &emoji)
+        .await?
+    {
+        return Err(AppError::NotFound("Reaction not found".into()));
+    }
+
+    state
+        .ws
+        .broadcast_to_channel(
+            channel_id,
+            ServerMessage::ReactionRemoved {
+                data: ReactionRemovedData {
+                    channel_id,
+                    message_id,
+                    member_id: auth.member_id,
+                    emoji,
+                },
+            },
+        )
+        .await;
+
     Ok(Json(()))
 }
+
+pub async fn get_pinned_messages(
+    State(state): State<Arc<AppState>>,
+    auth: AuthMember,
+    Path(channel_id): Path<Uuid>,
+) -> Result<Json<Vec<MessageWithKey>>> {
+    require_permission(&state, auth.member_id, permissions::READ_MESSAGES).await?;
+
+    state
+        .db
+        .get_channel(channel_id)
+        .await?
+        .ok_or(AppError::NotFound("Channel not found".into()))?;
+
+    let pinned = state
+        .db
+        .get_pinned_messages_with_senders(channel_id)
+        .await?;
+    Ok(Json(with_details(&state, pinned).await?))
+}
+
+pub async fn pin_message(
+    State(state): State<Arc<AppState>>,
+    auth: AuthMember,
+    Path((channel_id, message_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<MessageResponse>> {
+    require_permission(&state, auth.member_id, permissions::MANAGE_MESSAGES).await?;
+
+    let message = get_channel_message(&state, channel_id, message_id).await?;
+    if message.pinned_at.is_some() {
+        return Ok(Json(MessageResponse { message }));
+    }
+    if state.db.count_pinned_messages(channel_id).await? >= MAX_PINS_PER_CHANNEL {
+        return Err(AppError::BadRequest(format!(
+            "A channel can have at most {} pinned messages",
+            MAX_PINS_PER_CHANNEL
+        )));
+    }
+
+    let message = state
+        .db
+        .set_message_pinned(message_id, Some(auth.member_id))
+        .await?;
+
+    if let Some(pinned_at) = message.pinned_at {
+        state
+            .ws
+            .broadcast_to_channel(
+                channel_id,
+                ServerMessage::MessagePinned {
+                    data: MessagePinnedData {
+                        channel_id,
+                        message_id,
+                        pinned_by: auth.member_id,
+                        pinned_at,
+                    },
+                },
+            )
+            .await;
+    }
+
+    Ok(Json(MessageResponse { message }))
+}
+
+pub async fn unpin_message(
+    State(state): State<Arc<AppState>>,
+    auth: AuthMember,
+    Path((channel_id, message_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<MessageResponse>> {
+    require_permission(&state, auth.member_id, permissions::MANAGE_MESSAGES).await?;
+
+    let message = get_channel_message(&state, channel_id, message_id).await?;
+    if message.pinned_at.is_none() {
+        return Ok(Json(MessageResponse { message }));
+    }
+
+    let message = state.db.set_message_pinned(message_id, None).await?;
+
+    state
+        .ws
+        .broadcast_to_channel(
+            channel_id,
+            ServerMessage::MessageUnpinned {
+                data: MessageUnpinnedData {
+                    channel_id,
+                    message_id,
+                },
+            },
+        )
+        .await;
+
+    Ok(Json(MessageResponse { message }))
+}
@@ -39,7 +39,8 @@ impl FromRequestParts<Arc<AppState>> for AuthMember {
             .await?
             .ok_or(AppError::Unauthorized)?;
 
-        if session.expires_at < chrono::Utc::now() {
+        let now = chrono::Utc::now();
+        if session.expires_at < now || session.access_expires_at < now {
             return Err(AppError::Unauthorized);
         }
 
@@ -1,3 +1,4 @@
+pub mod audit_log;
 mod auth;
 mod channels;
 mod members;
@@ -7,6 +8,8 @@ pub mod rate_limit;
 mod roles;
 pub mod server;
 mod setup;
+pub mod uploads;
+pub mod voice;
 
 use axum::Router;
 use std::sync::Arc;
@@ -22,4 +25,5 @@ pub fn routes() -> Router<Arc<AppState>> {
         .nest("/members", members::routes())
         .nest("/roles", roles::routes())
         .nest("/server", server::routes())
+        .nest("/voice", voice::routes())
 }
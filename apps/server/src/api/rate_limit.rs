@@ -1,4 +1,5 @@
 use axum::{
+    body::Body,
     extract::{Request, State},
     http::{header::AUTHORIZATION, Method},
     middleware::Next,
@@ -8,10 +9,14 @@ use redis::Script;
 use sha2::{Digest, Sha256};
 use std::sync::Arc;
 
+use super::auth::RefreshRequest;
 use crate::{error::AppError, AppState};
 
+const MAX_REFRESH_BODY_BYTES: usize = 4096;
+
 pub enum RateLimitTier {
     Auth,
+    Refresh,
     WebSocketConnect,
     Read,
     Write,
@@ -21,6 +26,7 @@ impl RateLimitTier {
     pub fn limits(&self) -> (u32, u64, &'static str) {
         match self {
             RateLimitTier::Auth => (5, 60, "auth"),
+            RateLimitTier::Refresh => (10, 60, "refresh"),
             RateLimitTier::WebSocketConnect => (30, 60, "ws"),
             RateLimitTier::Read => (300, 60, "read"),
             RateLimitTier::Write => (60, 60, "write"),
@@ -28,7 +34,9 @@ impl RateLimitTier {
     }
 
     pub fn from_request(path: &str, method: &Method) -> Self {
-        if path.starts_with("/api/auth") || path.starts_with("/api/setup") {
+        if path == "/api/auth/refresh" {
+            RateLimitTier::Refresh
+        } else if path.starts_with("/api/auth") || path.starts_with("/api/setup") {
             RateLimitTier::Auth
         } else if path.starts_with("/ws") {
             RateLimitTier::WebSocketConnect
@@ -51,15 +59,32 @@ fn extract_ws_token_from_query(query: Option<&str>) -> Option<String> {
     None
 }
 
+/// Refresh requests carry no bearer token, so they are counted per refresh
+/// token rather than in the shared anonymous auth bucket.
+async fn refresh_token_identifier(request: Request) -> Result<(Request, String), AppError> {
+    let (parts, body) = request.into_parts();
+    let bytes = axum::body::to_bytes(body, MAX_REFRESH_BODY_BYTES)
+        .await
+        .map_err(|_| AppError::BadRequest("Request body too large".into()))?;
+
+    let identifier = serde_json::from_slice::<RefreshRequest>(&bytes)
+        .ok()
+        .and_then(|req| hex::decode(req.refresh_token).ok())
+        .map(|token| format!("refresh:{}", hex::encode(&Sha256::digest(&token)[..8])))
+        .unwrap_or_else(|| "anon".to_string());
+
+    Ok((Request::from_parts(parts, Body::from(bytes)), identifier))
+}
+
 pub async fn rate_limit_middleware(
     State(state): State<Arc<AppState>>,
     request: Request,
     next: Next,
 ) -> Result<Response, AppError> {
-    let path = request.uri().path();
+    let path = request.uri().path().to_string();
     let method = request.method().clone();
 
-    let tier = RateLimitTier::from_request(path, &method);
+    let tier = RateLimitTier::from_request(&path, &method);
     let (max_requests, window_seconds, tier_name) = tier.limits();
 
     let bearer = request
@@ -77,19 +102,24 @@ pub async fn rate_limit_middleware(
 
     let token = bearer.or(ws_token);
 
-    let user_identifier = if let Some(token) = token {
-        match hex::decode(&token) {
-            Ok(token_bytes) => {
-                let token_hash = Sha256::digest(&token_bytes);
-                format!("user:{}", hex::encode(&token_hash[..8]))
-            }
-            Err(_) => {
-                tracing::warn!("Invalid token format in rate limit check");
-                return Err(AppError::Unauthorized);
-            }
-        }
+    let (request, user_identifier) = if matches!(tier, RateLimitTier::Refresh) {
+        refresh_token_identifier(request).await?
     } else {
-        "anon".to_string()
+        let identifier = if let Some(token) = token {
+            match hex::decode(&token) {
+                Ok(token_bytes) => {
+                    let token_hash = Sha256::digest(&token_bytes);
+                    format!("user:{}", hex::encode(&token_hash[..8]))
+                }
+                Err(_) => {
+                    tracing::warn!("Invalid token format in rate limit check");
+                    return Err(AppError::Unauthorized);
+                }
+            }
+        } else {
+            "anon".to_string()
+        };
+        (request, identifier)
     };
 
     let rate_key = format!(
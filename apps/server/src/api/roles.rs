@@ -1,17 +1,20 @@
 use axum::{
     extract::{Path, State},
+    http::HeaderMap,
     routing::{delete, get, patch, post},
     Json, Router,
 };
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 use std::sync::Arc;
 use uuid::Uuid;
 
 use crate::error::{AppError, Result};
-use crate::models::{permissions, Role};
+use crate::models::{permissions, AuditAction, Role};
 use crate::ws::types::ServerMessage;
 use crate::AppState;
 
+use super::audit_log::{reason_from_headers, AuditEvent};
 use super::middleware::AuthMember;
 
 pub fn routes() -> Router<Arc<AppState>> {
@@ -63,6 +66,7 @@ pub struct CreateRoleRequest {
 pub async fn create_role(
     State(state): State<Arc<AppState>>,
     auth: AuthMember,
+    headers: HeaderMap,
     Json(req): Json<CreateRoleRequest>,
 ) -> Result<Json<ApiRole>> {
     let perms = state.db.get_member_permissions(auth.member_id).await?;
@@ -85,6 +89,12 @@ pub async fn create_role(
         .broadcast_all(ServerMessage::RoleCreated { role: role.clone() })
         .await;
 
+    AuditEvent::new(AuditAction::RoleCreate, role.id)
+        .after(&role)
+        .reason(reason_from_headers(&headers))
+        .record(&state, auth.member_id)
+        .await?;
+
     Ok(Json(ApiRole::from(role)))
 }
 
@@ -108,6 +118,7 @@ pub struct UpdateRoleRequest {
 pub async fn update_role(
     State(state): State<Arc<AppState>>,
     auth: AuthMember,
+    headers: HeaderMap,
     Path(id): Path<Uuid>,
     Json(req): Json<UpdateRoleRequest>,
 ) -> Result<Json<ApiRole>> {
@@ -123,6 +134,12 @@ pub async fn update_role(
         }
     }
 
+    let before = state
+        .db
+        .get_role(id)
+        .await?
+        .ok_or(AppError::NotFound("Role not found".into()))?;
+
     state
         .db
         .update_role(id, name_str, req.permissions, req.color, req.position)
@@ -139,12 +156,22 @@ pub async fn update_role(
         .broadcast_all(ServerMessage::RoleUpdated { role: role.clone() })
         .await;
 
+    super::voice::refresh_permissions(&state).await;
+
+    AuditEvent::new(AuditAction::RoleUpdate, id)
+        .before(&before)
+        .after(&role)
+        .reason(reason_from_headers(&headers))
+        .record(&state, auth.member_id)
+        .await?;
+
     Ok(Json(ApiRole::from(role)))
 }
 
 pub async fn delete_role(
     State(state): State<Arc<AppState>>,
     auth: AuthMember,
+    headers: HeaderMap,
     Path(id): Path<Uuid>,
 ) -> Result<Json<()>> {
     let perms = state.db.get_member_permissions(auth.member_id).await?;
@@ -152,17 +179,33 @@ pub async fn delete_role(
         return Err(AppError::Forbidden);
     }
 
+    let role = state
+        .db
+        .get_role(id)
+        .await?
+        .ok_or(AppError::NotFound("Role not found".into()))?;
+
     state.db.delete_role(id).await?;
     state
         .ws
         .broadcast_all(ServerMessage::RoleDeleted { role_id: id })
         .await;
+
+    super::voice::refresh_permissions(&state).await;
+
+    AuditEvent::new(AuditAction::RoleDelete, id)
+        .before(&role)
+        .reason(reason_from_headers(&headers))
+        .record(&state, auth.member_id)
+        .await?;
+
     Ok(Json(()))
 }
 
 pub async fn assign_role_to_member(
     State(state): State<Arc<AppState>>,
     auth: AuthMember,
+    headers: HeaderMap,
     Path((role_id, member_id)): Path<(Uuid, Uuid)>,
 ) -> Result<Json<()>> {
     let perms = state.db.get_member_permissions(auth.member_id).await?;
@@ -179,12 +222,22 @@ pub async fn assign_role_to_member(
             role_ids,
         })
         .await;
+
+    super::voice::refresh_permissions(&state).await;
+
+    AuditEvent::new(AuditAction::MemberRoleAdd, member_id)
+        .after(&json!({ "role_id": role_id }))
+        .reason(reason_from_headers(&headers))
+        .record(&state, auth.member_id)
+        .await?;
+
     Ok(Json(()))
 }
 
 pub async fn remove_role_from_member(
     State(state): State<Arc<AppState>>,
     auth: AuthMember,
+    headers: HeaderMap,
     Path((role_id, member_id)): Path<(Uuid, Uuid)>,
 ) -> Result<Json<()>> {
     let perms = state.db.get_member_permissions(auth.member_id).await?;
@@ -201,6 +254,15 @@ pub async fn remove_role_from_member(
             role_ids,
         })
         .await;
+
+    super::voice::refresh_permissions(&state).await;
+
+    AuditEvent::new(AuditAction::MemberRoleRemove, member_id)
+        .before(&json!({ "role_id": role_id }))
+        .reason(reason_from_headers(&headers))
+        .record(&state, auth.member_id)
+        .await?;
+
     Ok(Json(()))
 }
 
@@ -212,6 +274,7 @@ pub struct ReorderRolesRequest {
 pub async fn reorder_roles(
     State(state): State<Arc<AppState>>,
     auth: AuthMember,
+    headers: HeaderMap,
     Json(req): Json<ReorderRolesRequest>,
 ) -> Result<Json<Vec<ApiRole>>> {
     let perms = state.db.get_member_permissions(auth.member_id).await?;
@@ -227,6 +290,7 @@ pub async fn reorder_roles(
         return Err(AppError::BadRequest("Invalid role_ids".into()));
     }
 
+    let before = role_order(&roles);
     let roles = state.db.reorder_roles(req.role_ids).await?;
     for role in roles.iter().cloned() {
         state
@@ -235,5 +299,19 @@ pub async fn reorder_roles(
             .await;
     }
 
+    AuditEvent::new(AuditAction::RoleReorder, None)
+        .before(&before)
+        .after(&role_order(&roles))
+        .reason(reason_from_headers(&headers))
+        .record(&state, auth.member_id)
+        .await?;
+
     Ok(Json(roles.into_iter().map(ApiRole::from).collect()))
 }
+
+/// Role ids from highest to lowest position.
+fn role_order(roles: &[Role]) -> Vec<Uuid> {
+    let mut sorted: Vec<&Role> = roles.iter().collect();
+    sorted.sort_by_key(|r| std::cmp::Reverse(r.position));
+    sorted.into_iter().map(|r| r.id).collect()
+}
@@ -4,6 +4,7 @@ use argon2::{
 };
 use axum::{
     extract::State,
+    http::HeaderMap,
     routing::{get, post},
     Json, Router,
 };
@@ -12,9 +13,10 @@ use std::sync::Arc;
 use uuid::Uuid;
 
 use crate::error::{AppError, Result};
-use crate::models::permissions;
+use crate::models::{permissions, AuditAction, RetentionPolicy, ServerIdentity};
 use crate::AppState;
 
+use super::audit_log::{self, reason_from_headers, AuditEvent};
 use super::middleware::AuthMember;
 
 use axum::routing::delete;
@@ -25,6 +27,7 @@ pub fn routes() -> Router<Arc<AppState>> {
         .route("/password", post(set_password))
         .route("/password/remove", post(remove_password))
         .route("/info", get(get_server_info))
+        .route("/audit-log", get(audit_log::get_audit_log))
         .route("/", delete(delete_server))
 }
 
@@ -65,6 +68,21 @@ pub struct ServerInfoResponse {
     pub has_password: bool,
 }
 
+impl From<ServerIdentity> for ServerInfoResponse {
+    fn from(identity: ServerIdentity) -> Self {
+        Self {
+            name: identity.server_name,
+            description: identity.description,
+            is_discoverable: identity.is_discoverable,
+            icon_url: identity.icon_url,
+            max_users: identity.max_users,
+            max_upload_size_mb: identity.max_upload_size_mb,
+            message_retention: identity.message_retention,
+            has_password: identity.password_hash.is_some(),
+        }
+    }
+}
+
 pub async fn get_server_info(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<ServerInfoResponse>> {
@@ -74,16 +92,7 @@ pub async fn get_server_info(
         .await?
         .ok_or(AppError::ServerNotSetup)?;
 
-    Ok(Json(ServerInfoResponse {
-        name: identity.server_name,
-        description: identity.description,
-        is_discoverable: identity.is_discoverable,
-        icon_url: identity.icon_url,
-        max_users: identity.max_users,
-        max_upload_size_mb: identity.max_upload_size_mb,
-        message_retention: identity.message_retention,
-        has_password: identity.password_hash.is_some(),
-    }))
+    Ok(Json(ServerInfoResponse::from(identity)))
 }
 
 #[derive(Debug, Deserialize)]
@@ -100,10 +109,23 @@ pub struct UpdateServerSettingsRequest {
 pub async fn update_server_settings(
     State(state): State<Arc<AppState>>,
     auth: AuthMember,
+    headers: HeaderMap,
     Json(req): Json<UpdateServerSettingsRequest>,
 ) -> Result<Json<ServerInfoResponse>> {
     check_server_admin_or_owner(&state, auth.member_id).await?;
 
+    if let Some(retention) = &req.message_retention {
+        if RetentionPolicy::parse(retention).is_none() {
+            return Err(AppError::BadRequest("Invalid message retention".into()));
+        }
+    }
+
+    let before = state
+        .db
+        .get_server_identity()
+        .await?
+        .map(ServerInfoResponse::from);
+
     let identity = state
         .db
         .update_server_settings(
@@ -116,17 +138,16 @@ pub async fn update_server_settings(
             req.message_retention,
         )
         .await?;
+    let info = ServerInfoResponse::from(identity);
+
+    AuditEvent::new(AuditAction::ServerUpdate, None)
+        .before(&before)
+        .after(&info)
+        .reason(reason_from_headers(&headers))
+        .record(&state, auth.member_id)
+        .await?;
 
-    Ok(Json(ServerInfoResponse {
-        name: identity.server_name,
-        description: identity.description,
-        is_discoverable: identity.is_discoverable,
-        icon_url: identity.icon_url,
-        max_users: identity.max_users,
-        max_upload_size_mb: identity.max_upload_size_mb,
-        message_retention: identity.message_retention,
-        has_password: identity.password_hash.is_some(),
-    }))
+    Ok(Json(info))
 }
 
 #[derive(Debug, Deserialize)]
@@ -137,6 +158,7 @@ pub struct SetPasswordRequest {
 pub async fn set_password(
     State(state): State<Arc<AppState>>,
     auth: AuthMember,
+    headers: HeaderMap,
     Json(req): Json<SetPasswordRequest>,
 ) -> Result<Json<serde_json::Value>> {
     check_server_admin_or_owner(&state, auth.member_id).await?;
@@ -171,17 +193,28 @@ pub async fn set_password(
 
     state.db.set_server_password(Some(password_hash)).await?;
 
+    AuditEvent::new(AuditAction::ServerPasswordSet, None)
+        .reason(reason_from_headers(&headers))
+        .record(&state, auth.member_id)
+        .await?;
+
     Ok(Json(serde_json::json!({ "success": true })))
 }
 
 pub async fn remove_password(
     State(state): State<Arc<AppState>>,
     auth: AuthMember,
+    headers: HeaderMap,
 ) -> Result<Json<serde_json::Value>> {
     check_server_admin_or_owner(&state, auth.member_id).await?;
 
     state.db.set_server_password(None).await?;
 
+    AuditEvent::new(AuditAction::ServerPasswordRemove, None)
+        .reason(reason_from_headers(&headers))
+        .record(&state, auth.member_id)
+        .await?;
+
     Ok(Json(serde_json::json!({ "success": true })))
 }
 
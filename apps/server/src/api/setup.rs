@@ -10,8 +10,11 @@ use uuid::Uuid;
 
 use crate::error::{AppError, Result};
 use crate::federation::verify_federation_token;
+use crate::models::ChannelType;
 use crate::AppState;
 
+use super::auth::{issue_session_tokens, SessionTokens};
+
 pub fn routes() -> Router<Arc<AppState>> {
     Router::new()
         .route("/claim", post(claim_ownership))
@@ -57,7 +60,8 @@ pub struct ClaimOwnershipRequest {
 pub struct ClaimOwnershipResponse {
     pub success: bool,
     pub member_id: Uuid,
-    pub session_token: String,
+    #[serde(flatten)]
+    pub tokens: SessionTokens,
 }
 
 pub async fn claim_ownership(
@@ -122,12 +126,8 @@ pub async fn claim_ownership(
         .set_server_owner(user_info.user_id, Some(req.central_server_id))
         .await?;
 
-    let (session_token, token_hash) = generate_session_token();
-    let expires_at = chrono::Utc::now() + chrono::Duration::hours(24 * 30);
-    state
-        .db
-        .create_session(member.id, token_hash, expires_at)
-        .await?;
+    let (tokens, token_hashes) = issue_session_tokens(&state.config.auth);
+    state.db.create_session(member.id, &token_hashes).await?;
 
     if identity.central_registration_id.is_none() {
         if let Err(e) = register_with_central(&state, &identity).await {
@@ -156,6 +156,7 @@ pub async fn claim_ownership(
             "general".to_string(),
             Some("General discussion".to_string()),
             0,
+            ChannelType::Text,
         )
         .await
     {
@@ -165,19 +166,10 @@ pub async fn claim_ownership(
     Ok(Json(ClaimOwnershipResponse {
         success: true,
         member_id: member.id,
-        session_token,
+        tokens,
     }))
 }
 
-fn generate_session_token() -> (String, Vec<u8>) {
-    use rand::RngCore;
-    let mut token_bytes = [0u8; 32];
-    rand::thread_rng().fill_bytes(&mut token_bytes);
-    let token = hex::encode(token_bytes);
-    let token_hash = Sha256::digest(token_bytes).to_vec();
-    (token, token_hash)
-}
-
 async fn register_with_central(
     state: &AppState,
     identity: &crate::models::ServerIdentity,
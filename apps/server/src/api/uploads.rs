@@ -0,0 +1,236 @@
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{header, HeaderMap},
+    response::Response,
+    routing::{get, post},
+    Json, Router,
+};
+use futures::StreamExt;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::error::{AppError, Result};
+use crate::models::{permissions, Upload};
+use crate::AppState;
+
+use super::middleware::AuthMember;
+
+const MAX_FILENAME_CHARS: usize = 255;
+const MAX_MIME_TYPE_BYTES: usize = 127;
+
+/// Attachment bodies can be far larger than the API-wide request limit, so
+/// these routes are mounted outside it and enforce `max_upload_size_mb`
+/// themselves while streaming.
+pub fn routes() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", post(upload_file))
+        .route("/{upload_id}", get(download_file).delete(delete_file))
+}
+
+fn sanitize_filename(filename: &str) -> String {
+    filename
+        .chars()
+        .filter(|c| {
+            !c.is_control() && !matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|')
+        })
+        .take(MAX_FILENAME_CHARS)
+        .collect()
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &str) -> Result<&'a str> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| AppError::BadRequest(format!("Missing {} header", name)))
+}
+
+async fn require_channel_permission(
+    state: &AppState,
+    member_id: Uuid,
+    channel_id: Uuid,
+    required: &[i64],
+) -> Result<()> {
+    let perms = state
+        .db
+        .get_member_channel_permissions(member_id, channel_id)
+        .await?;
+    if required
+        .iter()
+        .all(|p| permissions::has_permission(perms, *p))
+    {
+        Ok(())
+    } else {
+        Err(AppError::Forbidden)
+    }
+}
+
+pub async fn upload_file(
+    State(state): State<Arc<AppState>>,
+    auth: AuthMember,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<Json<Upload>> {
+    let channel_id = Uuid::parse_str(header_str(&headers, "x-channel-id")?)
+        .map_err(|_| AppError::BadRequest("Invalid channel ID".into()))?;
+    let filename = sanitize_filename(header_str(&headers, "x-filename")?);
+    let content_type = headers
+        .get("x-mime-type")
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.is_empty() && v.len() <= MAX_MIME_TYPE_BYTES)
+        .unwrap_or("application/octet-stream")
+        .to_string();
+
+    if filename.is_empty() {
+        return Err(AppError::BadRequest("Invalid filename".into()));
+    }
+
+    state
+        .db
+        .get_channel(channel_id)
+        .await?
+        .ok_or(AppError::NotFound("Channel not found".into()))?;
+
+    require_channel_permission(
+        &state,
+        auth.member_id,
+        channel_id,
+        &[permissions::VIEW_CHANNELS, permissions::SEND_MESSAGES],
+    )
+    .await?;
+
+    let identity = state
+        .db
+        .get_server_identity()
+        .await?
+        .ok_or(AppError::ServerNotSetup)?;
+    let max_bytes = identity.max_upload_size_mb.max(0) as usize * 1024 * 1024;
+    let too_large = || {
+        AppError::BadRequest(format!(
+            "File too large. Maximum size is {} MB",
+            identity.max_upload_size_mb
+        ))
+    };
+
+    let declared_len = headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<usize>().ok());
+    if declared_len.is_some_and(|len| len > max_bytes) {
+        return Err(too_large());
+    }
+
+    let mut data = Vec::with_capacity(declared_len.unwrap_or(0));
+    let mut stream = body.into_data_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|_| AppError::BadRequest("Failed to read upload".into()))?;
+        if data.len() + chunk.len() > max_bytes {
+            return Err(too_large());
+        }
+        data.extend_from_slice(&chunk);
+    }
+
+    if data.is_empty() {
+        return Err(AppError::BadRequest("Empty upload".into()));
+    }
+
+    let file_size = data.len() as i64;
+    let storage_key = format!("channels/{}/{}", channel_id, Uuid::new_v4());
+    state.storage.put(&storage_key, data).await?;
+
+    let upload = match state
+        .db
+        .create_upload(
+            auth.member_id,
+            channel_id,
+            filename,
+            content_type,
+            file_size,
+            storage_key.clone(),
+        )
+        .await
+    {
+        Ok(upload) => upload,
+        Err(e) => {
+            if let Err(cleanup_err) = state.storage.delete(&storage_key).await {
+                tracing::warn!("Failed to remove unrecorded upload: {}", cleanup_err);
+            }
+            return Err(e);
+        }
+    };
+
+    Ok(Json(upload))
+}
+
+pub async fn download_file(
+    State(state): State<Arc<AppState>>,
+    auth: AuthMember,
+    Path(upload_id): Path<Uuid>,
+) -> Result<Response> {
+    let upload = state
+        .db
+        .get_upload(upload_id)
+        .await?
+        .ok_or(AppError::NotFound("Upload not found".into()))?;
+
+    // Until it is attached to a message, only the uploader can see an upload.
+    match (upload.message_id, upload.channel_id) {
+        (Some(_), Some(channel_id)) => {
+            require_channel_permission(
+                &state,
+                auth.member_id,
+                channel_id,
+                &[permissions::VIEW_CHANNELS, permissions::READ_MESSAGES],
+            )
+            .await?;
+        }
+        _ if upload.uploader_id == Some(auth.member_id) => {}
+        _ => return Err(AppError::NotFound("Upload not found".into())),
+    }
+
+    let data = state
+        .storage
+        .get(&upload.storage_path)
+        .await?
+        .ok_or(AppError::NotFound("Upload not found".into()))?;
+
+    Response::builder()
+        .header(header::CONTENT_TYPE, "application/octet-stream")
+        .header(header::CONTENT_LENGTH, data.len())
+        .header(
+            header::CACHE_CONTROL,
+            "private, max-age=31536000, immutable",
+        )
+        .body(Body::from(data))
+        .map_err(|e| AppError::Internal(format!("Failed to build response: {}", e)))
+}
+
+pub async fn delete_file(
+    State(state): State<Arc<AppState>>,
+    auth: AuthMember,
+    Path(upload_id): Path<Uuid>,
+) -> Result<Json<()>> {
+    let upload = state
+        .db
+        .get_upload(upload_id)
+        .await?
+        .ok_or(AppError::NotFound("Upload not found".into()))?;
+
+    if upload.uploader_id != Some(auth.member_id) {
+        let channel_id = upload
+            .channel_id
+            .ok_or(AppError::NotFound("Upload not found".into()))?;
+        require_channel_permission(
+            &state,
+            auth.member_id,
+            channel_id,
+            &[permissions::MANAGE_MESSAGES],
+        )
+        .await?;
+    }
+
+    state.storage.delete(&upload.storage_path).await?;
+    state.db.delete_upload(upload.id).await?;
+
+    Ok(Json(()))
+}
@@ -0,0 +1,331 @@
+use axum::{
+    extract::{Path, State},
+    routing::{get, patch, post},
+    Json, Router,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::error::{AppError, Result};
+use crate::media::generate_relay_token;
+use crate::models::{permissions, ChannelType};
+use crate::ws::types::{ServerMessage, VoiceParticipantLeftData};
+use crate::ws::voice::VoiceParticipant;
+use crate::AppState;
+
+use super::middleware::AuthMember;
+
+const RELAY_TOKEN_TTL_MINUTES: i64 = 5;
+
+pub fn routes() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/states", get(get_voice_states))
+        .route("/{channel_id}/participants", get(get_participants))
+        .route("/{channel_id}/join", post(join_channel))
+        .route("/{channel_id}/leave", post(leave_channel))
+        .route(
+            "/{channel_id}/state",
+            patch(update_voice_state).post(update_voice_state),
+        )
+}
+
+/// Takes the member out of voice, closes their relay connection and tells
+/// everyone. Used on leave, WebSocket disconnect, kick, ban and relay drop.
+pub async fn disconnect_member(state: &AppState, member_id: Uuid) {
+    if let Some(channel_id) = state.voice.leave(member_id).await {
+        announce_left(state, channel_id, member_id).await;
+    }
+}
+
+async fn announce_left(state: &AppState, channel_id: Uuid, member_id: Uuid) {
+    if let Some(relay) = &state.relay {
+        relay.disconnect(channel_id, member_id);
+    }
+    broadcast_voice_event(
+        state,
+        channel_id,
+        ServerMessage::VoiceParticipantLeft {
+            data: VoiceParticipantLeftData {
+                channel_id,
+                member_id,
+            },
+        },
+    )
+    .await;
+}
+
+/// Voice activity is only shown to members who can see the channel.
+async fn broadcast_voice_event(state: &AppState, channel_id: Uuid, message: ServerMessage) {
+    match state
+        .db
+        .get_members_with_channel_permission(channel_id, permissions::VIEW_CHANNELS)
+        .await
+    {
+        Ok(member_ids) => state.ws.send_to_members(&member_ids, message).await,
+        Err(e) => tracing::error!(
+            "Failed to load members for voice channel {}: {:?}",
+            channel_id,
+            e
+        ),
+    }
+}
+
+/// Called when the relay reports a dropped connection. Participants who
+/// rejoined after that connection was opened are left alone.
+pub async fn handle_relay_disconnect(
+    state: &AppState,
+    channel_id: Uuid,
+    member_id: Uuid,
+    connected_at: DateTime<Utc>,
+) {
+    if state
+        .voice
+        .leave_if_joined_before(channel_id, member_id, connected_at)
+        .await
+    {
+        announce_left(state, channel_id, member_id).await;
+    }
+}
+
+/// Re-evaluates every voice participant's channel permissions after roles or
+/// overrides change. Members who lost CONNECT (or can no longer see the
+/// channel) are disconnected; SPEAK changes are applied at the relay.
+pub async fn refresh_permissions(state: &AppState) {
+    for participant in state.voice.all_participants().await {
+        let perms = match state
+            .db
+            .get_member_channel_permissions(participant.member_id, participant.channel_id)
+            .await
+        {
+            Ok(perms) => perms,
+            Err(e) => {
+                tracing::error!(
+                    "Failed to load voice permissions for member {}: {:?}",
+                    participant.member_id,
+                    e
+                );
+                continue;
+            }
+        };
+
+        if !permissions::has_permission(perms, permissions::VIEW_CHANNELS)
+            || !permissions::has_permission(perms, permissions::CONNECT)
+        {
+            disconnect_member(state, participant.member_id).await;
+            continue;
+        }
+
+        let can_speak = permissions::has_permission(perms, permissions::SPEAK);
+        if let Some(updated) = state
+            .voice
+            .set_can_speak(participant.channel_id, participant.member_id, can_speak)
+            .await
+        {
+            if let Some(relay) = &state.relay {
+                relay.set_can_speak(updated.channel_id, updated.member_id, can_speak);
+            }
+            broadcast_voice_event(
+                state,
+                updated.channel_id,
+                ServerMessage::VoiceStateUpdated { data: updated },
+            )
+            .await;
+        }
+    }
+}
+
+/// Clears out a voice channel that is being deleted.
+pub async fn close_channel(state: &AppState, channel_id: Uuid) {
+    for member_id in state.voice.remove_channel(channel_id).await {
+        announce_left(state, channel_id, member_id).await;
+    }
+}
+
+async fn require_voice_channel(state: &AppState, channel_id: Uuid) -> Result<()> {
+    let channel = state
+        .db
+        .get_channel(channel_id)
+        .await?
+        .ok_or(AppError::NotFound("Channel not found".into()))?;
+    if channel.channel_type != ChannelType::Voice {
+        return Err(AppError::BadRequest("Not a voice channel".into()));
+    }
+    Ok(())
+}
+
+pub async fn get_voice_states(
+    State(state): State<Arc<AppState>>,
+    auth: AuthMember,
+) -> Result<Json<Vec<VoiceParticipant>>> {
+    let mut visible: HashMap<Uuid, bool> = HashMap::new();
+    let mut participants = Vec::new();
+    for participant in state.voice.all_participants().await {
+        let can_view = match visible.get(&participant.channel_id) {
+            Some(can_view) => *can_view,
+            None => {
+                let perms = state
+                    .db
+                    .get_member_channel_permissions(auth.member_id, participant.channel_id)
+                    .await?;
+                let can_view = permissions::has_permission(perms, permissions::VIEW_CHANNELS);
+                visible.insert(participant.channel_id, can_view);
+                can_view
+            }
+        };
+        if can_view {
+            participants.push(participant);
+        }
+    }
+    Ok(Json(participants))
+}
+
+pub async fn get_participants(
+    State(state): State<Arc<AppState>>,
+    auth: AuthMember,
+    Path(channel_id): Path<Uuid>,
+) -> Result<Json<Vec<VoiceParticipant>>> {
+    require_voice_channel(&state, channel_id).await?;
+
+    let perms = state
+        .db
+        .get_member_channel_permissions(auth.member_id, channel_id)
+        .await?;
+    if !permissions::has_permission(perms, permissions::VIEW_CHANNELS) {
+        return Err(AppError::Forbidden);
+    }
+
+    Ok(Json(state.voice.participants(channel_id).await))
+}
+
+#[derive(Debug, Serialize)]
+pub struct JoinVoiceResponse {
+    pub relay_endpoint: String,
+    pub relay_token: Vec<u8>,
+    pub expires_at: DateTime<Utc>,
+    pub participants: Vec<VoiceParticipant>,
+}
+
+pub async fn join_channel(
+    State(state): State<Arc<AppState>>,
+    auth: AuthMember,
+    Path(channel_id): Path<Uuid>,
+) -> Result<Json<JoinVoiceResponse>> {
+    if state.relay.is_none() {
+        return Err(AppError::BadRequest(
+            "Voice is disabled on this server".into(),
+        ));
+    }
+
+    require_voice_channel(&state, channel_id).await?;
+
+    let perms = state
+        .db
+        .get_member_channel_permissions(auth.member_id, channel_id)
+        .await?;
+    if !permissions::has_permission(perms, permissions::VIEW_CHANNELS)
+        || !permissions::has_permission(perms, permissions::CONNECT)
+    {
+        return Err(AppError::Forbidden);
+    }
+    let can_speak = permissions::has_permission(perms, permissions::SPEAK);
+
+    let member = state
+        .db
+        .get_member(auth.member_id)
+        .await?
+        .ok_or(AppError::NotFound("Member not found".into()))?;
+
+    let participant = VoiceParticipant {
+        channel_id,
+        member_id: auth.member_id,
+        username: member.username,
+        can_speak,
+        self_muted: false,
+        self_deafened: false,
+        joined_at: Utc::now(),
+    };
+
+    let previous = state
+        .voice
+        .join(participant.clone(), state.config.voice.max_participants)
+        .await?;
+    if let Some(previous_channel) = previous {
+        announce_left(&state, previous_channel, auth.member_id).await;
+    }
+
+    broadcast_voice_event(
+        &state,
+        channel_id,
+        ServerMessage::VoiceParticipantJoined { data: participant },
+    )
+    .await;
+
+    let expires_at = Utc::now() + Duration::minutes(RELAY_TOKEN_TTL_MINUTES);
+    let relay_token = generate_relay_token(
+        &state.config.voice.token_secret,
+        channel_id,
+        auth.member_id,
+        can_speak,
+        expires_at,
+    );
+
+    Ok(Json(JoinVoiceResponse {
+        relay_endpoint: state.config.voice.public_endpoint(),
+        relay_token,
+        expires_at,
+        participants: state.voice.participants(channel_id).await,
+    }))
+}
+
+pub async fn leave_channel(
+    State(state): State<Arc<AppState>>,
+    auth: AuthMember,
+    Path(channel_id): Path<Uuid>,
+) -> Result<Json<()>> {
+    if state
+        .voice
+        .leave_if_joined_before(channel_id, auth.member_id, Utc::now())
+        .await
+    {
+        announce_left(&state, channel_id, auth.member_id).await;
+    }
+    Ok(Json(()))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateVoiceStateRequest {
+    pub self_muted: Option<bool>,
+    pub self_deafened: Option<bool>,
+}
+
+pub async fn update_voice_state(
+    State(state): State<Arc<AppState>>,
+    auth: AuthMember,
+    Path(channel_id): Path<Uuid>,
+    Json(req): Json<UpdateVoiceStateRequest>,
+) -> Result<Json<VoiceParticipant>> {
+    let participant = state
+        .voice
+        .update_state(
+            channel_id,
+            auth.member_id,
+            req.self_muted,
+            req.self_deafened,
+        )
+        .await
+        .ok_or(AppError::NotFound("Not in this voice channel".into()))?;
+
+    broadcast_voice_event(
+        &state,
+        channel_id,
+        ServerMessage::VoiceStateUpdated {
+            data: participant.clone(),
+        },
+    )
+    .await;
+
+    Ok(Json(participant))
+}
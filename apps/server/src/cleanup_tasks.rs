@@ -1,30 +1,44 @@
 use anyhow::Result;
 use async_trait::async_trait;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+use crate::db::Database;
+use crate::models::RetentionPolicy;
+use crate::storage::Storage;
+
+const RETENTION_BATCH_SIZE: i64 = 1000;
+const UPLOAD_BATCH_SIZE: i64 = 100;
+const ORPHANED_UPLOAD_GRACE_HOURS: i64 = 24;
 
 #[derive(Debug, Clone)]
-#[allow(dead_code)]
 pub struct CleanupStats {
     pub task_name: String,
     pub items_cleaned: usize,
     pub duration_ms: u64,
 }
 
+impl CleanupStats {
+    fn new(task_name: &str, items_cleaned: usize, started: Instant) -> Self {
+        Self {
+            task_name: task_name.to_string(),
+            items_cleaned,
+            duration_ms: started.elapsed().as_millis() as u64,
+        }
+    }
+}
+
 #[async_trait]
-#[allow(dead_code)]
 pub trait CleanupTask: Send + Sync {
     async fn run(&self) -> Result<CleanupStats>;
     fn interval(&self) -> Duration;
     fn name(&self) -> &'static str;
 }
 
-#[allow(dead_code)]
 pub struct CleanupScheduler {
     tasks: Vec<Arc<dyn CleanupTask>>,
 }
 
-#[allow(dead_code)]
 impl CleanupScheduler {
     pub fn new(tasks: Vec<Arc<dyn CleanupTask>>) -> Self {
         Self { tasks }
@@ -75,3 +89,186 @@ impl CleanupScheduler {
         }
     }
 }
+
+/// Purges messages older than the server's `message_retention`, honouring
+/// per-channel overrides. Pinned messages are kept.
+pub struct MessageRetentionTask {
+    db: Database,
+}
+
+impl MessageRetentionTask {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl CleanupTask for MessageRetentionTask {
+    async fn run(&self) -> Result<CleanupStats> {
+        let started = Instant::now();
+        let Some(identity) = self.db.get_server_identity().await? else {
+            return Ok(CleanupStats::new(self.name(), 0, started));
+        };
+
+        let server_policy =
+            RetentionPolicy::parse(&identity.message_retention).unwrap_or_else(|| {
+                tracing::warn!(
+                    "Invalid server message_retention {:?}, keeping messages",
+                    identity.message_retention
+                );
+                RetentionPolicy::Forever
+            });
+
+        let now = chrono::Utc::now();
+        let mut purged = 0u64;
+        for channel in self.db.get_all_channels().await? {
+            let policy = match channel.message_retention.as_deref() {
+                Some(value) => RetentionPolicy::parse(value).unwrap_or_else(|| {
+                    tracing::warn!(
+                        "Invalid message_retention {:?} on channel {}, using server default",
+                        value,
+                        channel.id
+                    );
+                    server_policy
+                }),
+                None => server_policy,
+            };
+            let Some(cutoff) = policy.cutoff(now) else {
+                continue;
+            };
+
+            loop {
+                let deleted = self
+                    .db
+                    .purge_channel_messages(channel.id, cutoff, RETENTION_BATCH_SIZE)
+                    .await?;
+                purged += deleted;
+                if deleted < RETENTION_BATCH_SIZE as u64 {
+                    break;
+                }
+            }
+        }
+
+        Ok(CleanupStats::new(self.name(), purged as usize, started))
+    }
+
+    fn interval(&self) -> Duration {
+        Duration::from_secs(60 * 60)
+    }
+
+    fn name(&self) -> &'static str {
+        "message_retention"
+    }
+}
+
+pub struct ExpiredSessionTask {
+    db: Database,
+}
+
+impl ExpiredSessionTask {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl CleanupTask for ExpiredSessionTask {
+    async fn run(&self) -> Result<CleanupStats> {
+        let started = Instant::now();
+        let deleted = self.db.delete_expired_sessions().await?;
+        Ok(CleanupStats::new(self.name(), deleted as usize, started))
+    }
+
+    fn interval(&self) -> Duration {
+        Duration::from_secs(60 * 60)
+    }
+
+    fn name(&self) -> &'static str {
+        "expired_sessions"
+    }
+}
+
+pub struct StaleInviteTask {
+    db: Database,
+}
+
+impl StaleInviteTask {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl CleanupTask for StaleInviteTask {
+    async fn run(&self) -> Result<CleanupStats> {
+        let started = Instant::now();
+        let deleted = self.db.delete_stale_invites().await?;
+        Ok(CleanupStats::new(self.name(), deleted as usize, started))
+    }
+
+    fn interval(&self) -> Duration {
+        Duration::from_secs(60 * 60)
+    }
+
+    fn name(&self) -> &'static str {
+        "stale_invites"
+    }
+}
+
+/// Removes uploads that never got attached to a message, or whose message was
+/// deleted, once they are older than a grace period.
+pub struct OrphanedUploadTask {
+    db: Database,
+    storage: Arc<dyn Storage>,
+}
+
+impl OrphanedUploadTask {
+    pub fn new(db: Database, storage: Arc<dyn Storage>) -> Self {
+        Self { db, storage }
+    }
+}
+
+#[async_trait]
+impl CleanupTask for OrphanedUploadTask {
+    async fn run(&self) -> Result<CleanupStats> {
+        let started = Instant::now();
+        let created_before =
+            chrono::Utc::now() - chrono::Duration::hours(ORPHANED_UPLOAD_GRACE_HOURS);
+        let mut removed = 0usize;
+
+        loop {
+            let uploads = self
+                .db
+                .get_orphaned_uploads(created_before, UPLOAD_BATCH_SIZE)
+                .await?;
+            let batch_len = uploads.len();
+            let mut failed = 0usize;
+
+            for upload in uploads {
+                if let Err(e) = self.storage.delete(&upload.storage_path).await {
+                    tracing::warn!("Failed to remove upload {}: {}", upload.id, e);
+                    failed += 1;
+                    continue;
+                }
+                self.db.delete_upload(upload.id).await?;
+                removed += 1;
+            }
+
+            // Stop on a short batch, or when every row failed so the same
+            // rows would just come back again.
+            if batch_len < UPLOAD_BATCH_SIZE as usize || failed == batch_len {
+                break;
+            }
+        }
+
+        Ok(CleanupStats::new(self.name(), removed, started))
+    }
+
+    fn interval(&self) -> Duration {
+        Duration::from_secs(60 * 60)
+    }
+
+    fn name(&self) -> &'static str {
+        "orphaned_uploads"
+    }
+}
@@ -1,4 +1,5 @@
 use std::env;
+use std::path::PathBuf;
 
 #[derive(Debug, Clone)]
 pub struct Config {
@@ -6,6 +7,9 @@ pub struct Config {
     pub database: DatabaseConfig,
     pub redis: RedisConfig,
     pub security: SecurityConfig,
+    pub auth: AuthConfig,
+    pub storage: StorageConfig,
+    pub voice: VoiceConfig,
 }
 
 #[derive(Debug, Clone)]
@@ -61,6 +65,133 @@ pub struct RedisConfig {
     pub url: String,
 }
 
+#[derive(Debug, Clone)]
+pub struct AuthConfig {
+    pub access_token_expiry_minutes: u64,
+    pub refresh_token_expiry_days: u64,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            access_token_expiry_minutes: 15,
+            refresh_token_expiry_days: 30,
+        }
+    }
+}
+
+/// Where uploaded attachments are stored.
+#[derive(Debug, Clone)]
+pub enum StorageConfig {
+    Local { path: PathBuf },
+    S3(S3Config),
+}
+
+#[derive(Debug, Clone)]
+pub struct S3Config {
+    pub endpoint: String,
+    pub region: String,
+    pub bucket: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub force_path_style: bool,
+}
+
+impl StorageConfig {
+    pub fn load() -> anyhow::Result<Self> {
+        let backend = env::var("STORAGE_BACKEND").unwrap_or_else(|_| "local".to_string());
+        match backend.as_str() {
+            "local" => Ok(Self::Local {
+                path: env::var("STORAGE_LOCAL_PATH")
+                    .unwrap_or_else(|_| "uploads".to_string())
+                    .into(),
+            }),
+            "s3" => {
+                let required = |name: &str| {
+                    env::var(name).map_err(|_| {
+                        anyhow::anyhow!("{} must be set when STORAGE_BACKEND=s3", name)
+                    })
+                };
+                Ok(Self::S3(S3Config {
+                    endpoint: required("S3_ENDPOINT")?,
+                    region: required("S3_REGION")?,
+                    bucket: required("S3_BUCKET")?,
+                    access_key_id: required("S3_ACCESS_KEY_ID")?,
+                    secret_access_key: required("S3_SECRET_ACCESS_KEY")?,
+                    force_path_style: env::var("S3_FORCE_PATH_STYLE")
+                        .map(|v| v.parse().unwrap_or(false))
+                        .unwrap_or(false),
+                }))
+            }
+            other => anyhow::bail!(
+                "Unknown STORAGE_BACKEND '{}', expected 'local' or 's3'",
+                other
+            ),
+        }
+    }
+}
+
+/// The embedded QUIC media relay used by voice channels.
+#[derive(Debug, Clone)]
+pub struct VoiceConfig {
+    pub enabled: bool,
+    pub bind_host: String,
+    pub bind_port: u16,
+    /// Host clients dial; defaults to the host part of the public domain.
+    pub public_host: String,
+    pub token_secret: String,
+    pub cert_path: Option<String>,
+    pub key_path: Option<String>,
+    pub max_participants: usize,
+}
+
+impl VoiceConfig {
+    pub fn load(public_domain: &str) -> Self {
+        let bind_port = env::var("VOICE_RELAY_BIND_PORT")
+            .ok()
+            .and_then(|p| p.parse().ok())
+            .unwrap_or(10000);
+
+        // Tokens only live for minutes and the relay runs in-process, so a
+        // per-boot secret is fine when none is configured. Compose passes an
+        // unset variable through as an empty string, which counts as unset.
+        let token_secret = env::var("VOICE_RELAY_TOKEN_SECRET")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| {
+                use rand::RngCore;
+                let mut bytes = [0u8; 32];
+                rand::thread_rng().fill_bytes(&mut bytes);
+                hex::encode(bytes)
+            });
+
+        Self {
+            enabled: env::var("VOICE_ENABLED")
+                .map(|v| v.parse().unwrap_or(true))
+                .unwrap_or(true),
+            bind_host: env::var("VOICE_RELAY_BIND_HOST").unwrap_or_else(|_| "0.0.0.0".to_string()),
+            bind_port,
+            public_host: env::var("VOICE_RELAY_PUBLIC_HOST").unwrap_or_else(|_| {
+                public_domain
+                    .rsplit_once(':')
+                    .map_or(public_domain, |(host, _)| host)
+                    .to_string()
+            }),
+            token_secret,
+            cert_path: env::var("VOICE_RELAY_CERT_PATH").ok(),
+            key_path: env::var("VOICE_RELAY_KEY_PATH").ok(),
+            max_participants: env::var("VOICE_MAX_PARTICIPANTS")
+                .ok()
+                .and_then(|n| n.parse().ok())
+                .unwrap_or(25),
+        }
+    }
+
+    pub fn public_endpoint(&self) -> String {
+        format!("{}:{}", self.public_host, self.bind_port)
+    }
+}
+
 fn default_max_connections() -> u32 {
     let cores = num_cpus::get() as u32;
     (cores * 2 + 5).clamp(10, 50)
@@ -126,6 +257,7 @@ impl Config {
         };
         let mut database = DatabaseConfig::default();
         let mut redis = RedisConfig::default();
+        let mut auth = AuthConfig::default();
 
         if let Ok(url) = env::var("CENTRAL_API_URL") {
             server.central_url = url;
@@ -163,6 +295,18 @@ impl Config {
             server.allowed_origins = origins.split(',').map(|s| s.trim().to_string()).collect();
         }
 
+        if let Ok(minutes) = env::var("AUTH_ACCESS_TOKEN_EXPIRY_MINUTES") {
+            if let Ok(m) = minutes.parse() {
+                auth.access_token_expiry_minutes = m;
+            }
+        }
+
+        if let Ok(days) = env::var("AUTH_REFRESH_TOKEN_EXPIRY_DAYS") {
+            if let Ok(d) = days.parse() {
+                auth.refresh_token_expiry_days = d;
+            }
+        }
+
         if database.url.is_empty() {
             anyhow::bail!("DATABASE_URL environment variable must be set");
         }
@@ -172,12 +316,17 @@ impl Config {
         }
 
         let security = SecurityConfig::load()?;
+        let storage = StorageConfig::load()?;
+        let voice = VoiceConfig::load(&server.public_domain);
 
         let config = Config {
             server,
             database,
             redis,
             security,
+            auth,
+            storage,
+            voice,
         };
 
         tracing::info!(
@@ -0,0 +1,78 @@
+use serde_json::Value as JsonValue;
+use uuid::Uuid;
+
+use crate::error::Result;
+use crate::models::{AuditAction, AuditLogEntry};
+
+use super::Database;
+
+#[derive(Debug, Clone, Default)]
+pub struct AuditLogFilter {
+    pub action: Option<AuditAction>,
+    pub actor_id: Option<Uuid>,
+    pub target_id: Option<Uuid>,
+    /// Only entries older than this one. Entries are ordered by
+    /// `(created_at, id)` so rows sharing a timestamp are not skipped.
+    pub before: Option<Uuid>,
+}
+
+/// Entries newest first, with `has_more` set when older ones remain.
+pub struct AuditLogPage {
+    pub entries: Vec<AuditLogEntry>,
+    pub has_more: bool,
+}
+
+impl Database {
+    pub async fn append_audit_log(
+        &self,
+        actor_id: Uuid,
+        action: AuditAction,
+        target_id: Option<Uuid>,
+        before_state: Option<JsonValue>,
+        after_state: Option<JsonValue>,
+        reason: Option<String>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO audit_log (actor_id, action, target_id, before_state, after_state, reason)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+        )
+        .bind(actor_id)
+        .bind(action)
+        .bind(target_id)
+        .bind(before_state)
+        .bind(after_state)
+        .bind(reason)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn get_audit_log(&self, filter: AuditLogFilter, limit: i64) -> Result<AuditLogPage> {
+        let mut entries = sqlx::query_as::<_, AuditLogEntry>(
+            r#"
+            SELECT * FROM audit_log
+            WHERE ($1::text IS NULL OR action = $1)
+              AND ($2::uuid IS NULL OR actor_id = $2)
+              AND ($3::uuid IS NULL OR target_id = $3)
+              AND ($4::uuid IS NULL OR (created_at, id) < (
+                  SELECT created_at, id FROM audit_log WHERE id = $4
+              ))
+            ORDER BY created_at DESC, id DESC
+            LIMIT $5
+            "#,
+        )
+        .bind(filter.action)
+        .bind(filter.actor_id)
+        .bind(filter.target_id)
+        .bind(filter.before)
+        .bind(limit + 1)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let has_more = entries.len() as i64 > limit;
+        entries.truncate(limit as usize);
+        Ok(AuditLogPage { entries, has_more })
+    }
+}
@@ -1,7 +1,10 @@
 use uuid::Uuid;
 
 use crate::error::Result;
-use crate::models::{Category, ChannelPermissionOverride, Invite, MemberChannelKey, TextChannel};
+use crate::models::{
+    permissions, Category, ChannelPermissionOverride, ChannelType, Invite, MemberChannelKey,
+    TextChannel,
+};
 
 use super::Database;
 
@@ -68,11 +71,12 @@ impl Database {
         name: String,
         description: Option<String>,
         position: i32,
+        channel_type: ChannelType,
     ) -> Result<TextChannel> {
         let channel = sqlx::query_as::<_, TextChannel>(
             r#"
-            INSERT INTO text_channels (category_id, name, description, position)
-            VALUES ($1, $2, $3, $4)
+            INSERT INTO text_channels (category_id, name, description, position, channel_type)
+            VALUES ($1, $2, $3, $4, $5)
             RETURNING *
             "#,
         )
@@ -80,6 +84,7 @@ impl Database {
         .bind(name)
         .bind(description)
         .bind(position)
+        .bind(channel_type)
         .fetch_one(&self.pool)
         .await?;
         Ok(channel)
@@ -118,6 +123,7 @@ impl Database {
         description: Option<String>,
         category_id: Option<Option<Uuid>>,
         position: Option<i32>,
+        message_retention: Option<Option<String>>,
     ) -> Result<()> {
         let mut query = String::from("UPDATE text_channels SET ");
         let mut params: Vec<String> = vec![];
@@ -139,6 +145,10 @@ impl Database {
             params.push(format!("position = ${}", param_count));
             param_count += 1;
         }
+        if message_retention.is_some() {
+            params.push(format!("message_retention = ${}", param_count));
+            param_count += 1;
+        }
 
         if params.is_empty() {
             return Ok(());
@@ -161,6 +171,9 @@ impl Database {
         if let Some(p) = position {
             q = q.bind(p);
         }
+        if let Some(r) = message_retention {
+            q = q.bind(r);
+        }
 
         q = q.bind(channel_id);
         q.execute(&self.pool).await?;
@@ -230,6 +243,20 @@ impl Database {
         Ok(invites)
     }
 
+    /// Deletes invites that have expired or reached their use limit.
+    pub async fn delete_stale_invites(&self) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM invites
+            WHERE (expires_at IS NOT NULL AND expires_at <= NOW())
+               OR (max_uses IS NOT NULL AND uses >= max_uses)
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
     pub async fn delete_invite(&self, invite_id: Uuid) -> Result<()> {
         sqlx::query("DELETE FROM invites WHERE id = $1")
             .bind(invite_id)
@@ -332,6 +359,54 @@ impl Database {
         Ok(())
     }
 
+    /// The member's permissions in `channel_id` after role and member
+    /// overrides are applied.
+    pub async fn get_member_channel_permissions(
+        &self,
+        member_id: Uuid,
+        channel_id: Uuid,
+    ) -> Result<i64> {
+        let base = self.get_member_permissions(member_id).await?;
+        if base & permissions::ADMINISTRATOR != 0 {
+            return Ok(base);
+        }
+
+        let (role_allow, role_deny, member_allow, member_deny): (i64, i64, i64, i64) =
+            sqlx::query_as(
+                r#"
+                SELECT
+                    COALESCE(
+                        (SELECT BIT_OR(cpo.allow_permissions) FROM channel_permission_overrides cpo
+                         INNER JOIN member_roles mr ON cpo.role_id = mr.role_id
+                         WHERE cpo.channel_id = $1 AND mr.member_id = $2),
+                        0
+                    ),
+                    COALESCE(
+                        (SELECT BIT_OR(cpo.deny_permissions) FROM channel_permission_overrides cpo
+                         INNER JOIN member_roles mr ON cpo.role_id = mr.role_id
+                         WHERE cpo.channel_id = $1 AND mr.member_id = $2),
+                        0
+                    ),
+                    COALESCE(
+                        (SELECT allow_permissions FROM channel_permission_overrides
+                         WHERE channel_id = $1 AND member_id = $2),
+                        0
+                    ),
+                    COALESCE(
+                        (SELECT deny_permissions FROM channel_permission_overrides
+                         WHERE channel_id = $1 AND member_id = $2),
+                        0
+                    )
+                "#,
+            )
+            .bind(channel_id)
+            .bind(member_id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok((base | role_allow | member_allow) & !role_deny & !member_deny)
+    }
+
     pub async fn set_channel_permission_override(
         &self,
         channel_id: Uuid,
@@ -76,6 +76,41 @@ impl Database {
         Ok(())
     }
 
+    pub async fn update_member_username(&self, member_id: Uuid, username: &str) -> Result<Member> {
+        let member = sqlx::query_as::<_, Member>(
+            "UPDATE members SET username = $2 WHERE id = $1 RETURNING *",
+        )
+        .bind(member_id)
+        .bind(username)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(member)
+    }
+
+    /// Copies the member's username and public keys from Central.
+    pub async fn update_member_identity(
+        &self,
+        member_id: Uuid,
+        username: &str,
+        kem_public_key: &[u8],
+        dsa_public_key: &[u8],
+    ) -> Result<Member> {
+        let member = sqlx::query_as::<_, Member>(
+            r#"
+            UPDATE members SET username = $2, kem_public_key = $3, dsa_public_key = $4
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(member_id)
+        .bind(username)
+        .bind(kem_public_key)
+        .bind(dsa_public_key)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(member)
+    }
+
     pub async fn delete_member(&self, member_id: Uuid) -> Result<()> {
         sqlx::query("DELETE FROM members WHERE id = $1")
             .bind(member_id)
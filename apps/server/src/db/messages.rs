@@ -1,8 +1,9 @@
+use sqlx::postgres::PgRow;
 use sqlx::Row;
 use uuid::Uuid;
 
 use crate::error::Result;
-use crate::models::{Member, Message, Upload};
+use crate::models::{Member, Message, MessageReaction, Upload};
 
 use super::Database;
 
@@ -89,6 +90,7 @@ impl Database {
                 SELECT
                     m.id as msg_id, m.channel_id, m.sender_id, m.encrypted_content,
                     m.signature, m.reply_to_id, m.created_at as msg_created_at,
+                    m.updated_at as msg_updated_at, m.pinned_at, m.pinned_by,
                     mem.id as mem_id, mem.central_user_id, mem.username, mem.kem_public_key,
                     mem.dsa_public_key, mem.display_name, mem.avatar_url,
                     mem.joined_at
@@ -108,6 +110,7 @@ impl Database {
                 SELECT
                     m.id as msg_id, m.channel_id, m.sender_id, m.encrypted_content,
                     m.signature, m.reply_to_id, m.created_at as msg_created_at,
+                    m.updated_at as msg_updated_at, m.pinned_at, m.pinned_by,
                     mem.id as mem_id, mem.central_user_id, mem.username, mem.kem_public_key,
                     mem.dsa_public_key, mem.display_name, mem.avatar_url,
                     mem.joined_at
@@ -124,33 +127,155 @@ impl Database {
 
         let rows = query.fetch_all(&self.pool).await?;
 
-        let mut results = Vec::with_capacity(rows.len());
-        for row in rows {
-            let message = Message {
-                id: row.try_get("msg_id")?,
-                channel_id: row.try_get("channel_id")?,
-                sender_id: row.try_get("sender_id")?,
-                encrypted_content: row.try_get("encrypted_content")?,
-                signature: row.try_get("signature")?,
-                reply_to_id: row.try_get("reply_to_id")?,
-                created_at: row.try_get("msg_created_at")?,
-            };
-
-            let member = Member {
-                id: row.try_get("mem_id")?,
-                central_user_id: row.try_get("central_user_id")?,
-                username: row.try_get("username")?,
-                kem_public_key: row.try_get("kem_public_key")?,
-                dsa_public_key: row.try_get("dsa_public_key")?,
-                display_name: row.try_get("display_name")?,
-                avatar_url: row.try_get("avatar_url")?,
-                joined_at: row.try_get("joined_at")?,
-            };
-
-            results.push((message, member));
-        }
-
-        Ok(results)
+        rows.iter().map(message_with_sender).collect()
+    }
+
+    pub async fn get_pinned_messages_with_senders(
+        &self,
+        channel_id: Uuid,
+    ) -> Result<Vec<(Message, Member)>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT
+                m.id as msg_id, m.channel_id, m.sender_id, m.encrypted_content,
+                m.signature, m.reply_to_id, m.created_at as msg_created_at,
+                m.updated_at as msg_updated_at, m.pinned_at, m.pinned_by,
+                mem.id as mem_id, mem.central_user_id, mem.username, mem.kem_public_key,
+                mem.dsa_public_key, mem.display_name, mem.avatar_url,
+                mem.joined_at
+            FROM messages m
+            INNER JOIN members mem ON m.sender_id = mem.id
+            WHERE m.channel_id = $1 AND m.pinned_at IS NOT NULL
+            ORDER BY m.pinned_at DESC
+            "#,
+        )
+        .bind(channel_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(message_with_sender).collect()
+    }
+
+    pub async fn update_message_content(
+        &self,
+        message_id: Uuid,
+        encrypted_content: Vec<u8>,
+        signature: Vec<u8>,
+    ) -> Result<Message> {
+        let message = sqlx::query_as::<_, Message>(
+            r#"
+            UPDATE messages
+            SET encrypted_content = $2, signature = $3, updated_at = NOW()
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(message_id)
+        .bind(encrypted_content)
+        .bind(signature)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(message)
+    }
+
+    pub async fn set_message_pinned(
+        &self,
+        message_id: Uuid,
+        pinned_by: Option<Uuid>,
+    ) -> Result<Message> {
+        let message = sqlx::query_as::<_, Message>(
+            r#"
+            UPDATE messages
+            SET pinned_at = CASE WHEN $2::uuid IS NULL THEN NULL ELSE NOW() END,
+                pinned_by = $2
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(message_id)
+        .bind(pinned_by)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(message)
+    }
+
+    pub async fn count_pinned_messages(&self, channel_id: Uuid) -> Result<i64> {
+        let (count,): (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM messages WHERE channel_id = $1 AND pinned_at IS NOT NULL",
+        )
+        .bind(channel_id)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(count)
+    }
+
+    /// Returns `None` if the member already reacted with this emoji.
+    pub async fn add_reaction(
+        &self,
+        message_id: Uuid,
+        member_id: Uuid,
+        emoji: &str,
+    ) -> Result<Option<MessageReaction>> {
+        let reaction = sqlx::query_as::<_, MessageReaction>(
+            r#"
+            INSERT INTO message_reactions (message_id, member_id, emoji)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (message_id, member_id, emoji) DO NOTHING
+            RETURNING *
+            "#,
+        )
+        .bind(message_id)
+        .bind(member_id)
+        .bind(emoji)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(reaction)
+    }
+
+    pub async fn remove_reaction(
+        &self,
+        message_id: Uuid,
+        member_id: Uuid,
+        emoji: &str,
+    ) -> Result<bool> {
+        let result = sqlx::query(
+            "DELETE FROM message_reactions WHERE message_id = $1 AND member_id = $2 AND emoji = $3",
+        )
+        .bind(message_id)
+        .bind(member_id)
+        .bind(emoji)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Counts the different emojis on a message, not counting `emoji` itself.
+    pub async fn count_other_reaction_emojis(&self, message_id: Uuid, emoji: &str) -> Result<i64> {
+        let (count,): (i64,) = sqlx::query_as(
+            "SELECT COUNT(DISTINCT emoji) FROM message_reactions WHERE message_id = $1 AND emoji <> $2",
+        )
+        .bind(message_id)
+        .bind(emoji)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(count)
+    }
+
+    pub async fn get_reactions_for_messages(
+        &self,
+        message_ids: &[Uuid],
+    ) -> Result<Vec<MessageReaction>> {
+        let reactions = sqlx::query_as::<_, MessageReaction>(
+            r#"
+            SELECT * FROM message_reactions
+            WHERE message_id = ANY($1)
+            ORDER BY created_at ASC
+            "#,
+        )
+        .bind(message_ids)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(reactions)
     }
 
     pub async fn delete_message(&self, message_id: Uuid) -> Result<()> {
@@ -161,9 +286,36 @@ impl Database {
         Ok(())
     }
 
+    /// Deletes up to `limit` unpinned messages in `channel_id` created before
+    /// `before`. Callers loop until fewer than `limit` rows come back.
+    pub async fn purge_channel_messages(
+        &self,
+        channel_id: Uuid,
+        before: chrono::DateTime<chrono::Utc>,
+        limit: i64,
+    ) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM messages
+            WHERE id IN (
+                SELECT id FROM messages
+                WHERE channel_id = $1 AND created_at < $2 AND pinned_at IS NULL
+                LIMIT $3
+            )
+            "#,
+        )
+        .bind(channel_id)
+        .bind(before)
+        .bind(limit)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
     pub async fn create_upload(
         &self,
         uploader_id: Uuid,
+        channel_id: Uuid,
         filename: String,
         content_type: String,
         file_size: i64,
@@ -171,12 +323,13 @@ impl Database {
     ) -> Result<Upload> {
         let upload = sqlx::query_as::<_, Upload>(
             r#"
-            INSERT INTO uploads (uploader_id, filename, content_type, file_size, storage_path)
-            VALUES ($1, $2, $3, $4, $5)
+            INSERT INTO uploads (uploader_id, channel_id, filename, content_type, file_size, storage_path)
+            VALUES ($1, $2, $3, $4, $5, $6)
             RETURNING *
             "#,
         )
         .bind(uploader_id)
+        .bind(channel_id)
         .bind(filename)
         .bind(content_type)
         .bind(file_size)
@@ -194,6 +347,81 @@ impl Database {
         Ok(upload)
     }
 
+    /// Counts uploads in `upload_ids` that `uploader_id` made for `channel_id`
+    /// and that are not yet attached to a message.
+    pub async fn count_pending_uploads(
+        &self,
+        upload_ids: &[Uuid],
+        uploader_id: Uuid,
+        channel_id: Uuid,
+    ) -> Result<i64> {
+        let count: (i64,) = sqlx::query_as(
+            r#"
+            SELECT COUNT(*) FROM uploads
+            WHERE id = ANY($1) AND uploader_id = $2 AND channel_id = $3 AND message_id IS NULL
+            "#,
+        )
+        .bind(upload_ids)
+        .bind(uploader_id)
+        .bind(channel_id)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(count.0)
+    }
+
+    pub async fn attach_uploads(
+        &self,
+        message_id: Uuid,
+        upload_ids: &[Uuid],
+        uploader_id: Uuid,
+    ) -> Result<Vec<Upload>> {
+        let uploads = sqlx::query_as::<_, Upload>(
+            r#"
+            UPDATE uploads SET message_id = $1
+            WHERE id = ANY($2) AND uploader_id = $3 AND message_id IS NULL
+            RETURNING *
+            "#,
+        )
+        .bind(message_id)
+        .bind(upload_ids)
+        .bind(uploader_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(uploads)
+    }
+
+    pub async fn get_uploads_for_messages(&self, message_ids: &[Uuid]) -> Result<Vec<Upload>> {
+        let uploads = sqlx::query_as::<_, Upload>(
+            "SELECT * FROM uploads WHERE message_id = ANY($1) ORDER BY created_at ASC",
+        )
+        .bind(message_ids)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(uploads)
+    }
+
+    /// Uploads that were never attached to a message, or whose message has
+    /// since been deleted.
+    pub async fn get_orphaned_uploads(
+        &self,
+        created_before: chrono::DateTime<chrono::Utc>,
+        limit: i64,
+    ) -> Result<Vec<Upload>> {
+        let uploads = sqlx::query_as::<_, Upload>(
+            r#"
+            SELECT * FROM uploads
+            WHERE message_id IS NULL AND created_at < $1
+            ORDER BY created_at ASC
+            LIMIT $2
+            "#,
+        )
+        .bind(created_before)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(uploads)
+    }
+
     pub async fn delete_upload(&self, upload_id: Uuid) -> Result<()> {
         sqlx::query("DELETE FROM uploads WHERE id = $1")
             .bind(upload_id)
@@ -202,3 +430,31 @@ impl Database {
         Ok(())
     }
 }
+
+fn message_with_sender(row: &PgRow) -> Result<(Message, Member)> {
+    let message = Message {
+        id: row.try_get("msg_id")?,
+        channel_id: row.try_get("channel_id")?,
+        sender_id: row.try_get("sender_id")?,
+        encrypted_content: row.try_get("encrypted_content")?,
+        signature: row.try_get("signature")?,
+        reply_to_id: row.try_get("reply_to_id")?,
+        created_at: row.try_get("msg_created_at")?,
+        updated_at: row.try_get("msg_updated_at")?,
+        pinned_at: row.try_get("pinned_at")?,
+        pinned_by: row.try_get("pinned_by")?,
+    };
+
+    let member = Member {
+        id: row.try_get("mem_id")?,
+        central_user_id: row.try_get("central_user_id")?,
+        username: row.try_get("username")?,
+        kem_public_key: row.try_get("kem_public_key")?,
+        dsa_public_key: row.try_get("dsa_public_key")?,
+        display_name: row.try_get("display_name")?,
+        avatar_url: row.try_get("avatar_url")?,
+        joined_at: row.try_get("joined_at")?,
+    };
+
+    Ok((message, member))
+}
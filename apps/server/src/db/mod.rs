@@ -1,3 +1,4 @@
+mod audit_log;
 pub mod cache;
 pub mod cache_lock;
 mod channels;
@@ -7,6 +8,8 @@ mod roles;
 mod server_identity;
 mod sessions;
 
+pub use audit_log::AuditLogFilter;
+
 use redis::Client as RedisClient;
 use sqlx::PgPool;
 
@@ -30,7 +33,7 @@ impl Database {
     }
 
     pub async fn delete_all_data(&self) -> crate::error::Result<()> {
-        sqlx::query("TRUNCATE TABLE messages, member_roles, roles, sessions, members, categories, channels, bans CASCADE")
+        sqlx::query("TRUNCATE TABLE audit_log, messages, member_roles, roles, sessions, members, categories, channels, bans CASCADE")
             .execute(&self.pool)
             .await?;
 
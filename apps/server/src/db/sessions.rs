@@ -1,38 +1,181 @@
 use chrono::{DateTime, Utc};
+use sqlx::Row;
 use uuid::Uuid;
 
 use crate::error::Result;
-use crate::models::Session;
+use crate::models::{RefreshOutcome, Session, SessionTokenHashes};
 
 use super::Database;
 
+/// A client whose refresh response was lost (timeout, crash, app killed) will
+/// retry with the token it already spent. For this long after the rotation,
+/// retrying the session's most recently spent token issues a fresh pair
+/// instead of counting as reuse. The trade-off is that a thief holding that
+/// token gets the same window.
+const REFRESH_RETRY_GRACE_SECONDS: i64 = 30;
+
+async fn is_refresh_retry(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    session_id: Uuid,
+    used_at: DateTime<Utc>,
+) -> Result<bool> {
+    if used_at <= Utc::now() - chrono::Duration::seconds(REFRESH_RETRY_GRACE_SECONDS) {
+        return Ok(false);
+    }
+    let spent_later = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS (SELECT 1 FROM refresh_tokens WHERE session_id = $1 AND used_at > $2)",
+    )
+    .bind(session_id)
+    .bind(used_at)
+    .fetch_one(&mut **tx)
+    .await?;
+    Ok(!spent_later)
+}
+
 impl Database {
     pub async fn create_session(
         &self,
         member_id: Uuid,
-        token_hash: Vec<u8>,
-        expires_at: DateTime<Utc>,
+        tokens: &SessionTokenHashes,
     ) -> Result<Session> {
+        let mut tx = self.pool.begin().await?;
+
         let session = sqlx::query_as::<_, Session>(
             r#"
-            INSERT INTO sessions (member_id, token_hash, expires_at)
-            VALUES ($1, $2, $3)
+            INSERT INTO sessions (member_id, token_hash, access_expires_at, expires_at)
+            VALUES ($1, $2, $3, $4)
             RETURNING *
             "#,
         )
         .bind(member_id)
-        .bind(&token_hash)
-        .bind(expires_at)
-        .fetch_one(&self.pool)
+        .bind(&tokens.access_token_hash)
+        .bind(tokens.access_expires_at)
+        .bind(tokens.expires_at)
+        .fetch_one(&mut *tx)
         .await?;
 
-        let cache_key = format!("session:{}", hex::encode(&token_hash));
-        let serialized = serde_json::to_string(&session)
+        sqlx::query("INSERT INTO refresh_tokens (session_id, token_hash) VALUES ($1, $2)")
+            .bind(session.id)
+            .bind(&tokens.refresh_token_hash)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        self.cache_session(&session).await?;
+        Ok(session)
+    }
+
+    async fn cache_session(&self, session: &Session) -> Result<()> {
+        let cache_key = format!("session:{}", hex::encode(&session.token_hash));
+        let serialized = serde_json::to_string(session)
             .map_err(|e| crate::error::AppError::Internal(e.to_string()))?;
         let mut conn = self.redis_conn().await?;
         let _: () = redis::AsyncCommands::set_ex(&mut conn, &cache_key, serialized, 3600).await?;
+        Ok(())
+    }
 
-        Ok(session)
+    /// Swaps a refresh token for the pair in `tokens`. Refresh tokens are
+    /// single-use; replaying one that was already exchanged revokes the
+    /// session it belongs to, unless it is a prompt retry of the latest
+    /// token (see `REFRESH_RETRY_GRACE_SECONDS`).
+    pub async fn rotate_refresh_token(
+        &self,
+        refresh_token_hash: &[u8],
+        tokens: &SessionTokenHashes,
+    ) -> Result<RefreshOutcome> {
+        let mut tx = self.pool.begin().await?;
+
+        let row = sqlx::query(
+            r#"
+            SELECT rt.id, rt.used_at, s.id AS session_id, s.member_id, s.token_hash, s.expires_at
+            FROM refresh_tokens rt
+            JOIN sessions s ON s.id = rt.session_id
+            WHERE rt.token_hash = $1
+            FOR UPDATE OF rt, s
+            "#,
+        )
+        .bind(refresh_token_hash)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(row) = row else {
+            return Ok(RefreshOutcome::Invalid);
+        };
+        let session_id: Uuid = row.get("session_id");
+        let member_id: Uuid = row.get("member_id");
+        let old_token_hash: Vec<u8> = row.get("token_hash");
+
+        let used_at: Option<DateTime<Utc>> = row.get("used_at");
+        let is_retry = match used_at {
+            Some(used_at) => is_refresh_retry(&mut tx, session_id, used_at).await?,
+            None => false,
+        };
+
+        if used_at.is_some() && !is_retry {
+            sqlx::query("DELETE FROM sessions WHERE id = $1")
+                .bind(session_id)
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+
+            self.invalidate_session_cache(&old_token_hash).await?;
+            return Ok(RefreshOutcome::Reused { member_id });
+        }
+
+        if row.get::<DateTime<Utc>, _>("expires_at") <= Utc::now() {
+            return Ok(RefreshOutcome::Invalid);
+        }
+
+        if is_retry {
+            // The client never received the pair issued last time; drop it.
+            sqlx::query("DELETE FROM refresh_tokens WHERE session_id = $1 AND used_at IS NULL")
+                .bind(session_id)
+                .execute(&mut *tx)
+                .await?;
+        } else {
+            sqlx::query("UPDATE refresh_tokens SET used_at = NOW() WHERE id = $1")
+                .bind(row.get::<Uuid, _>("id"))
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        sqlx::query("INSERT INTO refresh_tokens (session_id, token_hash) VALUES ($1, $2)")
+            .bind(session_id)
+            .bind(&tokens.refresh_token_hash)
+            .execute(&mut *tx)
+            .await?;
+
+        let session = sqlx::query_as::<_, Session>(
+            r#"
+            UPDATE sessions
+            SET token_hash = $2, access_expires_at = $3, expires_at = $4
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(session_id)
+        .bind(&tokens.access_token_hash)
+        .bind(tokens.access_expires_at)
+        .bind(tokens.expires_at)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        self.invalidate_session_cache(&old_token_hash).await?;
+        self.cache_session(&session).await?;
+        Ok(RefreshOutcome::Rotated)
+    }
+
+    async fn invalidate_session_cache(&self, token_hash: &[u8]) -> Result<()> {
+        let cache_key = format!("session:{}", hex::encode(token_hash));
+        let mut conn = self.redis_conn().await?;
+        let _: () = redis::cmd("DEL")
+            .arg(&cache_key)
+            .query_async(&mut conn)
+            .await?;
+        Ok(())
     }
 
     async fn get_session_by_token_uncached(&self, token_hash: &[u8]) -> Result<Option<Session>> {
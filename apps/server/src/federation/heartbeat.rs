@@ -9,6 +9,8 @@ use uuid::Uuid;
 use crate::config::Config;
 use crate::db::Database;
 
+use super::identity::server_keypair;
+
 #[derive(Debug, Serialize)]
 struct HeartbeatRequest {
     server_id: Uuid,
@@ -78,18 +80,7 @@ impl HeartbeatService {
 
         let message_data = format!("{}:{}:{}:{}", server_id, member_count, timestamp, nonce);
 
-        use confide_sdk::crypto::keys::DsaKeyPair;
-        use confide_sdk::decrypt_aes_gcm;
-
-        let encryption_key = self.config.security.dsa_encryption_key;
-
-        let private_bytes = decrypt_aes_gcm(&encryption_key, &identity.dsa_private_key_encrypted)
-            .map_err(|e| format!("Failed to decrypt private key: {}", e))?;
-
-        let keypair = DsaKeyPair::from_bytes(&identity.dsa_public_key, &private_bytes)
-            .map_err(|e| format!("Invalid keypair: {}", e))?;
-
-        let signature = keypair
+        let signature = server_keypair(&identity, &self.config)?
             .sign(message_data.as_bytes())
             .map_err(|e| format!("Failed to sign: {}", e))?;
 
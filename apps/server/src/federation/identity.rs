@@ -1,8 +1,15 @@
+use chrono::Utc;
+use confide_sdk::crypto::keys::DsaKeyPair;
+use confide_sdk::decrypt_aes_gcm;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use uuid::Uuid;
 
+use crate::config::Config;
+use crate::db::Database;
+use crate::models::ServerIdentity;
+
 #[derive(Debug, Serialize)]
 struct VerifyTokenRequest {
     server_id: Uuid,
@@ -62,3 +69,82 @@ pub async fn verify_federation_token(
         Ok(None)
     }
 }
+
+#[derive(Debug, Serialize)]
+struct UserInfoRequest {
+    server_id: Uuid,
+    timestamp: i64,
+    nonce: Uuid,
+    signature: Vec<u8>,
+}
+
+/// Decrypts this server's identity key, which signs requests to Central.
+pub(super) fn server_keypair(
+    identity: &ServerIdentity,
+    config: &Config,
+) -> Result<DsaKeyPair, String> {
+    let private_bytes = decrypt_aes_gcm(
+        &config.security.dsa_encryption_key,
+        &identity.dsa_private_key_encrypted,
+    )
+    .map_err(|e| format!("Failed to decrypt private key: {}", e))?;
+
+    DsaKeyPair::from_bytes(&identity.dsa_public_key, &private_bytes)
+        .map_err(|e| format!("Invalid keypair: {}", e))
+}
+
+/// Fetches a user's current public identity from Central. Central only
+/// answers registered servers, so the request is signed like a heartbeat.
+pub async fn fetch_central_user(
+    client: &Client,
+    db: &Database,
+    config: &Config,
+    user_id: Uuid,
+) -> Result<FederationUserInfo, String> {
+    let identity = db
+        .get_server_identity()
+        .await
+        .map_err(|e| format!("DB error: {}", e))?
+        .ok_or("Server not setup")?;
+
+    let server_id = identity
+        .central_registration_id
+        .ok_or("Server not registered with Central")?;
+
+    let timestamp = Utc::now().timestamp();
+    let nonce = Uuid::new_v4();
+    let message_data = format!(
+        "user_info:{}:{}:{}:{}",
+        server_id, user_id, timestamp, nonce
+    );
+
+    let signature = server_keypair(&identity, config)?
+        .sign(message_data.as_bytes())
+        .map_err(|e| format!("Failed to sign: {}", e))?;
+
+    let request = UserInfoRequest {
+        server_id,
+        timestamp,
+        nonce,
+        signature,
+    };
+
+    let response = client
+        .post(format!(
+            "{}/federation/users/{}",
+            config.server.central_url, user_id
+        ))
+        .json(&request)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to contact Central: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("Central returned error: {}", response.status()));
+    }
+
+    response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse response: {}", e))
+}
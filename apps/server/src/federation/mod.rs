@@ -2,4 +2,4 @@ mod heartbeat;
 mod identity;
 
 pub use heartbeat::HeartbeatService;
-pub use identity::verify_federation_token;
+pub use identity::{fetch_central_user, verify_federation_token};
@@ -4,7 +4,9 @@ mod config;
 mod db;
 mod error;
 mod federation;
+mod media;
 mod models;
+mod storage;
 mod ws;
 
 use std::sync::Arc;
@@ -21,16 +23,23 @@ use tower_http::set_header::SetResponseHeaderLayer;
 use tower_http::trace::TraceLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
+use cleanup_tasks::{
+    CleanupScheduler, ExpiredSessionTask, MessageRetentionTask, OrphanedUploadTask, StaleInviteTask,
+};
 use config::Config;
 use db::Database;
 use federation::HeartbeatService;
-use ws::ConnectionManager;
+use storage::Storage;
+use ws::{ConnectionManager, VoiceStateManager};
 
 pub struct AppState {
     pub db: Database,
     pub config: Config,
     pub http_client: HttpClient,
     pub ws: ConnectionManager,
+    pub storage: Arc<dyn Storage>,
+    pub voice: Arc<VoiceStateManager>,
+    pub relay: Option<Arc<media::MediaRelay>>,
 }
 
 #[tokio::main]
@@ -43,6 +52,10 @@ async fn main() -> anyhow::Result<()> {
         .with(tracing_subscriber::fmt::layer())
         .init();
 
+    rustls::crypto::ring::default_provider()
+        .install_default()
+        .expect("Failed to install rustls crypto provider");
+
     let config = Config::load()?;
 
     let pool = PgPoolOptions::new()
@@ -74,14 +87,72 @@ async fn main() -> anyhow::Result<()> {
     }
 
     let http_client = HttpClient::new();
+    let storage = storage::from_config(&config.storage).await?;
+
+    let voice = Arc::new(VoiceStateManager::new());
+    let (relay_disconnect_tx, mut relay_disconnect_rx) =
+        tokio::sync::mpsc::channel::<media::RelayDisconnect>(256);
+    let relay = if config.voice.enabled {
+        let relay_config = media::MediaRelayConfig {
+            bind_addr: format!("{}:{}", config.voice.bind_host, config.voice.bind_port).parse()?,
+            token_secret: config.voice.token_secret.clone(),
+            cert_path: config.voice.cert_path.clone(),
+            key_path: config.voice.key_path.clone(),
+            max_participants: config.voice.max_participants,
+        };
+
+        let relay = Arc::new(
+            media::MediaRelay::new(relay_config, voice.clone(), relay_disconnect_tx).await?,
+        );
+        let relay_task = relay.clone();
+        tokio::spawn(async move {
+            if let Err(e) = relay_task.run().await {
+                tracing::error!("Media relay error: {:?}", e);
+            }
+        });
+
+        tracing::info!(
+            "Media relay started on {}:{}",
+            config.voice.bind_host,
+            config.voice.bind_port
+        );
+        Some(relay)
+    } else {
+        None
+    };
 
     let state = Arc::new(AppState {
         db: db.clone(),
         config: config.clone(),
         http_client,
         ws: ConnectionManager::new(),
+        storage: storage.clone(),
+        voice,
+        relay,
     });
 
+    let relay_state = state.clone();
+    tokio::spawn(async move {
+        while let Some(event) = relay_disconnect_rx.recv().await {
+            api::voice::handle_relay_disconnect(
+                &relay_state,
+                event.channel_id,
+                event.member_id,
+                event.connected_at,
+            )
+            .await;
+        }
+    });
+
+    let cleanup_scheduler = CleanupScheduler::new(vec![
+        Arc::new(MessageRetentionTask::new(db.clone())),
+        Arc::new(ExpiredSessionTask::new(db.clone())),
+        Arc::new(StaleInviteTask::new(db.clone())),
+        Arc::new(OrphanedUploadTask::new(db.clone(), storage)),
+    ]);
+    tokio::spawn(cleanup_scheduler.run());
+    tracing::info!("Cleanup scheduler started");
+
     if db.is_setup_complete().await? {
         let heartbeat_service =
             Arc::new(HeartbeatService::new(db.clone(), Arc::new(config.clone())));
@@ -109,13 +180,21 @@ async fn main() -> anyhow::Result<()> {
         .allow_headers([
             axum::http::header::AUTHORIZATION,
             axum::http::header::CONTENT_TYPE,
+            axum::http::HeaderName::from_static("x-channel-id"),
+            axum::http::HeaderName::from_static("x-filename"),
+            axum::http::HeaderName::from_static("x-mime-type"),
+            axum::http::HeaderName::from_static(api::audit_log::REASON_HEADER),
         ])
         .allow_credentials(true);
 
     let app = Router::new()
         .route("/health", get(|| async { "OK" }))
         .route("/ws", get(ws::ws_handler))
-        .nest("/api", api::routes())
+        .nest(
+            "/api",
+            api::routes().layer(RequestBodyLimitLayer::new(10 * 1024 * 1024)),
+        )
+        .nest("/api/uploads", api::uploads::routes())
         .layer(middleware::from_fn_with_state(
             state.clone(),
             api::rate_limit::rate_limit_middleware,
@@ -141,7 +220,6 @@ async fn main() -> anyhow::Result<()> {
             header::REFERRER_POLICY,
             axum::http::HeaderValue::from_static("strict-origin-when-cross-origin"),
         ))
-        .layer(RequestBodyLimitLayer::new(10 * 1024 * 1024))
         .layer(TraceLayer::new_for_http())
         .with_state(state);
 
@@ -0,0 +1,3 @@
+mod relay;
+
+pub use relay::{generate_relay_token, MediaRelay, MediaRelayConfig, RelayDisconnect};
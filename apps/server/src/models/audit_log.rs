@@ -0,0 +1,43 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    MemberKick,
+    MemberBan,
+    MemberUnban,
+    MemberRoleAdd,
+    MemberRoleRemove,
+    RoleCreate,
+    RoleUpdate,
+    RoleDelete,
+    RoleReorder,
+    CategoryCreate,
+    CategoryUpdate,
+    CategoryDelete,
+    ChannelCreate,
+    ChannelUpdate,
+    ChannelDelete,
+    PermissionOverrideSet,
+    PermissionOverrideDelete,
+    InviteCreate,
+    ServerUpdate,
+    ServerPasswordSet,
+    ServerPasswordRemove,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow, Serialize)]
+pub struct AuditLogEntry {
+    pub id: Uuid,
+    pub actor_id: Uuid,
+    pub action: AuditAction,
+    pub target_id: Option<Uuid>,
+    pub before_state: Option<JsonValue>,
+    pub after_state: Option<JsonValue>,
+    pub reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
@@ -1,5 +1,5 @@
 use chrono::{DateTime, Utc};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 #[derive(Debug, Clone, sqlx::FromRow, Serialize)]
@@ -10,6 +10,15 @@ pub struct Category {
     pub created_at: DateTime<Utc>,
 }
 
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ChannelType {
+    #[default]
+    Text,
+    Voice,
+}
+
 #[derive(Debug, Clone, sqlx::FromRow, Serialize)]
 pub struct TextChannel {
     pub id: Uuid,
@@ -17,6 +26,8 @@ pub struct TextChannel {
     pub name: String,
     pub description: Option<String>,
     pub position: i32,
+    pub message_retention: Option<String>,
+    pub channel_type: ChannelType,
     pub created_at: DateTime<Utc>,
 }
 
@@ -11,15 +11,30 @@ pub struct Message {
     pub signature: Vec<u8>,
     pub reply_to_id: Option<Uuid>,
     pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub pinned_at: Option<DateTime<Utc>>,
+    pub pinned_by: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow, Serialize)]
+pub struct MessageReaction {
+    pub id: Uuid,
+    pub message_id: Uuid,
+    pub member_id: Uuid,
+    pub emoji: String,
+    pub created_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone, sqlx::FromRow, Serialize)]
 pub struct Upload {
     pub id: Uuid,
-    pub uploader_id: Uuid,
+    pub uploader_id: Option<Uuid>,
+    pub channel_id: Option<Uuid>,
+    pub message_id: Option<Uuid>,
     pub filename: String,
     pub content_type: String,
     pub file_size: i64,
+    #[serde(skip_serializing)]
     pub storage_path: String,
     pub created_at: DateTime<Utc>,
 }
@@ -1,3 +1,4 @@
+mod audit_log;
 mod channel;
 mod member;
 mod message;
@@ -5,6 +6,7 @@ mod role;
 mod server_identity;
 mod session;
 
+pub use audit_log::*;
 pub use channel::*;
 pub use member::*;
 pub use message::*;
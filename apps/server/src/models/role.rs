@@ -27,8 +27,11 @@ pub mod permissions {
     pub const ADMINISTRATOR: i64 = 1 << 9;
     pub const MANAGE_ROLES: i64 = 1 << 10;
     pub const VIEW_CHANNELS: i64 = 1 << 11;
+    pub const CONNECT: i64 = 1 << 12;
+    pub const SPEAK: i64 = 1 << 13;
+    pub const VIEW_AUDIT_LOG: i64 = 1 << 14;
 
-    pub const DEFAULT_MEMBER: i64 = READ_MESSAGES | SEND_MESSAGES | VIEW_CHANNELS;
+    pub const DEFAULT_MEMBER: i64 = READ_MESSAGES | SEND_MESSAGES | VIEW_CHANNELS | CONNECT | SPEAK;
 
     pub fn has_permission(user_perms: i64, required: i64) -> bool {
         if user_perms & ADMINISTRATOR != 0 {
@@ -1,4 +1,4 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use uuid::Uuid;
 
 #[derive(Debug, Clone, sqlx::FromRow)]
@@ -19,3 +19,106 @@ pub struct ServerIdentity {
     pub password_hash: Option<String>,
     pub created_at: DateTime<Utc>,
 }
+
+/// How long messages are kept before the retention task purges them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetentionPolicy {
+    Forever,
+    For(Duration),
+}
+
+impl RetentionPolicy {
+    /// Parses values like `"12h"`, `"30d"`, `"2w"`, `"6mo"`, `"1y"` or
+    /// `"forever"`. A bare `"m"` also means months; there is no minutes unit.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim().to_ascii_lowercase();
+        if matches!(value.as_str(), "forever" | "never" | "off") {
+            return Some(Self::Forever);
+        }
+
+        let split = value.find(|c: char| !c.is_ascii_digit())?;
+        let (amount, unit) = value.split_at(split);
+        let amount: i64 = amount.parse().ok().filter(|n| *n > 0)?;
+        let hours_per_unit = match unit {
+            "h" => 1,
+            "d" => 24,
+            "w" => 24 * 7,
+            "m" | "mo" => 24 * 30,
+            "y" => 24 * 365,
+            _ => return None,
+        };
+
+        Duration::try_hours(amount.checked_mul(hours_per_unit)?).map(Self::For)
+    }
+
+    pub fn cutoff(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            Self::Forever => None,
+            Self::For(duration) => now.checked_sub_signed(*duration),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn days(n: i64) -> RetentionPolicy {
+        RetentionPolicy::For(Duration::days(n))
+    }
+
+    #[test]
+    fn test_parse_units() {
+        assert_eq!(
+            RetentionPolicy::parse("12h"),
+            Some(RetentionPolicy::For(Duration::hours(12)))
+        );
+        assert_eq!(RetentionPolicy::parse("30d"), Some(days(30)));
+        assert_eq!(RetentionPolicy::parse("2w"), Some(days(14)));
+        assert_eq!(RetentionPolicy::parse("6m"), Some(days(180)));
+        assert_eq!(RetentionPolicy::parse("6mo"), Some(days(180)));
+        assert_eq!(RetentionPolicy::parse("1y"), Some(days(365)));
+    }
+
+    #[test]
+    fn test_parse_is_case_and_whitespace_insensitive() {
+        assert_eq!(RetentionPolicy::parse(" 30D "), Some(days(30)));
+        assert_eq!(RetentionPolicy::parse("6MO"), Some(days(180)));
+        assert_eq!(
+            RetentionPolicy::parse("Forever"),
+            Some(RetentionPolicy::Forever)
+        );
+    }
+
+    #[test]
+    fn test_parse_forever_aliases() {
+        for value in ["forever", "never", "off"] {
+            assert_eq!(
+                RetentionPolicy::parse(value),
+                Some(RetentionPolicy::Forever)
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_values() {
+        for value in [
+            "", "d", "30", "0d", "-5d", "+5d", "1.5d", "30x", "30 d", "5min", "d30", "30dd", "é1d",
+        ] {
+            assert_eq!(RetentionPolicy::parse(value), None, "{value:?}");
+        }
+    }
+
+    #[test]
+    fn test_parse_rejects_overflow() {
+        assert_eq!(RetentionPolicy::parse("99999999999999999999y"), None);
+        assert_eq!(RetentionPolicy::parse("9223372036854775807y"), None);
+    }
+
+    #[test]
+    fn test_cutoff() {
+        let now = Utc::now();
+        assert_eq!(RetentionPolicy::Forever.cutoff(now), None);
+        assert_eq!(days(30).cutoff(now), Some(now - Duration::days(30)));
+    }
+}
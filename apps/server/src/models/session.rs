@@ -9,4 +9,25 @@ pub struct Session {
     pub token_hash: Vec<u8>,
     pub expires_at: DateTime<Utc>,
     pub created_at: DateTime<Utc>,
+    pub access_expires_at: DateTime<Utc>,
+}
+
+/// Hashes and expiries of a newly issued access/refresh token pair.
+#[derive(Debug, Clone)]
+pub struct SessionTokenHashes {
+    pub access_token_hash: Vec<u8>,
+    pub access_expires_at: DateTime<Utc>,
+    pub refresh_token_hash: Vec<u8>,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug)]
+pub enum RefreshOutcome {
+    Rotated,
+    /// A refresh token that was already exchanged came back; its session has
+    /// been revoked.
+    Reused {
+        member_id: Uuid,
+    },
+    Invalid,
 }
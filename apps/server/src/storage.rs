@@ -0,0 +1,161 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use aws_config::BehaviorVersion;
+use aws_credential_types::Credentials;
+use aws_sdk_s3::Client;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::config::{S3Config, StorageConfig};
+
+/// Blob store for uploaded attachments. Keys are generated by the server and
+/// only ever contain `[a-z0-9-/]`.
+#[async_trait]
+pub trait Storage: Send + Sync {
+    async fn put(&self, key: &str, data: Vec<u8>) -> Result<()>;
+    /// Returns `None` when the key does not exist.
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>>;
+    /// Deleting a missing key is not an error.
+    async fn delete(&self, key: &str) -> Result<()>;
+}
+
+pub async fn from_config(config: &StorageConfig) -> Result<Arc<dyn Storage>> {
+    match config {
+        StorageConfig::Local { path } => {
+            tokio::fs::create_dir_all(path)
+                .await
+                .with_context(|| format!("Failed to create upload directory {:?}", path))?;
+            tracing::info!("Using local upload storage at {:?}", path);
+            Ok(Arc::new(LocalStorage { root: path.clone() }))
+        }
+        StorageConfig::S3(s3) => {
+            tracing::info!(
+                "Using S3 upload storage with endpoint: {}, bucket: {}",
+                s3.endpoint,
+                s3.bucket
+            );
+            Ok(Arc::new(S3Storage::new(s3).await))
+        }
+    }
+}
+
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+#[async_trait]
+impl Storage for LocalStorage {
+    async fn put(&self, key: &str, data: Vec<u8>) -> Result<()> {
+        let path = self.root.join(key);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(&path, data)
+            .await
+            .with_context(|| format!("Failed to write upload {}", key))
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        match tokio::fs::read(self.root.join(key)).await {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("Failed to read upload {}", key)),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        match tokio::fs::remove_file(self.root.join(key)).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e).with_context(|| format!("Failed to delete upload {}", key)),
+        }
+    }
+}
+
+pub struct S3Storage {
+    client: Client,
+    bucket: String,
+}
+
+impl S3Storage {
+    async fn new(config: &S3Config) -> Self {
+        let credentials = Credentials::new(
+            &config.access_key_id,
+            &config.secret_access_key,
+            None,
+            None,
+            "static",
+        );
+
+        let sdk_config = aws_config::defaults(BehaviorVersion::latest())
+            .region(aws_config::Region::new(config.region.clone()))
+            .credentials_provider(credentials)
+            .load()
+            .await;
+
+        let s3_config = aws_sdk_s3::config::Builder::from(&sdk_config)
+            .endpoint_url(&config.endpoint)
+            .force_path_style(config.force_path_style)
+            .build();
+
+        Self {
+            client: Client::from_conf(s3_config),
+            bucket: config.bucket.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl Storage for S3Storage {
+    async fn put(&self, key: &str, data: Vec<u8>) -> Result<()> {
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .body(data.into())
+            .content_type("application/octet-stream")
+            .send()
+            .await
+            .with_context(|| format!("Failed to upload {} to bucket {}", key, self.bucket))?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let response = match self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+        {
+            Ok(response) => response,
+            Err(e) if e.as_service_error().is_some_and(|e| e.is_no_such_key()) => return Ok(None),
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("Failed to download {} from {}", key, self.bucket))
+            }
+        };
+
+        let data = response
+            .body
+            .collect()
+            .await
+            .with_context(|| format!("Failed to read {} from {}", key, self.bucket))?
+            .into_bytes()
+            .to_vec();
+        Ok(Some(data))
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        self.client
+            .delete_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+            .with_context(|| format!("Failed to delete {} from {}", key, self.bucket))?;
+        Ok(())
+    }
+}
@@ -12,7 +12,7 @@ use serde::Deserialize;
 use sha2::{Digest, Sha256};
 use uuid::Uuid;
 
-use crate::models::permissions;
+use crate::models::{permissions, Session};
 use crate::AppState;
 
 use super::types::{ClientMessage, MemberPresence, ServerMessage};
@@ -50,6 +50,7 @@ impl Drop for ConnectionGuard {
                 };
                 state.ws.broadcast_all_except(leave_msg, member_id).await;
                 state.ws.remove_connection(member_id).await;
+                crate::api::voice::disconnect_member(&state, member_id).await;
                 tracing::debug!("WebSocket cleanup via guard for member {}", member_id);
             });
         }
@@ -66,8 +67,8 @@ pub async fn ws_handler(
     State(state): State<Arc<AppState>>,
     Query(query): Query<WsQuery>,
 ) -> Response {
-    let member_id = match authenticate_token(&state, &query.token).await {
-        Ok(id) => id,
+    let session = match authenticate_token(&state, &query.token).await {
+        Ok(session) => session,
         Err(e) => {
             tracing::warn!("WebSocket auth failed: {}", e);
             return Response::builder()
@@ -77,10 +78,10 @@ pub async fn ws_handler(
         }
     };
 
-    ws.on_upgrade(move |socket| handle_socket(socket, state, member_id))
+    ws.on_upgrade(move |socket| handle_socket(socket, state, session))
 }
 
-async fn authenticate_token(state: &AppState, token: &str) -> Result<Uuid, String> {
+async fn authenticate_token(state: &AppState, token: &str) -> Result<Session, String> {
     let token_bytes = hex::decode(token).map_err(|_| "Authentication failed")?;
     let token_hash = Sha256::digest(&token_bytes).to_vec();
 
@@ -91,14 +92,16 @@ async fn authenticate_token(state: &AppState, token: &str) -> Result<Uuid, Strin
         .map_err(|_| "Authentication failed")?
         .ok_or("Authentication failed")?;
 
-    if session.expires_at < chrono::Utc::now() {
+    let now = chrono::Utc::now();
+    if session.expires_at < now || session.access_expires_at < now {
         return Err("Authentication failed".into());
     }
 
-    Ok(session.member_id)
+    Ok(session)
 }
 
-async fn handle_socket(mut socket: WebSocket, state: Arc<AppState>, member_id: Uuid) {
+async fn handle_socket(mut socket: WebSocket, state: Arc<AppState>, session: Session) {
+    let member_id = session.member_id;
     let _connection_guard = match state.ws.limiter.try_add_connection(member_id).await {
         Ok(guard) => guard,
         Err(e) => {
@@ -166,6 +169,25 @@ async fn handle_socket(mut socket: WebSocket, state: Arc<AppState>, member_id: U
         }
     };
 
+    let (expiry_tx, mut expiry_rx) = tokio::sync::watch::channel(session.access_expires_at);
+
+    let expiry_task = async move {
+        loop {
+            let expires_at = *expiry_rx.borrow_and_update();
+            let until_expiry = (expires_at - chrono::Utc::now())
+                .to_std()
+                .unwrap_or_default();
+            tokio::select! {
+                _ = tokio::time::sleep(until_expiry) => break,
+                changed = expiry_rx.changed() => {
+                    if changed.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    };
+
     let recv_task = {
         let state = state.clone();
         async move {
@@ -180,8 +202,34 @@ async fn handle_socket(mut socket: WebSocket, state: Arc<AppState>, member_id: U
                             );
                             continue;
                         }
-                        if let Ok(client_msg) = serde_json::from_str::<ClientMessage>(&text) {
-                            handle_client_message(&state, member_id, client_msg).await;
+                        match serde_json::from_str::<ClientMessage>(&text) {
+                            Ok(ClientMessage::Reauth { token }) => {
+                                let renewed = authenticate_token(&state, &token)
+                                    .await
+                                    .ok()
+                                    .filter(|s| s.id == session.id);
+                                let Some(renewed) = renewed else {
+                                    tracing::warn!(
+                                        "WebSocket re-auth failed for member {}",
+                                        member_id
+                                    );
+                                    break;
+                                };
+                                let _ = expiry_tx.send(renewed.access_expires_at);
+                                state
+                                    .ws
+                                    .send_to_member(
+                                        member_id,
+                                        ServerMessage::Reauthenticated {
+                                            expires_at: renewed.access_expires_at,
+                                        },
+                                    )
+                                    .await;
+                            }
+                            Ok(client_msg) => {
+                                handle_client_message(&state, member_id, client_msg).await;
+                            }
+                            Err(_) => {}
                         }
                     }
                     Message::Close(_) => break,
@@ -195,6 +243,9 @@ async fn handle_socket(mut socket: WebSocket, state: Arc<AppState>, member_id: U
     tokio::select! {
         _ = send_task => {},
         _ = recv_task => {},
+        _ = expiry_task => {
+            tracing::info!("Session token expired, closing WebSocket for member {}", member_id);
+        },
     }
 
     let leave_msg = ServerMessage::PresenceUpdate {
@@ -205,6 +256,7 @@ async fn handle_socket(mut socket: WebSocket, state: Arc<AppState>, member_id: U
     state.ws.broadcast_all_except(leave_msg, member_id).await;
 
     state.ws.remove_connection(member_id).await;
+    crate::api::voice::disconnect_member(&state, member_id).await;
     guard.mark_cleaned();
     tracing::debug!("WebSocket connection closed for member {}", member_id);
 }
@@ -321,5 +373,7 @@ async fn handle_client_message(state: &AppState, member_id: Uuid, msg: ClientMes
                 .send_to_member(member_id, ServerMessage::Pong)
                 .await;
         }
+        // Handled by the socket loop, which owns the token expiry.
+        ClientMessage::Reauth { .. } => {}
     }
 }
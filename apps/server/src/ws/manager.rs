@@ -230,6 +230,24 @@ impl ConnectionManager {
         }
     }
 
+    pub async fn send_to_members(&self, member_ids: &[Uuid], message: ServerMessage) {
+        let connections = self.connections.read().await;
+
+        for member_id in member_ids {
+            if let Some(conn) = connections.get(member_id) {
+                if try_send_with_backoff(&conn.sender, message.clone(), *member_id)
+                    .await
+                    .is_err()
+                {
+                    tracing::warn!(
+                        "Failed to send message to member {}, connection may be dead or channel full",
+                        member_id
+                    );
+                }
+            }
+        }
+    }
+
     pub async fn connection_count(&self) -> usize {
         self.connections.read().await.len()
     }
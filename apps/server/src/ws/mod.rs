@@ -3,6 +3,8 @@ pub mod connection_limit;
 mod handler;
 mod manager;
 pub mod types;
+pub mod voice;
 
 pub use handler::ws_handler;
 pub use manager::ConnectionManager;
+pub use voice::VoiceStateManager;
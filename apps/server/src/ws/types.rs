@@ -3,18 +3,35 @@ use uuid::Uuid;
 
 use crate::api::messages::MessageWithKey;
 use crate::models::{Category, Member, Role, TextChannel};
+use crate::ws::voice::VoiceParticipant;
 
 /// Messages sent from client to server
 #[derive(Debug, Clone, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum ClientMessage {
-    SubscribeChannel { channel_id: Uuid },
-    UnsubscribeChannel { channel_id: Uuid },
-    Typing { channel_id: Uuid },
-    StopTyping { channel_id: Uuid },
-    SubscribeUser { user_id: Uuid },
-    UpdatePresence { status: String },
+    SubscribeChannel {
+        channel_id: Uuid,
+    },
+    UnsubscribeChannel {
+        channel_id: Uuid,
+    },
+    Typing {
+        channel_id: Uuid,
+    },
+    StopTyping {
+        channel_id: Uuid,
+    },
+    SubscribeUser {
+        user_id: Uuid,
+    },
+    UpdatePresence {
+        status: String,
+    },
     Ping,
+    /// Swaps in a refreshed session token without reconnecting.
+    Reauth {
+        token: String,
+    },
 }
 
 /// Messages sent from server to client
@@ -26,6 +43,9 @@ pub enum ServerMessage {
         member_id: Uuid,
     },
     Pong,
+    Reauthenticated {
+        expires_at: chrono::DateTime<chrono::Utc>,
+    },
 
     // Messages
     NewMessage {
@@ -35,6 +55,21 @@ pub enum ServerMessage {
     MessageDeleted {
         data: MessageDeletedData,
     },
+    MessageEdited {
+        data: MessageEditedData,
+    },
+    ReactionAdded {
+        data: ReactionAddedData,
+    },
+    ReactionRemoved {
+        data: ReactionRemovedData,
+    },
+    MessagePinned {
+        data: MessagePinnedData,
+    },
+    MessageUnpinned {
+        data: MessageUnpinnedData,
+    },
 
     // Typing indicators
     TypingStart {
@@ -112,6 +147,17 @@ pub enum ServerMessage {
         presences: Vec<MemberPresence>,
     },
 
+    // Voice
+    VoiceParticipantJoined {
+        data: VoiceParticipant,
+    },
+    VoiceParticipantLeft {
+        data: VoiceParticipantLeftData,
+    },
+    VoiceStateUpdated {
+        data: VoiceParticipant,
+    },
+
     // Errors
     Error {
         message: String,
@@ -142,3 +188,49 @@ pub struct MessageDeletedData {
     pub channel_id: Uuid,
     pub message_id: Uuid,
 }
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MessageEditedData {
+    pub channel_id: Uuid,
+    pub message_id: Uuid,
+    pub encrypted_content: Vec<u8>,
+    pub signature: Vec<u8>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReactionAddedData {
+    pub id: Uuid,
+    pub channel_id: Uuid,
+    pub message_id: Uuid,
+    pub member_id: Uuid,
+    pub emoji: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReactionRemovedData {
+    pub channel_id: Uuid,
+    pub message_id: Uuid,
+    pub member_id: Uuid,
+    pub emoji: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MessagePinnedData {
+    pub channel_id: Uuid,
+    pub message_id: Uuid,
+    pub pinned_by: Uuid,
+    pub pinned_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MessageUnpinnedData {
+    pub channel_id: Uuid,
+    pub message_id: Uuid,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VoiceParticipantLeftData {
+    pub channel_id: Uuid,
+    pub member_id: Uuid,
+}
@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::error::{AppError, Result};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VoiceParticipant {
+    pub channel_id: Uuid,
+    pub member_id: Uuid,
+    pub username: String,
+    pub can_speak: bool,
+    pub self_muted: bool,
+    pub self_deafened: bool,
+    pub joined_at: DateTime<Utc>,
+}
+
+/// Who is in which voice channel. This is presence-like state, so it lives in
+/// memory and is rebuilt as members reconnect after a restart.
+#[derive(Debug, Default)]
+pub struct VoiceStateManager {
+    channels: RwLock<HashMap<Uuid, HashMap<Uuid, VoiceParticipant>>>,
+}
+
+impl VoiceStateManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the participant to their channel, moving them out of any other
+    /// voice channel first. Returns the channel they were moved out of.
+    pub async fn join(
+        &self,
+        participant: VoiceParticipant,
+        max_participants: usize,
+    ) -> Result<Option<Uuid>> {
+        let mut channels = self.channels.write().await;
+
+        let target = channels.entry(participant.channel_id).or_default();
+        if !target.contains_key(&participant.member_id) && target.len() >= max_participants {
+            return Err(AppError::BadRequest("Voice channel is full".into()));
+        }
+        target.insert(participant.member_id, participant.clone());
+
+        let mut previous = None;
+        channels.retain(|channel_id, members| {
+            if *channel_id != participant.channel_id
+                && members.remove(&participant.member_id).is_some()
+            {
+                previous = Some(*channel_id);
+            }
+            !members.is_empty()
+        });
+
+        Ok(previous)
+    }
+
+    /// Removes the member from whichever voice channel they are in.
+    pub async fn leave(&self, member_id: Uuid) -> Option<Uuid> {
+        let mut channels = self.channels.write().await;
+        let mut left = None;
+        channels.retain(|channel_id, members| {
+            if members.remove(&member_id).is_some() {
+                left = Some(*channel_id);
+            }
+            !members.is_empty()
+        });
+        left
+    }
+
+    /// Removes the member only if they joined no later than `before`, so a
+    /// stale relay disconnect cannot kick someone who has since rejoined.
+    pub async fn leave_if_joined_before(
+        &self,
+        channel_id: Uuid,
+        member_id: Uuid,
+        before: DateTime<Utc>,
+    ) -> bool {
+        let mut channels = self.channels.write().await;
+        let Some(members) = channels.get_mut(&channel_id) else {
+            return false;
+        };
+        let removed = members
+            .get(&member_id)
+            .is_some_and(|p| p.joined_at <= before)
+            && members.remove(&member_id).is_some();
+        if members.is_empty() {
+            channels.remove(&channel_id);
+        }
+        removed
+    }
+
+    pub async fn update_state(
+        &self,
+        channel_id: Uuid,
+        member_id: Uuid,
+        self_muted: Option<bool>,
+        self_deafened: Option<bool>,
+    ) -> Option<VoiceParticipant> {
+        let mut channels = self.channels.write().await;
+        let participant = channels.get_mut(&channel_id)?.get_mut(&member_id)?;
+        if let Some(muted) = self_muted {
+            participant.self_muted = muted;
+        }
+        if let Some(deafened) = self_deafened {
+            participant.self_deafened = deafened;
+        }
+        Some(participant.clone())
+    }
+
+    /// Updates the participant's speak permission. Returns the participant
+    /// only if the flag actually changed.
+    pub async fn set_can_speak(
+        &self,
+        channel_id: Uuid,
+        member_id: Uuid,
+        can_speak: bool,
+    ) -> Option<VoiceParticipant> {
+        let mut channels = self.channels.write().await;
+        let participant = channels.get_mut(&channel_id)?.get_mut(&member_id)?;
+        if participant.can_speak == can_speak {
+            return None;
+        }
+        participant.can_speak = can_speak;
+        Some(participant.clone())
+    }
+
+    pub async fn participant(&self, channel_id: Uuid, member_id: Uuid) -> Option<VoiceParticipant> {
+        let channels = self.channels.read().await;
+        channels.get(&channel_id)?.get(&member_id).cloned()
+    }
+
+    pub async fn participants(&self, channel_id: Uuid) -> Vec<VoiceParticipant> {
+        let channels = self.channels.read().await;
+        let mut participants: Vec<_> = channels
+            .get(&channel_id)
+            .map(|members| members.values().cloned().collect())
+            .unwrap_or_default();
+        participants.sort_by_key(|p| p.joined_at);
+        participants
+    }
+
+    pub async fn all_participants(&self) -> Vec<VoiceParticipant> {
+        let channels = self.channels.read().await;
+        let mut participants: Vec<_> = channels
+            .values()
+            .flat_map(|members| members.values().cloned())
+            .collect();
+        participants.sort_by_key(|p| p.joined_at);
+        participants
+    }
+
+    /// Drops every participant of a deleted channel.
+    pub async fn remove_channel(&self, channel_id: Uuid) -> Vec<Uuid> {
+        let mut channels = self.channels.write().await;
+        channels
+            .remove(&channel_id)
+            .map(|members| members.into_keys().collect())
+            .unwrap_or_default()
+    }
+}